@@ -0,0 +1,208 @@
+use smart_channel::channel;
+use std::{
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+use tokio::time::Duration;
+
+use crate::{
+    handle::HubHandle,
+    notifier::{NotifierHub, Receiver, Sender, SmartChannelId, NOTIFIER_CHANNEL_SIZE},
+    sync::lock,
+};
+
+/// Defines when the hub raises an `Alert`.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlertPolicy {
+    /// A subscriber with more messages waiting in its buffer after a publish is lagging.
+    pub max_pending: Option<usize>,
+    /// A writing that had to wait for some room in a buffer longer than this is slow.
+    pub latency_budget: Option<Duration>,
+}
+
+/// An event on the health of the hub, see `NotifierHub::get_alert_waiter`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Alert<ChannelId> {
+    /// The subscriber has more than `max_pending` messages waiting after a publish on the channel.
+    Lagging {
+        channel: ChannelId,
+        subscriber: SmartChannelId,
+        pending: usize,
+    },
+    /// A writing to the subscriber exceeded the latency budget. The channel is `None` for the broadcasts
+    /// over several channels.
+    SlowWriting {
+        channel: Option<ChannelId>,
+        subscriber: SmartChannelId,
+        latency: Duration,
+    },
+}
+
+/// Type alias for the receivers returned by the get_alert_waiter method of the Hub
+pub type AlertWaiter<ChannelId> = Receiver<Alert<ChannelId>, SmartChannelId>;
+
+/// The waiters notified of each alert. It is shared with the writing handlers.
+pub(crate) type AlertWaiters<ChannelId> = Arc<Mutex<Vec<Sender<Alert<ChannelId>, SmartChannelId>>>>;
+
+/// Reports the slow writings of a writing handler, already bound to the channel of the publish.
+pub(crate) type LatencyReporter = Arc<dyn Fn(SmartChannelId, Duration) + Send + Sync>;
+
+/// Builds the latency reporter of a publish from its channel.
+type LatencyHook<ChannelId> = Arc<dyn Fn(Option<&ChannelId>) -> LatencyReporter + Send + Sync>;
+
+/// The alert policy of a hub, and its latency hook.
+pub(crate) struct Alerting<ChannelId> {
+    policy: AlertPolicy,
+    reporter: LatencyHook<ChannelId>,
+}
+
+/// Notifies the waiters of the alert. A waiter that does not read its alerts misses some.
+fn raise<ChannelId: Clone>(waiters: &AlertWaiters<ChannelId>, alert: Alert<ChannelId>) {
+    let mut waiters = lock(waiters);
+    waiters.retain(|w| !w.is_closed());
+    for waiter in waiters.iter() {
+        let _ = waiter.try_send(alert.clone());
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Sets when the hub raises an alert, `None` disables the alerts.
+    /// The lag of the subscribers is checked after each publish on a channel,
+    /// the latency is measured for the writings that had to wait for some room in a buffer.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{alert::{Alert, AlertPolicy}, notifier::NotifierHub};
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// hub.set_alert_policy(Some(AlertPolicy { max_pending: Some(1), latency_budget: None }));
+    /// let mut alerts = hub.get_alert_waiter();
+    /// let _receiver = hub.subscribe(&"metrics", 10);
+    ///
+    /// hub.clone_send(1, &"metrics").unwrap();
+    /// hub.clone_send(2, &"metrics").unwrap();
+    /// assert!(matches!(alerts.try_recv().unwrap(), Alert::Lagging { pending: 2, .. }));
+    /// ```
+    pub fn set_alert_policy(&mut self, policy: Option<AlertPolicy>)
+    where
+        ChannelId: Clone + Send + Sync + 'static,
+    {
+        self.alerting = policy.map(|policy| {
+            let waiters = Arc::clone(&self.alert_waiters);
+            Alerting {
+                policy,
+                reporter: Arc::new(move |channel: Option<&ChannelId>| {
+                    let waiters = Arc::clone(&waiters);
+                    let channel = channel.cloned();
+                    Arc::new(move |subscriber, latency| {
+                        raise(
+                            &waiters,
+                            Alert::SlowWriting {
+                                channel: channel.clone(),
+                                subscriber,
+                                latency,
+                            },
+                        )
+                    })
+                }),
+            }
+        });
+    }
+
+    /// Returns the alert policy of the hub.
+    pub fn alert_policy(&self) -> Option<AlertPolicy> {
+        self.alerting.as_ref().map(|alerting| alerting.policy)
+    }
+
+    /// Returns a waiter notified of each alert raised by the hub.
+    pub fn get_alert_waiter(&mut self) -> AlertWaiter<ChannelId> {
+        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, self.get_new_id());
+        lock(&self.alert_waiters).push(sender);
+        receiver
+    }
+
+    /// Returns the latency budget of a publish on the given channel and its reporter, if it is enabled.
+    pub(crate) fn latency_reporter(
+        &self,
+        channel: Option<&ChannelId>,
+    ) -> Option<(Duration, LatencyReporter)> {
+        let alerting = self.alerting.as_ref()?;
+        let budget = alerting.policy.latency_budget?;
+        Some((budget, (alerting.reporter)(channel)))
+    }
+
+    /// Raises a `Lagging` alert for each subscriber of the channel with too many messages waiting.
+    pub(crate) fn check_lag(&self, id: &ChannelId)
+    where
+        ChannelId: Clone,
+    {
+        let Some(max_pending) = self.alerting.as_ref().and_then(|a| a.policy.max_pending) else {
+            return;
+        };
+        for sender in self.senders_of(id) {
+            let pending = sender.max_capacity() - sender.capacity();
+            if pending > max_pending {
+                raise(
+                    &self.alert_waiters,
+                    Alert::Lagging {
+                        channel: id.clone(),
+                        subscriber: *sender.id(),
+                        pending,
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::set_alert_policy`.
+    pub fn set_alert_policy(&self, policy: Option<AlertPolicy>)
+    where
+        ChannelId: Clone + Send + Sync + 'static,
+    {
+        self.with(|hub| hub.set_alert_policy(policy))
+    }
+
+    /// See `NotifierHub::get_alert_waiter`.
+    pub fn get_alert_waiter(&self) -> AlertWaiter<ChannelId> {
+        self.with(|hub| hub.get_alert_waiter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_writing_alert() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_alert_policy(Some(AlertPolicy {
+            max_pending: None,
+            latency_budget: Some(Duration::from_millis(10)),
+        }));
+        let mut alerts = hub.get_alert_waiter();
+        let mut receiver = hub.subscribe(&"channel1", 1);
+
+        hub.clone_send(1, &"channel1").unwrap();
+        let handler = hub.clone_send(2, &"channel1").unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(receiver.recv().await.unwrap(), 1);
+        handler.wait(None).await.into_result().unwrap();
+
+        match alerts.recv().await.unwrap() {
+            Alert::SlowWriting {
+                channel,
+                subscriber,
+                latency,
+            } => {
+                assert_eq!(channel, Some("channel1"));
+                assert_eq!(subscriber, receiver.id());
+                assert!(latency >= Duration::from_millis(50));
+            }
+            alert => panic!("Unexpected alert {alert:?}"),
+        }
+        assert!(alerts.try_recv().is_err());
+    }
+}
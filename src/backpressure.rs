@@ -0,0 +1,120 @@
+use std::{
+    hash::Hash,
+    sync::{Arc, Mutex, Weak},
+};
+use tokio::{
+    sync::watch,
+    time::{sleep, Duration},
+};
+
+use crate::{handle::HubHandle, notifier::NotifierHub, sync::lock};
+
+/// How often the watches returned by `backpressure_watch` check the buffers of the subscribers.
+pub const PRESSURE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Returns the pressure of the channel, the occupancy of the fullest buffer of its subscribers,
+    /// from `0.0` when they are all empty to `1.0` when one of them is full and slows down the publishes.
+    /// A channel without subscriber has no pressure.
+    pub fn pressure(&self, id: &ChannelId) -> f64 {
+        self.senders_of(self.aliases.get(id).unwrap_or(id))
+            .iter()
+            .map(|sender| {
+                let pending = sender.max_capacity() - sender.capacity();
+                pending as f64 / sender.max_capacity() as f64
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Updates the pressure of the channel until the hub or all the receivers of the watch are dropped.
+async fn watch_pressure<M, ChannelId: Eq + Hash>(
+    hub: Weak<Mutex<NotifierHub<M, ChannelId>>>,
+    id: ChannelId,
+    pressure: watch::Sender<f64>,
+) {
+    while !pressure.is_closed() {
+        sleep(PRESSURE_POLL_INTERVAL).await;
+        let Some(hub) = hub.upgrade() else {
+            return;
+        };
+        let current = lock(&hub).pressure(&id);
+        pressure.send_if_modified(|last| {
+            let changed = *last != current;
+            *last = current;
+            changed
+        });
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    /// Returns a watch of the pressure of the channel, see `NotifierHub::pressure`, so the producers can slow down
+    /// before their publishes do. The pressure is checked every `PRESSURE_POLL_INTERVAL` by a task of the hub,
+    /// the watch being notified when it changes, until all its receivers are dropped.
+    ///
+    /// Must be called within a tokio runtime, as the pressure is followed by a task.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let mut receiver = handle.subscribe(&"jobs", 4);
+    /// let mut pressure = handle.backpressure_watch(&"jobs");
+    ///
+    /// for job in 0..4 {
+    ///     handle.clone_send(job, &"jobs").unwrap().wait(None).await;
+    /// }
+    /// pressure.wait_for(|pressure| *pressure == 1.0).await.unwrap();
+    /// while receiver.try_recv().is_ok() {}
+    /// pressure.wait_for(|pressure| *pressure == 0.0).await.unwrap();
+    /// # }
+    /// ```
+    pub fn backpressure_watch(&self, id: &ChannelId) -> watch::Receiver<f64> {
+        let (pressure, watch) = watch::channel(self.with(|hub| hub.pressure(id)));
+        tokio::spawn(watch_pressure(
+            Arc::downgrade(&self.hub),
+            id.clone(),
+            pressure,
+        ));
+        watch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_backpressure_watch() {
+        let handle: HubHandle<u32, &'static str> = NotifierHub::new().into_handle();
+        let mut pressure = handle.backpressure_watch(&"channel1");
+        assert_eq!(*pressure.borrow(), 0.0);
+
+        let mut slow = handle.subscribe(&"channel1", 4);
+        let mut fast = handle.subscribe(&"channel1", 2);
+        for msg in 0..2 {
+            handle
+                .clone_send(msg, &"channel1")
+                .unwrap()
+                .wait(None)
+                .await;
+        }
+        while fast.try_recv().is_ok() {}
+        pressure.changed().await.unwrap();
+        assert_eq!(*pressure.borrow_and_update(), 0.5);
+
+        while slow.try_recv().is_ok() {}
+        pressure.changed().await.unwrap();
+        assert_eq!(*pressure.borrow_and_update(), 0.0);
+
+        drop(handle);
+        assert!(pressure.changed().await.is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use std::hash::Hash;
+use tokio::{
+    sync::oneshot,
+    time::{timeout_at, Duration, Instant},
+};
+
+use crate::{
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::{NotifierHub, SmartChannelId},
+    writing_handler::WritingHandler,
+};
+
+/// The token a subscriber uses to signal it is done with a message sent by `barrier_send`.
+/// Dropping it without calling `ack` means the message has not been processed.
+#[derive(Debug)]
+pub struct Ack {
+    sender: oneshot::Sender<()>,
+}
+
+impl Ack {
+    /// Signals the message has been processed.
+    pub fn ack(self) {
+        let _ = self.sender.send(());
+    }
+}
+
+/// A message delivered by `barrier_send`, along with the token to acknowledge it.
+#[derive(Debug)]
+pub struct Acked<M> {
+    pub msg: M,
+    pub ack: Ack,
+}
+
+/// Waits for the subscribers reached by `barrier_send` to acknowledge their message.
+pub struct BarrierHandler<M: Send + 'static, ChannelId> {
+    id: ChannelId,
+    writings: WritingHandler<Acked<M>>,
+    acks: Vec<(SmartChannelId, oneshot::Receiver<()>)>,
+    timeout: Duration,
+}
+
+impl<M: Send + 'static, ChannelId> BarrierHandler<M, ChannelId> {
+    /// Returns the number of subscribers that have to acknowledge the message.
+    pub fn len(&self) -> usize {
+        self.acks.len()
+    }
+
+    /// Returns true if the channel had no subscriber, so the barrier is already passed.
+    pub fn is_empty(&self) -> bool {
+        self.acks.is_empty()
+    }
+
+    /// Resolves once every subscriber acknowledged its message, and returns their number.
+    /// Returns `BarrierIncomplete` with the subscribers that did not acknowledge before the timeout,
+    /// because their writing failed, because they dropped the `Ack`, or because they were too slow.
+    pub async fn wait(self) -> Result<usize, NotifierError<M, ChannelId>> {
+        let deadline = Instant::now() + self.timeout;
+        // A failed writing drops its `Ack`, so the outcome of the writings is read from the acknowledgements
+        let _ = timeout_at(deadline, self.writings.wait(None)).await;
+        let n = self.acks.len();
+        let mut missing = Vec::new();
+        for (subscriber, ack) in self.acks {
+            if !matches!(timeout_at(deadline, ack).await, Ok(Ok(()))) {
+                missing.push(subscriber);
+            }
+        }
+        if missing.is_empty() {
+            Ok(n)
+        } else {
+            Err(NotifierError::BarrierIncomplete {
+                id: self.id,
+                missing,
+            })
+        }
+    }
+}
+
+impl<M, ChannelId> NotifierHub<Acked<M>, ChannelId>
+where
+    M: Clone + Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Sends a clone of the message to each subscriber of the channel along with an `Ack`,
+    /// so the returned handler resolves only once all of them processed it, or at the timeout.
+    /// This gives a synchronization point for phase changes, such as a configuration reload.
+    /// Fails with `NotifierError::NoSubscriber`, handing the message back, if the channel has no subscriber,
+    /// as a parked message could not be acknowledged in time.
+    /// The messages are queued behind the publishes waiting in the sequencer of the channel, if any,
+    /// so the barrier is only passed once the subscribers processed these publishes too.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{barrier::Acked, notifier::NotifierHub};
+    /// use tokio::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::<Acked<&str>, _>::new();
+    /// let mut receiver = hub.subscribe(&"config", 10);
+    /// tokio::spawn(async move {
+    ///     while let Some(Acked { msg, ack }) = receiver.recv().await {
+    ///         println!("Reloading {msg}");
+    ///         ack.ack();
+    ///     }
+    /// });
+    ///
+    /// let barrier = hub.barrier_send("v2", &"config", Duration::from_secs(1)).unwrap();
+    /// assert_eq!(barrier.wait().await.unwrap(), 1);
+    /// # }
+    /// ```
+    pub fn barrier_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<BarrierHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        let (writings, acks) = self.publish_with_reply(&id, msg, |sender, msg| {
+            let (ack, acked) = oneshot::channel();
+            let msg = Acked {
+                msg: msg.clone(),
+                ack: Ack { sender: ack },
+            };
+            (msg, (*sender.id(), acked))
+        })?;
+        Ok(BarrierHandler {
+            id,
+            writings,
+            acks,
+            timeout,
+        })
+    }
+}
+
+impl<M, ChannelId> HubHandle<Acked<M>, ChannelId>
+where
+    M: Clone + Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `NotifierHub::barrier_send`.
+    pub fn barrier_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<BarrierHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.barrier_send(msg, id, timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_barrier_send() {
+        let handle = NotifierHub::<Acked<u32>, &'static str>::new().into_handle();
+        for delay in [10, 50] {
+            let mut receiver = handle.subscribe(&"channel1", 10);
+            tokio::spawn(async move {
+                let Acked { msg, ack } = receiver.recv().await.unwrap();
+                assert_eq!(msg, 1);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                ack.ack();
+            });
+        }
+
+        let start = Instant::now();
+        let barrier = handle
+            .barrier_send(1, &"channel1", Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(barrier.len(), 2);
+        assert_eq!(barrier.wait().await.unwrap(), 2);
+        assert_eq!(start.elapsed(), Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_barrier_without_subscriber() {
+        let mut hub = NotifierHub::<Acked<u32>, &'static str>::new();
+        hub.set_park_buffer(&"channel1", Some(1));
+        assert!(matches!(
+            hub.barrier_send(1, &"channel1", Duration::from_secs(1)),
+            Err(NotifierError::NoSubscriber {
+                id: "channel1",
+                msg: 1
+            })
+        ));
+        let mut receiver = hub.subscribe(&"channel1", 10);
+        assert!(receiver.try_recv().is_err());
+
+        // Nor is the message dropped once the channel is over
+        hub.set_park_buffer(&"channel1", None);
+        // As `clean_channel` would, the message type not being `Clone`
+        drop(receiver);
+        hub.senders.get_mut(&"channel1").unwrap().clear();
+        assert!(matches!(
+            hub.barrier_send(2, &"channel1", Duration::from_secs(1)),
+            Err(NotifierError::NoSubscriber {
+                id: "channel1",
+                msg: 2
+            })
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_barrier_incomplete() {
+        let mut hub = NotifierHub::<Acked<u32>, &'static str>::new();
+        let mut acking = hub.subscribe(&"channel1", 10);
+        let mut dropping = hub.subscribe(&"channel1", 10);
+        let _silent = hub.subscribe(&"channel1", 10);
+
+        let barrier = hub
+            .barrier_send(1, &"channel1", Duration::from_secs(1))
+            .unwrap();
+        acking.try_recv().unwrap().ack.ack();
+        drop(dropping.try_recv().unwrap());
+        match barrier.wait().await {
+            Err(NotifierError::BarrierIncomplete { id, missing }) => {
+                assert_eq!(id, "channel1");
+                assert_eq!(missing.len(), 2);
+                assert!(!missing.contains(&acking.id()));
+            }
+            _ => panic!("The barrier should not be passed"),
+        }
+    }
+}
@@ -0,0 +1,481 @@
+use futures::{future::ready, stream, SinkExt, Stream, StreamExt};
+use std::{hash::Hash, io, sync::mpsc, thread};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    select,
+    sync::{broadcast, oneshot, watch},
+    task::{JoinError, JoinHandle},
+};
+pub use tokio_util::codec::{Decoder, Encoder, LengthDelimitedCodec, LinesCodec};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+#[cfg(unix)]
+pub use tokio::signal::unix::SignalKind;
+
+use crate::handle::HubHandle;
+
+/// The size of the channels subscribed by `pipe_to_writer` and `to_std_receiver`.
+pub const PIPE_CHANNEL_SIZE: usize = 100;
+
+/// Controls a task forwarding items into a channel. Dropping the guard stops the task.
+pub struct IngestGuard {
+    pub(crate) task: JoinHandle<usize>,
+}
+
+impl Drop for IngestGuard {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl IngestGuard {
+    /// Stops the forwarding, the item being published is still delivered.
+    pub fn stop(&self) {
+        self.task.abort();
+    }
+
+    /// Returns true if the source is exhausted or the forwarding has been stopped.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Waits for the source to be exhausted and returns the number of published items.
+    /// Returns an error if the forwarding has been stopped.
+    pub async fn join(mut self) -> Result<usize, JoinError> {
+        (&mut self.task).await
+    }
+}
+
+/// Controls a task writing the messages of a channel into a sink.
+/// Dropping the guard stops the task as `stop` does, but without waiting for it.
+pub struct PipeGuard<E> {
+    pub(crate) stop: Option<oneshot::Sender<()>>,
+    pub(crate) task: JoinHandle<Result<usize, E>>,
+}
+
+impl<E: From<io::Error>> PipeGuard<E> {
+    /// Asks the task to stop. It unsubscribes, writes the messages still buffered for it,
+    /// then flushes and shuts down the sink.
+    pub fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+
+    /// Returns true if the task is over.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Waits for the end of the task and returns the number of written messages.
+    /// Without a call to `stop`, the task ends when the channel is over or when writing fails.
+    pub async fn join(self) -> Result<usize, E> {
+        let PipeGuard { stop, task } = self;
+        let result = task.await;
+        drop(stop);
+        result.map_err(io::Error::other)?
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    /// Spawns a task publishing each item of the stream on the channel with `clone_send`.
+    /// The next item is only pulled once the previous one has been written to all the subscribers,
+    /// so a slow subscriber slows down the source. Items that can't be published, for instance
+    /// while the channel has no subscriber, are dropped.
+    ///
+    /// Example:
+    /// ```rust
+    /// use futures::stream;
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let mut receiver = handle.subscribe(&"ticks", 10);
+    ///
+    /// let guard = handle.ingest(&"ticks", stream::iter([1, 2, 3]));
+    /// assert_eq!(guard.join().await.unwrap(), 3);
+    /// assert_eq!(receiver.recv().await.unwrap(), 1);
+    /// # }
+    /// ```
+    pub fn ingest<S>(&self, id: &ChannelId, stream: S) -> IngestGuard
+    where
+        S: Stream<Item = M> + Send + 'static,
+    {
+        let handle = self.clone();
+        let id = id.clone();
+        let task = tokio::spawn(async move {
+            let mut forwarded = 0;
+            let mut stream = std::pin::pin!(stream);
+            while let Some(item) = stream.next().await {
+                if let Ok(handler) = handle.clone_send(item, &id) {
+                    let _ = handler.wait(None).await;
+                    forwarded += 1;
+                }
+            }
+            forwarded
+        });
+        IngestGuard { task }
+    }
+
+    /// Spawns a task republishing the values of a tokio broadcast channel on the channel, as `ingest` does,
+    /// so the code still sending on the broadcast channel can be migrated later.
+    /// The values missed because the receiver lagged are skipped, and the task ends when all the senders are dropped.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    /// use tokio::sync::broadcast;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let mut receiver = handle.subscribe(&"prices", 10);
+    ///
+    /// let (sender, legacy) = broadcast::channel(10);
+    /// let guard = handle.mirror_broadcast(&"prices", legacy);
+    /// sender.send(42).unwrap();
+    /// drop(sender);
+    /// assert_eq!(guard.join().await.unwrap(), 1);
+    /// assert_eq!(receiver.recv().await.unwrap(), 42);
+    /// # }
+    /// ```
+    pub fn mirror_broadcast(
+        &self,
+        id: &ChannelId,
+        receiver: broadcast::Receiver<M>,
+    ) -> IngestGuard {
+        let values = stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(value) => return Some((value, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        self.ingest(id, values)
+    }
+
+    /// Spawns a task republishing the values of a tokio watch channel on the channel, as `ingest` does.
+    /// The current value is published first, then each new value. The values replaced before being
+    /// published are skipped, as a watch receiver would. The task ends when the sender is dropped.
+    pub fn mirror_watch(&self, id: &ChannelId, mut receiver: watch::Receiver<M>) -> IngestGuard
+    where
+        M: Sync,
+    {
+        receiver.mark_changed();
+        let values = stream::unfold(receiver, |mut receiver| async move {
+            receiver.changed().await.ok()?;
+            let value = receiver.borrow_and_update().clone();
+            Some((value, receiver))
+        });
+        self.ingest(id, values)
+    }
+
+    /// Spawns a task publishing a message on the mapped channel each time one of the OS signals is received,
+    /// as `ingest` does, so the shutdown and reload requests go through the hub. The message is built from
+    /// the `SignalKind` with `From`. Returns an error if a signal can't be listened to.
+    /// Once the task is spawned, the default action of the signals, such as ending the process, is disabled.
+    ///
+    /// Example:
+    /// ```rust,no_run
+    /// use notifier_hub::{bridge::SignalKind, notifier::NotifierHub};
+    ///
+    /// #[derive(Clone)]
+    /// struct Signal(SignalKind);
+    ///
+    /// impl From<SignalKind> for Signal {
+    ///     fn from(kind: SignalKind) -> Self {
+    ///         Signal(kind)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let mut shutdown = handle.subscribe(&"shutdown", 1);
+    /// let _guard = handle
+    ///     .bind_signals(&[(SignalKind::terminate(), "shutdown"), (SignalKind::hangup(), "reload")])
+    ///     .unwrap();
+    /// let Signal(_kind) = shutdown.recv().await.unwrap();
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    pub fn bind_signals(&self, bindings: &[(SignalKind, ChannelId)]) -> io::Result<IngestGuard>
+    where
+        M: From<SignalKind>,
+    {
+        let mut signals = Vec::with_capacity(bindings.len());
+        for (kind, id) in bindings {
+            let signal = tokio::signal::unix::signal(*kind)?;
+            let (kind, id) = (*kind, id.clone());
+            signals.push(
+                stream::unfold(signal, move |mut signal| {
+                    let id = id.clone();
+                    async move {
+                        signal.recv().await?;
+                        Some(((kind, id), signal))
+                    }
+                })
+                .boxed(),
+            );
+        }
+
+        let handle = self.clone();
+        let task = tokio::spawn(async move {
+            let mut published = 0;
+            let mut signals = stream::select_all(signals);
+            while let Some((kind, id)) = signals.next().await {
+                if let Ok(handler) = handle.clone_send(M::from(kind), &id) {
+                    let _ = handler.wait(None).await;
+                    published += 1;
+                }
+            }
+            published
+        });
+        Ok(IngestGuard { task })
+    }
+
+    /// Spawns a task reading frames from the reader, decoding them into messages with the decoder
+    /// and publishing them on the channel, as `ingest` does. A line-delimited or length-delimited
+    /// reader can be decoded with `JsonCodec` when the `json` feature is enabled.
+    /// The ingestion stops at the end of the reader, or at the first frame that can't be decoded.
+    pub fn ingest_reader<R, D>(&self, id: &ChannelId, reader: R, decoder: D) -> IngestGuard
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        D: Decoder<Item = M> + Send + 'static,
+        D::Error: Send,
+    {
+        let frames = FramedRead::new(reader, decoder)
+            .take_while(|frame| ready(frame.is_ok()))
+            .filter_map(|frame| ready(frame.ok()));
+        self.ingest(id, frames)
+    }
+
+    /// Subscribes to the channel and returns a std receiver of its messages, for the synchronous threads
+    /// that don't run an async runtime. The messages are relayed by a thread, the std channel having
+    /// `PIPE_CHANNEL_SIZE` places, so a slow consumer slows down the publishers as any subscriber does.
+    /// The subscription is removed once the std receiver is dropped and a message can't be relayed,
+    /// and the std channel is closed when the channel is over.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let receiver = handle.to_std_receiver(&"frames");
+    /// let consumer = std::thread::spawn(move || receiver.recv().unwrap());
+    ///
+    /// handle.clone_send(7, &"frames").unwrap().wait(None).await;
+    /// assert_eq!(consumer.join().unwrap(), 7);
+    /// # }
+    /// ```
+    pub fn to_std_receiver(&self, id: &ChannelId) -> mpsc::Receiver<M> {
+        let handle = self.clone();
+        let id = id.clone();
+        let mut receiver = self.subscribe(&id, PIPE_CHANNEL_SIZE);
+        let (sender, std_receiver) = mpsc::sync_channel(PIPE_CHANNEL_SIZE);
+        thread::spawn(move || {
+            while let Some(msg) = receiver.blocking_recv() {
+                if sender.send(msg).is_err() {
+                    break;
+                }
+            }
+            let _ = handle.unsubscribe(&id, &receiver);
+        });
+        std_receiver
+    }
+
+    /// Subscribes to the channel and spawns a task writing each message to the writer with the given encoder,
+    /// for instance a `LinesCodec` for a file or a `LengthDelimitedCodec` for a socket.
+    /// The writer is flushed each time there is no more message to write, and shut down at the end.
+    /// The subscription is removed when the task ends.
+    pub fn pipe_to_writer<W, E>(&self, id: &ChannelId, writer: W, encoder: E) -> PipeGuard<E::Error>
+    where
+        W: AsyncWrite + Send + Unpin + 'static,
+        E: Encoder<M> + Send + 'static,
+        E::Error: Send + 'static,
+    {
+        let handle = self.clone();
+        let id = id.clone();
+        let mut receiver = self.subscribe(&id, PIPE_CHANNEL_SIZE);
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut sink = FramedWrite::new(writer, encoder);
+            let mut written = 0;
+            let result = loop {
+                select! {
+                    msg = receiver.recv() => match msg {
+                        Some(msg) => {
+                            if let Err(e) = sink.feed(msg).await {
+                                break Err(e);
+                            }
+                            written += 1;
+                            if receiver.is_empty() {
+                                if let Err(e) = sink.flush().await {
+                                    break Err(e);
+                                }
+                            }
+                        }
+                        None => break Ok(()),
+                    },
+                    _ = &mut stopped => break Ok(()),
+                }
+            };
+            let _ = handle.unsubscribe(&id, &receiver);
+            result?;
+            // Once unsubscribed, the messages left in the buffer are the last ones to write
+            while let Ok(msg) = receiver.try_recv() {
+                sink.feed(msg).await?;
+                written += 1;
+            }
+            sink.close().await?;
+            Ok(written)
+        });
+        PipeGuard {
+            stop: Some(stop),
+            task,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotifierHub;
+    use futures::stream;
+    use tokio::{
+        io::AsyncReadExt,
+        time::{sleep, Duration},
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ingest_backpressure() {
+        let handle = NotifierHub::<u32, &'static str>::new().into_handle();
+        let mut receiver = handle.subscribe(&"channel1", 1);
+        let guard = handle.ingest(&"channel1", stream::iter(0..10));
+
+        sleep(Duration::from_millis(10)).await;
+        assert!(!guard.is_finished());
+        for i in 0..10 {
+            assert_eq!(receiver.recv().await.unwrap(), i);
+        }
+        assert_eq!(guard.join().await.unwrap(), 10);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ingest_stop() {
+        let handle = NotifierHub::<u32, &'static str>::new().into_handle();
+        let mut receiver = handle.subscribe(&"channel1", 10);
+        let ticks = stream::unfold(0, |i| async move {
+            sleep(Duration::from_secs(1)).await;
+            Some((i, i + 1))
+        });
+        let guard = handle.ingest(&"channel1", ticks);
+
+        assert_eq!(receiver.recv().await.unwrap(), 0);
+        guard.stop();
+        assert!(guard.join().await.is_err());
+        sleep(Duration::from_secs(5)).await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mirror_watch() {
+        let handle = NotifierHub::<u32, &'static str>::new().into_handle();
+        let mut receiver = handle.subscribe(&"channel1", 10);
+        let (sender, legacy) = watch::channel(0);
+        let guard = handle.mirror_watch(&"channel1", legacy);
+
+        assert_eq!(receiver.recv().await.unwrap(), 0);
+        sender.send(1).unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), 1);
+        drop(sender);
+        assert_eq!(guard.join().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_std_receiver() {
+        let handle = NotifierHub::<u32, &'static str>::new().into_handle();
+        let receiver = handle.to_std_receiver(&"channel1");
+        for i in 0..3 {
+            handle.clone_send(i, &"channel1").unwrap();
+        }
+        let received =
+            tokio::task::spawn_blocking(move || receiver.iter().take(3).collect::<Vec<_>>());
+        assert_eq!(received.await.unwrap(), vec![0, 1, 2]);
+
+        // The relay notices the drop of the std receiver at the next message
+        let mut departure = handle.get_destruction_waiter(&"channel1");
+        let _ = handle.clone_send(3, &"channel1");
+        assert!(departure.recv().await.is_some());
+        assert_eq!(handle.channel_number_subscriber(&"channel1"), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bind_signals() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Signal(SignalKind);
+
+        impl From<SignalKind> for Signal {
+            fn from(kind: SignalKind) -> Self {
+                Signal(kind)
+            }
+        }
+
+        let handle = NotifierHub::<Signal, &'static str>::new().into_handle();
+        let mut receiver = handle.subscribe(&"channel1", 10);
+        let guard = handle
+            .bind_signals(&[(SignalKind::user_defined1(), "channel1")])
+            .unwrap();
+
+        let status = std::process::Command::new("kill")
+            .args(["-USR1", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            Signal(SignalKind::user_defined1())
+        );
+        guard.stop();
+    }
+
+    #[tokio::test]
+    async fn test_pipe_to_writer() {
+        let handle = NotifierHub::<String, &'static str>::new().into_handle();
+        let (writer, mut reader) = tokio::io::duplex(1024);
+        let mut guard = handle.pipe_to_writer(&"channel1", writer, LinesCodec::new());
+
+        handle
+            .clone_send("first".to_string(), &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap();
+        handle
+            .clone_send("second".to_string(), &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap();
+        // The messages not yet received by the task are still written
+        guard.stop();
+        assert_eq!(guard.join().await.unwrap(), 2);
+        assert_eq!(handle.channel_number_subscriber(&"channel1"), 0);
+
+        let mut output = String::new();
+        reader.read_to_string(&mut output).await.unwrap();
+        assert_eq!(output, "first\nsecond\n");
+    }
+}
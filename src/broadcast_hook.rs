@@ -0,0 +1,139 @@
+use std::{hash::Hash, sync::Arc};
+
+use crate::{handle::HubHandle, notifier::NotifierHub};
+
+/// Called with the channel of a publish and its number of subscribers. The channel is `None` for the broadcasts
+/// over all the channels.
+pub(crate) type BroadcastHook<ChannelId> = Arc<dyn Fn(Option<&ChannelId>, usize) + Send + Sync>;
+
+/// The hooks called around the fanout of each publish.
+pub(crate) struct BroadcastHooks<ChannelId> {
+    pub(crate) before: Option<BroadcastHook<ChannelId>>,
+    pub(crate) after: Option<BroadcastHook<ChannelId>>,
+}
+
+impl<ChannelId> Default for BroadcastHooks<ChannelId> {
+    fn default() -> Self {
+        Self {
+            before: None,
+            after: None,
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Registers the callback invoked right before the fanout of each publish on a running channel,
+    /// with the channel and its number of subscribers, replacing the previous one.
+    /// The hooks are called inline by the publishing task, so they should be quick.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    /// use std::sync::{atomic::{AtomicUsize, Ordering}, Arc};
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let reached = Arc::new(AtomicUsize::new(0));
+    /// let counter = Arc::clone(&reached);
+    /// hub.set_before_broadcast(move |_channel, subscribers| {
+    ///     counter.fetch_add(subscribers, Ordering::Relaxed);
+    /// });
+    ///
+    /// let _receivers = [hub.subscribe(&"ticks", 10), hub.subscribe(&"ticks", 10)];
+    /// hub.clone_send(1, &"ticks").unwrap();
+    /// assert_eq!(reached.load(Ordering::Relaxed), 2);
+    /// ```
+    pub fn set_before_broadcast(
+        &mut self,
+        hook: impl Fn(Option<&ChannelId>, usize) + Send + Sync + 'static,
+    ) {
+        self.broadcast_hooks.before = Some(Arc::new(hook));
+    }
+
+    /// Registers the callback invoked right after the fanout of each publish on a running channel, replacing
+    /// the previous one. The messages have been put in the buffers that had some room, the other writings
+    /// are pending in the returned handler or in the sequencer of the channel.
+    pub fn set_after_broadcast(
+        &mut self,
+        hook: impl Fn(Option<&ChannelId>, usize) + Send + Sync + 'static,
+    ) {
+        self.broadcast_hooks.after = Some(Arc::new(hook));
+    }
+
+    /// Removes both broadcast hooks.
+    pub fn remove_broadcast_hooks(&mut self) {
+        self.broadcast_hooks = BroadcastHooks::default();
+    }
+
+    /// Runs the fanout of a publish between the broadcast hooks.
+    pub(crate) fn hooked<T>(
+        &self,
+        channel: Option<&ChannelId>,
+        subscribers: usize,
+        fanout: impl FnOnce() -> T,
+    ) -> T {
+        if let Some(before) = &self.broadcast_hooks.before {
+            before(channel, subscribers);
+        }
+        let result = fanout();
+        if let Some(after) = &self.broadcast_hooks.after {
+            after(channel, subscribers);
+        }
+        result
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::set_before_broadcast`.
+    pub fn set_before_broadcast(
+        &self,
+        hook: impl Fn(Option<&ChannelId>, usize) + Send + Sync + 'static,
+    ) {
+        self.with(|hub| hub.set_before_broadcast(hook))
+    }
+
+    /// See `NotifierHub::set_after_broadcast`.
+    pub fn set_after_broadcast(
+        &self,
+        hook: impl Fn(Option<&ChannelId>, usize) + Send + Sync + 'static,
+    ) {
+        self.with(|hub| hub.set_after_broadcast(hook))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_broadcast_hooks() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let before = Arc::clone(&calls);
+        hub.set_before_broadcast(move |channel, n| {
+            before.lock().unwrap().push(("before", channel.copied(), n))
+        });
+        let after = Arc::clone(&calls);
+        hub.set_after_broadcast(move |channel, n| {
+            after.lock().unwrap().push(("after", channel.copied(), n))
+        });
+
+        let _receiver1 = hub.subscribe(&"channel1", 10);
+        let _receiver2 = hub.subscribe(&"channel2", 10);
+        hub.clone_send(1, &"channel1").unwrap();
+        hub.broadcast_clone(2);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                ("before", Some("channel1"), 1),
+                ("after", Some("channel1"), 1),
+                ("before", None, 2),
+                ("after", None, 2),
+            ]
+        );
+
+        hub.remove_broadcast_hooks();
+        hub.clone_send(3, &"channel1").unwrap();
+        assert_eq!(calls.lock().unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::Mutex,
+};
+
+use crate::{
+    credits::Balance,
+    dedup::DedupWindow,
+    handler::HandlerCompletion,
+    initial_data::InitialData,
+    journal::Journal,
+    metadata::ChannelMetadata,
+    notifier::{
+        ChannelState, CreationSender, DestructionSender, MessageSender, NotifierHub, SenderList,
+        SmartChannelId,
+    },
+    park::ParkBuffer,
+    rate_limit::TokenBucket,
+    sequencer::Sequencer,
+    state_waiter::StateSender,
+    waiter::{CoalescedSender, Departure, EventSender},
+};
+
+type Events<M, ChannelId, E> = Vec<Box<dyn EventSender<M, ChannelId, E>>>;
+
+/// Everything the hub keeps for a channel, taken out of its maps at once so that renaming, forgetting
+/// and transferring a channel can't miss any of them. A map keyed by channel added to the hub
+/// belongs here, in `take_channel`, `insert_channel` and `is_known`.
+pub(crate) struct ChannelEntry<M, ChannelId> {
+    pub(crate) senders: Option<SenderList<M>>,
+    pub(crate) subscriber_limit: Option<usize>,
+    pub(crate) rate_limit: Option<Mutex<TokenBucket>>,
+    pub(crate) publish_grants: Option<HashSet<u64>>,
+    pub(crate) dedup_window: Option<Mutex<DedupWindow>>,
+    pub(crate) sequencer: Option<Sequencer>,
+    pub(crate) parked: Option<Mutex<ParkBuffer<M>>>,
+    pub(crate) metadata: Option<ChannelMetadata>,
+    pub(crate) initial_data: Option<InitialData<M>>,
+    pub(crate) journal: Option<Mutex<Journal<M>>>,
+    pub(crate) handler_completions: Option<Vec<HandlerCompletion>>,
+    pub(crate) format: Option<String>,
+    pub(crate) waiters: ChannelWaiters<M, ChannelId>,
+}
+
+/// The waiters of a channel and the last state they have been notified of. They observe the hub they have
+/// been taken from, so they stay in it when the subscribers of the channel are transferred to another hub.
+pub(crate) struct ChannelWaiters<M, ChannelId> {
+    creation_senders: Option<Vec<CreationSender>>,
+    destruction_senders: Option<Vec<DestructionSender<M>>>,
+    state_senders: Option<Vec<StateSender>>,
+    observed_state: Option<ChannelState>,
+    coalesced_waiters: Option<Vec<CoalescedSender>>,
+    creation_events: Option<Events<M, ChannelId, ()>>,
+    destruction_events: Option<Events<M, ChannelId, Departure<M>>>,
+}
+
+impl<M, ChannelId> Default for ChannelWaiters<M, ChannelId> {
+    fn default() -> Self {
+        Self {
+            creation_senders: None,
+            destruction_senders: None,
+            state_senders: None,
+            observed_state: None,
+            coalesced_waiters: None,
+            creation_events: None,
+            destruction_events: None,
+        }
+    }
+}
+
+impl<M, ChannelId> ChannelEntry<M, ChannelId> {
+    /// Returns an entry holding only the waiters.
+    pub(crate) fn of_waiters(waiters: ChannelWaiters<M, ChannelId>) -> Self {
+        Self {
+            senders: None,
+            subscriber_limit: None,
+            rate_limit: None,
+            publish_grants: None,
+            dedup_window: None,
+            sequencer: None,
+            parked: None,
+            metadata: None,
+            initial_data: None,
+            journal: None,
+            handler_completions: None,
+            format: None,
+            waiters,
+        }
+    }
+}
+
+/// Everything the hub keeps for a subscriber besides its channels, copied along when the subscriber
+/// is transferred to another hub. A map keyed by subscriber added to the hub belongs here,
+/// in `subscriber_entry`, `insert_subscriber` and `subscriber_removed`.
+pub(crate) struct SubscriberEntry<M> {
+    control_lane: Option<MessageSender<M>>,
+    inbox: Option<MessageSender<M>>,
+    credits: Option<Balance>,
+}
+
+/// Appends the values of a channel to the ones the map already has for it.
+fn extend<K: Eq + Hash + Clone, V: Default + Extend<V::Item> + IntoIterator>(
+    map: &mut HashMap<K, V>,
+    id: &K,
+    values: Option<V>,
+) {
+    if let Some(values) = values {
+        map.entry(id.clone()).or_default().extend(values);
+    }
+}
+
+/// Inserts the setting of a channel, unless the map already has one for it.
+fn keep<K: Eq + Hash + Clone, V>(map: &mut HashMap<K, V>, id: &K, value: Option<V>) {
+    if let Some(value) = value {
+        map.entry(id.clone()).or_insert(value);
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Returns `true` if the channel is known by the hub, with subscribers, waiters or settings.
+    pub(crate) fn is_known(&self, id: &ChannelId) -> bool {
+        self.senders.contains_key(id)
+            || self.subscriber_limits.contains_key(id)
+            || self.rate_limits.contains_key(id)
+            || self.publish_grants.contains_key(id)
+            || self.dedup_windows.contains_key(id)
+            || self.sequencers.contains_key(id)
+            || self.parked.contains_key(id)
+            || self.metadata.contains_key(id)
+            || self.initial_data.contains_key(id)
+            || self.journals.contains_key(id)
+            || self.handler_completions.contains_key(id)
+            || self.formats.channels.contains_key(id)
+            || self.creation_senders.contains_key(id)
+            || self.destruction_senders.contains_key(id)
+            || self.state_senders.contains_key(id)
+            || self.observed_states.contains_key(id)
+            || self.coalesced_waiters.contains_key(id)
+            || self.creation_events.contains_key(id)
+            || self.destruction_events.contains_key(id)
+    }
+
+    /// Removes the channel from all the maps of the hub and returns what they held for it.
+    /// The aliases and the groups referring to the channel are left to the caller.
+    pub(crate) fn take_channel(&mut self, id: &ChannelId) -> ChannelEntry<M, ChannelId> {
+        ChannelEntry {
+            senders: self.senders.remove(id),
+            subscriber_limit: self.subscriber_limits.remove(id),
+            rate_limit: self.rate_limits.remove(id),
+            publish_grants: self.publish_grants.remove(id),
+            dedup_window: self.dedup_windows.remove(id),
+            sequencer: self.sequencers.remove(id),
+            parked: self.parked.remove(id),
+            metadata: self.metadata.remove(id),
+            initial_data: self.initial_data.remove(id),
+            journal: self.journals.remove(id),
+            handler_completions: self.handler_completions.remove(id),
+            format: self.formats.channels.remove(id),
+            waiters: ChannelWaiters {
+                creation_senders: self.creation_senders.remove(id),
+                destruction_senders: self.destruction_senders.remove(id),
+                state_senders: self.state_senders.remove(id),
+                observed_state: self.observed_states.remove(id),
+                coalesced_waiters: self.coalesced_waiters.remove(id),
+                creation_events: self.creation_events.remove(id),
+                destruction_events: self.destruction_events.remove(id),
+            },
+        }
+    }
+
+    /// Puts the entry in the maps of the hub under the given id. The subscribers, waiters and grants are added
+    /// to the ones the channel already has, and the settings are only inserted where the channel has none.
+    pub(crate) fn insert_channel(&mut self, id: &ChannelId, entry: ChannelEntry<M, ChannelId>) {
+        extend(&mut self.senders, id, entry.senders);
+        keep(&mut self.subscriber_limits, id, entry.subscriber_limit);
+        keep(&mut self.rate_limits, id, entry.rate_limit);
+        extend(&mut self.publish_grants, id, entry.publish_grants);
+        keep(&mut self.dedup_windows, id, entry.dedup_window);
+        keep(&mut self.sequencers, id, entry.sequencer);
+        keep(&mut self.parked, id, entry.parked);
+        keep(&mut self.metadata, id, entry.metadata);
+        keep(&mut self.initial_data, id, entry.initial_data);
+        keep(&mut self.journals, id, entry.journal);
+        extend(&mut self.handler_completions, id, entry.handler_completions);
+        keep(&mut self.formats.channels, id, entry.format);
+        let waiters = entry.waiters;
+        extend(&mut self.creation_senders, id, waiters.creation_senders);
+        extend(
+            &mut self.destruction_senders,
+            id,
+            waiters.destruction_senders,
+        );
+        extend(&mut self.state_senders, id, waiters.state_senders);
+        keep(&mut self.observed_states, id, waiters.observed_state);
+        extend(&mut self.coalesced_waiters, id, waiters.coalesced_waiters);
+        extend(&mut self.creation_events, id, waiters.creation_events);
+        extend(&mut self.destruction_events, id, waiters.destruction_events);
+    }
+}
+
+impl<M: Clone, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Returns what the hub keeps for the subscriber, sharing it with the hub.
+    pub(crate) fn subscriber_entry(&self, id: &SmartChannelId) -> SubscriberEntry<M> {
+        SubscriberEntry {
+            control_lane: self.control_lanes.get(id).cloned(),
+            inbox: self.inboxes.get(id).cloned(),
+            credits: self.credits.get(id),
+        }
+    }
+
+    /// Binds the entry to the subscriber of the sender, and monitors it if the drop detection is enabled.
+    /// What the hub already keeps for the subscriber is not replaced.
+    pub(crate) fn insert_subscriber(
+        &mut self,
+        sender: &MessageSender<M>,
+        entry: SubscriberEntry<M>,
+    ) {
+        let id = sender.id();
+        keep(&mut self.control_lanes, id, entry.control_lane);
+        keep(&mut self.inboxes, id, entry.inbox);
+        if let Some(balance) = entry.credits {
+            if self.credits.get(id).is_none() {
+                self.credits.insert(*id, balance);
+            }
+        }
+        self.watch_drop(sender);
+    }
+}
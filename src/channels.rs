@@ -0,0 +1,79 @@
+/// Generates a channel id enum, so the channels of a project are checked at compile time instead of being loose strings,
+/// and matching on them is exhaustive. The enum derives `Clone`, `Copy`, `Debug`, `Eq`, `Hash`, `PartialEq`,
+/// implements `Display` with the name of each variant, and gets the `ALL` constant listing its variants
+/// and a `name` method.
+///
+/// `channels! { Config, Telemetry }` generates a public enum named `Channel`, a name, a visibility and attributes
+/// can also be given: `channels! { #[derive(PartialOrd)] pub(crate) enum Topic { Config, Telemetry } }`.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::{channels, notifier::NotifierHub};
+///
+/// channels! { Config, Telemetry, Shutdown }
+///
+/// let mut hub = NotifierHub::new();
+/// let mut receiver = hub.subscribe(&Channel::Config, 10);
+/// hub.clone_send("reload", &Channel::Config).unwrap();
+/// assert_eq!(receiver.try_recv().unwrap(), "reload");
+///
+/// assert_eq!(Channel::ALL.len(), 3);
+/// assert_eq!(Channel::Telemetry.to_string(), "Telemetry");
+/// ```
+#[macro_export]
+macro_rules! channels {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident { $($variant:ident),+ $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        $vis enum $name {
+            $($variant),+
+        }
+
+        impl $name {
+            /// All the channels, in declaration order.
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+
+            /// Returns the name of the channel.
+            pub const fn name(&self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant)),+
+                }
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+    };
+    ($($variant:ident),+ $(,)?) => {
+        $crate::channels! { pub enum Channel { $($variant),+ } }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notifier::NotifierHub;
+
+    channels! {
+        /// The channels of the tests.
+        #[derive(PartialOrd, Ord)]
+        pub(crate) enum Topic { Orders, Payments, }
+    }
+
+    #[tokio::test]
+    async fn test_channels_macro() {
+        let mut hub: NotifierHub<u32, Topic> = NotifierHub::new();
+        let mut receiver = hub.subscribe_multiple(Topic::ALL, 10);
+        for (i, topic) in Topic::ALL.iter().enumerate() {
+            hub.clone_send(i as u32, topic).unwrap();
+        }
+        assert_eq!(receiver.try_recv().unwrap(), 0);
+        assert_eq!(receiver.try_recv().unwrap(), 1);
+
+        assert!(Topic::Orders < Topic::Payments);
+        assert_eq!(Topic::Payments.name(), "Payments");
+        assert_eq!(format!("{}", Topic::Orders), "Orders");
+    }
+}
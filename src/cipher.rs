@@ -0,0 +1,172 @@
+use std::io;
+
+use crate::codec::Codec;
+
+/// Encrypts and authenticates the frames of a codec wrapped in `Encrypted`.
+/// `open` must fail with an `InvalidData` error on a frame that has been altered or sealed with another key,
+/// the bridge then stopping at that frame as it does for the frames that can't be decoded.
+pub trait FrameCipher {
+    /// Encrypts an encoded message.
+    fn seal(&mut self, frame: &[u8]) -> io::Result<Vec<u8>>;
+    /// Checks and decrypts a sealed frame.
+    fn open(&mut self, frame: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+/// Wraps a codec to seal its frames with a `FrameCipher`, so the bridges between hubs crossing an untrusted network
+/// do not expose their messages. A `Compressed` codec should be wrapped, not wrap it, as the sealed frames do not compress.
+/// Like the other codecs, it is delimited in a byte stream by a `FrameCodec`.
+///
+/// Example, with a toy cipher that must not be used for real:
+/// ```rust
+/// use notifier_hub::{
+///     cipher::{Encrypted, FrameCipher},
+///     codec::Codec,
+/// };
+/// use std::io;
+///
+/// struct Utf8;
+///
+/// impl Codec<String> for Utf8 {
+///     fn encode(&mut self, msg: &String) -> io::Result<Vec<u8>> {
+///         Ok(msg.clone().into_bytes())
+///     }
+///
+///     fn decode(&mut self, frame: &[u8]) -> io::Result<String> {
+///         String::from_utf8(frame.to_vec()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+///     }
+/// }
+///
+/// /// Xors the frame with the key and appends a checksum of the key and the frame.
+/// struct Xor(u8);
+///
+/// impl Xor {
+///     fn checksum(&self, frame: &[u8]) -> u8 {
+///         frame.iter().fold(self.0, |sum, byte| sum.rotate_left(1) ^ byte)
+///     }
+/// }
+///
+/// impl FrameCipher for Xor {
+///     fn seal(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+///         let mut sealed: Vec<u8> = frame.iter().map(|byte| byte ^ self.0).collect();
+///         sealed.push(self.checksum(frame));
+///         Ok(sealed)
+///     }
+///
+///     fn open(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+///         let (checksum, sealed) = frame.split_last().ok_or(io::ErrorKind::InvalidData)?;
+///         let opened: Vec<u8> = sealed.iter().map(|byte| byte ^ self.0).collect();
+///         match self.checksum(&opened) == *checksum {
+///             true => Ok(opened),
+///             false => Err(io::ErrorKind::InvalidData.into()),
+///         }
+///     }
+/// }
+///
+/// let mut codec = Encrypted::new(Utf8, Xor(0x5a));
+/// let frame = codec.encode(&"secret".to_string()).unwrap();
+/// assert_ne!(&frame[..6], b"secret");
+/// assert_eq!(codec.decode(&frame).unwrap(), "secret");
+/// assert!(Encrypted::new(Utf8, Xor(0x42)).decode(&frame).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Encrypted<C, K> {
+    codec: C,
+    cipher: K,
+    /// The buffer the messages are encoded in before being sealed.
+    buffer: Vec<u8>,
+}
+
+impl<C, K> Encrypted<C, K> {
+    /// Returns the codec sealing the frames of `codec` with the cipher.
+    pub fn new(codec: C, cipher: K) -> Self {
+        Self {
+            codec,
+            cipher,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<M, C: Codec<M>, K: FrameCipher> Codec<M> for Encrypted<C, K> {
+    fn encode(&mut self, msg: &M) -> io::Result<Vec<u8>> {
+        self.buffer.clear();
+        self.codec.encode_into(msg, &mut self.buffer)?;
+        self.cipher.seal(&self.buffer)
+    }
+
+    fn decode(&mut self, frame: &[u8]) -> io::Result<M> {
+        let frame = self.cipher.open(frame)?;
+        self.codec.decode(&frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bridge::LengthDelimitedCodec,
+        codec::{FrameCodec, Framing},
+        notifier::NotifierHub,
+    };
+
+    struct Bytes;
+
+    impl Codec<Vec<u8>> for Bytes {
+        fn encode(&mut self, msg: &Vec<u8>) -> io::Result<Vec<u8>> {
+            Ok(msg.clone())
+        }
+
+        fn decode(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+            Ok(frame.to_vec())
+        }
+    }
+
+    /// Prefixes the frame with the key, and reverses it.
+    struct Reversed(u8);
+
+    impl FrameCipher for Reversed {
+        fn seal(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+            Ok([self.0].iter().chain(frame.iter().rev()).copied().collect())
+        }
+
+        fn open(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+            match frame.split_first() {
+                Some((key, sealed)) if *key == self.0 => Ok(sealed.iter().rev().copied().collect()),
+                _ => Err(io::ErrorKind::InvalidData.into()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_bridge() {
+        let source = NotifierHub::<Vec<u8>, &'static str>::new().into_handle();
+        let destination = NotifierHub::<Vec<u8>, &'static str>::new().into_handle();
+        let mut receiver = destination.subscribe(&"channel1", 10);
+        let (writer, reader) = tokio::io::duplex(64);
+        let codec = |key| {
+            let framing = Framing::LengthDelimited(LengthDelimitedCodec::new());
+            FrameCodec::with_framing(framing, Encrypted::new(Bytes, Reversed(key)))
+        };
+
+        let _pipe = source.pipe_to_writer(&"channel1", writer, codec(1));
+        let ingest = destination.ingest_reader(&"channel1", reader, codec(1));
+        source
+            .clone_send(vec![1, 2, 3], &"channel1")
+            .unwrap()
+            .wait(None)
+            .await;
+        assert_eq!(receiver.recv().await.unwrap(), vec![1, 2, 3]);
+        drop(ingest);
+
+        // A peer with another key stops at the first frame
+        let (writer, reader) = tokio::io::duplex(64);
+        let _pipe = source.pipe_to_writer(&"channel2", writer, codec(1));
+        let ingest = destination.ingest_reader(&"channel2", reader, codec(2));
+        source
+            .clone_send(vec![4], &"channel2")
+            .unwrap()
+            .wait(None)
+            .await;
+        assert_eq!(ingest.join().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,262 @@
+use smart_channel::channel;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+use tokio::time::{Duration, Instant};
+
+use crate::{
+    clock::SharedClock,
+    notifier::{NotifierHub, Receiver, Sender, SmartChannelId, NOTIFIER_CHANNEL_SIZE},
+    sync::lock,
+};
+
+/// Defines when the circuit of a subscriber opens.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BreakerPolicy {
+    /// Number of consecutive failed writings opening the circuit of a subscriber (at least 1).
+    pub threshold: usize,
+    /// How long nothing is written to the subscriber once its circuit is open.
+    /// After it, the next writing is attempted: a success closes the circuit, a failure opens it again.
+    pub cooldown: Duration,
+}
+
+/// Emitted each time the circuit of a subscriber changes.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum BreakerEvent {
+    /// The subscriber failed too many times, the writings to it are skipped for a cooldown.
+    Opened(SmartChannelId),
+    /// A writing to the subscriber succeeded after its cooldown.
+    Closed(SmartChannelId),
+}
+
+/// Type alias for the receivers returned by the get_breaker_waiter method of the Hub
+pub type BreakerWaiter = Receiver<BreakerEvent, SmartChannelId>;
+
+/// The health of a subscriber that failed at least once since its last success.
+#[derive(Default)]
+struct Health {
+    /// Consecutive failures.
+    failures: usize,
+    /// Set while the circuit is open, until the end of the cooldown.
+    open_until: Option<Instant>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    policy: Option<BreakerPolicy>,
+    subscribers: HashMap<SmartChannelId, Health>,
+    waiters: Vec<Sender<BreakerEvent, SmartChannelId>>,
+    /// The clock of the hub, the tokio time if it has not been replaced.
+    clock: Option<SharedClock>,
+}
+
+impl BreakerState {
+    fn now(&self) -> Instant {
+        self.clock
+            .as_ref()
+            .map_or_else(Instant::now, |clock| clock.now())
+    }
+}
+
+/// Tracks the failed writings of each subscriber. It is shared with the writing handlers,
+/// so the writings that are completed after the publish are also taken into account.
+#[derive(Default)]
+pub(crate) struct CircuitBreaker {
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// Replaces the clock measuring the cooldowns.
+    pub(crate) fn set_clock(&self, clock: SharedClock) {
+        lock(&self.state).clock = Some(clock);
+    }
+
+    /// Returns true if a policy is set.
+    pub(crate) fn is_enabled(&self) -> bool {
+        lock(&self.state).policy.is_some()
+    }
+
+    /// Returns false if the circuit of the subscriber is open.
+    pub(crate) fn allows(&self, id: &SmartChannelId) -> bool {
+        let state = lock(&self.state);
+        match state.subscribers.get(id).and_then(|h| h.open_until) {
+            Some(open_until) => state.now() >= open_until,
+            None => true,
+        }
+    }
+
+    /// Records the outcome of a writing to the subscriber, and notifies the waiters if its circuit changed.
+    pub(crate) fn record(&self, id: SmartChannelId, success: bool) {
+        let mut state = lock(&self.state);
+        let Some(policy) = state.policy else {
+            return;
+        };
+        let event = if success {
+            match state.subscribers.remove(&id) {
+                Some(Health {
+                    open_until: Some(_),
+                    ..
+                }) => Some(BreakerEvent::Closed(id)),
+                _ => None,
+            }
+        } else {
+            let now = state.now();
+            let health = state.subscribers.entry(id).or_default();
+            health.failures += 1;
+            match health.open_until {
+                // Writings started before the opening
+                Some(open_until) if now < open_until => None,
+                _ if health.failures >= policy.threshold.max(1) => {
+                    health.open_until = Some(now + policy.cooldown);
+                    Some(BreakerEvent::Opened(id))
+                }
+                _ => None,
+            }
+        };
+        if let Some(event) = event {
+            state.waiters.retain(|w| !w.is_closed());
+            for waiter in &state.waiters {
+                let _ = waiter.try_send(event); // A waiter that does not read its events misses some
+            }
+        }
+    }
+
+    /// Forgets the subscriber, when it unsubscribed.
+    pub(crate) fn forget(&self, id: &SmartChannelId) {
+        lock(&self.state).subscribers.remove(id);
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Sets the circuit breaker policy of the hub, `None` disables it.
+    /// When enabled, the subscribers failing to receive too many publishes in a row are skipped for a cooldown,
+    /// and their writings are reported as `FailureKind::CircuitOpen`. A writing fails when the subscriber is dropped,
+    /// when it panics, when it times out in `wait`, or when the buffer is full in `DeliveryMode::Deterministic`.
+    /// Changing the policy resets the health of all the subscribers.
+    pub fn set_circuit_breaker(&mut self, policy: Option<BreakerPolicy>) {
+        let mut state = lock(&self.breaker.state);
+        state.policy = policy;
+        state.subscribers.clear();
+    }
+
+    /// Returns the circuit breaker policy of the hub.
+    pub fn circuit_breaker(&self) -> Option<BreakerPolicy> {
+        lock(&self.breaker.state).policy
+    }
+
+    /// Returns true if the circuit of the subscriber is open.
+    pub fn is_circuit_open(&self, id: &SmartChannelId) -> bool {
+        !self.breaker.allows(id)
+    }
+
+    /// Returns a waiter notified each time the circuit of a subscriber opens or closes.
+    pub fn get_breaker_waiter(&mut self) -> BreakerWaiter {
+        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, self.get_new_id());
+        lock(&self.breaker.state).waiters.push(sender);
+        receiver
+    }
+
+    /// Returns the breaker to give to the writing handlers of the publishes, if it is enabled.
+    pub(crate) fn active_breaker(&self) -> Option<Arc<CircuitBreaker>> {
+        self.breaker.is_enabled().then(|| Arc::clone(&self.breaker))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::{NotifierError, SendFailure},
+        error_hook::FailureKind,
+        writing_handler::DeliveryMode,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_circuit_opens_and_closes() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_delivery_mode(DeliveryMode::Deterministic);
+        hub.set_circuit_breaker(Some(BreakerPolicy {
+            threshold: 2,
+            cooldown: Duration::from_secs(1),
+        }));
+        let mut events = hub.get_breaker_waiter();
+        let mut wedged = hub.subscribe(&"channel1", 1);
+        let mut healthy = hub.subscribe(&"channel1", 100);
+
+        hub.clone_send(0, &"channel1").unwrap();
+        for i in 1..=2 {
+            let result = hub
+                .clone_send(i, &"channel1")
+                .unwrap()
+                .wait(None)
+                .await
+                .into_result();
+            assert!(matches!(result, Err(NotifierError::WritingSendError(_))));
+        }
+        assert!(hub.is_circuit_open(&wedged.id()));
+        assert!(!hub.is_circuit_open(&healthy.id()));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            BreakerEvent::Opened(wedged.id())
+        );
+
+        let result = hub
+            .clone_send(3, &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result();
+        match result {
+            Err(NotifierError::WritingSendError(errors)) => {
+                assert!(matches!(
+                    errors[..],
+                    [SendFailure { subscriber: Some(id), kind: FailureKind::CircuitOpen, .. }] if id == wedged.id()
+                ))
+            }
+            _ => panic!("The wedged subscriber should have been skipped"),
+        }
+
+        assert_eq!(wedged.recv().await.unwrap(), 0);
+        tokio::time::advance(Duration::from_secs(1)).await;
+        hub.clone_send(4, &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap();
+        assert_eq!(
+            events.try_recv().unwrap(),
+            BreakerEvent::Closed(wedged.id())
+        );
+        assert_eq!(wedged.recv().await.unwrap(), 4);
+        for i in 0..=4 {
+            assert_eq!(healthy.recv().await.unwrap(), i);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeouts_open_the_circuit() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_circuit_breaker(Some(BreakerPolicy {
+            threshold: 1,
+            cooldown: Duration::from_secs(10),
+        }));
+        let wedged = hub.subscribe(&"channel1", 1);
+
+        hub.clone_send(0, &"channel1").unwrap();
+        let handler = hub.clone_send(1, &"channel1").unwrap();
+        assert_eq!(handler.pending(), 1);
+        assert!(!handler.wait(Some(Duration::from_millis(10))).await.all_ok());
+        assert!(hub.is_circuit_open(&wedged.id()));
+
+        // Nothing is spawned anymore for the wedged subscriber
+        let handler = hub.clone_send(2, &"channel1").unwrap();
+        assert_eq!(handler.pending(), 0);
+
+        hub.set_circuit_breaker(None);
+        assert!(!hub.is_circuit_open(&wedged.id()));
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    fmt,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+use tokio::time::{Duration, Instant};
+
+use crate::{
+    handle::HubHandle,
+    notifier::NotifierHub,
+    sync::{get_mut, lock},
+};
+
+/// The source of the current time of a hub, read by the rate limits, the circuit breaker cooldowns,
+/// the deduplication windows and the audit log.
+/// The timers of the writings, such as the send timeout, are tokio timers, which follow `tokio::time::pause`.
+pub trait Clock: Send + Sync + 'static {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+}
+
+/// The clock used by default, reading the tokio time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when it is told to, for testing the time based features without sleeping.
+/// Its clones share the same time.
+#[derive(Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl MockClock {
+    /// Returns a clock stopped at the current instant.
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        *lock(&self.now) += duration;
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for MockClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockClock")
+            .field("now", &self.now())
+            .finish()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *lock(&self.now)
+    }
+}
+
+/// The clock of a hub, shared with its circuit breaker.
+pub(crate) type SharedClock = Arc<dyn Clock>;
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Replaces the clock of the hub. The rate limits keep the budget they had, the time elapsed
+    /// being measured with the new clock from now on.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{clock::MockClock, notifier::NotifierHub};
+    /// use std::time::Duration;
+    ///
+    /// let clock = MockClock::new();
+    /// let mut hub = NotifierHub::new();
+    /// hub.set_clock(clock.clone());
+    /// hub.set_rate_limit(&"events", 1, 1);
+    /// let _receiver = hub.subscribe(&"events", 10);
+    ///
+    /// hub.clone_send(1, &"events").unwrap();
+    /// assert!(hub.clone_send(2, &"events").is_err());
+    /// clock.advance(Duration::from_secs(1));
+    /// assert!(hub.clone_send(3, &"events").is_ok());
+    /// ```
+    pub fn set_clock(&mut self, clock: impl Clock) {
+        let clock: SharedClock = Arc::new(clock);
+        let now = clock.now();
+        for bucket in self
+            .rate_limits
+            .values_mut()
+            .chain(&mut self.hub_rate_limit)
+        {
+            get_mut(bucket).rewind(now);
+        }
+        self.breaker.set_clock(Arc::clone(&clock));
+        self.clock = clock;
+    }
+
+    /// Returns the current instant of the clock of the hub.
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::set_clock`.
+    pub fn set_clock(&self, clock: impl Clock) {
+        self.with(|hub| hub.set_clock(clock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_breaker::BreakerPolicy;
+
+    #[tokio::test]
+    async fn test_mock_clock() {
+        let clock = MockClock::new();
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_clock(clock.clone());
+        hub.set_circuit_breaker(Some(BreakerPolicy {
+            threshold: 1,
+            cooldown: Duration::from_secs(5),
+        }));
+        assert_eq!(hub.now(), clock.now());
+
+        let closed = hub.subscribe(&"channel1", 10);
+        let subscriber = closed.id();
+        drop(closed);
+        let _ = hub.clone_send(1, &"channel1");
+        assert!(!hub.breaker.allows(&subscriber));
+
+        clock.advance(Duration::from_secs(4));
+        assert!(!hub.breaker.allows(&subscriber));
+        clock.advance(Duration::from_secs(1));
+        assert!(hub.breaker.allows(&subscriber));
+    }
+}
@@ -1,4 +1,5 @@
 /// This trait should implement message if you want to use shutdown-kind methods in the hub.
+/// When the close message depends on the channel, use `NotifierHub::shutdown_with_factory` instead.
 pub trait ClosableMessage {
     /// Returns the designated close message for this type.
     fn get_close_message() -> Self;
@@ -0,0 +1,281 @@
+use std::{io, marker::PhantomData};
+use tokio_util::{
+    bytes::{BufMut, BytesMut},
+    codec::{Decoder, Encoder, LengthDelimitedCodec, LinesCodec},
+};
+
+/// Turns a message into the bytes of a frame and back.
+/// Wrapped in a `FrameCodec`, it can be given to the bridges of the hub.
+pub trait Codec<M> {
+    /// Encodes the message into a frame.
+    fn encode(&mut self, msg: &M) -> io::Result<Vec<u8>>;
+    /// Decodes a frame into a message.
+    fn decode(&mut self, frame: &[u8]) -> io::Result<M>;
+
+    /// Encodes the message at the end of the buffer, which `FrameCodec` reuses for all the frames.
+    /// The default implementation copies the frame returned by `encode`, the codecs able to write
+    /// into a buffer should override it to avoid one allocation per message.
+    fn encode_into(&mut self, msg: &M, buffer: &mut Vec<u8>) -> io::Result<()> {
+        buffer.extend_from_slice(&self.encode(msg)?);
+        Ok(())
+    }
+}
+
+/// How the frames are delimited in a byte stream.
+#[derive(Debug)]
+pub enum Framing {
+    /// Each frame is a line ending with `\n`. Only suitable for textual codecs.
+    Lines(LinesCodec),
+    /// Each frame is prefixed by its length.
+    LengthDelimited(LengthDelimitedCodec),
+}
+
+/// Delimits the frames produced by a `Codec`, so it can be given to `HubHandle::ingest_reader`
+/// and `HubHandle::pipe_to_writer`.
+/// The messages are encoded in a buffer kept from one frame to the next, and the length-delimited frames
+/// are decoded in place, so the frames are not allocated one by one.
+#[derive(Debug)]
+pub struct FrameCodec<C, M> {
+    framing: Framing,
+    codec: C,
+    /// The buffer the messages are encoded in, emptied after each frame.
+    buffer: Vec<u8>,
+    _message: PhantomData<fn() -> M>,
+}
+
+impl<C, M> FrameCodec<C, M> {
+    /// Returns a codec delimiting the frames of `codec` with the given framing.
+    pub fn with_framing(framing: Framing, codec: C) -> Self {
+        Self {
+            framing,
+            codec,
+            buffer: Vec::new(),
+            _message: PhantomData,
+        }
+    }
+}
+
+impl<C: Codec<M>, M> Decoder for FrameCodec<C, M> {
+    type Item = M;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<M>, io::Error> {
+        match &mut self.framing {
+            Framing::Lines(codec) => match codec
+                .decode(src)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            {
+                Some(line) => Ok(Some(self.codec.decode(line.as_bytes())?)),
+                None => Ok(None),
+            },
+            // The frame is split from the source buffer, without copy
+            Framing::LengthDelimited(codec) => match codec.decode(src)? {
+                Some(frame) => Ok(Some(self.codec.decode(&frame)?)),
+                None => Ok(None),
+            },
+        }
+    }
+}
+
+impl<C: Codec<M>, M> Encoder<M> for FrameCodec<C, M> {
+    type Error = io::Error;
+
+    fn encode(&mut self, msg: M, dst: &mut BytesMut) -> Result<(), io::Error> {
+        self.buffer.clear();
+        self.codec.encode_into(&msg, &mut self.buffer)?;
+        let frame = self.buffer.as_slice();
+        match &mut self.framing {
+            Framing::Lines(_) => {
+                dst.reserve(frame.len() + 1);
+                dst.put_slice(frame);
+                dst.put_u8(b'\n');
+                Ok(())
+            }
+            Framing::LengthDelimited(codec) => codec.encode(frame, dst),
+        }
+    }
+}
+
+/// Encodes the messages in JSON with serde. Available with the `json` feature.
+#[cfg(feature = "json")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl<M: serde::Serialize + serde::de::DeserializeOwned> Codec<M> for Json {
+    fn encode(&mut self, msg: &M) -> io::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(msg)?)
+    }
+
+    fn decode(&mut self, frame: &[u8]) -> io::Result<M> {
+        Ok(serde_json::from_slice(frame)?)
+    }
+
+    fn encode_into(&mut self, msg: &M, buffer: &mut Vec<u8>) -> io::Result<()> {
+        Ok(serde_json::to_writer(buffer, msg)?)
+    }
+}
+
+/// Encodes and decodes messages as JSON frames. Available with the `json` feature.
+#[cfg(feature = "json")]
+pub type JsonCodec<M> = FrameCodec<Json, M>;
+
+#[cfg(feature = "json")]
+impl<M> FrameCodec<Json, M> {
+    /// Returns a codec reading and writing one JSON document per line.
+    pub fn lines() -> Self {
+        Self::with_framing(Framing::Lines(LinesCodec::new()), Json)
+    }
+
+    /// Returns a codec reading and writing JSON documents prefixed by their length.
+    pub fn length_delimited() -> Self {
+        Self::with_framing(Framing::LengthDelimited(LengthDelimitedCodec::new()), Json)
+    }
+}
+
+/// Encodes the messages in protobuf with prost. Available with the `prost` feature.
+#[cfg(feature = "prost")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Prost;
+
+#[cfg(feature = "prost")]
+impl<M: prost::Message + Default> Codec<M> for Prost {
+    fn encode(&mut self, msg: &M) -> io::Result<Vec<u8>> {
+        Ok(msg.encode_to_vec())
+    }
+
+    fn decode(&mut self, frame: &[u8]) -> io::Result<M> {
+        M::decode(frame).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn encode_into(&mut self, msg: &M, buffer: &mut Vec<u8>) -> io::Result<()> {
+        msg.encode(buffer)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Encodes and decodes messages as length-delimited protobuf frames. Available with the `prost` feature.
+#[cfg(feature = "prost")]
+pub type ProstCodec<M> = FrameCodec<Prost, M>;
+
+#[cfg(feature = "prost")]
+impl<M> FrameCodec<Prost, M> {
+    /// Returns a codec reading and writing protobuf messages prefixed by their length.
+    /// Protobuf being binary, its frames can't be delimited by lines.
+    pub fn length_delimited() -> Self {
+        Self::with_framing(Framing::LengthDelimited(LengthDelimitedCodec::new()), Prost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "json", feature = "prost"))]
+    use crate::notifier::NotifierHub;
+
+    #[test]
+    fn test_frame_buffer_is_reused() {
+        struct Utf8;
+
+        impl Codec<String> for Utf8 {
+            fn encode(&mut self, msg: &String) -> io::Result<Vec<u8>> {
+                Ok(msg.clone().into_bytes())
+            }
+
+            fn decode(&mut self, frame: &[u8]) -> io::Result<String> {
+                String::from_utf8(frame.to_vec())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+
+            fn encode_into(&mut self, msg: &String, buffer: &mut Vec<u8>) -> io::Result<()> {
+                buffer.extend_from_slice(msg.as_bytes());
+                Ok(())
+            }
+        }
+
+        let mut codec =
+            FrameCodec::with_framing(Framing::LengthDelimited(LengthDelimitedCodec::new()), Utf8);
+        let mut frames = BytesMut::new();
+        codec
+            .encode("a long first message".to_string(), &mut frames)
+            .unwrap();
+        let buffer = codec.buffer.as_ptr();
+        codec.encode("second".to_string(), &mut frames).unwrap();
+        assert_eq!(codec.buffer.as_ptr(), buffer);
+
+        assert_eq!(
+            codec.decode(&mut frames).unwrap().unwrap(),
+            "a long first message"
+        );
+        assert_eq!(codec.decode(&mut frames).unwrap().unwrap(), "second");
+        assert!(codec.decode(&mut frames).unwrap().is_none());
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_ingest_json_lines() {
+        let handle = NotifierHub::<Vec<u32>, &'static str>::new().into_handle();
+        let mut receiver = handle.subscribe(&"channel1", 10);
+        let input: &[u8] = b"[1, 2]\n[3]\nnot json\n[4]\n";
+
+        let guard = handle.ingest_reader(&"channel1", input, JsonCodec::lines());
+        assert_eq!(guard.join().await.unwrap(), 2);
+        assert_eq!(receiver.recv().await.unwrap(), vec![1, 2]);
+        assert_eq!(receiver.recv().await.unwrap(), vec![3]);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_length_delimited_round_trip() {
+        let source = NotifierHub::<String, &'static str>::new().into_handle();
+        let destination = NotifierHub::<String, &'static str>::new().into_handle();
+        let mut receiver = destination.subscribe(&"channel1", 10);
+        let (writer, reader) = tokio::io::duplex(1024);
+
+        let mut pipe = source.pipe_to_writer(&"channel1", writer, JsonCodec::length_delimited());
+        let ingest = destination.ingest_reader(&"channel1", reader, JsonCodec::length_delimited());
+        source
+            .clone_send("Hello".to_string(), &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), "Hello");
+
+        pipe.stop();
+        assert_eq!(pipe.join().await.unwrap(), 1);
+        assert_eq!(ingest.join().await.unwrap(), 1);
+    }
+
+    #[cfg(feature = "prost")]
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Reading {
+        #[prost(string, tag = "1")]
+        sensor: String,
+        #[prost(double, tag = "2")]
+        value: f64,
+    }
+
+    #[cfg(feature = "prost")]
+    #[tokio::test]
+    async fn test_prost_round_trip() {
+        let source = NotifierHub::<Reading, &'static str>::new().into_handle();
+        let destination = NotifierHub::<Reading, &'static str>::new().into_handle();
+        let mut receiver = destination.subscribe(&"readings", 10);
+        let (writer, reader) = tokio::io::duplex(1024);
+
+        let mut pipe = source.pipe_to_writer(&"readings", writer, ProstCodec::length_delimited());
+        let ingest = destination.ingest_reader(&"readings", reader, ProstCodec::length_delimited());
+        let reading = Reading {
+            sensor: "thermometer\n1".to_string(),
+            value: 21.5,
+        };
+        source.clone_send(reading.clone(), &"readings").unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), reading);
+
+        pipe.stop();
+        assert_eq!(pipe.join().await.unwrap(), 1);
+        assert_eq!(ingest.join().await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,284 @@
+use std::{future::Future, pin::Pin};
+use tokio::time::{error::Elapsed, timeout, timeout_at, Duration, Instant};
+
+use crate::notifier::{MessageReceiver, SmartChannelId};
+
+/// A receiver of a hub, possibly wrapped in some adapters of `SubscriptionExt`.
+/// The adapters keep the `MessageReceiver` they are built on, so it can still be given to the methods of the hub
+/// such as `unsubscribe` or `is_subscribed`.
+pub trait Subscription {
+    /// The messages of the channel.
+    type Msg;
+    /// The items produced by the adapters.
+    type Item;
+
+    /// Returns the receiver of the subscription.
+    fn receiver(&self) -> &MessageReceiver<Self::Msg>;
+
+    /// Returns the id of the subscriber.
+    fn id(&self) -> SmartChannelId {
+        self.receiver().id()
+    }
+
+    /// Receives the next item, `None` once the subscription is over.
+    fn recv(&mut self) -> impl Future<Output = Option<Self::Item>> + Send;
+}
+
+impl<M: Send> Subscription for MessageReceiver<M> {
+    type Msg = M;
+    type Item = M;
+
+    fn receiver(&self) -> &MessageReceiver<M> {
+        self
+    }
+
+    async fn recv(&mut self) -> Option<M> {
+        (**self).recv().await
+    }
+}
+
+/// Adapters of the subscriptions, see `Subscription`.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::{combinators::{Subscription, SubscriptionExt}, notifier::NotifierHub};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut hub = NotifierHub::new();
+/// let mut evens = hub.subscribe(&"numbers", 10).filter(|n: &u32| n % 2 == 0).map(|n| n * 10);
+/// for n in 1..=4 {
+///     hub.clone_send(n, &"numbers").unwrap();
+/// }
+/// assert_eq!(evens.recv().await, Some(20));
+/// assert_eq!(evens.recv().await, Some(40));
+///
+/// hub.unsubscribe(&"numbers", evens.receiver()).unwrap();
+/// assert_eq!(evens.recv().await, None);
+/// # }
+/// ```
+pub trait SubscriptionExt: Subscription + Sized {
+    /// Only yields the items matching the predicate.
+    fn filter<F>(self, predicate: F) -> Filter<Self, F>
+    where
+        F: FnMut(&Self::Item) -> bool,
+    {
+        Filter {
+            inner: self,
+            predicate,
+        }
+    }
+
+    /// Transforms each item.
+    fn map<F, T>(self, f: F) -> Map<Self, F>
+    where
+        F: FnMut(Self::Item) -> T,
+    {
+        Map { inner: self, f }
+    }
+
+    /// Ends the subscription once the cancellation future is over, even if some messages are still waiting.
+    fn take_until<C>(self, cancellation: C) -> TakeUntil<Self>
+    where
+        C: Future<Output = ()> + Send + 'static,
+    {
+        TakeUntil {
+            inner: self,
+            cancellation: Some(Box::pin(cancellation)),
+        }
+    }
+
+    /// Yields an `Elapsed` error each time no item is received within the duration, the subscription goes on.
+    fn timeout_per_message(self, duration: Duration) -> Timeout<Self> {
+        Timeout {
+            inner: self,
+            duration,
+        }
+    }
+
+    /// Groups the items by chunks of at most `size` items, a chunk being yielded at the latest `max_wait`
+    /// after its first item.
+    fn chunked(self, size: usize, max_wait: Duration) -> Chunked<Self> {
+        Chunked {
+            inner: self,
+            size: size.max(1),
+            max_wait,
+        }
+    }
+}
+
+impl<S: Subscription> SubscriptionExt for S {}
+
+/// See `SubscriptionExt::filter`.
+pub struct Filter<S, F> {
+    inner: S,
+    predicate: F,
+}
+
+impl<S, F> Subscription for Filter<S, F>
+where
+    S: Subscription + Send,
+    S::Item: Send,
+    F: FnMut(&S::Item) -> bool + Send,
+{
+    type Msg = S::Msg;
+    type Item = S::Item;
+
+    fn receiver(&self) -> &MessageReceiver<S::Msg> {
+        self.inner.receiver()
+    }
+
+    async fn recv(&mut self) -> Option<S::Item> {
+        loop {
+            let item = self.inner.recv().await?;
+            if (self.predicate)(&item) {
+                return Some(item);
+            }
+        }
+    }
+}
+
+/// See `SubscriptionExt::map`.
+pub struct Map<S, F> {
+    inner: S,
+    f: F,
+}
+
+impl<S, F, T> Subscription for Map<S, F>
+where
+    S: Subscription + Send,
+    F: FnMut(S::Item) -> T + Send,
+{
+    type Msg = S::Msg;
+    type Item = T;
+
+    fn receiver(&self) -> &MessageReceiver<S::Msg> {
+        self.inner.receiver()
+    }
+
+    async fn recv(&mut self) -> Option<T> {
+        self.inner.recv().await.map(&mut self.f)
+    }
+}
+
+/// See `SubscriptionExt::take_until`.
+pub struct TakeUntil<S> {
+    inner: S,
+    /// Taken once the cancellation is over.
+    cancellation: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl<S: Subscription + Send> Subscription for TakeUntil<S> {
+    type Msg = S::Msg;
+    type Item = S::Item;
+
+    fn receiver(&self) -> &MessageReceiver<S::Msg> {
+        self.inner.receiver()
+    }
+
+    async fn recv(&mut self) -> Option<S::Item> {
+        let cancellation = self.cancellation.as_mut()?;
+        tokio::select! {
+            biased;
+            _ = cancellation => {
+                self.cancellation = None;
+                None
+            }
+            item = self.inner.recv() => item,
+        }
+    }
+}
+
+/// See `SubscriptionExt::timeout_per_message`.
+pub struct Timeout<S> {
+    inner: S,
+    duration: Duration,
+}
+
+impl<S: Subscription + Send> Subscription for Timeout<S> {
+    type Msg = S::Msg;
+    type Item = Result<S::Item, Elapsed>;
+
+    fn receiver(&self) -> &MessageReceiver<S::Msg> {
+        self.inner.receiver()
+    }
+
+    async fn recv(&mut self) -> Option<Self::Item> {
+        match timeout(self.duration, self.inner.recv()).await {
+            Ok(item) => item.map(Ok),
+            Err(elapsed) => Some(Err(elapsed)),
+        }
+    }
+}
+
+/// See `SubscriptionExt::chunked`.
+pub struct Chunked<S> {
+    inner: S,
+    size: usize,
+    max_wait: Duration,
+}
+
+impl<S> Subscription for Chunked<S>
+where
+    S: Subscription + Send,
+    S::Item: Send,
+{
+    type Msg = S::Msg;
+    type Item = Vec<S::Item>;
+
+    fn receiver(&self) -> &MessageReceiver<S::Msg> {
+        self.inner.receiver()
+    }
+
+    async fn recv(&mut self) -> Option<Vec<S::Item>> {
+        let first = self.inner.recv().await?;
+        let deadline = Instant::now() + self.max_wait;
+        let mut chunk = vec![first];
+        while chunk.len() < self.size {
+            match timeout_at(deadline, self.inner.recv()).await {
+                Ok(Some(item)) => chunk.push(item),
+                Ok(None) | Err(_) => break,
+            }
+        }
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotifierHub;
+    use tokio::sync::oneshot;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_combinators() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let (cancel, cancelled) = oneshot::channel::<()>();
+        let mut chunks = hub
+            .subscribe(&"channel1", 10)
+            .take_until(async move {
+                let _ = cancelled.await;
+            })
+            .chunked(2, Duration::from_millis(10));
+        let mut timed = hub
+            .subscribe(&"channel1", 10)
+            .timeout_per_message(Duration::from_millis(10));
+
+        for i in 0..3 {
+            hub.clone_send(i, &"channel1").unwrap();
+        }
+        assert_eq!(chunks.recv().await, Some(vec![0, 1]));
+        assert_eq!(chunks.recv().await, Some(vec![2]));
+        assert!(hub.is_subscribed(&"channel1", chunks.receiver()));
+
+        for i in 0..3 {
+            assert_eq!(timed.recv().await.unwrap().unwrap(), i);
+        }
+        assert!(timed.recv().await.unwrap().is_err());
+
+        cancel.send(()).unwrap();
+        hub.clone_send(3, &"channel1").unwrap();
+        assert_eq!(chunks.recv().await, None);
+        assert_eq!(timed.id(), timed.receiver().id());
+    }
+}
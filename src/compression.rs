@@ -0,0 +1,225 @@
+use std::io;
+
+use crate::codec::Codec;
+
+/// Compresses the frames of a codec wrapped in `Compressed`.
+/// The crate does not depend on any compression library, so no zstd or lz4 implementation is built in:
+/// they are brought by implementing this trait over the crate of the algorithm.
+pub trait Compression {
+    /// Compresses an encoded message.
+    fn compress(&mut self, frame: &[u8]) -> io::Result<Vec<u8>>;
+    /// Restores an encoded message from its compressed frame.
+    fn decompress(&mut self, frame: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+/// The first byte of a frame whose payload is stored as encoded.
+const RAW: u8 = 0;
+/// The first byte of a frame whose payload is compressed.
+const COMPRESSED: u8 = 1;
+
+/// Wraps a codec to compress its frames of at least `threshold` bytes, the small ones being kept as they are
+/// since compressing them costs more than it saves. Each frame starts with a byte telling if it is compressed,
+/// so both ends only have to agree on the codec and the compression, not on the threshold.
+/// Like the other codecs, it is delimited in a byte stream by a `FrameCodec`. Registered as the format
+/// of a channel, it also compresses the large messages recorded by its journal, see `NotifierHub::set_journal`.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::{
+///     codec::Codec,
+///     compression::{Compressed, Compression},
+/// };
+/// use std::io;
+///
+/// struct Utf8;
+///
+/// impl Codec<String> for Utf8 {
+///     fn encode(&mut self, msg: &String) -> io::Result<Vec<u8>> {
+///         Ok(msg.clone().into_bytes())
+///     }
+///
+///     fn decode(&mut self, frame: &[u8]) -> io::Result<String> {
+///         String::from_utf8(frame.to_vec()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+///     }
+/// }
+///
+/// /// Stores the repeated bytes once with their count.
+/// struct RunLength;
+///
+/// impl Compression for RunLength {
+///     fn compress(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+///         let mut compressed = Vec::new();
+///         for byte in frame {
+///             match compressed.len() {
+///                 n if n > 0 && compressed[n - 1] == *byte && compressed[n - 2] < u8::MAX => compressed[n - 2] += 1,
+///                 _ => compressed.extend([1, *byte]),
+///             }
+///         }
+///         Ok(compressed)
+///     }
+///
+///     fn decompress(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+///         Ok(frame.chunks(2).flat_map(|run| vec![run[1]; run[0] as usize]).collect())
+///     }
+/// }
+///
+/// let mut codec = Compressed::new(Utf8, RunLength, 16);
+/// let large = "a".repeat(100);
+/// let frame = codec.encode(&large).unwrap();
+/// assert!(frame.len() < large.len());
+/// assert_eq!(codec.decode(&frame).unwrap(), large);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Compressed<C, Z> {
+    codec: C,
+    compression: Z,
+    threshold: usize,
+    /// The buffer the messages are encoded in before their compression.
+    buffer: Vec<u8>,
+}
+
+impl<C, Z> Compressed<C, Z> {
+    /// Returns the codec compressing the frames of `codec` of at least `threshold` bytes.
+    pub fn new(codec: C, compression: Z, threshold: usize) -> Self {
+        Self {
+            codec,
+            compression,
+            threshold,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns the size from which the frames are compressed.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+}
+
+impl<M, C: Codec<M>, Z: Compression> Codec<M> for Compressed<C, Z> {
+    fn encode(&mut self, msg: &M) -> io::Result<Vec<u8>> {
+        let mut frame = Vec::new();
+        self.encode_into(msg, &mut frame)?;
+        Ok(frame)
+    }
+
+    fn decode(&mut self, frame: &[u8]) -> io::Result<M> {
+        match frame.split_first() {
+            Some((&RAW, payload)) => self.codec.decode(payload),
+            Some((&COMPRESSED, payload)) => {
+                let payload = self.compression.decompress(payload)?;
+                self.codec.decode(&payload)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the frame has no compression flag",
+            )),
+        }
+    }
+
+    fn encode_into(&mut self, msg: &M, buffer: &mut Vec<u8>) -> io::Result<()> {
+        self.buffer.clear();
+        self.codec.encode_into(msg, &mut self.buffer)?;
+        if self.buffer.len() < self.threshold {
+            buffer.push(RAW);
+            buffer.extend_from_slice(&self.buffer);
+        } else {
+            buffer.push(COMPRESSED);
+            buffer.extend_from_slice(&self.compression.compress(&self.buffer)?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bridge::LengthDelimitedCodec,
+        codec::{FrameCodec, Framing},
+        notifier::NotifierHub,
+    };
+
+    #[derive(Clone)]
+    struct Bytes;
+
+    impl Codec<Vec<u8>> for Bytes {
+        fn encode(&mut self, msg: &Vec<u8>) -> io::Result<Vec<u8>> {
+            Ok(msg.clone())
+        }
+
+        fn decode(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+            Ok(frame.to_vec())
+        }
+    }
+
+    /// Keeps the first byte of the frame with its length, for frames repeating a single byte.
+    #[derive(Clone)]
+    struct Repeated;
+
+    impl Compression for Repeated {
+        fn compress(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+            let mut compressed = (frame.len() as u32).to_be_bytes().to_vec();
+            compressed.extend(frame.first());
+            Ok(compressed)
+        }
+
+        fn decompress(&mut self, frame: &[u8]) -> io::Result<Vec<u8>> {
+            let len = u32::from_be_bytes(frame[..4].try_into().unwrap());
+            Ok(vec![frame[4]; len as usize])
+        }
+    }
+
+    #[test]
+    fn test_threshold() {
+        let mut codec = Compressed::new(Bytes, Repeated, 8);
+        let small = codec.encode(&vec![7; 4]).unwrap();
+        assert_eq!(small, [RAW, 7, 7, 7, 7]);
+        let large = codec.encode(&vec![7; 1000]).unwrap();
+        assert_eq!(large.len(), 6);
+        assert_eq!(codec.decode(&small).unwrap(), vec![7; 4]);
+        assert_eq!(codec.decode(&large).unwrap(), vec![7; 1000]);
+        assert!(codec.decode(&[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compressed_journal() {
+        let mut hub = NotifierHub::<Vec<u8>, &'static str>::new();
+        hub.register_format("compressed", Compressed::new(Bytes, Repeated, 8));
+        hub.set_channel_format(&"channel1", Some("compressed"));
+        hub.set_journal(&"channel1", Some(10));
+        let mut receiver = hub.subscribe(&"channel1", 10);
+        hub.clone_send(vec![3; 10_000], &"channel1").unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), vec![3; 10_000]);
+
+        assert_eq!(hub.request_replay(&"channel1", &receiver, 0).unwrap(), 1);
+        assert_eq!(receiver.recv().await.unwrap(), vec![3; 10_000]);
+    }
+
+    #[tokio::test]
+    async fn test_compressed_bridge() {
+        let source = NotifierHub::<Vec<u8>, &'static str>::new().into_handle();
+        let destination = NotifierHub::<Vec<u8>, &'static str>::new().into_handle();
+        let mut receiver = destination.subscribe(&"channel1", 10);
+        let (writer, reader) = tokio::io::duplex(64);
+        let codec = || {
+            let framing = Framing::LengthDelimited(LengthDelimitedCodec::new());
+            FrameCodec::with_framing(framing, Compressed::new(Bytes, Repeated, 8))
+        };
+
+        let mut pipe = source.pipe_to_writer(&"channel1", writer, codec());
+        let ingest = destination.ingest_reader(&"channel1", reader, codec());
+        for msg in [vec![1; 2], vec![2; 10_000]] {
+            source
+                .clone_send(msg, &"channel1")
+                .unwrap()
+                .wait(None)
+                .await;
+        }
+        assert_eq!(receiver.recv().await.unwrap(), vec![1; 2]);
+        assert_eq!(receiver.recv().await.unwrap(), vec![2; 10_000]);
+
+        pipe.stop();
+        assert_eq!(pipe.join().await.unwrap(), 2);
+        assert_eq!(ingest.join().await.unwrap(), 2);
+    }
+}
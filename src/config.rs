@@ -0,0 +1,244 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    alert::AlertPolicy,
+    circuit_breaker::BreakerPolicy,
+    gc::GcPolicy,
+    metadata::ChannelMetadata,
+    notifier::NotifierHub,
+    quarantine::QuarantinePolicy,
+    rate_limit::RateLimitAction,
+    sequencer::Sequencer,
+    sync::lock,
+    writing_handler::{DeliveryMode, Duration},
+};
+
+/// The configuration of a hub, without its subscribers and waiters. It is serializable with the `serde` feature,
+/// so the messaging topology of a service can be persisted and re-created across restarts.
+///
+/// A channel only exists through its subscribers, so the configuration holds the settings of the channels,
+/// that are applied as soon as they are subscribed again. The lists of an exported configuration are sorted,
+/// so a hub made with `from_config` exports the same configuration.
+///
+/// What only lives along with the hub is not part of it: the publish tokens, the messages held by the journals,
+/// the park buffers, the deduplication windows and the quarantine, and the code given to the hub, such as the hooks,
+/// the initial data, the registered formats and the handlers. The drop detection is not part of it either,
+/// as it is enabled on a `HubHandle`, see `HubHandle::set_drop_detection`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HubConfig<ChannelId> {
+    /// See `NotifierHub::set_delivery_mode`.
+    pub delivery_mode: DeliveryMode,
+    /// See `NotifierHub::set_send_timeout`.
+    pub send_timeout: Option<Duration>,
+    /// See `NotifierHub::set_gc_policy`.
+    pub gc_policy: GcPolicy,
+    /// See `NotifierHub::set_rate_limit_action`.
+    pub rate_limit_action: RateLimitAction,
+    /// See `NotifierHub::set_hub_rate_limit`, as messages per second and burst size.
+    pub hub_rate_limit: Option<(u32, u32)>,
+    /// See `NotifierHub::set_rate_limit`, as messages per second and burst size.
+    pub rate_limits: Vec<(ChannelId, (u32, u32))>,
+    /// See `NotifierHub::set_dedup_window`.
+    pub dedup_windows: Vec<(ChannelId, Duration)>,
+    /// See `NotifierHub::set_subscriber_limit`.
+    pub subscriber_limits: Vec<(ChannelId, usize)>,
+    /// See `NotifierHub::set_circuit_breaker`.
+    pub circuit_breaker: Option<BreakerPolicy>,
+    /// See `NotifierHub::set_quarantine_policy`.
+    pub quarantine: Option<QuarantinePolicy>,
+    /// See `NotifierHub::set_audit_capacity`.
+    pub audit_capacity: Option<usize>,
+    /// See `NotifierHub::set_alert_policy`.
+    pub alert_policy: Option<AlertPolicy>,
+    /// See `NotifierHub::define_group`.
+    pub groups: Vec<(String, Vec<ChannelId>)>,
+    /// The aliases left by `NotifierHub::rename_channel`, from the old id to the new one.
+    pub aliases: Vec<(ChannelId, ChannelId)>,
+    /// See `NotifierHub::describe_channel`.
+    pub metadata: Vec<(ChannelId, ChannelMetadata)>,
+    /// See `NotifierHub::set_channel_format`. The formats themselves are code, so they are registered again.
+    pub channel_formats: Vec<(ChannelId, String)>,
+    /// The sequenced channels, the channels of each list sharing an ordering task,
+    /// see `NotifierHub::enable_shared_sequencer`.
+    pub sequencers: Vec<Vec<ChannelId>>,
+    /// See `NotifierHub::set_park_buffer`.
+    pub park_buffers: Vec<(ChannelId, usize)>,
+    /// See `NotifierHub::set_journal`.
+    pub journals: Vec<(ChannelId, usize)>,
+}
+
+/// Returns the entries of the map sorted by key, with their value mapped.
+fn sorted<K: Ord + Clone, V, T>(map: &HashMap<K, V>, value: impl Fn(&V) -> T) -> Vec<(K, T)> {
+    let mut pairs: Vec<_> = map.iter().map(|(k, v)| (k.clone(), value(v))).collect();
+    pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+    pairs
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Returns the configuration of the hub.
+    pub fn export_config(&self) -> HubConfig<ChannelId>
+    where
+        ChannelId: Ord,
+    {
+        let mut sequencers: Vec<(&Sequencer, Vec<ChannelId>)> = Vec::new();
+        for (id, sequencer) in &self.sequencers {
+            match sequencers
+                .iter_mut()
+                .find(|(task, _)| task.same_task(sequencer))
+            {
+                Some((_, ids)) => ids.push(id.clone()),
+                None => sequencers.push((sequencer, vec![id.clone()])),
+            }
+        }
+        let mut sequencers: Vec<_> = sequencers.into_iter().map(|(_, ids)| ids).collect();
+        sequencers.iter_mut().for_each(|ids| ids.sort());
+        sequencers.sort();
+
+        HubConfig {
+            delivery_mode: self.delivery_mode(),
+            send_timeout: self.send_timeout(),
+            gc_policy: self.gc_policy(),
+            rate_limit_action: self.rate_limit_action(),
+            hub_rate_limit: self
+                .hub_rate_limit
+                .as_ref()
+                .map(|bucket| lock(bucket).limit()),
+            rate_limits: sorted(&self.rate_limits, |bucket| lock(bucket).limit()),
+            dedup_windows: sorted(&self.dedup_windows, |window| lock(window).window()),
+            subscriber_limits: sorted(&self.subscriber_limits, |limit| *limit),
+            circuit_breaker: self.circuit_breaker(),
+            quarantine: self.quarantine_policy(),
+            audit_capacity: self.audit_capacity(),
+            alert_policy: self.alert_policy(),
+            groups: sorted(&self.groups, |group| group.channels.clone()),
+            aliases: sorted(&self.aliases, Clone::clone),
+            metadata: sorted(&self.metadata, Clone::clone),
+            channel_formats: sorted(&self.formats.channels, Clone::clone),
+            sequencers,
+            park_buffers: sorted(&self.parked, |buffer| lock(buffer).capacity),
+            journals: sorted(&self.journals, |journal| lock(journal).capacity),
+        }
+    }
+
+    /// Returns an empty hub with the given configuration.
+    ///
+    /// Must be called within a tokio runtime if the configuration has sequencers,
+    /// as their ordering tasks are spawned right away.
+    pub fn from_config(config: HubConfig<ChannelId>) -> Self
+    where
+        ChannelId: Send + Sync + 'static,
+    {
+        let mut hub = Self::new();
+        hub.set_delivery_mode(config.delivery_mode);
+        hub.set_send_timeout(config.send_timeout);
+        hub.set_gc_policy(config.gc_policy);
+        hub.set_rate_limit_action(config.rate_limit_action);
+        hub.set_hub_rate_limit(config.hub_rate_limit);
+        hub.set_circuit_breaker(config.circuit_breaker);
+        hub.set_quarantine_policy(config.quarantine);
+        hub.set_audit_capacity(config.audit_capacity);
+        hub.set_alert_policy(config.alert_policy);
+        hub.aliases.extend(config.aliases);
+        for (id, (msgs_per_sec, burst)) in config.rate_limits {
+            hub.set_rate_limit(&id, msgs_per_sec, burst);
+        }
+        for (id, window) in config.dedup_windows {
+            hub.set_dedup_window(&id, Some(window));
+        }
+        for (id, limit) in config.subscriber_limits {
+            hub.set_subscriber_limit(&id, Some(limit));
+        }
+        for (name, channels) in config.groups {
+            hub.groups.entry(name).or_default().channels = channels;
+        }
+        hub.metadata.extend(config.metadata);
+        hub.formats.channels.extend(config.channel_formats);
+        for ids in config.sequencers {
+            hub.enable_shared_sequencer(&ids);
+        }
+        for (id, capacity) in config.park_buffers {
+            hub.set_park_buffer(&id, Some(capacity));
+        }
+        for (id, capacity) in config.journals {
+            hub.set_journal(&id, Some(capacity));
+        }
+        hub
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configured_hub() -> NotifierHub<u32, String> {
+        let mut hub = NotifierHub::new();
+        hub.set_delivery_mode(DeliveryMode::Deterministic);
+        hub.set_send_timeout(Some(Duration::from_millis(500)));
+        for channel in ["metrics", "traces", "logs", "events"] {
+            hub.set_rate_limit(&channel.to_string(), 100, 10);
+            hub.set_journal(&channel.to_string(), Some(5));
+        }
+        hub.enable_shared_sequencer(&["orders".to_string(), "payments".to_string()]);
+        hub.enable_sequencer(&"ledger".to_string());
+        hub.set_park_buffer(&"jobs".to_string(), Some(3));
+        hub.set_alert_policy(Some(AlertPolicy {
+            max_pending: Some(10),
+            latency_budget: None,
+        }));
+        hub.set_subscriber_limit(&"admin".to_string(), Some(1));
+        hub.set_circuit_breaker(Some(BreakerPolicy {
+            threshold: 3,
+            cooldown: Duration::from_secs(5),
+        }));
+        hub.define_group("ingest", &["a".to_string(), "b".to_string()]);
+        hub.describe_channel(&"metrics".to_string(), "host metrics", ["ops"]);
+        hub.set_channel_format(&"metrics".to_string(), Some("json"));
+        let _receiver = hub.subscribe(&"old".to_string(), 10);
+        hub.rename_channel(&"old".to_string(), "new".to_string(), true)
+            .unwrap();
+        hub
+    }
+
+    #[tokio::test]
+    async fn test_config_round_trip() {
+        let config = configured_hub().export_config();
+        let channels = config.rate_limits.iter().map(|(id, _)| id.as_str());
+        assert_eq!(
+            channels.collect::<Vec<_>>(),
+            ["events", "logs", "metrics", "traces"]
+        );
+        assert_eq!(config.aliases, vec![("old".to_string(), "new".to_string())]);
+        assert_eq!(
+            config.sequencers,
+            vec![
+                vec!["ledger".to_string()],
+                vec!["orders".to_string(), "payments".to_string()]
+            ]
+        );
+
+        let mut hub = NotifierHub::<u32, String>::from_config(config.clone());
+        assert_eq!(hub.export_config(), config);
+        assert_eq!(hub.subscriber_limit(&"admin".to_string()), Some(1));
+        assert!(hub
+            .channel_metadata(&"metrics".to_string())
+            .unwrap()
+            .has_tag("ops"));
+        assert_eq!(hub.channel_format(&"metrics".to_string()), Some("json"));
+        assert_eq!(hub.park_buffer(&"jobs".to_string()), Some(3));
+        assert!(hub.is_sequenced(&"payments".to_string()));
+
+        let mut receiver = hub.subscribe_group("ingest", 10).unwrap();
+        hub.clone_send(1, &"b".to_string()).unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_config_serialization() {
+        let config = configured_hub().export_config();
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: HubConfig<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, config);
+    }
+}
@@ -0,0 +1,184 @@
+use std::hash::Hash;
+
+use crate::{
+    error::{NotifierError, SendFailure},
+    handle::HubHandle,
+    notifier::{Admission, MessageSender, NotifierHub},
+    writing_handler::{BroadcastReport, WritingHandler},
+};
+
+/// A channel prepared for many publishes, obtained with `NotifierHub::broadcast_context`.
+/// It keeps a snapshot of the subscribers of the channel, taken again only when the subscribers of the hub
+/// changed, and the buffer of the failures of the previous reports given to `recycle`.
+pub struct BroadcastContext<M: Send + 'static, ChannelId> {
+    /// The channel, as given by the caller.
+    id: ChannelId,
+    /// The generation of the hub when the snapshot was taken, `None` before the first publish.
+    generation: Option<u64>,
+    senders: Vec<MessageSender<M>>,
+    /// An empty buffer for the failures of the next publish.
+    errors: Vec<SendFailure<M, ChannelId>>,
+}
+
+impl<M: Send + 'static, ChannelId> BroadcastContext<M, ChannelId> {
+    /// Returns the channel of the context.
+    pub fn id(&self) -> &ChannelId {
+        &self.id
+    }
+
+    /// Returns the number of subscribers in the snapshot, as of the last publish.
+    pub fn len(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Returns true if the snapshot has no subscriber.
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+
+    /// Gives back the report of a publish, so its buffer of failures is reused by the next publish.
+    pub fn recycle(&mut self, report: BroadcastReport<M, ChannelId>) {
+        let mut errors = report.into_failures();
+        if errors.capacity() > self.errors.capacity() {
+            errors.clear();
+            self.errors = errors;
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Records that the subscribers of a channel changed, so the broadcast contexts take a new snapshot.
+    pub(crate) fn subscribers_changed(&mut self) {
+        self.generation += 1;
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Returns a context for many publishes on the channel with `context_send`.
+    pub fn broadcast_context(&self, id: &ChannelId) -> BroadcastContext<M, ChannelId> {
+        BroadcastContext {
+            id: id.clone(),
+            generation: None,
+            senders: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Same as `clone_send`, the subscribers being taken from the snapshot of the context.
+    /// The snapshot is only taken again when a subscriber has been added or removed in the hub since the last call,
+    /// the protections, rate limits and sequencers of the channel being checked at each publish.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let _receivers = [hub.subscribe(&"ticks", 100), hub.subscribe(&"ticks", 100)];
+    ///
+    /// let mut context = hub.broadcast_context(&"ticks");
+    /// for tick in 0..10 {
+    ///     let report = hub.context_send(&mut context, tick).unwrap().wait(None).await;
+    ///     assert_eq!(report.delivered(), 2);
+    ///     context.recycle(report);
+    /// }
+    /// # }
+    /// ```
+    pub fn context_send(
+        &self,
+        context: &mut BroadcastContext<M, ChannelId>,
+        msg: M,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        if context.generation != Some(self.generation) {
+            context.senders.clear();
+            context
+                .senders
+                .extend_from_slice(self.senders_of(&context.id));
+            context.generation = Some(self.generation);
+        }
+        // The channels without subscribers follow the usual path, with its parking and errors
+        if context.senders.is_empty() {
+            return self.clone_send(msg, &context.id);
+        }
+
+        let id = self.aliases.get(&context.id).unwrap_or(&context.id);
+        let result = match self.admit(id, false) {
+            Ok(Admission::Fanout(not_before)) => {
+                let handler = self
+                    .fanout_handler(id, not_before)
+                    .with_errors(std::mem::take(&mut context.errors));
+                self.journal(id, &msg);
+                let senders = &context.senders;
+                Ok(
+                    self.hooked(Some(id), senders.len(), || match self.sequencers.get(id) {
+                        Some(sequencer) => sequencer.publish(handler, msg, senders),
+                        None => handler.cloning_broadcast(msg, senders),
+                    }),
+                )
+            }
+            Ok(_) => return self.clone_send(msg, &context.id),
+            Err(refusal) => Err(refusal.into_error(id.clone(), msg)),
+        };
+        self.audit(None, id, &result);
+        if result.is_ok() {
+            self.check_lag(id);
+        }
+        result
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::broadcast_context`.
+    pub fn broadcast_context(&self, id: &ChannelId) -> BroadcastContext<M, ChannelId> {
+        self.with(|hub| hub.broadcast_context(id))
+    }
+
+    /// See `NotifierHub::context_send`.
+    pub fn context_send(
+        &self,
+        context: &mut BroadcastContext<M, ChannelId>,
+        msg: M,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.context_send(context, msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_context_refresh() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut context = hub.broadcast_context(&"channel1");
+        assert!(matches!(
+            hub.context_send(&mut context, 0),
+            Err(NotifierError::ChannelUninitialized("channel1"))
+        ));
+
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        hub.context_send(&mut context, 1).unwrap();
+        assert_eq!(context.len(), 1);
+        let receiver2 = hub.subscribe(&"channel1", 10);
+        hub.context_send(&mut context, 2).unwrap();
+        assert_eq!(context.len(), 2);
+
+        hub.unsubscribe(&"channel1", &receiver2).unwrap();
+        drop(receiver2);
+        let report = hub.context_send(&mut context, 3).unwrap().wait(None).await;
+        assert!(report.all_ok());
+        assert_eq!(context.len(), 1);
+        for i in 1..=3 {
+            assert_eq!(receiver1.recv().await.unwrap(), i);
+        }
+    }
+}
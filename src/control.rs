@@ -0,0 +1,181 @@
+use smart_channel::channel;
+use std::hash::Hash;
+use tokio::sync::mpsc::error::TryRecvError;
+
+use crate::{
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::{
+        MessageReceiver, MessageSender, NotifierHub, SmartChannelId, NOTIFIER_CHANNEL_SIZE,
+    },
+    writing_handler::WritingHandler,
+};
+
+/// A receiver with a control lane, returned by `NotifierHub::subscribe_with_control`.
+/// The control messages are read before the data messages queued in the buffer, so a subscriber
+/// with a large backlog still sees the close message of a shutdown promptly.
+#[derive(Debug)]
+pub struct ControlledReceiver<M> {
+    control: MessageReceiver<M>,
+    data: MessageReceiver<M>,
+}
+
+impl<M> ControlledReceiver<M> {
+    /// Returns the id of the subscriber, shared by both lanes.
+    pub fn id(&self) -> SmartChannelId {
+        self.data.id()
+    }
+
+    /// Returns the receiver of the data messages, for the methods of the hub taking a receiver
+    /// such as `unsubscribe` or `is_subscribed`.
+    pub fn data_receiver(&self) -> &MessageReceiver<M> {
+        &self.data
+    }
+
+    /// Receives the next message, the control messages first.
+    /// Returns `None` once the data lane is over and no control message is waiting.
+    pub async fn recv(&mut self) -> Option<M> {
+        tokio::select! {
+            biased;
+            Some(msg) = self.control.recv() => Some(msg),
+            msg = self.data.recv() => msg,
+        }
+    }
+
+    /// Same as `recv` without waiting.
+    pub fn try_recv(&mut self) -> Result<M, TryRecvError> {
+        match self.control.try_recv() {
+            Ok(msg) => Ok(msg),
+            Err(_) => self.data.try_recv(),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Returns the senders to use for a control message, the control lane of each subscriber having one.
+    pub(crate) fn control_lanes(&self, senders: &[MessageSender<M>]) -> Vec<MessageSender<M>>
+    where
+        M: Clone,
+    {
+        senders
+            .iter()
+            .map(|s| self.control_lanes.get(s.id()).unwrap_or(s).clone())
+            .collect()
+    }
+
+    /// Removes the control lanes whose receiver is dropped.
+    pub(crate) fn clean_control_lanes(&mut self) {
+        self.control_lanes.retain(|_, lane| !lane.is_closed());
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `subscribe`, the receiver having a control lane besides its buffer. The close messages of the shutdowns
+    /// and the messages published with `control_send` go through the control lane, so they are read ahead of the
+    /// data messages already queued.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let mut receiver = hub.subscribe_with_control(&"feed", 100);
+    /// for i in 0..100 {
+    ///     hub.clone_send(format!("update {i}"), &"feed").unwrap();
+    /// }
+    ///
+    /// hub.shutdown_with_factory(&"feed", |_| "close".to_string()).unwrap();
+    /// assert_eq!(receiver.try_recv().unwrap(), "close");
+    /// ```
+    pub fn subscribe_with_control(
+        &mut self,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> ControlledReceiver<M> {
+        let data = self.subscribe(id, channel_size);
+        let (lane, control) = channel(NOTIFIER_CHANNEL_SIZE, data.id());
+        if !self.draining {
+            self.control_lanes.insert(data.id(), lane);
+        }
+        ControlledReceiver { control, data }
+    }
+
+    /// Same as `clone_send`, the message going through the control lane of the subscribers having one,
+    /// ahead of their queued data messages. The other subscribers receive it as a regular message.
+    /// The control messages bypass the rate limits and the sequencer of the channel, but are parked
+    /// or given to the drop hook as the others when the channel has no subscriber.
+    pub fn control_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        let id = self.aliases.get(id).unwrap_or(id);
+        match self.admit_unthrottled(id, false) {
+            Ok(admission) => Ok(self.carry_out(
+                id,
+                admission,
+                msg,
+                |handler, senders, msg| {
+                    handler.cloning_broadcast(msg, &self.control_lanes(senders))
+                },
+                |msg| msg,
+            )),
+            Err(refusal) => Err(refusal.into_error(id.clone(), msg)),
+        }
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::subscribe_with_control`.
+    pub fn subscribe_with_control(
+        &self,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> ControlledReceiver<M> {
+        self.with(|hub| hub.subscribe_with_control(id, channel_size))
+    }
+
+    /// See `NotifierHub::control_send`.
+    pub fn control_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.control_send(msg, id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_control_lane() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut controlled = hub.subscribe_with_control(&"channel1", 10);
+        let mut plain = hub.subscribe(&"channel1", 10);
+
+        hub.clone_send("data".to_string(), &"channel1").unwrap();
+        hub.control_send("pause".to_string(), &"channel1").unwrap();
+        assert_eq!(controlled.recv().await.unwrap(), "pause");
+        assert_eq!(controlled.recv().await.unwrap(), "data");
+        assert_eq!(plain.try_recv().unwrap(), "data");
+        assert_eq!(plain.try_recv().unwrap(), "pause");
+
+        hub.clone_send("data".to_string(), &"channel1").unwrap();
+        hub.shutdown_with_factory(&"channel1", |_| "close".to_string())
+            .unwrap();
+        assert_eq!(controlled.recv().await.unwrap(), "close");
+        assert_eq!(controlled.recv().await.unwrap(), "data");
+        assert!(controlled.recv().await.is_none());
+        assert!(hub.control_lanes.is_empty());
+    }
+}
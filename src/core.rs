@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use crate::{error::NotifierError, notifier::ChannelState};
+
+/// What a publish does with its message, decided by `route` and carried out by the hub.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Route {
+    /// The message is written to the subscribers of the channel, once the rate limits allow it.
+    Fanout,
+    /// The message is kept in the park buffer of the channel until its next subscriber.
+    Park,
+    /// The channel is over and does not park its messages, the message is dropped without error.
+    Discard,
+    /// The channel has never had a subscriber and does not park its messages, the publish fails.
+    Reject,
+}
+
+/// Returns the state of a channel from its number of subscribers, `None` if the hub does not know it.
+pub fn channel_state(subscribers: Option<usize>) -> ChannelState {
+    match subscribers {
+        Some(0) => ChannelState::Over,
+        Some(_) => ChannelState::Running,
+        None => ChannelState::Uninitialised,
+    }
+}
+
+/// Decides the route of a message published to a channel in the given state, `parking` telling if the channel has a park buffer.
+/// The decision depends on nothing else, so it is the same whatever the runtime writing the messages.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::{core::{route, Route}, notifier::ChannelState};
+///
+/// assert_eq!(route(ChannelState::Running, false), Route::Fanout);
+/// assert_eq!(route(ChannelState::Uninitialised, true), Route::Park);
+/// assert_eq!(route(ChannelState::Uninitialised, false), Route::Reject);
+/// ```
+pub fn route(state: ChannelState, parking: bool) -> Route {
+    match (state, parking) {
+        (ChannelState::Running, _) => Route::Fanout,
+        (_, true) => Route::Park,
+        (ChannelState::Over, false) => Route::Discard,
+        (ChannelState::Uninitialised, false) => Route::Reject,
+    }
+}
+
+/// Why a publish is refused, decided by `admit`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Refusal {
+    /// The channel is protected and the publish is not made with a valid token.
+    NotAllowed,
+    /// The channel has never had a subscriber and does not park its messages.
+    Uninitialised,
+    /// The publish exceeded a rate limit, it can be retried after the duration.
+    /// The rate limits depend on the clock, so `admit` leaves them to the hub.
+    RateLimited(Duration),
+}
+
+impl Refusal {
+    /// Returns the error of the refused publish on the channel, handing the message back.
+    pub(crate) fn into_error<T, ChannelId>(
+        self,
+        id: ChannelId,
+        msg: T,
+    ) -> NotifierError<T, ChannelId> {
+        match self {
+            Refusal::NotAllowed => NotifierError::PublishNotAllowed { id, msg },
+            Refusal::Uninitialised => NotifierError::ChannelUninitialized(id),
+            Refusal::RateLimited(retry_after) => NotifierError::RateLimited {
+                id,
+                msg,
+                retry_after,
+            },
+        }
+    }
+}
+
+/// Decides whether a publish is accepted and its route, `protected` telling if the channel is protected
+/// and the publish is made without a token. Every publish on a channel goes through this decision,
+/// the accepted ones written to the subscribers being throttled by the rate limits afterwards.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::{core::{admit, Refusal, Route}, notifier::ChannelState};
+///
+/// assert_eq!(admit(ChannelState::Over, false, false), Ok(Route::Discard));
+/// assert_eq!(admit(ChannelState::Running, false, true), Err(Refusal::NotAllowed));
+/// assert_eq!(admit(ChannelState::Uninitialised, false, false), Err(Refusal::Uninitialised));
+/// ```
+pub fn admit(state: ChannelState, parking: bool, protected: bool) -> Result<Route, Refusal> {
+    if protected {
+        return Err(Refusal::NotAllowed);
+    }
+    match route(state, parking) {
+        Route::Reject => Err(Refusal::Uninitialised),
+        route => Ok(route),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        let states = [None, Some(0), Some(2)].map(channel_state);
+        assert_eq!(
+            states,
+            [
+                ChannelState::Uninitialised,
+                ChannelState::Over,
+                ChannelState::Running
+            ]
+        );
+        let routes = states.map(|state| (route(state, false), route(state, true)));
+        assert_eq!(
+            routes,
+            [
+                (Route::Reject, Route::Park),
+                (Route::Discard, Route::Park),
+                (Route::Fanout, Route::Fanout)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_admit() {
+        assert_eq!(
+            admit(ChannelState::Running, true, true),
+            Err(Refusal::NotAllowed)
+        );
+        assert_eq!(
+            admit(ChannelState::Uninitialised, true, false),
+            Ok(Route::Park)
+        );
+        assert_eq!(
+            admit(ChannelState::Uninitialised, false, false),
+            Err(Refusal::Uninitialised)
+        );
+    }
+}
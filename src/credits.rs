@@ -0,0 +1,312 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::mpsc::error::{TryRecvError, TrySendError};
+
+use crate::{
+    error_hook::FailureKind,
+    handle::HubHandle,
+    notifier::{MessageReceiver, MessageSender, NotifierHub, SmartChannelId},
+    sync::lock,
+};
+
+/// The credits left to a subscriber, shared by its receiver, which grants them, and by the hubs writing to it.
+pub(crate) type Balance = Arc<AtomicUsize>;
+
+/// The credits left to the subscribers made with `subscribe_with_credits`, shared with the writing handlers,
+/// which spend them.
+#[derive(Default)]
+pub(crate) struct Credits {
+    balances: Mutex<HashMap<SmartChannelId, Balance>>,
+}
+
+impl Credits {
+    /// Returns true if no subscriber is limited by its credits.
+    pub(crate) fn is_empty(&self) -> bool {
+        lock(&self.balances).is_empty()
+    }
+
+    /// Spends a credit of the subscriber, returns false if it has none left.
+    /// The subscribers not made with `subscribe_with_credits` are not limited.
+    pub(crate) fn spend(&self, id: &SmartChannelId) -> bool {
+        match lock(&self.balances).get(id) {
+            Some(balance) => balance
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok(),
+            None => true,
+        }
+    }
+
+    /// Gives back the credit spent for a writing that did not happen.
+    pub(crate) fn refund(&self, id: &SmartChannelId) {
+        if let Some(balance) = lock(&self.balances).get(id) {
+            balance.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Limits the subscriber by the given balance.
+    pub(crate) fn insert(&self, id: SmartChannelId, balance: Balance) {
+        lock(&self.balances).insert(id, balance);
+    }
+
+    /// Returns the balance of the subscriber, if it is limited by its credits.
+    pub(crate) fn get(&self, id: &SmartChannelId) -> Option<Balance> {
+        lock(&self.balances).get(id).cloned()
+    }
+
+    /// Forgets the credits of a removed subscriber.
+    pub(crate) fn remove(&self, id: &SmartChannelId) {
+        lock(&self.balances).remove(id);
+    }
+}
+
+/// A receiver whose subscriber is only written to up to the credits it grants, returned by
+/// `NotifierHub::subscribe_with_credits`. Each message written to it spends a credit, and once it has none left
+/// the writings fail with `FailureKind::NoCredit`, handing the message back in the report of the publish.
+pub struct CreditReceiver<M> {
+    receiver: MessageReceiver<M>,
+    balance: Balance,
+}
+
+impl<M> CreditReceiver<M> {
+    /// Returns the id of the subscriber.
+    pub fn id(&self) -> SmartChannelId {
+        self.receiver.id()
+    }
+
+    /// Returns the receiver, for the methods of the hub taking a receiver such as `unsubscribe` or `is_subscribed`.
+    pub fn receiver(&self) -> &MessageReceiver<M> {
+        &self.receiver
+    }
+
+    /// Allows `n` more messages to be written to the subscriber.
+    pub fn grant(&self, n: usize) {
+        let _ = self
+            .balance
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |balance| {
+                Some(balance.saturating_add(n))
+            });
+    }
+
+    /// Returns the number of messages that can still be written to the subscriber.
+    pub fn credits(&self) -> usize {
+        self.balance.load(Ordering::SeqCst)
+    }
+
+    /// Receives the next message, `None` once the subscription is over.
+    pub async fn recv(&mut self) -> Option<M> {
+        self.receiver.recv().await
+    }
+
+    /// Same as `recv` without waiting.
+    pub fn try_recv(&mut self) -> Result<M, TryRecvError> {
+        self.receiver.try_recv()
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Same as `subscribe`, the hub only writing to the subscriber the messages it has been granted credits for,
+    /// starting with `credits`, see `CreditReceiver::grant`. Unlike a full buffer, which makes the publishes wait,
+    /// a subscriber out of credits fails its writings right away, so the flow is bounded end to end.
+    /// The close messages of the shutdowns do not need a credit.
+    /// As with `subscribe`, the returned receiver is already over while the hub is draining.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let mut receiver = hub.subscribe_with_credits(&"jobs", 10, 1);
+    ///
+    /// assert_eq!(hub.clone_send("first", &"jobs").unwrap().wait(None).await.delivered(), 1);
+    /// let report = hub.clone_send("second", &"jobs").unwrap().wait(None).await;
+    /// assert_eq!(report.failed(), 1);
+    ///
+    /// assert_eq!(receiver.recv().await.unwrap(), "first");
+    /// receiver.grant(1);
+    /// hub.clone_send("third", &"jobs").unwrap();
+    /// assert_eq!(receiver.recv().await.unwrap(), "third");
+    /// # }
+    /// ```
+    pub fn subscribe_with_credits(
+        &mut self,
+        id: &ChannelId,
+        channel_size: usize,
+        credits: usize,
+    ) -> CreditReceiver<M> {
+        let balance = Balance::default();
+        // The credits are set before the parked messages are written, so the flush spends them
+        let receiver = self.subscribe_prepared(id, channel_size, |hub, subscriber| {
+            balance.store(credits, Ordering::SeqCst);
+            hub.credits.insert(subscriber, Arc::clone(&balance));
+        });
+        CreditReceiver { receiver, balance }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Admits a writing to the subscriber made without a writing handler, as `WritingHandler` does for its own:
+    /// the circuit of the subscriber must be closed, and a credit is spent if it is limited by its credits.
+    pub(crate) fn admit_writing(&self, id: &SmartChannelId) -> Result<(), FailureKind> {
+        if !self.breaker.allows(id) {
+            return Err(FailureKind::CircuitOpen);
+        }
+        if !self.credits.spend(id) {
+            return Err(FailureKind::NoCredit);
+        }
+        Ok(())
+    }
+
+    /// Writes the message to the subscriber without waiting, if `admit_writing` admits it, and hands it back
+    /// otherwise. The credit is given back if the buffer is full, and a closed receiver is reported to the breaker.
+    pub(crate) fn try_write(&self, sender: &MessageSender<M>, msg: M) -> Result<(), M> {
+        if self.admit_writing(sender.id()).is_err() {
+            return Err(msg);
+        }
+        match sender.try_send(msg) {
+            Ok(()) => {
+                self.breaker.record(*sender.id(), true);
+                Ok(())
+            }
+            Err(e) => {
+                self.credits.refund(sender.id());
+                if let TrySendError::Closed(_) = e {
+                    self.breaker.record(*sender.id(), false);
+                }
+                Err(e.into_inner())
+            }
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::subscribe_with_credits`.
+    pub fn subscribe_with_credits(
+        &self,
+        id: &ChannelId,
+        channel_size: usize,
+        credits: usize,
+    ) -> CreditReceiver<M> {
+        self.with(|hub| hub.subscribe_with_credits(id, channel_size, credits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{closable_trait::ClosableMessage, error::NotifierError};
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Job {
+        Run(u32),
+        Close,
+    }
+
+    impl ClosableMessage for Job {
+        fn get_close_message() -> Self {
+            Job::Close
+        }
+    }
+
+    #[tokio::test]
+    async fn test_credits() {
+        let mut hub: NotifierHub<Job, &'static str> = NotifierHub::new();
+        let mut credited = hub.subscribe_with_credits(&"channel1", 10, 2);
+        let mut plain = hub.subscribe(&"channel1", 10);
+
+        for job in 0..3 {
+            hub.clone_send(Job::Run(job), &"channel1").unwrap();
+        }
+        let report = hub
+            .clone_send(Job::Run(3), &"channel1")
+            .unwrap()
+            .wait(None)
+            .await;
+        assert_eq!((report.delivered(), report.failed()), (1, 1));
+        let failure = &report.failures()[0];
+        assert_eq!(failure.kind, FailureKind::NoCredit);
+        assert_eq!(failure.subscriber, Some(credited.id()));
+        assert_eq!(failure.msg, Some(Job::Run(3)));
+        assert_eq!(credited.credits(), 0);
+
+        credited.grant(1);
+        hub.clone_send(Job::Run(4), &"channel1").unwrap();
+        hub.shutdown_clone(&"channel1").unwrap();
+        for expected in [0, 1, 4].map(Job::Run).into_iter().chain([Job::Close]) {
+            assert_eq!(credited.recv().await.unwrap(), expected);
+        }
+        assert_eq!(plain.try_recv().unwrap(), Job::Run(0));
+        assert!(hub.credits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_credits_of_transactions() {
+        let mut hub: NotifierHub<Job, &'static str> = NotifierHub::new();
+        let mut credited = hub.subscribe_with_credits(&"channel1", 10, 1);
+        let mut plain = hub.subscribe(&"channel2", 10);
+
+        let mut transaction = hub.transaction();
+        transaction
+            .send(Job::Run(0), &"channel1")
+            .send(Job::Run(1), &"channel2");
+        assert_eq!(transaction.commit().unwrap(), 2);
+        assert_eq!(credited.credits(), 0);
+
+        let mut transaction = hub.transaction();
+        transaction
+            .send(Job::Run(2), &"channel2")
+            .send(Job::Run(3), &"channel1");
+        assert!(matches!(
+            transaction.commit(),
+            Err(NotifierError::TransactionRolledBack("channel1", id)) if id == credited.id()
+        ));
+        assert_eq!(credited.try_recv().unwrap(), Job::Run(0));
+        assert!(credited.try_recv().is_err());
+        assert_eq!(plain.try_recv().unwrap(), Job::Run(1));
+        assert!(plain.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_credits_of_parked_messages() {
+        let mut hub: NotifierHub<Job, &'static str> = NotifierHub::new();
+        hub.set_park_buffer(&"channel1", Some(10));
+        for job in 0..3 {
+            hub.clone_send(Job::Run(job), &"channel1").unwrap();
+        }
+
+        let mut credited = hub.subscribe_with_credits(&"channel1", 10, 2);
+        assert_eq!(credited.try_recv().unwrap(), Job::Run(0));
+        assert_eq!(credited.try_recv().unwrap(), Job::Run(1));
+        assert!(credited.try_recv().is_err());
+        assert_eq!(credited.credits(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_credits_of_replays() {
+        let mut hub: NotifierHub<Job, &'static str> = NotifierHub::new();
+        hub.set_journal(&"channel1", Some(10));
+        let mut credited = hub.subscribe_with_credits(&"channel1", 10, 3);
+        for job in 0..2 {
+            hub.clone_send(Job::Run(job), &"channel1").unwrap();
+        }
+        assert_eq!(credited.credits(), 1);
+
+        assert_eq!(
+            hub.request_replay(&"channel1", credited.receiver(), 0)
+                .unwrap(),
+            1
+        );
+        for expected in [0, 1, 0].map(Job::Run) {
+            assert_eq!(credited.try_recv().unwrap(), expected);
+        }
+        assert!(credited.try_recv().is_err());
+        assert_eq!(credited.credits(), 0);
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    collections::{hash_map::RandomState, HashSet, VecDeque},
+    hash::{BuildHasher, Hash},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+use tokio::time::{Duration, Instant};
+
+use crate::{
+    error::NotifierError, handle::HubHandle, notifier::NotifierHub, sync::lock,
+    writing_handler::WritingHandler,
+};
+
+/// Implemented by the messages carrying an id, so the hub can recognize a message published twice.
+pub trait MessageId {
+    /// Returns the id of the message, the same message published again must return the same id.
+    fn message_id(&self) -> u64;
+}
+
+/// A message with an id generated by the hub, see `NotifierHub::tag`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tagged<M> {
+    /// The id of the message.
+    pub id: u64,
+    /// The message itself.
+    pub msg: M,
+}
+
+impl<M> MessageId for Tagged<M> {
+    fn message_id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// The ids published on a channel during its window.
+#[derive(Debug)]
+pub(crate) struct DedupWindow {
+    window: Duration,
+    seen: HashSet<u64>,
+    /// The ids in publish order, to forget them once the window is over.
+    order: VecDeque<(Instant, u64)>,
+}
+
+impl DedupWindow {
+    /// Returns true if each remembered id has a single entry in the publish order.
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn is_consistent(&self) -> bool {
+        self.seen.len() == self.order.len()
+            && self.order.iter().all(|(_, id)| self.seen.contains(id))
+    }
+
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns the duration of the window.
+    pub(crate) fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Forgets the ids published before the window.
+    fn expire(&mut self, now: Instant) {
+        while let Some(&(published, id)) = self.order.front() {
+            if now.duration_since(published) < self.window {
+                break;
+            }
+            self.order.pop_front();
+            self.seen.remove(&id);
+        }
+    }
+
+    fn contains(&mut self, id: u64, now: Instant) -> bool {
+        self.expire(now);
+        self.seen.contains(&id)
+    }
+
+    fn insert(&mut self, id: u64, now: Instant) {
+        if self.seen.insert(id) {
+            self.order.push_back((now, id));
+        }
+    }
+}
+
+/// Returns the first id generated by a hub. It is random, so the ids of different hubs don't collide
+/// when their messages are bridged together.
+pub(crate) fn first_message_id() -> AtomicU64 {
+    AtomicU64::new(RandomState::new().hash_one(0u8))
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Skips the messages published with `dedup_send` on the channel if a message with the same id
+    /// was already published on it during the `window`. `None` disables the deduplication of the channel.
+    /// Setting a new window forgets the ids already published.
+    pub fn set_dedup_window(&mut self, id: &ChannelId, window: Option<Duration>)
+    where
+        ChannelId: Clone,
+    {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        match window {
+            Some(window) => self
+                .dedup_windows
+                .insert(id, Mutex::new(DedupWindow::new(window))),
+            None => self.dedup_windows.remove(&id),
+        };
+    }
+
+    /// Returns the deduplication window of the channel, if any.
+    pub fn dedup_window(&self, id: &ChannelId) -> Option<Duration> {
+        let id = self.aliases.get(id).unwrap_or(id);
+        self.dedup_windows.get(id).map(|w| lock(w).window())
+    }
+
+    /// Returns true if a message with this id was published on the channel during its window.
+    pub fn is_duplicate(&self, id: &ChannelId, msg_id: u64) -> bool {
+        let id = self.aliases.get(id).unwrap_or(id);
+        self.dedup_windows
+            .get(id)
+            .is_some_and(|w| lock(w).contains(msg_id, self.now()))
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: MessageId + Clone + Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `clone_send`, but the message is skipped if its id was already published on the channel
+    /// during its deduplication window, protecting the subscribers from the retries of the publishers
+    /// and from the loops between bridged hubs. A skipped message returns an empty handler.
+    /// The id is only remembered if the message is published, so a failed publish can be retried.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    /// use std::time::Duration;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// hub.set_dedup_window(&"orders", Some(Duration::from_secs(60)));
+    /// let mut receiver = hub.subscribe(&"orders", 10);
+    ///
+    /// let order = hub.tag("order 1");
+    /// hub.dedup_send(order.clone(), &"orders").unwrap();
+    /// hub.dedup_send(order, &"orders").unwrap(); // Retried by the publisher
+    /// assert_eq!(receiver.try_recv().unwrap().msg, "order 1");
+    /// assert!(receiver.try_recv().is_err());
+    /// ```
+    pub fn dedup_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        let resolved = self.aliases.get(id).unwrap_or(id);
+        let Some(window) = self.dedup_windows.get(resolved) else {
+            return self.clone_send(msg, id);
+        };
+        let msg_id = msg.message_id();
+        let mut window = lock(window);
+        if window.contains(msg_id, self.now()) {
+            return Ok(WritingHandler::empty());
+        }
+        let handler = self.clone_send(msg, id)?;
+        window.insert(msg_id, self.now());
+        Ok(handler)
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<Tagged<M>, ChannelId> {
+    /// Wraps the message with a new id, unique for this hub. Publishing the returned message again
+    /// with `dedup_send`, or through a bridge, keeps the id so the copies are recognized.
+    pub fn tag(&self, msg: M) -> Tagged<M> {
+        Tagged {
+            id: self.next_message_id.fetch_add(1, Ordering::Relaxed),
+            msg,
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::set_dedup_window`.
+    pub fn set_dedup_window(&self, id: &ChannelId, window: Option<Duration>) {
+        self.with(|hub| hub.set_dedup_window(id, window))
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: MessageId + Clone + Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::dedup_send`.
+    pub fn dedup_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.dedup_send(msg, id))
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> HubHandle<Tagged<M>, ChannelId> {
+    /// See `NotifierHub::tag`.
+    pub fn tag(&self, msg: M) -> Tagged<M> {
+        self.with(|hub| hub.tag(msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Order(u64);
+
+    impl MessageId for Order {
+        fn message_id(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dedup_window() {
+        let mut hub: NotifierHub<Order, &'static str> = NotifierHub::new();
+        hub.set_dedup_window(&"orders", Some(Duration::from_secs(10)));
+        let mut receiver = hub.subscribe(&"orders", 10);
+
+        hub.dedup_send(Order(1), &"orders").unwrap();
+        assert!(hub.is_duplicate(&"orders", 1));
+        assert_eq!(hub.dedup_send(Order(1), &"orders").unwrap().pending(), 0);
+        hub.dedup_send(Order(2), &"orders").unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), Order(1));
+        assert_eq!(receiver.try_recv().unwrap(), Order(2));
+        assert!(receiver.try_recv().is_err());
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(!hub.is_duplicate(&"orders", 1));
+        hub.dedup_send(Order(1), &"orders").unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), Order(1));
+    }
+
+    #[tokio::test]
+    async fn test_failed_publish_is_not_remembered() {
+        let mut hub: NotifierHub<Tagged<u32>, &'static str> = NotifierHub::new();
+        hub.set_dedup_window(&"orders", Some(Duration::from_secs(10)));
+        let msg = hub.tag(1);
+        assert_ne!(hub.tag(1).id, msg.id);
+
+        assert!(hub.dedup_send(msg.clone(), &"orders").is_err());
+        let mut receiver = hub.subscribe(&"orders", 10);
+        hub.dedup_send(msg.clone(), &"orders").unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), msg);
+    }
+}
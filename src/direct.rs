@@ -0,0 +1,370 @@
+use smart_channel::channel;
+use std::hash::Hash;
+
+use crate::{
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::{MessageReceiver, MessageSender, NotifierHub, SmartChannelId},
+    writing_handler::WritingHandler,
+};
+
+/// The private inbox of a subscriber, returned by `NotifierHub::subscribe_with_inbox`.
+/// It receives the messages given to `send_direct` for this subscriber, and is over once
+/// the subscriber is no longer subscribed to any channel.
+pub type Inbox<M> = MessageReceiver<M>;
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Same as `subscribe`, along with a private inbox for the subscriber, registered in the hub under the id
+    /// of its receiver, so the others can write to it with `send_direct` without a channel per subscriber.
+    /// The inbox is closed when the subscriber leaves its last channel, by an unsubscription, a shutdown,
+    /// `clean_channel` or a garbage collection.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let (client, mut inbox) = hub.subscribe_with_inbox(&"news", 10);
+    ///
+    /// hub.send_direct("welcome", &client.id()).unwrap().wait(None).await;
+    /// assert_eq!(inbox.recv().await, Some("welcome"));
+    ///
+    /// hub.unsubscribe(&"news", &client).unwrap();
+    /// assert_eq!(inbox.recv().await, None);
+    /// # }
+    /// ```
+    pub fn subscribe_with_inbox(
+        &mut self,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> (MessageReceiver<M>, Inbox<M>) {
+        let receiver = self.subscribe(id, channel_size);
+        let (sender, inbox) = channel(channel_size, receiver.id());
+        if self.is_subscriber(&receiver.id()) {
+            self.inboxes.insert(receiver.id(), sender);
+        }
+        (receiver, inbox)
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Writes the message to the receiver only, once through each channel it is subscribed to, for the
+    /// control messages targeting a particular consumer. The other subscribers of these channels receive nothing,
+    /// and the message skips the rate limits, the sequencers, the journals and the broadcast hooks of the channels.
+    /// Fails with `NotifierError::UnknownSubscriber`, handing the message back, if the receiver is not
+    /// subscribed to any channel, and with `NotifierError::PublishNotAllowed` if one of its channels is protected.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let mut worker = hub.subscribe_multiple(&["jobs", "urgent"], 10);
+    /// let mut other = hub.subscribe(&"jobs", 10);
+    ///
+    /// hub.send_to_subscriber("pause", &worker).unwrap().wait(None).await;
+    /// assert_eq!(worker.recv().await, Some("pause"));
+    /// assert_eq!(worker.recv().await, Some("pause"));
+    /// assert!(other.try_recv().is_err());
+    ///
+    /// hub.send_to_subscriber_on("resume", &worker, &"urgent").unwrap().wait(None).await;
+    /// assert_eq!(worker.recv().await, Some("resume"));
+    /// # }
+    /// ```
+    pub fn send_to_subscriber(
+        &self,
+        msg: M,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        let mut senders = Vec::new();
+        for (id, channel) in &self.senders {
+            if let Some(sender) = channel.iter().find(|sender| sender.is_bound_to(receiver)) {
+                if let Err(refusal) = self.route_of(id, false) {
+                    return Err(refusal.into_error(id.clone(), msg));
+                }
+                senders.push(sender);
+            }
+        }
+        if senders.is_empty() {
+            return Err(NotifierError::UnknownSubscriber {
+                subscriber: receiver.id(),
+                msg,
+            });
+        }
+        Ok(self.publish_handler(None).cloning_broadcast(msg, senders))
+    }
+
+    /// Writes the message once to the subscriber with the given id, the one of its receiver, whatever the
+    /// channels it is subscribed to, for the point-to-point replies without a channel per subscriber.
+    /// The message goes to the inbox of the subscriber if it has one, see `subscribe_with_inbox`.
+    /// As `send_to_subscriber`, the other subscribers receive nothing, and it fails with
+    /// `NotifierError::UnknownSubscriber` if the subscriber is not subscribed to any channel.
+    /// Without an inbox, the message goes through a channel of the subscriber that accepts the publish, in order
+    /// with the publishes queued by its sequencer if any, so the protected channels are passed over. It only fails
+    /// with `NotifierError::PublishNotAllowed` if all of them are protected. Which of several open channels is used
+    /// is unspecified, see `send_to_subscriber_on` to pick it. An inbox belongs to no channel, so no protection applies to it.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let mut client = hub.subscribe_multiple(&["news", "sports"], 10);
+    /// let requester = client.id(); // Given along with the request of the client
+    ///
+    /// hub.send_direct("reply", &requester).unwrap().wait(None).await;
+    /// assert_eq!(client.recv().await, Some("reply"));
+    /// assert!(client.try_recv().is_err());
+    /// # }
+    /// ```
+    pub fn send_direct(
+        &self,
+        msg: M,
+        subscriber: &SmartChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        if let Some(inbox) = self.inboxes.get(subscriber) {
+            return Ok(self.publish_handler(None).cloning_broadcast(msg, [inbox]));
+        }
+        let mut refused = None;
+        for (id, channel) in &self.senders {
+            let Some(sender) = channel.iter().find(|sender| sender.id() == subscriber) else {
+                continue;
+            };
+            match self.route_of(id, false) {
+                Ok(_) => return Ok(self.write_through(msg, sender, id)),
+                Err(refusal) => refused = Some((id, refusal)),
+            }
+        }
+        match refused {
+            Some((id, refusal)) => Err(refusal.into_error(id.clone(), msg)),
+            None => Err(NotifierError::UnknownSubscriber {
+                subscriber: *subscriber,
+                msg,
+            }),
+        }
+    }
+
+    /// Same as `send_to_subscriber`, only through the given channel, in order with the publishes queued by
+    /// its sequencer if any.
+    /// Fails with `NotifierError::NotSubscribed` if the receiver is not subscribed to it,
+    /// and with `NotifierError::PublishNotAllowed` if it is protected.
+    pub fn send_to_subscriber_on(
+        &self,
+        msg: M,
+        receiver: &MessageReceiver<M>,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        let id = self.aliases.get(id).unwrap_or(id);
+        let sender = self
+            .senders_of(id)
+            .iter()
+            .find(|sender| sender.is_bound_to(receiver))
+            .ok_or_else(|| NotifierError::NotSubscribed(id.clone()))?;
+        if let Err(refusal) = self.route_of(id, false) {
+            return Err(refusal.into_error(id.clone(), msg));
+        }
+        Ok(self.write_through(msg, sender, id))
+    }
+
+    /// Writes the message to the subscriber through the channel, queued in its sequencer if it has one.
+    fn write_through(
+        &self,
+        msg: M,
+        sender: &MessageSender<M>,
+        id: &ChannelId,
+    ) -> WritingHandler<M, ChannelId> {
+        let handler = self.publish_handler(Some(id));
+        match self.sequencers.get(id) {
+            Some(sequencer) => sequencer.publish(handler, msg, [sender]),
+            None => handler.cloning_broadcast(msg, [sender]),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::subscribe_with_inbox`.
+    pub fn subscribe_with_inbox(
+        &self,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> (MessageReceiver<M>, Inbox<M>) {
+        self.with(|hub| hub.subscribe_with_inbox(id, channel_size))
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::send_to_subscriber`.
+    pub fn send_to_subscriber(
+        &self,
+        msg: M,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.send_to_subscriber(msg, receiver))
+    }
+
+    /// See `NotifierHub::send_direct`.
+    pub fn send_direct(
+        &self,
+        msg: M,
+        subscriber: &SmartChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.send_direct(msg, subscriber))
+    }
+
+    /// See `NotifierHub::send_to_subscriber_on`.
+    pub fn send_to_subscriber_on(
+        &self,
+        msg: M,
+        receiver: &MessageReceiver<M>,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.send_to_subscriber_on(msg, receiver, id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_to_subscriber() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut receiver1 = hub.subscribe_multiple(&["channel1", "channel2"], 10);
+        let mut receiver2 = hub.subscribe(&"channel1", 10);
+        hub.rename_channel(&"channel2", "channel3", true).unwrap();
+
+        hub.send_to_subscriber(1, &receiver1)
+            .unwrap()
+            .wait(None)
+            .await;
+        hub.send_to_subscriber_on(2, &receiver1, &"channel2")
+            .unwrap()
+            .wait(None)
+            .await;
+        for i in [1, 1, 2] {
+            assert_eq!(receiver1.recv().await, Some(i));
+        }
+        assert!(receiver2.try_recv().is_err());
+        assert!(matches!(
+            hub.send_to_subscriber_on(3, &receiver2, &"channel3"),
+            Err(NotifierError::NotSubscribed("channel3"))
+        ));
+
+        hub.grant_publish(&"channel3");
+        assert!(matches!(
+            hub.send_to_subscriber(3, &receiver1),
+            Err(NotifierError::PublishNotAllowed {
+                id: "channel3",
+                msg: 3
+            })
+        ));
+        assert!(matches!(
+            hub.send_to_subscriber_on(3, &receiver1, &"channel2"),
+            Err(NotifierError::PublishNotAllowed {
+                id: "channel3",
+                msg: 3
+            })
+        ));
+        hub.grant_publish(&"channel1");
+        assert!(matches!(
+            hub.send_direct(3, &receiver2.id()),
+            Err(NotifierError::PublishNotAllowed {
+                id: "channel1",
+                msg: 3
+            })
+        ));
+        assert!(receiver1.try_recv().is_err());
+
+        hub.unsubscribe(&"channel1", &receiver2).unwrap();
+        let Err(err) = hub.send_to_subscriber(4, &receiver2) else {
+            panic!("The receiver is no longer subscribed");
+        };
+        assert_eq!(err.into_undelivered(), vec![4]);
+    }
+
+    #[tokio::test]
+    async fn test_send_direct() {
+        let handle: HubHandle<u32, &'static str> = NotifierHub::new().into_handle();
+        let mut receiver1 = handle.subscribe_multiple(&["channel1", "channel2"], 10);
+        let mut receiver2 = handle.subscribe(&"channel1", 10);
+
+        handle
+            .send_direct(1, &receiver1.id())
+            .unwrap()
+            .wait(None)
+            .await;
+        assert_eq!(receiver1.recv().await, Some(1));
+        assert!(receiver1.try_recv().is_err());
+        assert!(receiver2.try_recv().is_err());
+
+        // The protected channel is passed over for the open one
+        handle.with(|hub| hub.grant_publish(&"channel1"));
+        handle
+            .send_direct(2, &receiver1.id())
+            .unwrap()
+            .wait(None)
+            .await;
+        assert_eq!(receiver1.recv().await, Some(2));
+        handle.with(|hub| hub.grant_publish(&"channel2"));
+        assert!(matches!(
+            handle.send_direct(2, &receiver1.id()),
+            Err(NotifierError::PublishNotAllowed { msg: 2, .. })
+        ));
+
+        let subscriber = handle.with(|hub| hub.get_new_id());
+        assert!(matches!(
+            handle.send_direct(2, &subscriber),
+            Err(NotifierError::UnknownSubscriber { msg: 2, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_inbox() {
+        let handle: HubHandle<u32, &'static str> = NotifierHub::new().into_handle();
+        let (receiver1, mut inbox1) = handle.subscribe_with_inbox(&"channel1", 10);
+        let (receiver2, mut inbox2) = handle.subscribe_with_inbox(&"channel1", 10);
+        handle.with(|hub| {
+            let sender = hub.senders_of(&"channel1")[0].clone();
+            hub.insert_sender(sender, &"channel2");
+        });
+
+        handle
+            .send_direct(1, &receiver1.id())
+            .unwrap()
+            .wait(None)
+            .await;
+        handle.clone_send(2, &"channel1").unwrap().wait(None).await;
+        assert_eq!(inbox1.recv().await, Some(1));
+        assert!(inbox1.try_recv().is_err());
+        assert!(inbox2.try_recv().is_err());
+
+        // The inbox lives as long as the subscriber is in some channel
+        handle.unsubscribe(&"channel1", &receiver1).unwrap();
+        handle
+            .send_direct(3, &receiver1.id())
+            .unwrap()
+            .wait(None)
+            .await;
+        assert_eq!(inbox1.recv().await, Some(3));
+        handle.unsubscribe_all(&receiver1);
+        assert_eq!(inbox1.recv().await, None);
+
+        drop(receiver2);
+        handle.with(|hub| hub.collect_garbage());
+        assert_eq!(inbox2.recv().await, None);
+    }
+}
@@ -0,0 +1,136 @@
+use std::{future::Future, hash::Hash};
+use tokio::time::{sleep, Duration};
+
+use crate::{
+    handle::HubHandle,
+    notifier::NotifierHub,
+    weak_sender::{Downgrade, WeakMessageSender},
+};
+
+/// How often `drained` checks the subscribers.
+pub const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Starts draining the hub before a restart: the `try_subscribe` family and `subscribe_group`
+    /// fail with a `Draining` error, while `subscribe`, `subscribe_multiple` and `subscribe_with_credits`
+    /// return a receiver that is already over, as they can't fail. The subscribers that need to know
+    /// why their subscription ended right away should use the `try_subscribe` family, or check `is_draining`.
+    /// The existing subscribers and the publishes are not affected, so the in-flight work can finish.
+    pub fn begin_drain(&mut self) {
+        self.draining = true;
+    }
+
+    /// Stops draining the hub, the subscriptions are accepted again.
+    pub fn end_drain(&mut self) {
+        self.draining = false;
+    }
+
+    /// Returns true if the hub is draining.
+    pub fn is_draining(&self) -> bool {
+        self.draining
+    }
+
+    /// Returns a future resolving once all the channels are over, meaning all the current subscribers
+    /// dropped their receiver (the closed senders are removed by the next `clean_channel`). The future does not borrow the hub, and does not keep the receivers open.
+    /// The subscribers are checked every `DRAIN_POLL_INTERVAL`, and the ones added after this call are not awaited,
+    /// which is why it is meant to be called after `begin_drain`.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub: NotifierHub<u32, &str> = NotifierHub::new();
+    /// let receiver = hub.subscribe(&"jobs", 10);
+    ///
+    /// hub.begin_drain();
+    /// let drained = hub.drained();
+    /// drop(receiver); // The last job is done
+    /// drained.await;
+    /// # }
+    /// ```
+    pub fn drained(&self) -> impl Future<Output = ()> + Send + 'static
+    where
+        M: Send + 'static,
+    {
+        let subscribers: Vec<WeakMessageSender<M>> = self
+            .senders
+            .values()
+            .flatten()
+            .map(Downgrade::downgrade)
+            .collect();
+        async move {
+            while subscribers.iter().any(WeakMessageSender::is_alive) {
+                sleep(DRAIN_POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::begin_drain`.
+    pub fn begin_drain(&self) {
+        self.with(|hub| hub.begin_drain())
+    }
+
+    /// See `NotifierHub::end_drain`.
+    pub fn end_drain(&self) {
+        self.with(|hub| hub.end_drain())
+    }
+
+    /// See `NotifierHub::is_draining`.
+    pub fn is_draining(&self) -> bool {
+        self.with(|hub| hub.is_draining())
+    }
+
+    /// See `NotifierHub::drained`, the hub is only locked while the subscribers are collected.
+    pub fn drained(&self) -> impl Future<Output = ()> + Send + 'static
+    where
+        M: Send + 'static,
+    {
+        self.with(|hub| hub.drained())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::NotifierError, notifier::ChannelState};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drain() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut receiver = hub.subscribe(&"channel1", 10);
+
+        hub.begin_drain();
+        assert!(matches!(
+            hub.try_subscribe(&"channel1", 10),
+            Err(NotifierError::Draining)
+        ));
+        assert!(matches!(
+            hub.try_subscribe_multiple(&["channel1", "channel2"], 10),
+            Err(NotifierError::Draining)
+        ));
+        let mut late = hub.subscribe(&"channel2", 10);
+        assert!(late.recv().await.is_none());
+        let mut late = hub.subscribe_multiple(&["channel2", "channel3"], 10);
+        assert!(late.recv().await.is_none());
+        let mut late = hub.subscribe_with_credits(&"channel2", 10, 1);
+        assert!(late.recv().await.is_none());
+        assert_eq!(hub.channel_state(&"channel2"), ChannelState::Uninitialised);
+
+        let drained = tokio::spawn(hub.drained());
+        hub.clone_send(1, &"channel1").unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), 1);
+        sleep(Duration::from_secs(1)).await;
+        assert!(!drained.is_finished());
+
+        drop(receiver);
+        drained.await.unwrap();
+        assert_eq!(hub.clean_channel(&"channel1"), ChannelState::Over);
+
+        hub.end_drain();
+        assert!(hub.try_subscribe(&"channel1", 10).is_ok());
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Mutex, Weak},
+};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    handle::HubHandle,
+    notifier::{MessageSender, NotifierHub, SmartChannelId},
+    sync::lock,
+};
+
+/// Spawns the monitor of a subscriber, and returns its cancellation.
+type SpawnMonitor<M> = Box<dyn Fn(&MessageSender<M>) -> oneshot::Sender<()> + Send + Sync>;
+
+/// The monitors of the subscribers of a hub detecting the dropped receivers.
+pub(crate) struct DropDetection<M> {
+    spawn_monitor: SpawnMonitor<M>,
+    /// Binding each monitored subscriber with the cancellation of its monitor, dropping it stops the monitor.
+    monitors: HashMap<SmartChannelId, oneshot::Sender<()>>,
+}
+
+impl<M> DropDetection<M> {
+    /// Monitors the subscriber, unless it already is.
+    fn watch(&mut self, sender: &MessageSender<M>) {
+        if !self.monitors.contains_key(sender.id()) {
+            let cancel = (self.spawn_monitor)(sender);
+            self.monitors.insert(*sender.id(), cancel);
+        }
+    }
+}
+
+/// Returns the spawner of the monitors, reporting the dropped receivers on `dropped`.
+/// A monitor only holds the tokio sender, as the sender of a subscriber is shared by all its channels.
+fn spawn_monitor<M: Send + 'static>(
+    dropped: mpsc::UnboundedSender<SmartChannelId>,
+) -> SpawnMonitor<M> {
+    Box::new(move |sender| {
+        let (cancel, cancelled) = oneshot::channel();
+        let (id, sender, dropped) = (*sender.id(), (**sender).clone(), dropped.clone());
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = sender.closed() => {
+                    let _ = dropped.send(id);
+                }
+                _ = cancelled => {}
+            }
+        });
+        cancel
+    })
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Monitors the new subscriber if the drop detection is enabled.
+    pub(crate) fn watch_drop(&mut self, sender: &MessageSender<M>) {
+        if let Some(detection) = &mut self.drop_detection {
+            detection.watch(sender);
+        }
+    }
+
+    /// Returns true if the dropped receivers are detected, see `HubHandle::set_drop_detection`.
+    pub fn has_drop_detection(&self) -> bool {
+        self.drop_detection.is_some()
+    }
+
+    /// Stops the monitor of the subscriber, so it doesn't keep its receiver open.
+    pub(crate) fn unwatch_drop(&mut self, id: &SmartChannelId) {
+        if let Some(detection) = &mut self.drop_detection {
+            detection.monitors.remove(id);
+        }
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Cleans the channels of the subscriber whose receiver has been dropped.
+    fn subscriber_dropped(&mut self, id: &SmartChannelId) {
+        if let Some(detection) = &mut self.drop_detection {
+            detection.monitors.remove(id);
+        }
+        let channels: Vec<_> = self
+            .senders
+            .iter()
+            .filter(|(_, senders)| senders.iter().any(|sender| sender.id() == id))
+            .map(|(channel, _)| channel.clone())
+            .collect();
+        for channel in channels {
+            self.clean_channel(&channel);
+        }
+    }
+}
+
+/// Cleans the channels of the dropped receivers reported by the monitors, until the hub is dropped
+/// or the drop detection disabled.
+async fn reap<M, ChannelId>(
+    hub: Weak<Mutex<NotifierHub<M, ChannelId>>>,
+    mut dropped: mpsc::UnboundedReceiver<SmartChannelId>,
+) where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    while let Some(id) = dropped.recv().await {
+        let Some(hub) = hub.upgrade() else {
+            return;
+        };
+        lock(&hub).subscriber_dropped(&id);
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    /// Enables or disables the detection of the dropped receivers. Once enabled, each subscriber is monitored
+    /// by a task of the hub, and when its receiver is dropped the hub cleans its channels right away as
+    /// `clean_channel` does: the sender is removed, the state waiters are given the new state and the
+    /// destruction waiters notified with the `DestructionReason::DetectedClosed` reason, without calling
+    /// `clean_channel` manually.
+    ///
+    /// Must be called within a tokio runtime, as the monitors are tasks. The monitor of a subscriber removed
+    /// otherwise is stopped by the next run of the runtime, until then its receiver is not over yet.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::{ChannelState, NotifierHub};
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let handle = NotifierHub::<String, &str>::new().into_handle();
+    /// handle.set_drop_detection(true);
+    /// let mut destructions = handle.get_destruction_waiter(&"jobs");
+    /// let worker = handle.subscribe(&"jobs", 10);
+    ///
+    /// drop(worker);
+    /// destructions.recv().await.unwrap();
+    /// assert_eq!(handle.channel_state(&"jobs"), ChannelState::Over);
+    /// # }
+    /// ```
+    pub fn set_drop_detection(&self, enabled: bool) {
+        let weak = Arc::downgrade(&self.hub);
+        self.with(|hub| {
+            if !enabled {
+                hub.drop_detection = None;
+                return;
+            }
+            if hub.drop_detection.is_some() {
+                return;
+            }
+            let (dropped, received) = mpsc::unbounded_channel();
+            tokio::spawn(reap(weak, received));
+            let mut detection = DropDetection {
+                spawn_monitor: spawn_monitor(dropped),
+                monitors: HashMap::new(),
+            };
+            for sender in hub.senders.values().flatten() {
+                detection.watch(sender);
+            }
+            hub.drop_detection = Some(detection);
+        })
+    }
+
+    /// See `NotifierHub::has_drop_detection`.
+    pub fn has_drop_detection(&self) -> bool {
+        self.with(|hub| hub.has_drop_detection())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notifier::ChannelState, waiter::DestructionReason};
+    use tokio::time::{sleep, Duration};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drop_detection() {
+        let handle: HubHandle<u32, &'static str> = NotifierHub::new().into_handle();
+        let before = handle.subscribe_multiple(&["channel1", "channel2"], 10);
+        handle.set_drop_detection(true);
+        let mut departures = handle.get_departure_waiter(&"channel1");
+        let mut states = handle.get_state_waiter(&"channel2");
+        let mut after = handle.subscribe(&"channel1", 10);
+
+        drop(before);
+        sleep(Duration::from_millis(1)).await;
+        let departure = departures.recv().await.unwrap();
+        assert_eq!(departure.reason, DestructionReason::DetectedClosed);
+        assert_eq!(states.recv().await, Some(ChannelState::Running));
+        assert_eq!(states.recv().await, Some(ChannelState::Over));
+        assert_eq!(handle.channel_number_subscriber(&"channel1"), 1);
+
+        // The monitor of an unsubscribed receiver no longer keeps it open
+        handle.unsubscribe(&"channel1", &after).unwrap();
+        let departure = departures.recv().await.unwrap();
+        assert_eq!(departure.reason, DestructionReason::Unsubscribed);
+        drop(departure);
+        assert_eq!(after.recv().await, None);
+        handle.with(|hub| assert!(hub.drop_detection.as_ref().unwrap().monitors.is_empty()));
+
+        handle.set_drop_detection(false);
+        let receiver = handle.subscribe(&"channel1", 10);
+        drop(receiver);
+        sleep(Duration::from_millis(1)).await;
+        assert_eq!(handle.channel_number_subscriber(&"channel1"), 1);
+    }
+}
@@ -0,0 +1,103 @@
+use std::{hash::Hash, sync::Arc};
+
+use crate::notifier::NotifierHub;
+
+/// Why a message has been discarded by the hub, as given to the drop hook.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DropReason {
+    /// The channel is over and does not park its messages.
+    ChannelOver,
+    /// The park buffer of the channel was full, its oldest message made room for the new one,
+    /// or the buffer has no room at all and the new message is dropped.
+    ParkOverflow,
+    /// The park buffer of the channel has been shrunk or disabled by `set_park_buffer`.
+    ParkResized,
+    /// The parked message did not fit in the buffer of the first subscriber of the channel.
+    FlushOverflow,
+}
+
+/// Called with each message discarded by the hub.
+pub(crate) type DropHook<M, ChannelId> = Arc<dyn Fn(&ChannelId, DropReason, M) + Send + Sync>;
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Registers the callback invoked with each message the hub discards, replacing the previous one,
+    /// so the dropped payloads can be logged or persisted instead of silently lost.
+    /// The callback is called inline by the operation dropping the message, so it should be quick.
+    /// The failed writings are not messages discarded by the hub, see `set_error_hook` for them.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub: NotifierHub<String, &str> = NotifierHub::new();
+    /// hub.set_drop_hook(|channel, reason, msg| eprintln!("{msg} dropped from {channel}: {reason:?}"));
+    /// hub.set_park_buffer(&"events", Some(1));
+    /// hub.clone_send("first".to_string(), &"events").unwrap();
+    /// hub.clone_send("second".to_string(), &"events").unwrap(); // "first dropped from events: ParkOverflow"
+    /// ```
+    pub fn set_drop_hook(
+        &mut self,
+        hook: impl Fn(&ChannelId, DropReason, M) + Send + Sync + 'static,
+    ) {
+        self.drop_hook = Some(Arc::new(hook));
+    }
+
+    /// Removes the drop hook.
+    pub fn remove_drop_hook(&mut self) {
+        self.drop_hook = None;
+    }
+
+    /// Returns true if a drop hook is registered.
+    pub fn has_drop_hook(&self) -> bool {
+        self.drop_hook.is_some()
+    }
+
+    /// Hands the discarded message to the drop hook, if any.
+    pub(crate) fn dropped(&self, id: &ChannelId, reason: DropReason, msg: M) {
+        if let Some(hook) = &self.drop_hook {
+            hook(id, reason, msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_drop_hook() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let hooked = dropped.clone();
+        hub.set_drop_hook(move |channel, reason, msg| {
+            hooked.lock().unwrap().push((*channel, reason, msg))
+        });
+
+        hub.set_park_buffer(&"channel1", Some(3));
+        for msg in 0..4 {
+            hub.clone_send(msg, &"channel1").unwrap();
+        }
+        hub.set_park_buffer(&"channel1", Some(2));
+        let receiver = hub.subscribe(&"channel1", 1);
+        hub.set_park_buffer(&"channel1", None);
+        hub.unsubscribe(&"channel1", &receiver).unwrap();
+        hub.clone_send(4, &"channel1").unwrap();
+        hub.send_with(&"channel1", || 5).unwrap();
+        hub.control_send(6, &"channel1").unwrap();
+
+        hub.remove_drop_hook();
+        hub.clone_send(7, &"channel1").unwrap();
+        assert_eq!(
+            *dropped.lock().unwrap(),
+            vec![
+                ("channel1", DropReason::ParkOverflow, 0),
+                ("channel1", DropReason::ParkResized, 1),
+                ("channel1", DropReason::FlushOverflow, 3),
+                ("channel1", DropReason::ChannelOver, 4),
+                ("channel1", DropReason::ChannelOver, 5),
+                ("channel1", DropReason::ChannelOver, 6),
+            ]
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    hash::Hash,
+};
+
+use crate::{
+    error::NotifierError,
+    notifier::{MessageReceiver, NotifierHub},
+    writing_handler::WritingHandler,
+};
+
+/// A registered channel of a `DynHub`, with the hub of its message type.
+struct DynChannel {
+    type_id: TypeId,
+    type_name: &'static str,
+    hub: Box<dyn Any + Send>,
+}
+
+/// A hub whose channels carry different message types, for the plugin systems where one hub
+/// per message type does not scale. Each channel is bound to its type at registration,
+/// and every subscription or publish is checked against it at runtime.
+/// Behind each channel is a `NotifierHub` of its type, reachable with `hub` for the rest of the API.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::{dyn_hub::DynHub, error::NotifierError};
+///
+/// let mut hub = DynHub::new();
+/// hub.register::<String>("logs").unwrap();
+/// hub.register::<u64>("ticks").unwrap();
+///
+/// let mut logs = hub.subscribe_typed::<String>(&"logs", 10).unwrap();
+/// hub.clone_send("started".to_string(), &"logs").unwrap();
+/// assert_eq!(logs.try_recv().unwrap(), "started");
+///
+/// assert!(matches!(
+///     hub.subscribe_typed::<String>(&"ticks", 10),
+///     Err(NotifierError::TypeMismatch { .. })
+/// ));
+/// ```
+pub struct DynHub<ChannelId: Eq + Hash> {
+    channels: HashMap<ChannelId, DynChannel>,
+}
+
+impl<ChannelId: Eq + Hash> Default for DynHub<ChannelId> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<ChannelId: Eq + Hash> DynHub<ChannelId> {
+    /// Returns a hub without any channel.
+    pub fn new() -> Self {
+        Self {
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Returns true if the channel is registered.
+    pub fn is_registered(&self, id: &ChannelId) -> bool {
+        self.channels.contains_key(id)
+    }
+
+    /// Returns the name of the message type of the channel, if it is registered.
+    pub fn message_type(&self, id: &ChannelId) -> Option<&'static str> {
+        self.channels.get(id).map(|channel| channel.type_name)
+    }
+}
+
+impl<ChannelId: Eq + Hash + Clone + Send + 'static> DynHub<ChannelId> {
+    /// Returns all the registered channels.
+    pub fn channels(&self) -> Vec<ChannelId> {
+        self.channels.keys().cloned().collect()
+    }
+
+    /// Registers the channel with its message type. Registering it again with the same type does nothing,
+    /// but registering it with another type fails with `ChannelAlreadyExist`.
+    pub fn register<T: Send + 'static>(
+        &mut self,
+        id: ChannelId,
+    ) -> Result<(), NotifierError<T, ChannelId>> {
+        match self.channels.get(&id) {
+            Some(channel) if channel.type_id == TypeId::of::<T>() => Ok(()),
+            Some(_) => Err(NotifierError::ChannelAlreadyExist(id)),
+            None => {
+                self.channels.insert(
+                    id,
+                    DynChannel {
+                        type_id: TypeId::of::<T>(),
+                        type_name: type_name::<T>(),
+                        hub: Box::new(NotifierHub::<T, ChannelId>::new()),
+                    },
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Unregisters the channel, its subscribers seeing the end of their channel.
+    /// Returns false if the channel was not registered.
+    pub fn unregister(&mut self, id: &ChannelId) -> bool {
+        self.channels.remove(id).is_some()
+    }
+
+    /// Returns the hub behind the channel, or an error if the channel is not registered with this type.
+    pub fn hub<T: Send + 'static>(
+        &self,
+        id: &ChannelId,
+    ) -> Result<&NotifierHub<T, ChannelId>, NotifierError<T, ChannelId>> {
+        let channel = self
+            .channels
+            .get(id)
+            .ok_or_else(|| NotifierError::ChannelNotExist(id.clone()))?;
+        channel
+            .hub
+            .downcast_ref()
+            .ok_or_else(|| Self::mismatch::<T>(id, channel))
+    }
+
+    /// Same as `hub`, with an exclusive access.
+    pub fn hub_mut<T: Send + 'static>(
+        &mut self,
+        id: &ChannelId,
+    ) -> Result<&mut NotifierHub<T, ChannelId>, NotifierError<T, ChannelId>> {
+        let channel = self
+            .channels
+            .get_mut(id)
+            .ok_or_else(|| NotifierError::ChannelNotExist(id.clone()))?;
+        if channel.type_id != TypeId::of::<T>() {
+            return Err(Self::mismatch::<T>(id, channel));
+        }
+        channel
+            .hub
+            .downcast_mut()
+            .ok_or_else(|| NotifierError::ChannelNotExist(id.clone()))
+    }
+
+    /// Subscribes to the channel, failing right away if it is not registered with the type `T`.
+    pub fn subscribe_typed<T: Send + 'static>(
+        &mut self,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> Result<MessageReceiver<T>, NotifierError<T, ChannelId>> {
+        Ok(self.hub_mut::<T>(id)?.subscribe(id, channel_size))
+    }
+
+    /// Publishes on the channel as `NotifierHub::clone_send`, once checked that it carries the type `T`.
+    pub fn clone_send<T: Clone + Send + 'static>(
+        &self,
+        msg: T,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<T, ChannelId>, NotifierError<T, ChannelId>> {
+        self.hub::<T>(id)?.clone_send(msg, id)
+    }
+
+    fn mismatch<T>(id: &ChannelId, channel: &DynChannel) -> NotifierError<T, ChannelId> {
+        NotifierError::TypeMismatch {
+            id: id.clone(),
+            expected: channel.type_name,
+            found: type_name::<T>(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_dyn_hub() {
+        let mut hub: DynHub<&'static str> = DynHub::new();
+        hub.register::<u32>("numbers").unwrap();
+        hub.register::<String>("names").unwrap();
+        hub.register::<u32>("numbers").unwrap();
+        assert!(matches!(
+            hub.register::<String>("numbers"),
+            Err(NotifierError::ChannelAlreadyExist("numbers"))
+        ));
+        assert_eq!(hub.message_type(&"numbers"), Some("u32"));
+
+        let mut numbers = hub.subscribe_typed::<u32>(&"numbers", 10).unwrap();
+        let mut names = hub.subscribe_typed::<String>(&"names", 10).unwrap();
+        hub.clone_send(1u32, &"numbers").unwrap();
+        hub.clone_send("Alice".to_string(), &"names").unwrap();
+        assert_eq!(numbers.recv().await.unwrap(), 1);
+        assert_eq!(names.recv().await.unwrap(), "Alice");
+
+        assert!(matches!(
+            hub.clone_send(2u64, &"numbers"),
+            Err(NotifierError::TypeMismatch {
+                id: "numbers",
+                expected: "u32",
+                found: "u64"
+            })
+        ));
+        assert!(matches!(
+            hub.subscribe_typed::<u32>(&"ages", 10),
+            Err(NotifierError::ChannelNotExist("ages"))
+        ));
+
+        assert!(hub.unregister(&"numbers"));
+        assert!(numbers.recv().await.is_none());
+    }
+}
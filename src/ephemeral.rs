@@ -0,0 +1,160 @@
+use std::{
+    hash::Hash,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Weak},
+};
+use tokio::sync::mpsc;
+
+use crate::{
+    handle::HubHandle,
+    notifier::{ChannelState, MessageReceiver, NotifierHub},
+    sync::lock,
+};
+
+/// A receiver returned by `HubHandle::subscribe_ephemeral`. It derefs to the `MessageReceiver`,
+/// and dropping it lets the hub remove the channel if it was its last subscriber.
+#[derive(Debug)]
+pub struct EphemeralReceiver<M, ChannelId> {
+    receiver: MessageReceiver<M>,
+    /// Taken when the receiver is dropped.
+    channel: Option<ChannelId>,
+    leaves: mpsc::UnboundedSender<ChannelId>,
+}
+
+impl<M, ChannelId> Deref for EphemeralReceiver<M, ChannelId> {
+    type Target = MessageReceiver<M>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+impl<M, ChannelId> DerefMut for EphemeralReceiver<M, ChannelId> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.receiver
+    }
+}
+
+impl<M, ChannelId> Drop for EphemeralReceiver<M, ChannelId> {
+    fn drop(&mut self) {
+        // The hub is not locked here, as the receiver may be dropped while it is
+        if let Some(channel) = self.channel.take() {
+            let _ = self.leaves.send(channel);
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Removes the channel from all the maps of the hub: its subscribers, waiters, settings and publish grants.
+    /// The aliases pointing to it are removed too, only the groups keep it.
+    /// The state waiters are sent the `Uninitialised` state before being removed.
+    pub(crate) fn forget_channel(&mut self, id: &ChannelId) {
+        for sender in self.senders.remove(id).into_iter().flatten() {
+            self.subscriber_removed(sender.id());
+        }
+        self.subscribers_changed();
+        self.notify_state(id);
+        self.take_channel(id);
+        self.aliases.retain(|_, target| target != id);
+    }
+}
+
+/// Removes the channels left by their last ephemeral subscriber, until the hub is dropped.
+async fn reap<M, ChannelId: Eq + Hash + Clone>(
+    hub: Weak<std::sync::Mutex<NotifierHub<M, ChannelId>>>,
+    mut leaves: mpsc::UnboundedReceiver<ChannelId>,
+) {
+    while let Some(channel) = leaves.recv().await {
+        let Some(hub) = hub.upgrade() else {
+            return;
+        };
+        let mut hub = lock(&hub);
+        if hub.remove_closed_senders(&channel).0 != ChannelState::Running {
+            hub.forget_channel(&channel);
+        }
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    /// Same as `subscribe`, for the channels living as long as an entity such as a request or a connection.
+    /// Once the last subscriber of the channel is gone and one of them was ephemeral, the channel is removed from
+    /// all the maps of the hub, with its waiters, settings and publish grants, without calling `clean_channel`
+    /// or collecting the garbage. The removal is made by a task of the hub, right after the receiver is dropped.
+    ///
+    /// Must be called within a tokio runtime, as the task is spawned by the first ephemeral subscription.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::{ChannelState, NotifierHub};
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let handle = NotifierHub::<String, &str>::new().into_handle();
+    /// let receiver = handle.subscribe_ephemeral(&"request/17", 10);
+    /// handle.with(|hub| hub.set_subscriber_limit(&"request/17", Some(1)));
+    ///
+    /// drop(receiver);
+    /// tokio::task::yield_now().await; // Lets the task of the hub remove the channel
+    /// assert_eq!(handle.channel_state(&"request/17"), ChannelState::Uninitialised);
+    /// assert_eq!(handle.with(|hub| hub.subscriber_limit(&"request/17")), None);
+    /// # }
+    /// ```
+    pub fn subscribe_ephemeral(
+        &self,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> EphemeralReceiver<M, ChannelId> {
+        let weak = Arc::downgrade(&self.hub);
+        self.with(|hub| {
+            let leaves = hub
+                .ephemeral_leaves
+                .get_or_insert_with(|| {
+                    let (leaves, received) = mpsc::unbounded_channel();
+                    tokio::spawn(reap(weak, received));
+                    leaves
+                })
+                .clone();
+            EphemeralReceiver {
+                receiver: hub.subscribe(id, channel_size),
+                channel: Some(hub.aliases.get(id).unwrap_or(id).clone()),
+                leaves,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::{sleep, Duration};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ephemeral_channel() {
+        let handle: HubHandle<u32, &'static str> = NotifierHub::new().into_handle();
+        let mut first = handle.subscribe_ephemeral(&"request1", 10);
+        let second = handle.subscribe(&"request1", 10);
+        handle.with(|hub| hub.set_rate_limit(&"request1", 100, 10));
+        let _waiter = handle.with(|hub| hub.get_creation_waiter(&"request1"));
+
+        handle.clone_send(1, &"request1").unwrap();
+        assert_eq!(first.recv().await.unwrap(), 1);
+        drop(first);
+        sleep(Duration::from_millis(1)).await;
+        assert_eq!(handle.channel_state(&"request1"), ChannelState::Running);
+
+        drop(second);
+        let last = handle.subscribe_ephemeral(&"request1", 10);
+        handle.unsubscribe(&"request1", &last).unwrap();
+        drop(last);
+        sleep(Duration::from_millis(1)).await;
+        handle.with(|hub| {
+            assert_eq!(hub.channel_state(&"request1"), ChannelState::Uninitialised);
+            assert!(hub.rate_limits.is_empty());
+            assert!(hub.creation_senders.is_empty());
+        });
+    }
+}
@@ -1,6 +1,8 @@
 use thiserror::Error;
 use tokio::{sync::mpsc::error::SendError, task::JoinError, time::Duration};
 
+use crate::{error_hook::FailureKind, notifier::SmartChannelId, publisher::PublisherId};
+
 #[macro_export]
 macro_rules! unexpected {
     ($kind:ident) => {
@@ -21,11 +23,9 @@ pub enum NotifierError<M, ChannelId> {
     SendingError(SendError<M>),
     #[error("Failed to wait for a writing because of this: {0:?}")]
     JoiningError(JoinError),
-    /// This one returns a vector conaining all the send errors and join errors during the writing phase
+    /// This one returns a vector containing all the failed writings of the writing phase
     #[error("Failed to send a message from the writing handler due to this: {0:?}")]
-    WritingSendError(Vec<NotifierError<M, ChannelId>>),
-    #[error("Timeout during the wait of a writing task, duration: {0:?}")]
-    WritingTimeout(Duration),
+    WritingSendError(Vec<SendFailure<M, ChannelId>>),
     #[error("This error was not expected. Please report an issue to https://github.com/ZivoMartin/AsyncForge with this code: {0:?}")]
     UnexpectedError(UnexpectedErrorKind),
     #[error("The given receiver is no subscribed to the channel {0:?}")]
@@ -38,4 +38,234 @@ pub enum NotifierError<M, ChannelId> {
     ChannelOver(ChannelId),
     #[error("The channel {0:?} does not exist")]
     ChannelNotExist(ChannelId),
+    #[error("The group {0} does not exist")]
+    GroupNotExist(String),
+    #[error("The channel {0:?} already exists")]
+    ChannelAlreadyExist(ChannelId),
+    /// The channel of the `DynHub` carries another message type
+    #[error("The channel {id:?} carries {expected} messages, not {found}")]
+    TypeMismatch {
+        id: ChannelId,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The hub is draining, so it does not accept new subscriptions
+    #[error("The hub is draining")]
+    Draining,
+    #[error("The channel {0:?} reached its subscriber limit")]
+    SubscriberLimitReached(ChannelId),
+    /// The subscriber of the channel was not able to accept its message, so nothing has been delivered
+    #[error("The transaction has been rolled back as the subscriber {1:?} of the channel {0:?} can't accept the message")]
+    TransactionRolledBack(ChannelId, SmartChannelId),
+    /// The channel is protected and the publish has not been made with a valid token, the message is handed back
+    #[error("Publishing on the channel {id:?} is not allowed")]
+    PublishNotAllowed { id: ChannelId, msg: M },
+    /// An error caused by a publish made through a `Publisher`, with its identity
+    #[error("The publisher {publisher} failed: {error:?}")]
+    FromPublisher {
+        publisher: PublisherId,
+        error: Box<NotifierError<M, ChannelId>>,
+    },
+    /// Some subscribers of the channel did not acknowledge the message of `barrier_send` in time
+    #[error("The subscribers {missing:?} of the channel {id:?} did not acknowledge the barrier")]
+    BarrierIncomplete {
+        id: ChannelId,
+        missing: Vec<SmartChannelId>,
+    },
+    /// No subscriber joined the channel before the timeout of `publish_when_ready`, the message is handed back
+    #[error("No subscriber joined the channel {id:?} in time")]
+    NotReady { id: ChannelId, msg: M },
+    /// The publish exceeded the rate limit of the channel or of the hub, the message is handed back
+    #[error("The channel {id:?} exceeded its rate limit, retry after {retry_after:?}")]
+    RateLimited {
+        id: ChannelId,
+        msg: M,
+        retry_after: Duration,
+    },
+    /// The publish waits for the subscribers of the channel to answer, but the channel has none, so the message
+    /// is handed back rather than parked or dropped
+    #[error("The channel {id:?} has no subscriber to answer")]
+    NoSubscriber { id: ChannelId, msg: M },
+    /// A replay has been requested on a channel without journal, see `NotifierHub::set_journal`
+    #[error("The channel {0:?} has no journal")]
+    JournalDisabled(ChannelId),
+    /// The journal of the channel no longer holds the first requested messages, it starts at `oldest`
+    #[error("The journal of the channel {id:?} starts at the sequence number {oldest}")]
+    ReplayUnavailable { id: ChannelId, oldest: u64 },
+    /// The format of the channel failed to decode the message recorded by its journal under the sequence number
+    #[error("The journal of the channel {id:?} can't decode its message {seq}")]
+    JournalUnreadable { id: ChannelId, seq: u64 },
+    /// The message has been sent to a subscriber that is not subscribed to any channel of the hub
+    #[error("The subscriber {subscriber:?} is not subscribed to any channel")]
+    UnknownSubscriber { subscriber: SmartChannelId, msg: M },
+}
+
+impl<M, ChannelId> NotifierError<M, ChannelId> {
+    /// Returns the messages the error hands back, for a retry or a dead letter queue.
+    /// The messages of the writings that timed out, were aborted or panicked are lost,
+    /// and the quarantined ones are in the quarantine of the hub.
+    pub fn undelivered(&self) -> Vec<&M> {
+        match self {
+            NotifierError::SendingError(SendError(msg))
+            | NotifierError::PublishNotAllowed { msg, .. }
+            | NotifierError::NotReady { msg, .. }
+            | NotifierError::RateLimited { msg, .. }
+            | NotifierError::NoSubscriber { msg, .. }
+            | NotifierError::UnknownSubscriber { msg, .. } => vec![msg],
+            NotifierError::WritingSendError(failures) => failures
+                .iter()
+                .filter_map(|failure| failure.msg.as_ref())
+                .collect(),
+            NotifierError::NotSubscribedMultiple(errors) => {
+                errors.iter().flat_map(|e| e.undelivered()).collect()
+            }
+            NotifierError::FromPublisher { error, .. } => error.undelivered(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Same as `undelivered`, taking the messages back.
+    pub fn into_undelivered(self) -> Vec<M> {
+        match self {
+            NotifierError::SendingError(SendError(msg))
+            | NotifierError::PublishNotAllowed { msg, .. }
+            | NotifierError::NotReady { msg, .. }
+            | NotifierError::RateLimited { msg, .. }
+            | NotifierError::NoSubscriber { msg, .. }
+            | NotifierError::UnknownSubscriber { msg, .. } => vec![msg],
+            NotifierError::WritingSendError(failures) => failures
+                .into_iter()
+                .filter_map(|failure| failure.msg)
+                .collect(),
+            NotifierError::NotSubscribedMultiple(errors) => errors
+                .into_iter()
+                .flat_map(|e| e.into_undelivered())
+                .collect(),
+            NotifierError::FromPublisher { error, .. } => error.into_undelivered(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the undelivered messages of a broadcast along with the subscriber they were for,
+    /// so they can be written again to the same subscribers.
+    pub fn into_undelivered_by_subscriber(self) -> Vec<(SmartChannelId, M)> {
+        match self {
+            NotifierError::WritingSendError(failures) => failures
+                .into_iter()
+                .filter_map(|failure| Some((failure.subscriber?, failure.msg?)))
+                .collect(),
+            NotifierError::FromPublisher { error, .. } => error.into_undelivered_by_subscriber(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A failed writing, see `NotifierError::WritingSendError`.
+#[derive(Debug)]
+pub struct SendFailure<M, ChannelId> {
+    /// The channel of the writing. It is `None` for a broadcast over several channels
+    /// and for the writings made without a channel, such as to an inbox, see `in_channel`.
+    pub channel: Option<ChannelId>,
+    /// The subscriber the message was for, `None` if it is unknown.
+    pub subscriber: Option<SmartChannelId>,
+    pub kind: FailureKind,
+    /// The message, when it has not been delivered and could be handed back.
+    pub msg: Option<M>,
+}
+
+impl<M> SendFailure<M, ()> {
+    pub(crate) fn new(subscriber: SmartChannelId, kind: FailureKind, msg: Option<M>) -> Self {
+        Self {
+            channel: None,
+            subscriber: Some(subscriber),
+            kind,
+            msg,
+        }
+    }
+
+    /// Returns the failure bound to its channel, when the caller knows it.
+    pub fn in_channel<ChannelId>(self, channel: ChannelId) -> SendFailure<M, ChannelId> {
+        self.bound(Some(channel))
+    }
+
+    /// Returns the failure bound to the channel, if any.
+    pub(crate) fn bound<ChannelId>(self, channel: Option<ChannelId>) -> SendFailure<M, ChannelId> {
+        SendFailure {
+            channel,
+            subscriber: self.subscriber,
+            kind: self.kind,
+            msg: self.msg,
+        }
+    }
+}
+
+impl<M, ChannelId> SendFailure<M, ChannelId> {
+    /// Returns the failure without its channel.
+    pub(crate) fn unbound(self) -> SendFailure<M, ()> {
+        SendFailure {
+            channel: None,
+            subscriber: self.subscriber,
+            kind: self.kind,
+            msg: self.msg,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotifierHub;
+    use std::{error::Error, fmt::Display};
+
+    #[test]
+    fn test_error_trait() {
+        fn assert_error<E: Error + Display>() {}
+        assert_error::<NotifierError<String, String>>();
+
+        let error: NotifierError<String, String> = NotifierError::FromPublisher {
+            publisher: PublisherId::new("billing"),
+            error: Box::new(NotifierError::ChannelNotExist("invoices".to_string())),
+        };
+        let error: Box<dyn Error> = Box::new(error);
+        assert_eq!(
+            error.to_string(),
+            "The publisher billing failed: ChannelNotExist(\"invoices\")"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_undelivered() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let closed = hub.subscribe(&"channel1", 10);
+        let mut open = hub.subscribe(&"channel1", 10);
+        let closed_id = closed.id();
+        drop(closed);
+
+        let error = hub
+            .clone_send("Lost".to_string(), &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap_err();
+        assert_eq!(error.undelivered(), vec!["Lost"]);
+        match &error {
+            NotifierError::WritingSendError(failures) => {
+                assert_eq!(failures[0].channel, Some("channel1"))
+            }
+            _ => panic!("Expected a writing failure"),
+        }
+        assert_eq!(
+            error.into_undelivered_by_subscriber(),
+            vec![(closed_id, "Lost".to_string())]
+        );
+        assert_eq!(open.recv().await.unwrap(), "Lost");
+
+        hub.grant_publish(&"channel1");
+        let error = hub
+            .clone_send("Refused".to_string(), &"channel1")
+            .err()
+            .unwrap();
+        assert_eq!(error.into_undelivered(), vec!["Refused".to_string()]);
+    }
 }
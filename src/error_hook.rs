@@ -0,0 +1,152 @@
+use futures::future::BoxFuture;
+use std::{future::Future, hash::Hash, sync::Arc};
+
+use crate::notifier::{NotifierHub, SmartChannelId};
+
+/// What made a writing fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FailureKind {
+    /// The receiver of the subscriber is closed.
+    Closed,
+    /// The buffer of the subscriber was full, in `DeliveryMode::Deterministic`.
+    Full,
+    /// The writing did not finish before the timeout of `wait`.
+    Timeout,
+    /// The writing panicked, for instance in a custom `Clone` implementation.
+    Panicked,
+    /// The writing has been aborted before its end.
+    Aborted,
+    /// The circuit of the subscriber is open.
+    CircuitOpen,
+    /// The message has been quarantined.
+    Quarantined,
+    /// The subscriber has no credit left, see `subscribe_with_credits`.
+    NoCredit,
+}
+
+/// A failed writing, as given to the error hook of the hub.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorEvent<ChannelId> {
+    /// The channel of the publish, `None` for the broadcasts over several channels.
+    pub channel: Option<ChannelId>,
+    /// The subscriber the writing was for, `None` if it is unknown.
+    pub subscriber: Option<SmartChannelId>,
+    pub kind: FailureKind,
+}
+
+/// Reports the failures of a writing handler, already bound to the channel of the publish.
+pub(crate) type FailureReporter = Arc<dyn Fn(Option<SmartChannelId>, FailureKind) + Send + Sync>;
+
+/// Builds the reporter of a publish from its channel.
+pub(crate) type ErrorHook<ChannelId> =
+    Arc<dyn Fn(Option<&ChannelId>) -> FailureReporter + Send + Sync>;
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Registers the callback invoked each time a writing fails, panics or times out, replacing the previous one.
+    /// The failures are reported even if the writing handler is ignored, for instance when notifying the waiters.
+    /// Each call is spawned on the current tokio runtime, the failures happening outside of a runtime are not reported.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub: NotifierHub<String, &str> = NotifierHub::new();
+    /// hub.set_error_hook(|event| async move {
+    ///     eprintln!("{:?} failed on {:?}: {:?}", event.subscriber, event.channel, event.kind);
+    /// });
+    /// ```
+    pub fn set_error_hook<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn(ErrorEvent<ChannelId>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+        ChannelId: Clone + Send + Sync + 'static,
+    {
+        let hook: Arc<dyn Fn(ErrorEvent<ChannelId>) -> BoxFuture<'static, ()> + Send + Sync> =
+            Arc::new(move |event| Box::pin(hook(event)));
+        self.error_hook = Some(Arc::new(move |channel: Option<&ChannelId>| {
+            let hook = Arc::clone(&hook);
+            let channel = channel.cloned();
+            Arc::new(move |subscriber, kind| {
+                if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                    runtime.spawn(hook(ErrorEvent {
+                        channel: channel.clone(),
+                        subscriber,
+                        kind,
+                    }));
+                }
+            })
+        }));
+    }
+
+    /// Removes the error hook.
+    pub fn remove_error_hook(&mut self) {
+        self.error_hook = None;
+    }
+
+    /// Returns true if an error hook is registered.
+    pub fn has_error_hook(&self) -> bool {
+        self.error_hook.is_some()
+    }
+
+    /// Returns the reporter of a publish on the given channel, if an error hook is registered.
+    pub(crate) fn failure_reporter(&self, channel: Option<&ChannelId>) -> Option<FailureReporter> {
+        self.error_hook.as_ref().map(|hook| hook(channel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_error_hook() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let (events, mut received) = mpsc::unbounded_channel();
+        hub.set_error_hook(move |event| {
+            let events = events.clone();
+            async move {
+                let _ = events.send(event);
+            }
+        });
+
+        let receiver = hub.subscribe(&"channel1", 10);
+        let subscriber = receiver.id();
+        drop(receiver);
+        let _ = hub.clone_send(1, &"channel1");
+
+        let event = received.recv().await.unwrap();
+        assert_eq!(event.channel, Some("channel1"));
+        assert_eq!(event.subscriber, Some(subscriber));
+        assert_eq!(event.kind, FailureKind::Closed);
+
+        hub.remove_error_hook();
+        let _ = hub.clone_send(2, &"channel1");
+        tokio::task::yield_now().await;
+        assert!(received.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_detached_failures_are_reported() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let (events, mut received) = mpsc::unbounded_channel();
+        hub.set_error_hook(move |event| {
+            let events = events.clone();
+            async move {
+                let _ = events.send(event);
+            }
+        });
+
+        let mut receiver = hub.subscribe(&"channel1", 1);
+        hub.clone_send(1, &"channel1").unwrap().detach();
+        hub.clone_send(2, &"channel1").unwrap().detach();
+        assert_eq!(receiver.recv().await.unwrap(), 1);
+        assert_eq!(receiver.recv().await.unwrap(), 2);
+
+        hub.clone_send(3, &"channel1").unwrap();
+        hub.clone_send(4, &"channel1").unwrap().detach();
+        drop(receiver);
+        let event = received.recv().await.unwrap();
+        assert_eq!(event.kind, FailureKind::Closed);
+    }
+}
@@ -0,0 +1,216 @@
+use std::hash::Hash;
+use tokio::{select, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    handle::HubHandle,
+    notifier::{MessageReceiver, SmartChannelId},
+};
+
+/// The size of the channels subscribed to forward the messages between federated hubs.
+pub const FEDERATION_CHANNEL_SIZE: usize = 100;
+
+/// Defines which channels are mirrored between a parent hub and a child hub.
+#[derive(Clone, Debug)]
+pub struct Propagation<ChannelId> {
+    /// The publishes on these channels of the child are mirrored to the parent.
+    pub upward: Vec<ChannelId>,
+    /// The publishes on these channels of the parent are mirrored to the child.
+    pub downward: Vec<ChannelId>,
+}
+
+impl<ChannelId> Default for Propagation<ChannelId> {
+    fn default() -> Self {
+        Self {
+            upward: Vec::new(),
+            downward: Vec::new(),
+        }
+    }
+}
+
+/// The link between a parent hub and a child hub, returned by `HubHandle::attach_child`.
+/// Dropping it detaches the child.
+pub struct Federation {
+    token: CancellationToken,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Drop for Federation {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+impl Federation {
+    /// Stops the propagation and waits for the forwarding subscriptions to be removed.
+    pub async fn detach(mut self) {
+        self.token.cancel();
+        for task in self.tasks.drain(..) {
+            let _ = task.await;
+        }
+    }
+}
+
+/// A forwarding of a channel from a hub to another one.
+struct Forward<M, ChannelId: Eq + Hash> {
+    id: ChannelId,
+    source: HubHandle<M, ChannelId>,
+    receiver: MessageReceiver<M>,
+    target: HubHandle<M, ChannelId>,
+    /// The subscription forwarding the other way, which must not receive back what is forwarded.
+    except: Option<SmartChannelId>,
+}
+
+impl<M, ChannelId> Forward<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    fn spawn(mut self, token: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let msg = select! {
+                    msg = self.receiver.recv() => msg,
+                    _ = token.cancelled() => None,
+                };
+                let Some(msg) = msg else {
+                    break;
+                };
+                let handler = self
+                    .target
+                    .with(|hub| hub.unchecked_clone_send(msg, &self.id, self.except));
+                if let Ok(handler) = handler {
+                    let _ = handler.wait(None).await;
+                }
+            }
+            let _ = self.source.unsubscribe(&self.id, &self.receiver);
+        })
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    /// Attaches `child` under this hub. The messages published on the upward channels of the child
+    /// are published on the parent, and those published on the downward channels of the parent are published on the child.
+    /// A message forwarded by the federation is never sent back where it comes from, so a channel can be propagated
+    /// in both directions. The hubs must form a tree, a hub being the child of its own descendant would loop.
+    /// The forwarded messages are published on the target as with `clone_send`, through its journals, park buffers
+    /// and sequencers, only the protection of its channels being skipped. A message refused by a rate limit is lost.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{federation::Propagation, notifier::NotifierHub};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let global = NotifierHub::new().into_handle();
+    /// let module = NotifierHub::new().into_handle();
+    /// let _federation = global.attach_child(&module, Propagation {
+    ///     upward: vec!["events"],
+    ///     downward: vec![],
+    /// });
+    ///
+    /// let mut receiver = global.subscribe(&"events", 10);
+    /// module.clone_send("Module started", &"events").unwrap();
+    /// assert_eq!(receiver.recv().await.unwrap(), "Module started");
+    /// # }
+    /// ```
+    pub fn attach_child(
+        &self,
+        child: &HubHandle<M, ChannelId>,
+        propagation: Propagation<ChannelId>,
+    ) -> Federation {
+        let subscribe = |hub: &HubHandle<M, ChannelId>, ids: &[ChannelId]| -> Vec<_> {
+            ids.iter()
+                .map(|id| (id.clone(), hub.subscribe(id, FEDERATION_CHANNEL_SIZE)))
+                .collect()
+        };
+        let upward = subscribe(child, &propagation.upward);
+        let downward = subscribe(self, &propagation.downward);
+        let ids = |subscriptions: &[(ChannelId, MessageReceiver<M>)]| -> Vec<_> {
+            subscriptions
+                .iter()
+                .map(|(id, receiver)| (id.clone(), receiver.id()))
+                .collect()
+        };
+        let (upward_ids, downward_ids) = (ids(&upward), ids(&downward));
+        let except = |ids: &[(ChannelId, SmartChannelId)], id: &ChannelId| {
+            ids.iter()
+                .find(|(other, _)| other == id)
+                .map(|(_, receiver)| *receiver)
+        };
+
+        let token = CancellationToken::new();
+        let mut tasks = Vec::new();
+        for (id, receiver) in upward {
+            let except = except(&downward_ids, &id);
+            let forward = Forward {
+                id,
+                source: child.clone(),
+                receiver,
+                target: self.clone(),
+                except,
+            };
+            tasks.push(forward.spawn(token.clone()));
+        }
+        for (id, receiver) in downward {
+            let except = except(&upward_ids, &id);
+            let forward = Forward {
+                id,
+                source: self.clone(),
+                receiver,
+                target: child.clone(),
+                except,
+            };
+            tasks.push(forward.spawn(token.clone()));
+        }
+        Federation { token, tasks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotifierHub;
+    use tokio::time::{sleep, Duration};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_propagation_without_loop() {
+        let parent = NotifierHub::<String, &'static str>::new().into_handle();
+        let child = NotifierHub::<String, &'static str>::new().into_handle();
+        let federation = parent.attach_child(
+            &child,
+            Propagation {
+                upward: vec!["shared"],
+                downward: vec!["shared", "config"],
+            },
+        );
+        let mut parent_receiver = parent.subscribe(&"shared", 10);
+        let mut child_receiver = child.subscribe_multiple(&["shared", "config"], 10);
+
+        child
+            .clone_send("From child".to_string(), &"shared")
+            .unwrap();
+        assert_eq!(parent_receiver.recv().await.unwrap(), "From child");
+        assert_eq!(child_receiver.recv().await.unwrap(), "From child");
+
+        child.set_journal(&"config", Some(10));
+        parent
+            .clone_send("From parent".to_string(), &"config")
+            .unwrap();
+        assert_eq!(child_receiver.recv().await.unwrap(), "From parent");
+        assert_eq!(child.journal_seq(&"config"), Some(1));
+
+        // The paused clock only moves once the forwarding tasks are idle
+        sleep(Duration::from_millis(10)).await;
+        assert!(parent_receiver.try_recv().is_err());
+        assert!(child_receiver.try_recv().is_err());
+
+        federation.detach().await;
+        assert_eq!(parent.channel_number_subscriber(&"config"), 0);
+        assert_eq!(child.channel_number_subscriber(&"shared"), 1);
+    }
+}
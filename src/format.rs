@@ -0,0 +1,296 @@
+use std::{collections::HashMap, hash::Hash, io, sync::Arc};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+#[cfg(feature = "json")]
+use crate::codec::Json;
+use crate::{
+    bridge::{IngestGuard, PipeGuard},
+    codec::{Codec, FrameCodec, Framing},
+    handle::HubHandle,
+    notifier::NotifierHub,
+};
+
+/// A codec picked from the format registry of the hub.
+pub type DynCodec<M> = Box<dyn Codec<M> + Send>;
+
+impl<M> Codec<M> for DynCodec<M> {
+    fn encode(&mut self, msg: &M) -> io::Result<Vec<u8>> {
+        (**self).encode(msg)
+    }
+
+    fn decode(&mut self, frame: &[u8]) -> io::Result<M> {
+        (**self).decode(frame)
+    }
+
+    fn encode_into(&mut self, msg: &M, buffer: &mut Vec<u8>) -> io::Result<()> {
+        (**self).encode_into(msg, buffer)
+    }
+}
+
+type CodecFactory<M> = Arc<dyn Fn() -> DynCodec<M> + Send + Sync>;
+
+/// The serialization formats known by the hub, and the one used by each channel.
+pub(crate) struct FormatRegistry<M, ChannelId> {
+    codecs: HashMap<&'static str, CodecFactory<M>>,
+    pub(crate) channels: HashMap<ChannelId, String>,
+}
+
+impl<M, ChannelId> Default for FormatRegistry<M, ChannelId> {
+    fn default() -> Self {
+        Self {
+            codecs: HashMap::new(),
+            channels: HashMap::new(),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Registers a serialization format under the given name, replacing the previous one.
+    /// Each bridge and each journal using the format gets its own copy of the codec.
+    pub fn register_format<C>(&mut self, name: &'static str, codec: C)
+    where
+        C: Codec<M> + Clone + Send + Sync + 'static,
+    {
+        self.formats
+            .codecs
+            .insert(name, Arc::new(move || Box::new(codec.clone())));
+    }
+
+    /// Returns the names of the registered formats.
+    pub fn formats(&self) -> Vec<&'static str> {
+        self.formats.codecs.keys().copied().collect()
+    }
+
+    /// Selects the format the bridges of the channel use, see `HubHandle::pipe_in_format`,
+    /// and the one its journal records the messages in, see `set_journal`.
+    /// `None` removes the format of the channel. The format may be registered later.
+    pub fn set_channel_format(&mut self, id: &ChannelId, format: Option<&str>) {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        match format {
+            Some(format) => self.formats.channels.insert(id, format.to_string()),
+            None => self.formats.channels.remove(&id),
+        };
+    }
+
+    /// Returns the name of the format of the channel, if any.
+    pub fn channel_format(&self, id: &ChannelId) -> Option<&str> {
+        let id = self.aliases.get(id).unwrap_or(id);
+        self.formats.channels.get(id).map(String::as_str)
+    }
+
+    /// Returns a codec of the format of the channel, or a `NotFound` error if the channel has no format
+    /// or if its format is not registered.
+    pub fn channel_codec(&self, id: &ChannelId) -> io::Result<DynCodec<M>> {
+        let format = self
+            .channel_format(id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the channel has no format"))?;
+        let factory = self.formats.codecs.get(format).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("the format {format} is not registered"),
+            )
+        })?;
+        Ok(factory())
+    }
+}
+
+#[cfg(feature = "json")]
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: serde::Serialize + serde::de::DeserializeOwned + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Registers the `Json` codec as the `json` format. Available with the `json` feature.
+    /// It is the only format built in the crate: bincode, CBOR and MessagePack are not dependencies of it,
+    /// so their codecs have to be registered with `register_format`.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::<Vec<u32>, &str>::new();
+    /// hub.register_json_format();
+    /// hub.set_channel_format(&"metrics", Some("json"));
+    /// let mut codec = hub.channel_codec(&"metrics").unwrap();
+    /// assert_eq!(codec.encode(&vec![1, 2]).unwrap(), b"[1,2]");
+    /// ```
+    pub fn register_json_format(&mut self) {
+        self.register_format("json", Json);
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    /// Same as `pipe_to_writer`, the messages being encoded in the format of the channel and delimited by the framing.
+    /// Returns a `NotFound` error if the channel has no registered format, see `NotifierHub::set_channel_format`.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{
+    ///     bridge::LengthDelimitedCodec,
+    ///     codec::{Codec, Framing},
+    ///     notifier::NotifierHub,
+    /// };
+    /// use std::io;
+    ///
+    /// #[derive(Clone)]
+    /// struct Utf8;
+    ///
+    /// impl Codec<String> for Utf8 {
+    ///     fn encode(&mut self, msg: &String) -> io::Result<Vec<u8>> {
+    ///         Ok(msg.clone().into_bytes())
+    ///     }
+    ///
+    ///     fn decode(&mut self, frame: &[u8]) -> io::Result<String> {
+    ///         String::from_utf8(frame.to_vec()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// handle.with(|hub| {
+    ///     hub.register_format("utf8", Utf8);
+    ///     hub.set_channel_format(&"logs", Some("utf8"));
+    ///     hub.set_channel_format(&"replica", Some("utf8"));
+    /// });
+    /// let framing = || Framing::LengthDelimited(LengthDelimitedCodec::new());
+    /// let (writer, reader) = tokio::io::duplex(1024);
+    /// let mut replica = handle.subscribe(&"replica", 10);
+    /// let _pipe = handle.pipe_in_format(&"logs", writer, framing()).unwrap();
+    /// let _ingest = handle.ingest_in_format(&"replica", reader, framing()).unwrap();
+    ///
+    /// handle.clone_send("started".to_string(), &"logs").unwrap().wait(None).await;
+    /// assert_eq!(replica.recv().await.unwrap(), "started");
+    /// # }
+    /// ```
+    pub fn pipe_in_format<W>(
+        &self,
+        id: &ChannelId,
+        writer: W,
+        framing: Framing,
+    ) -> io::Result<PipeGuard<io::Error>>
+    where
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let codec = self.with(|hub| hub.channel_codec(id))?;
+        Ok(self.pipe_to_writer(id, writer, FrameCodec::with_framing(framing, codec)))
+    }
+
+    /// Same as `ingest_reader`, the frames being decoded in the format of the channel.
+    /// Returns a `NotFound` error if the channel has no registered format.
+    pub fn ingest_in_format<R>(
+        &self,
+        id: &ChannelId,
+        reader: R,
+        framing: Framing,
+    ) -> io::Result<IngestGuard>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        let codec = self.with(|hub| hub.channel_codec(id))?;
+        Ok(self.ingest_reader(id, reader, FrameCodec::with_framing(framing, codec)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::{LengthDelimitedCodec, LinesCodec};
+
+    #[derive(Clone)]
+    struct Decimal;
+
+    impl Codec<u32> for Decimal {
+        fn encode(&mut self, msg: &u32) -> io::Result<Vec<u8>> {
+            Ok(msg.to_string().into_bytes())
+        }
+
+        fn decode(&mut self, frame: &[u8]) -> io::Result<u32> {
+            std::str::from_utf8(frame)
+                .ok()
+                .and_then(|frame| frame.parse().ok())
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))
+        }
+    }
+
+    #[derive(Clone)]
+    struct BigEndian;
+
+    impl Codec<u32> for BigEndian {
+        fn encode(&mut self, msg: &u32) -> io::Result<Vec<u8>> {
+            Ok(msg.to_be_bytes().to_vec())
+        }
+
+        fn decode(&mut self, frame: &[u8]) -> io::Result<u32> {
+            let bytes = frame
+                .try_into()
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+            Ok(u32::from_be_bytes(bytes))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_channel_formats() {
+        let source = NotifierHub::<u32, &'static str>::new().into_handle();
+        let destination = NotifierHub::<u32, &'static str>::new().into_handle();
+        for handle in [&source, &destination] {
+            handle.with(|hub| {
+                hub.register_format("decimal", Decimal);
+                hub.register_format("big_endian", BigEndian);
+                hub.set_channel_format(&"text", Some("decimal"));
+                hub.set_channel_format(&"binary", Some("big_endian"));
+            });
+        }
+        let mut text = destination.subscribe(&"text", 10);
+        let mut binary = destination.subscribe(&"binary", 10);
+        let (text_writer, text_reader) = tokio::io::duplex(1024);
+        let (binary_writer, binary_reader) = tokio::io::duplex(1024);
+        let lines = || Framing::Lines(LinesCodec::new());
+        let length_delimited = || Framing::LengthDelimited(LengthDelimitedCodec::new());
+
+        let mut pipes = [
+            source
+                .pipe_in_format(&"text", text_writer, lines())
+                .unwrap(),
+            source
+                .pipe_in_format(&"binary", binary_writer, length_delimited())
+                .unwrap(),
+        ];
+        let _ingests = [
+            destination
+                .ingest_in_format(&"text", text_reader, lines())
+                .unwrap(),
+            destination
+                .ingest_in_format(&"binary", binary_reader, length_delimited())
+                .unwrap(),
+        ];
+        source.clone_send(42, &"text").unwrap().wait(None).await;
+        source.clone_send(7, &"binary").unwrap().wait(None).await;
+        assert_eq!(text.recv().await.unwrap(), 42);
+        assert_eq!(binary.recv().await.unwrap(), 7);
+        for pipe in &mut pipes {
+            pipe.stop();
+        }
+
+        source.with(|hub| hub.set_channel_format(&"text", Some("unknown")));
+        let (writer, _) = tokio::io::duplex(1024);
+        let error = source.pipe_in_format(&"text", writer, lines()).err();
+        assert_eq!(error.map(|e| e.kind()), Some(io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn test_channel_format_follows_rename() {
+        let mut hub = NotifierHub::<u32, &'static str>::new();
+        hub.set_channel_format(&"text", Some("decimal"));
+        hub.rename_channel(&"text", "renamed", false).unwrap();
+        assert_eq!(hub.channel_format(&"text"), None);
+        assert_eq!(hub.channel_format(&"renamed"), Some("decimal"));
+
+        hub.forget_channel(&"renamed");
+        assert_eq!(hub.channel_format(&"renamed"), None);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{collections::HashMap, hash::Hash, sync::Arc};
+use tokio::{
+    sync::Mutex,
+    task::JoinHandle,
+    time::{interval, Duration},
+};
+
+use crate::notifier::{NotifierHub, Sender, SmartChannelId};
+
+/// Defines when the hub collects its garbage: Over channels and waiters whose receiver has been dropped.
+/// Note that a collected channel becomes `Uninitialised` instead of `Over`.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GcPolicy {
+    /// The garbage is only collected by calling `collect_garbage`, or by a task spawned with `spawn_periodic_gc`.
+    #[default]
+    Manual,
+    /// The garbage is collected after each subscription, unsubscription and waiter creation.
+    /// Each collection goes through all the channels of the hub.
+    OnMutation,
+}
+
+/// Describes what has been removed from the hub by a garbage collection.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GcReport<ChannelId> {
+    /// The channels that were Over, after removing their closed senders.
+    pub channels: Vec<ChannelId>,
+    /// The number of creation waiters whose receiver was dropped.
+    pub creation_waiters: usize,
+    /// The number of destruction waiters whose receiver was dropped.
+    pub destruction_waiters: usize,
+}
+
+impl<ChannelId> Default for GcReport<ChannelId> {
+    fn default() -> Self {
+        Self {
+            channels: Vec::new(),
+            creation_waiters: 0,
+            destruction_waiters: 0,
+        }
+    }
+}
+
+impl<ChannelId> GcReport<ChannelId> {
+    /// Returns true if nothing has been collected.
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty() && self.creation_waiters == 0 && self.destruction_waiters == 0
+    }
+
+    /// Adds the content of the other report to this one.
+    pub fn merge(&mut self, other: GcReport<ChannelId>) {
+        self.channels.extend(other.channels);
+        self.creation_waiters += other.creation_waiters;
+        self.destruction_waiters += other.destruction_waiters;
+    }
+}
+
+/// Removes the closed senders of each entry, and the entries left empty. Returns the number of removed senders.
+pub(crate) fn remove_closed<T, ChannelId: Eq + Hash>(
+    map: &mut HashMap<ChannelId, Vec<Sender<T, SmartChannelId>>>,
+) -> usize {
+    let mut removed = 0;
+    map.retain(|_, senders| {
+        let n = senders.len();
+        senders.retain(|s| !s.is_closed());
+        removed += n - senders.len();
+        !senders.is_empty()
+    });
+    removed
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Sets the garbage collection policy of the hub.
+    pub fn set_gc_policy(&mut self, policy: GcPolicy) {
+        self.gc_policy = policy;
+    }
+
+    /// Returns the garbage collection policy of the hub.
+    pub fn gc_policy(&self) -> GcPolicy {
+        self.gc_policy
+    }
+
+    /// Removes the closed senders of all the channels, then the channels that are Over,
+    /// and the waiters whose receiver has been dropped. Returns what has been collected.
+    pub fn collect_garbage(&mut self) -> GcReport<ChannelId> {
+        for senders in self.senders.values_mut() {
+            senders.retain(|s| !s.is_closed());
+        }
+        for group in self.groups.values_mut() {
+            group.subscribers.retain(|sender| sender.is_alive());
+        }
+        self.subscribers_changed();
+        let channels: Vec<_> = self
+            .senders
+            .iter()
+            .filter(|(_, senders)| senders.is_empty())
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &channels {
+            self.senders.remove(id);
+            self.notify_state(id);
+        }
+        let inboxes = std::mem::take(&mut self.inboxes);
+        self.inboxes = inboxes
+            .into_iter()
+            .filter(|(id, _)| self.is_subscriber(id))
+            .collect();
+        let (creation_waiters, destruction_waiters) = self.remove_closed_waiters();
+        GcReport {
+            channels,
+            creation_waiters,
+            destruction_waiters,
+        }
+    }
+
+    /// Returns what has been collected automatically since the last call,
+    /// either because of the `OnMutation` policy or by a periodic task.
+    pub fn take_gc_report(&mut self) -> GcReport<ChannelId> {
+        std::mem::take(&mut self.gc_report)
+    }
+
+    /// Collects the garbage if the policy is `OnMutation`.
+    pub(crate) fn on_mutation(&mut self) {
+        if self.gc_policy == GcPolicy::OnMutation {
+            self.collect_pending_garbage();
+        }
+    }
+
+    /// Collects the garbage and keeps the report for `take_gc_report`.
+    fn collect_pending_garbage(&mut self) {
+        let report = self.collect_garbage();
+        self.gc_report.merge(report);
+    }
+}
+
+/// Spawns a task collecting the garbage of the hub every `period`. The reports are kept for `take_gc_report`.
+/// The task only holds a weak reference to the hub and stops once the hub is dropped.
+pub fn spawn_periodic_gc<M, ChannelId>(
+    hub: &Arc<Mutex<NotifierHub<M, ChannelId>>>,
+    period: Duration,
+) -> JoinHandle<()>
+where
+    M: Send + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    let hub = Arc::downgrade(hub);
+    tokio::spawn(async move {
+        let mut interval = interval(period);
+        loop {
+            interval.tick().await;
+            match hub.upgrade() {
+                Some(hub) => hub.lock().await.collect_pending_garbage(),
+                None => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::ChannelState;
+
+    #[tokio::test]
+    async fn test_collect_garbage() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let receiver1 = hub.subscribe(&"channel1", 100);
+        let receiver2 = hub.subscribe(&"channel2", 100);
+        let _receiver3 = hub.subscribe(&"channel3", 100);
+        let creation_waiter = hub.get_creation_waiter(&"channel1");
+        let _destruction_waiter = hub.get_destruction_waiter(&"channel1");
+
+        hub.unsubscribe(&"channel1", &receiver1).unwrap();
+        drop(receiver2);
+        drop(creation_waiter);
+
+        let mut report = hub.collect_garbage();
+        report.channels.sort();
+        assert_eq!(report.channels, vec!["channel1", "channel2"]);
+        assert_eq!(report.creation_waiters, 1);
+        assert_eq!(report.destruction_waiters, 0);
+        assert_eq!(hub.channel_state(&"channel1"), ChannelState::Uninitialised);
+        assert_eq!(hub.channel_state(&"channel2"), ChannelState::Uninitialised);
+        assert_eq!(hub.channel_state(&"channel3"), ChannelState::Running);
+        assert_eq!(hub.number_of_creation_waiter(&"channel1"), 0);
+        assert_eq!(hub.number_of_destruction_waiter(&"channel1"), 1);
+
+        assert!(hub.collect_garbage().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_gc_on_mutation() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        hub.set_gc_policy(GcPolicy::OnMutation);
+        let receiver = hub.subscribe(&"channel1", 100);
+
+        assert_eq!(
+            hub.unsubscribe(&"channel1", &receiver).unwrap(),
+            ChannelState::Uninitialised
+        );
+        let report = hub.take_gc_report();
+        assert_eq!(report.channels, vec!["channel1"]);
+        assert!(hub.take_gc_report().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_periodic_gc() {
+        let hub = Arc::new(Mutex::new(NotifierHub::<String, &'static str>::new()));
+        let receiver = hub.lock().await.subscribe(&"channel1", 100);
+        let task = spawn_periodic_gc(&hub, Duration::from_millis(10));
+
+        drop(receiver);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(hub.lock().await.take_gc_report().channels, vec!["channel1"]);
+
+        drop(hub);
+        task.await.unwrap(); // The task stops with the hub
+    }
+}
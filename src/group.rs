@@ -0,0 +1,217 @@
+use smart_channel::channel;
+use std::hash::Hash;
+
+use crate::{
+    error::NotifierError,
+    notifier::{MessageReceiver, MessageSender, NotifierHub, SmartChannelId},
+    waiter::DestructionReason,
+    weak_sender::{Downgrade, WeakMessageSender},
+};
+
+/// A named set of channels, and the subscribers that follow it.
+pub(crate) struct ChannelGroup<M, ChannelId> {
+    pub(crate) channels: Vec<ChannelId>,
+    /// The receivers returned by `subscribe_group`. The senders are weak, so a group does not keep
+    /// the receivers of its subscribers open once they are unsubscribed from all their channels.
+    pub(crate) subscribers: Vec<WeakMessageSender<M>>,
+}
+
+impl<M, ChannelId> Default for ChannelGroup<M, ChannelId> {
+    fn default() -> Self {
+        Self {
+            channels: Vec::new(),
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Returns the channels of the group, if it is defined.
+    pub fn group_channels(&self, name: &str) -> Option<&[ChannelId]> {
+        self.groups.get(name).map(|group| group.channels.as_slice())
+    }
+
+    /// Removes the definition of the group. The subscribers of the group stay subscribed to its channels,
+    /// but they no longer follow its changes.
+    pub fn remove_group(&mut self, name: &str) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /// Makes the groups followed by the subscriber of the sender follow the sender instead, after a resubscription.
+    pub(crate) fn regroup(&mut self, sender: &MessageSender<M>) {
+        for group in self.groups.values_mut() {
+            for subscriber in group
+                .subscribers
+                .iter_mut()
+                .filter(|subscriber| subscriber.id() == sender.id())
+            {
+                *subscriber = sender.downgrade();
+            }
+        }
+    }
+
+    /// Removes the subscriber from the groups it follows, and the subscribers whose receiver is gone.
+    pub(crate) fn leave_groups(&mut self, id: &SmartChannelId) {
+        for group in self.groups.values_mut() {
+            group
+                .subscribers
+                .retain(|sender| sender.id() != id && sender.is_alive());
+        }
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Defines or redefines the group `name` as the given channels.
+    /// The existing subscribers of the group are subscribed to the added channels
+    /// and unsubscribed from the removed ones. A subscriber that left all its channels,
+    /// by `unsubscribe_all` or a shutdown, no longer follows the group.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// hub.define_group("ingest", &["a", "b"]);
+    /// let mut receiver = hub.subscribe_group("ingest", 10).unwrap();
+    ///
+    /// hub.define_group("ingest", &["a", "b", "c"]);
+    /// hub.clone_send("Hello", &"c").unwrap();
+    /// assert_eq!(receiver.try_recv().unwrap(), "Hello");
+    /// ```
+    pub fn define_group(&mut self, name: &str, channels: &[ChannelId]) {
+        let mut group = self.groups.remove(name).unwrap_or_default();
+        let subscribers: Vec<MessageSender<M>> = group
+            .subscribers
+            .iter()
+            .filter_map(WeakMessageSender::upgrade)
+            .filter(|sender| !sender.is_closed())
+            .collect();
+
+        let mut new_channels: Vec<ChannelId> = Vec::with_capacity(channels.len());
+        for id in channels {
+            if !new_channels.contains(id) {
+                new_channels.push(id.clone());
+            }
+        }
+        for id in group
+            .channels
+            .iter()
+            .filter(|id| !new_channels.contains(id))
+        {
+            for sender in &subscribers {
+                self.detach(id, sender);
+            }
+        }
+        for id in new_channels
+            .iter()
+            .filter(|id| !group.channels.contains(id))
+        {
+            for sender in &subscribers {
+                self.insert_sender(sender.clone(), id);
+            }
+        }
+        group.channels = new_channels;
+        group.subscribers = subscribers.iter().map(Downgrade::downgrade).collect();
+        self.groups.insert(name.to_string(), group);
+    }
+
+    /// Subscribes to all the channels of the group at once. The receiver follows the redefinitions of the group.
+    /// Returns a `GroupNotExist` error if the group is not defined, or a `Draining` error if the hub is draining.
+    pub fn subscribe_group(
+        &mut self,
+        name: &str,
+        channel_size: usize,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        if self.draining {
+            return Err(NotifierError::Draining);
+        }
+        let channels = match self.groups.get(name) {
+            Some(group) => group.channels.clone(),
+            None => return Err(NotifierError::GroupNotExist(name.to_string())),
+        };
+        let (sender, receiver) = channel(channel_size, self.get_new_id());
+        for id in &channels {
+            self.insert_sender(sender.clone(), id);
+        }
+        if let Some(group) = self.groups.get_mut(name) {
+            group.subscribers.push(sender.downgrade());
+        }
+        Ok(receiver)
+    }
+
+    /// Removes the sender from the channel if it is there, and notifies the destruction waiters.
+    fn detach(&mut self, id: &ChannelId, sender: &MessageSender<M>) {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        let Some(senders) = self.senders.get_mut(&id) else {
+            return;
+        };
+        let n = senders.len();
+        senders.retain(|s| s.id() != sender.id());
+        if senders.len() != n {
+            self.subscribers_changed();
+            let _ = self.notify_destruction(&id, sender.clone(), DestructionReason::Unsubscribed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::ChannelState;
+
+    #[tokio::test]
+    async fn test_group_redefinition() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        hub.define_group("ingest", &["a", "b"]);
+        let mut receiver = hub.subscribe_group("ingest", 10).unwrap();
+        assert_eq!(hub.subscribed_list(&receiver).len(), 2);
+
+        let mut destruction_waiter = hub.get_destruction_waiter(&"a");
+        hub.define_group("ingest", &["b", "c", "c"]);
+        assert_eq!(hub.group_channels("ingest").unwrap(), &["b", "c"]);
+        assert!(destruction_waiter.try_recv().is_ok());
+        assert_eq!(hub.channel_state(&"a"), ChannelState::Over);
+
+        hub.clone_send("On c".to_string(), &"c").unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), "On c");
+
+        assert!(hub.remove_group("ingest"));
+        hub.define_group("ingest", &["d"]);
+        assert!(!hub.is_subscribed(&"d", &receiver));
+        assert!(hub.is_subscribed(&"b", &receiver));
+    }
+
+    #[tokio::test]
+    async fn test_group_left_by_subscriber() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        hub.define_group("ingest", &["a", "b"]);
+        let receiver = hub.subscribe_group("ingest", 10).unwrap();
+        hub.unsubscribe_all(&receiver);
+        hub.define_group("ingest", &["a", "b", "c"]);
+        assert!(hub.subscribed_list(&receiver).is_empty());
+
+        let mut receiver = hub.subscribe_group("ingest", 10).unwrap();
+        hub.shutdown_with_factory(&"a", |_| "Closed".to_string())
+            .unwrap();
+        hub.shutdown_channels_with_factory(&["b", "c"], |_| "Closed".to_string());
+        hub.define_group("ingest", &["d"]);
+        assert!(hub.subscribed_list(&receiver).is_empty());
+        for _ in 0..3 {
+            assert_eq!(receiver.recv().await.unwrap(), "Closed");
+        }
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_unknown_group() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        assert!(matches!(
+            hub.subscribe_group("unknown", 10),
+            Err(NotifierError::GroupNotExist(name)) if name == "unknown"
+        ));
+    }
+}
@@ -0,0 +1,421 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    closable_trait::ClosableMessage,
+    error::NotifierError,
+    notifier::{
+        ChannelState, CreationWaiter, DestructionWaiter, MessageReceiver, MessageSender,
+        NotifierHub, ShutdownResults,
+    },
+    notifier_trait::Notifier,
+    sync::{into_inner, lock},
+    weak_sender::WeakMessageSender,
+    writing_handler::WritingHandler,
+};
+
+/// A cheap cloneable handle on a hub, obtained with `NotifierHub::into_handle`.
+/// It exposes the API of the hub with `&self`, so it can be passed around like a client object
+/// instead of threading an `Arc<Mutex<NotifierHub>>` everywhere.
+/// The hub is behind a blocking mutex, which is fine as none of its operations awaits.
+/// The methods that are not mirrored here are reachable with `with`.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::notifier::NotifierHub;
+///
+/// let handle = NotifierHub::new().into_handle();
+/// let publisher = handle.clone();
+///
+/// let mut receiver = handle.subscribe(&"channel1", 10);
+/// publisher.clone_send("Hello", &"channel1").unwrap();
+/// assert_eq!(receiver.try_recv().unwrap(), "Hello");
+/// ```
+pub struct HubHandle<M, ChannelId: Eq + Hash> {
+    pub(crate) hub: Arc<Mutex<NotifierHub<M, ChannelId>>>,
+}
+
+impl<M, ChannelId: Eq + Hash> Clone for HubHandle<M, ChannelId> {
+    fn clone(&self) -> Self {
+        Self {
+            hub: Arc::clone(&self.hub),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Moves the hub behind a cheap cloneable `HubHandle`.
+    pub fn into_handle(self) -> HubHandle<M, ChannelId> {
+        HubHandle {
+            hub: Arc::new(Mutex::new(self)),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> HubHandle<M, ChannelId> {
+    /// Locks the hub. A panic during a previous operation does not make the hub unusable.
+    fn lock(&self) -> MutexGuard<'_, NotifierHub<M, ChannelId>> {
+        lock(&self.hub)
+    }
+
+    /// Runs the closure with an exclusive access to the hub, for the methods not mirrored by the handle.
+    pub fn with<T>(&self, f: impl FnOnce(&mut NotifierHub<M, ChannelId>) -> T) -> T {
+        f(&mut self.lock())
+    }
+
+    /// Returns the hub if this is the last handle, otherwise the handle is given back.
+    pub fn into_inner(self) -> Result<NotifierHub<M, ChannelId>, Self> {
+        match Arc::try_unwrap(self.hub) {
+            Ok(hub) => Ok(into_inner(hub)),
+            Err(hub) => Err(Self { hub }),
+        }
+    }
+
+    /// See `NotifierHub::is_subscribed`.
+    pub fn is_subscribed(&self, channel: &ChannelId, receiver: &MessageReceiver<M>) -> bool {
+        self.lock().is_subscribed(channel, receiver)
+    }
+
+    /// See `NotifierHub::get_weak_sender`.
+    pub fn get_weak_sender(
+        &self,
+        channel: &ChannelId,
+        receiver: &MessageReceiver<M>,
+    ) -> Option<WeakMessageSender<M>> {
+        self.lock().get_weak_sender(channel, receiver)
+    }
+
+    /// See `NotifierHub::channel_state`.
+    pub fn channel_state(&self, id: &ChannelId) -> ChannelState {
+        self.lock().channel_state(id)
+    }
+
+    /// See `NotifierHub::channel_number_subscriber`.
+    pub fn channel_number_subscriber(&self, id: &ChannelId) -> usize {
+        self.lock().channel_number_subscriber(id)
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::send_with`.
+    pub fn send_with(
+        &self,
+        id: &ChannelId,
+        factory: impl Fn() -> M,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.lock().send_with(id, factory)
+    }
+}
+
+impl<M, ChannelId> HubHandle<Arc<M>, ChannelId>
+where
+    M: Send + Sync + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::broadcast_arc`.
+    pub fn broadcast_arc(&self, msg: M) -> WritingHandler<Arc<M>, ChannelId> {
+        self.lock().broadcast_arc(msg)
+    }
+
+    /// See `NotifierHub::arc_send`.
+    pub fn arc_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<Arc<M>, ChannelId>, NotifierError<Arc<M>, ChannelId>> {
+        self.lock().arc_send(msg, id)
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::clean_channel`.
+    pub fn clean_channel(&self, channel: &ChannelId) -> ChannelState {
+        self.lock().clean_channel(channel)
+    }
+
+    /// See `NotifierHub::clean_all`.
+    pub fn clean_all(&self) -> HashMap<ChannelId, ChannelState> {
+        self.lock().clean_all()
+    }
+
+    /// See `NotifierHub::unsubscribe_all`.
+    pub fn unsubscribe_all(&self, receiver: &MessageReceiver<M>) -> Vec<ChannelId> {
+        self.lock().unsubscribe_all(receiver)
+    }
+
+    /// See `NotifierHub::unsubscribe`.
+    pub fn unsubscribe(
+        &self,
+        id: &ChannelId,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<ChannelState, NotifierError<M, ChannelId>> {
+        self.lock().unsubscribe(id, receiver)
+    }
+
+    /// See `NotifierHub::unsubscribe_multiple`.
+    pub fn unsubscribe_multiple(
+        &self,
+        ids: &[ChannelId],
+        receiver: &MessageReceiver<M>,
+    ) -> Result<(), NotifierError<M, ChannelId>> {
+        self.lock().unsubscribe_multiple(ids, receiver)
+    }
+
+    /// See `NotifierHub::broadcast_clone`.
+    pub fn broadcast_clone(&self, msg: M) -> WritingHandler<M, ChannelId> {
+        self.lock().broadcast_clone(msg)
+    }
+
+    /// See `NotifierHub::clone_send`.
+    pub fn clone_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.lock().clone_send(msg, id)
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::get_channels`.
+    pub fn get_channels(&self) -> Vec<ChannelId> {
+        self.lock().get_channels()
+    }
+
+    /// See `NotifierHub::subscribe`.
+    pub fn subscribe(&self, id: &ChannelId, channel_size: usize) -> MessageReceiver<M> {
+        self.lock().subscribe(id, channel_size)
+    }
+
+    /// See `NotifierHub::try_subscribe`.
+    pub fn try_subscribe(
+        &self,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        self.lock().try_subscribe(id, channel_size)
+    }
+
+    /// See `NotifierHub::subscribed_list`.
+    pub fn subscribed_list(&self, receiver: &MessageReceiver<M>) -> Vec<ChannelId> {
+        self.lock().subscribed_list(receiver)
+    }
+
+    /// See `NotifierHub::get_creation_waiter`.
+    pub fn get_creation_waiter(&self, id: &ChannelId) -> CreationWaiter {
+        self.lock().get_creation_waiter(id)
+    }
+
+    /// See `NotifierHub::get_destruction_waiter`.
+    pub fn get_destruction_waiter(&self, id: &ChannelId) -> DestructionWaiter<M> {
+        self.lock().get_destruction_waiter(id)
+    }
+
+    /// See `NotifierHub::rename_channel`.
+    pub fn rename_channel(
+        &self,
+        old: &ChannelId,
+        new: ChannelId,
+        keep_alias: bool,
+    ) -> Result<(), NotifierError<M, ChannelId>> {
+        self.lock().rename_channel(old, new, keep_alias)
+    }
+}
+
+impl<M: Clone, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::resubscribe`.
+    pub fn resubscribe(
+        &self,
+        id: &ChannelId,
+        old_receiver: MessageReceiver<M>,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        self.lock().resubscribe(id, old_receiver)
+    }
+
+    /// See `NotifierHub::subscribe_multiple`.
+    pub fn subscribe_multiple(&self, ids: &[ChannelId], channel_size: usize) -> MessageReceiver<M> {
+        self.lock().subscribe_multiple(ids, channel_size)
+    }
+
+    /// See `NotifierHub::try_subscribe_multiple`.
+    pub fn try_subscribe_multiple(
+        &self,
+        ids: &[ChannelId],
+        channel_size: usize,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        self.lock().try_subscribe_multiple(ids, channel_size)
+    }
+
+    /// See `NotifierHub::get_sender`.
+    pub fn get_sender(
+        &self,
+        channel: &ChannelId,
+        receiver: &MessageReceiver<M>,
+    ) -> Option<MessageSender<M>> {
+        self.lock().get_sender(channel, receiver)
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static + Clone,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::shutdown_with_factory`.
+    pub fn shutdown_with_factory(
+        &self,
+        channel: &ChannelId,
+        factory: impl Fn(&ChannelId) -> M,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.lock().shutdown_with_factory(channel, factory)
+    }
+
+    /// See `NotifierHub::shutdown_all_with_factory`.
+    pub fn shutdown_all_with_factory(&self, factory: impl Fn(&ChannelId) -> M) {
+        self.lock().shutdown_all_with_factory(factory)
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static + Clone + ClosableMessage,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::shutdown_clone`.
+    pub fn shutdown_clone(
+        &self,
+        channel: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.lock().shutdown_clone(channel)
+    }
+
+    /// See `NotifierHub::shutdown_channels`.
+    pub fn shutdown_channels(
+        &self,
+        channels: &[ChannelId],
+    ) -> (ShutdownResults<M, ChannelId>, WritingHandler<M, ChannelId>) {
+        self.lock().shutdown_channels(channels)
+    }
+
+    /// See `NotifierHub::shutdown_all_clone`.
+    pub fn shutdown_all_clone(&self) {
+        self.lock().shutdown_all_clone()
+    }
+}
+
+impl<M, ChannelId> Notifier<M, ChannelId> for HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    fn subscribe(&mut self, id: &ChannelId, channel_size: usize) -> MessageReceiver<M> {
+        HubHandle::subscribe(self, id, channel_size)
+    }
+
+    fn subscribe_multiple(&mut self, ids: &[ChannelId], channel_size: usize) -> MessageReceiver<M> {
+        HubHandle::subscribe_multiple(self, ids, channel_size)
+    }
+
+    fn unsubscribe(
+        &mut self,
+        id: &ChannelId,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<ChannelState, NotifierError<M, ChannelId>> {
+        HubHandle::unsubscribe(self, id, receiver)
+    }
+
+    fn clone_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        HubHandle::clone_send(self, msg, id)
+    }
+
+    fn broadcast_clone(&self, msg: M) -> WritingHandler<M, ChannelId> {
+        HubHandle::broadcast_clone(self, msg)
+    }
+
+    fn shutdown_clone(
+        &mut self,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>>
+    where
+        M: ClosableMessage,
+    {
+        HubHandle::shutdown_clone(self, id)
+    }
+
+    fn channel_state(&self, id: &ChannelId) -> ChannelState {
+        HubHandle::channel_state(self, id)
+    }
+
+    fn get_creation_waiter(&mut self, id: &ChannelId) -> CreationWaiter {
+        HubHandle::get_creation_waiter(self, id)
+    }
+
+    fn get_destruction_waiter(&mut self, id: &ChannelId) -> DestructionWaiter<M> {
+        HubHandle::get_destruction_waiter(self, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync + Clone>(_: &T) {}
+
+    #[tokio::test]
+    async fn test_handle_across_tasks() {
+        let handle = NotifierHub::<String, &'static str>::new().into_handle();
+        assert_send_sync(&handle);
+        let mut creation_waiter = handle.get_creation_waiter(&"channel1");
+
+        let subscriber = handle.clone();
+        let task = tokio::spawn(async move {
+            let mut receiver = subscriber.subscribe(&"channel1", 10);
+            receiver.recv().await.unwrap()
+        });
+
+        creation_waiter.recv().await.unwrap();
+        handle
+            .clone_send("Hello".to_string(), &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap();
+        assert_eq!(task.await.unwrap(), "Hello");
+        assert_eq!(handle.channel_state(&"channel1"), ChannelState::Running);
+    }
+
+    #[tokio::test]
+    async fn test_into_inner() {
+        let handle = NotifierHub::<String, &'static str>::new().into_handle();
+        let _receiver = handle.subscribe(&"channel1", 10);
+        let other = handle.clone();
+
+        let handle = handle.into_inner().err().unwrap();
+        drop(other);
+        let hub = handle.into_inner().ok().unwrap();
+        assert_eq!(hub.channel_number_subscriber(&"channel1"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_with() {
+        let handle = NotifierHub::<String, &'static str>::new().into_handle();
+        handle.with(|hub| hub.set_subscriber_limit(&"channel1", Some(0)));
+        assert!(handle.try_subscribe(&"channel1", 10).is_err());
+    }
+}
@@ -0,0 +1,433 @@
+use futures::future::{join_all, BoxFuture};
+use std::{future::Future, hash::Hash, sync::Arc};
+use tokio::{
+    sync::{watch, Semaphore},
+    task::{JoinHandle, JoinSet},
+    time::{timeout_at, Duration, Instant},
+};
+
+use crate::{
+    closable_trait::ClosableMessage,
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::{MessageReceiver, NotifierHub, SmartChannelId},
+};
+
+type OnClose = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+/// Signals the hub that a handler subscription is over, its sender being dropped once the processing
+/// and the `on_close` callback are done, or when the subscription is stopped or dropped.
+pub(crate) struct HandlerCompletion {
+    id: SmartChannelId,
+    done: watch::Receiver<()>,
+}
+
+impl HandlerCompletion {
+    fn is_done(&self) -> bool {
+        self.done.has_changed().is_err()
+    }
+}
+
+/// The handler subscriptions of a channel shut down by `shutdown_handlers`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HandlerShutdown {
+    /// The subscriptions that handled their messages and ran their `on_close` callback in time.
+    pub completed: Vec<SmartChannelId>,
+    /// The subscriptions still running at the timeout.
+    pub timed_out: Vec<SmartChannelId>,
+}
+
+/// A subscription whose messages are processed by a callback, obtained with `HubHandle::subscribe_handler`.
+/// The messages are handled one at a time, in order, unless `concurrency` allows more of them at once.
+/// Nothing is processed until `spawn` is called, the messages published meanwhile waiting in the channel.
+pub struct HandlerSubscription<M, F> {
+    receiver: MessageReceiver<M>,
+    handler: F,
+    concurrency: usize,
+    on_close: Option<OnClose>,
+    done: watch::Sender<()>,
+}
+
+impl<M, F, Fut> HandlerSubscription<M, F>
+where
+    M: Send + 'static,
+    F: Fn(M) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    /// Processes up to `n` messages in parallel, each one in its own task. The next message is only received
+    /// once one of the `n` running ones is handled, so the in-flight work is bounded and the slow handlers
+    /// push back on the publishers through the channel. The messages may then be handled out of order.
+    pub fn concurrency(mut self, n: usize) -> Self {
+        self.concurrency = n.max(1);
+        self
+    }
+
+    /// Processes the messages one at a time, in the order they were published, which is the default.
+    pub fn in_order(self) -> Self {
+        self.concurrency(1)
+    }
+
+    /// Sets the callback awaited once the subscription is over and its messages are handled,
+    /// so the consumer can flush its state. `shutdown_handlers` waits for it before reporting the shutdown done.
+    /// It is not called if the processing is stopped.
+    pub fn on_close<C, CFut>(mut self, on_close: C) -> Self
+    where
+        C: FnOnce() -> CFut + Send + 'static,
+        CFut: Future<Output = ()> + Send + 'static,
+    {
+        self.on_close = Some(Box::new(move || Box::pin(on_close())));
+        self
+    }
+
+    /// Returns the id of the subscriber.
+    pub fn id(&self) -> SmartChannelId {
+        self.receiver.id()
+    }
+
+    /// Starts processing the messages. The processing ends once the subscription is over and the
+    /// messages already received are handled, or when the guard is dropped.
+    pub fn spawn(self) -> HandlerGuard {
+        let Self {
+            mut receiver,
+            handler,
+            concurrency,
+            on_close,
+            done,
+        } = self;
+        let id = receiver.id();
+        let task = tokio::spawn(async move {
+            let permits = Arc::new(Semaphore::new(concurrency));
+            let mut running = JoinSet::new();
+            loop {
+                let Ok(permit) = permits.clone().acquire_owned().await else {
+                    break;
+                };
+                let Some(msg) = receiver.recv().await else {
+                    break;
+                };
+                let handled = handler(msg);
+                running.spawn(async move {
+                    handled.await;
+                    drop(permit);
+                });
+                while running.try_join_next().is_some() {}
+            }
+            while running.join_next().await.is_some() {}
+            if let Some(on_close) = on_close {
+                on_close().await;
+            }
+            drop(done);
+        });
+        HandlerGuard { id, task }
+    }
+}
+
+/// Controls a spawned `HandlerSubscription`. Dropping the guard stops the processing, aborting the running handlers.
+pub struct HandlerGuard {
+    id: SmartChannelId,
+    task: JoinHandle<()>,
+}
+
+impl Drop for HandlerGuard {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl HandlerGuard {
+    /// Returns the id of the subscriber.
+    pub fn id(&self) -> SmartChannelId {
+        self.id
+    }
+
+    /// Stops the processing, aborting the running handlers.
+    pub fn stop(&self) {
+        self.task.abort();
+    }
+
+    /// Returns true once the processing is over, the subscription being over or the processing stopped.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Keeps the completion signal of a handler subscription, dropping the ones of the subscriptions that are over.
+    fn register_handler(&mut self, channel: &ChannelId, completion: HandlerCompletion) {
+        let completions = self.handler_completions.entry(channel.clone()).or_default();
+        completions.retain(|completion| !completion.is_done());
+        completions.push(completion);
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Subscribes to the channel a callback invoked on each message, instead of a receiver to poll.
+    /// The returned subscription is configured with `concurrency` or `in_order`, then started with `spawn`.
+    /// Like a receiver, the subscription ends once its channel is shut down, after handling the close message.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    /// use std::sync::{atomic::{AtomicU32, Ordering}, Arc};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let total = Arc::new(AtomicU32::new(0));
+    /// let counter = total.clone();
+    /// let guard = handle
+    ///     .subscribe_handler(&"orders", 10, move |amount: u32| {
+    ///         let counter = counter.clone();
+    ///         async move {
+    ///             counter.fetch_add(amount, Ordering::SeqCst);
+    ///         }
+    ///     })
+    ///     .concurrency(4)
+    ///     .spawn();
+    ///
+    /// for amount in 1..=3 {
+    ///     handle.clone_send(amount, &"orders").unwrap().wait(None).await;
+    /// }
+    /// handle.shutdown_with_factory(&"orders", |_| 0).unwrap();
+    /// while !guard.is_finished() {
+    ///     tokio::task::yield_now().await;
+    /// }
+    /// assert_eq!(total.load(Ordering::SeqCst), 6);
+    /// # }
+    /// ```
+    pub fn subscribe_handler<F, Fut>(
+        &self,
+        id: &ChannelId,
+        channel_size: usize,
+        handler: F,
+    ) -> HandlerSubscription<M, F>
+    where
+        F: Fn(M) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (done, completion) = watch::channel(());
+        let receiver = self.with(|hub| {
+            let receiver = hub.subscribe(id, channel_size);
+            let completion = HandlerCompletion {
+                id: receiver.id(),
+                done: completion,
+            };
+            hub.register_handler(id, completion);
+            receiver
+        });
+        HandlerSubscription {
+            receiver,
+            handler,
+            concurrency: 1,
+            on_close: None,
+            done,
+        }
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static + Clone,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Shuts down the channel as `shutdown_with_factory` does, then waits for its handler subscriptions
+    /// to handle their remaining messages and run their `on_close` callback, up to the timeout.
+    /// The subscriptions that are stopped or that were never spawned count as completed.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    /// use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+    /// use tokio::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let flushed = Arc::new(AtomicBool::new(false));
+    /// let on_close = flushed.clone();
+    /// let _guard = handle
+    ///     .subscribe_handler(&"orders", 10, |_amount: u32| async {})
+    ///     .on_close(move || async move { on_close.store(true, Ordering::SeqCst) })
+    ///     .spawn();
+    ///
+    /// let report = handle
+    ///     .shutdown_handlers_with_factory(&"orders", |_| 0, Duration::from_secs(1))
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!((report.completed.len(), report.timed_out.len()), (1, 0));
+    /// assert!(flushed.load(Ordering::SeqCst));
+    /// # }
+    /// ```
+    pub async fn shutdown_handlers_with_factory(
+        &self,
+        channel: &ChannelId,
+        factory: impl Fn(&ChannelId) -> M,
+        timeout: Duration,
+    ) -> Result<HandlerShutdown, NotifierError<M, ChannelId>> {
+        let deadline = Instant::now() + timeout;
+        let (writings, completions) = self.with(|hub| {
+            let writings = hub.shutdown_with_factory(channel, factory)?;
+            let completions = hub
+                .handler_completions
+                .remove(hub.aliases.get(channel).unwrap_or(channel))
+                .unwrap_or_default();
+            Ok((writings, completions))
+        })?;
+        writings
+            .wait(Some(deadline.saturating_duration_since(Instant::now())))
+            .await;
+        let mut waiting: Vec<_> = completions
+            .iter()
+            .map(|completion| completion.done.clone())
+            .collect();
+        let _ = timeout_at(
+            deadline,
+            join_all(waiting.iter_mut().map(|done| done.changed())),
+        )
+        .await;
+        let (completed, timed_out): (Vec<_>, Vec<_>) = completions
+            .iter()
+            .partition(|completion| completion.is_done());
+        Ok(HandlerShutdown {
+            completed: completed.iter().map(|completion| completion.id).collect(),
+            timed_out: timed_out.iter().map(|completion| completion.id).collect(),
+        })
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static + Clone + ClosableMessage,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `shutdown_handlers_with_factory`, the close message being obtained via the ClosableTrait.
+    pub async fn shutdown_handlers(
+        &self,
+        channel: &ChannelId,
+        timeout: Duration,
+    ) -> Result<HandlerShutdown, NotifierError<M, ChannelId>> {
+        self.shutdown_handlers_with_factory(channel, |_| M::get_close_message(), timeout)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotifierHub;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+    use tokio::time::{sleep, Duration};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_handler_concurrency() {
+        let handle: HubHandle<u64, &'static str> = NotifierHub::new().into_handle();
+        let (in_flight, max_in_flight) =
+            (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let (current, max) = (in_flight.clone(), max_in_flight.clone());
+        let parallel = handle
+            .subscribe_handler(&"channel1", 10, move |msg| {
+                let (current, max) = (current.clone(), max.clone());
+                async move {
+                    max.fetch_max(current.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    sleep(Duration::from_millis(10 * (4 - msg))).await;
+                    current.fetch_sub(1, Ordering::SeqCst);
+                }
+            })
+            .concurrency(2)
+            .spawn();
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let order = handled.clone();
+        let sequential = handle
+            .subscribe_handler(&"channel1", 10, move |msg| {
+                let order = order.clone();
+                async move {
+                    sleep(Duration::from_millis(10 * (4 - msg))).await;
+                    order.lock().unwrap().push(msg);
+                }
+            })
+            .concurrency(3)
+            .in_order()
+            .spawn();
+
+        for msg in 0..4 {
+            handle
+                .clone_send(msg, &"channel1")
+                .unwrap()
+                .wait(None)
+                .await;
+        }
+        handle.shutdown_with_factory(&"channel1", |_| 4).unwrap();
+        sleep(Duration::from_millis(200)).await;
+        assert!(parallel.is_finished() && sequential.is_finished());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+        assert_eq!(*handled.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_handlers() {
+        let handle: HubHandle<u64, &'static str> = NotifierHub::new().into_handle();
+        let flushed = Arc::new(Mutex::new(Vec::new()));
+        let (handled, on_close) = (flushed.clone(), flushed.clone());
+        let flushing = handle
+            .subscribe_handler(&"channel1", 10, move |msg| {
+                let handled = handled.clone();
+                async move {
+                    sleep(Duration::from_millis(10)).await;
+                    handled.lock().unwrap().push(msg);
+                }
+            })
+            .on_close(move || async move {
+                sleep(Duration::from_millis(10)).await;
+                on_close.lock().unwrap().push(u64::MAX);
+            })
+            .spawn();
+        let slow = handle
+            .subscribe_handler(&"channel1", 10, |_| sleep(Duration::from_secs(10)))
+            .spawn();
+        let stopped = handle
+            .subscribe_handler(&"channel1", 10, |_| async {})
+            .spawn();
+        stopped.stop();
+
+        handle.clone_send(1, &"channel1").unwrap().wait(None).await;
+        let start = Instant::now();
+        let report = handle
+            .shutdown_handlers_with_factory(&"channel1", |_| 2, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert_eq!(report.completed, vec![flushing.id(), stopped.id()]);
+        assert_eq!(report.timed_out, vec![slow.id()]);
+        assert_eq!(*flushed.lock().unwrap(), vec![1, 2, u64::MAX]);
+        assert!(handle
+            .shutdown_handlers_with_factory(&"channel1", |_| 2, Duration::from_secs(1))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_handlers_after_rename() {
+        let handle: HubHandle<u64, &'static str> = NotifierHub::new().into_handle();
+        let handler = handle
+            .subscribe_handler(&"channel1", 10, |_| async {})
+            .spawn();
+        handle
+            .with(|hub| hub.rename_channel(&"channel1", "renamed", true))
+            .unwrap();
+        assert!(!handle.with(|hub| hub.handler_completions.contains_key(&"channel1")));
+
+        let report = handle
+            .shutdown_handlers_with_factory(&"channel1", |_| 2, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(report.completed, vec![handler.id()]);
+        assert!(handle.with(|hub| hub.handler_completions.is_empty()));
+    }
+}
@@ -0,0 +1,155 @@
+use std::{hash::Hash, sync::Arc};
+
+use crate::{
+    channel_entry::ChannelEntry, error::NotifierError, handle::HubHandle, notifier::NotifierHub,
+};
+
+impl<M: Clone, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Moves the subscribers of the channel into the other hub at once, so a hub can be replaced
+    /// without the subscribers having to reconnect: their receivers keep working and are fed by the other hub.
+    /// Everything the hub keeps for the channel is moved along, unless the other hub already defines it: its settings,
+    /// sequencer, journal, park buffer, metadata and handler completions. The control lanes, inboxes and credits
+    /// of the subscribers are shared with the other hub, and kept by `self` only while they are subscribed to another
+    /// of its channels. The creation waiters of the other hub are notified, and the messages it parked on the channel
+    /// are written to the first moved subscriber.
+    /// The channel becomes uninitialised in `self`, whose waiters stay, without notifying its destruction waiters
+    /// as no subscriber is dead.
+    /// Returns the number of moved subscribers, or an error if the channel does not exist.
+    pub fn transfer_subscribers(
+        &mut self,
+        id: &ChannelId,
+        other: &mut NotifierHub<M, ChannelId>,
+    ) -> Result<usize, NotifierError<M, ChannelId>> {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        if !self.senders.contains_key(&id) {
+            return Err(NotifierError::ChannelNotExist(id));
+        }
+        let mut entry = self.take_channel(&id);
+        // The waiters observe this hub, so they stay in it
+        let waiters = std::mem::take(&mut entry.waiters);
+        self.insert_channel(&id, ChannelEntry::of_waiters(waiters));
+
+        let target = other.aliases.get(&id).unwrap_or(&id).clone();
+        let senders = entry.senders.as_deref().unwrap_or_default();
+        if let Some(sender) = senders.first() {
+            other.flush_parked(&target, sender);
+        }
+        for sender in senders {
+            other.insert_subscriber(sender, self.subscriber_entry(sender.id()));
+        }
+        let moved: Vec<_> = senders.iter().map(|sender| *sender.id()).collect();
+        other.insert_channel(&target, entry);
+        for id in &moved {
+            self.breaker.forget(id);
+            self.subscriber_removed(id);
+        }
+
+        let _ = other.notify_creation(&target);
+        other.subscribers_changed();
+        other.on_mutation();
+        self.notify_state(&id);
+        self.subscribers_changed();
+        self.on_mutation();
+        Ok(moved.len())
+    }
+}
+
+impl<M: Clone, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// Same as `NotifierHub::transfer_subscribers`, both hubs are locked for the whole move.
+    /// Transferring to the same hub leaves it untouched.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let blue = NotifierHub::new().into_handle();
+    /// let mut receiver = blue.subscribe(&"orders", 10);
+    ///
+    /// let green = NotifierHub::new().into_handle();
+    /// assert_eq!(blue.transfer_subscribers(&"orders", &green).unwrap(), 1);
+    /// green.clone_send("first order", &"orders").unwrap();
+    /// assert_eq!(receiver.try_recv().unwrap(), "first order");
+    /// ```
+    pub fn transfer_subscribers(
+        &self,
+        id: &ChannelId,
+        other: &HubHandle<M, ChannelId>,
+    ) -> Result<usize, NotifierError<M, ChannelId>> {
+        if Arc::ptr_eq(&self.hub, &other.hub) {
+            return self.with(|hub| match hub.channel_number_subscriber(id) {
+                0 => Err(NotifierError::ChannelNotExist(id.clone())),
+                subscribers => Ok(subscribers),
+            });
+        }
+        self.with(|hub| other.with(|other| hub.transfer_subscribers(id, other)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::ChannelState;
+
+    #[tokio::test]
+    async fn test_transfer_subscribers() {
+        let mut blue = NotifierHub::<u32, &'static str>::new();
+        let mut receiver1 = blue.subscribe(&"channel1", 10);
+        let mut receiver2 = blue.subscribe(&"channel1", 10);
+        blue.set_subscriber_limit(&"channel1", Some(3));
+
+        let mut green = NotifierHub::new();
+        let mut waiter = green.get_creation_waiter(&"channel1");
+        let mut receiver3 = green.subscribe(&"channel1", 10);
+        waiter.recv().await.unwrap();
+
+        assert_eq!(
+            blue.transfer_subscribers(&"channel1", &mut green).unwrap(),
+            2
+        );
+        assert_eq!(blue.channel_state(&"channel1"), ChannelState::Uninitialised);
+        assert_eq!(green.channel_number_subscriber(&"channel1"), 3);
+        assert_eq!(green.subscriber_limit(&"channel1"), Some(3));
+        assert!(waiter.recv().await.is_some());
+
+        green.clone_send(1, &"channel1").unwrap();
+        assert_eq!(receiver1.try_recv().unwrap(), 1);
+        assert_eq!(receiver2.try_recv().unwrap(), 1);
+        assert_eq!(receiver3.try_recv().unwrap(), 1);
+        assert!(blue.clone_send(2, &"channel1").is_err());
+        assert!(blue.transfer_subscribers(&"channel1", &mut green).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_subscriber_state() {
+        let mut blue = NotifierHub::<u32, &'static str>::new();
+        let mut credited = blue.subscribe_with_credits(&"channel1", 10, 1);
+        let mut controlled = blue.subscribe_with_control(&"channel1", 10);
+        blue.set_journal(&"channel1", Some(10));
+        blue.clone_send(1, &"channel1").unwrap();
+
+        let mut green = NotifierHub::new();
+        green.set_park_buffer(&"channel1", Some(10));
+        green.clone_send(2, &"channel1").unwrap();
+        assert_eq!(
+            blue.transfer_subscribers(&"channel1", &mut green).unwrap(),
+            2
+        );
+        assert!(blue.credits.is_empty() && blue.control_lanes.is_empty());
+        assert_eq!(blue.journal_seq(&"channel1"), None);
+        assert_eq!(green.journal_seq(&"channel1"), Some(1));
+        assert_eq!(green.parked_len(&"channel1"), 0);
+
+        let report = green.clone_send(3, &"channel1").unwrap().wait(None).await;
+        assert_eq!((report.delivered(), report.failed()), (1, 1));
+        credited.grant(1);
+        green.clone_send(4, &"channel1").unwrap();
+        assert_eq!(credited.try_recv().unwrap(), 1);
+        assert_eq!(credited.try_recv().unwrap(), 2);
+        assert_eq!(credited.try_recv().unwrap(), 4);
+
+        green.clone_send(5, &"channel1").unwrap();
+        green.control_send(6, &"channel1").unwrap();
+        assert_eq!(controlled.recv().await.unwrap(), 6);
+        assert_eq!(controlled.recv().await.unwrap(), 1);
+    }
+}
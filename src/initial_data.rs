@@ -0,0 +1,150 @@
+use std::{future::Future, hash::Hash, sync::Arc};
+
+use smart_channel::channel;
+
+use crate::{
+    handle::HubHandle,
+    notifier::{MessageSender, NotifierHub},
+};
+
+/// Takes the sender of a new subscriber and returns the sender to insert in the hub, the messages given to the
+/// latter being delivered after the initial data.
+pub(crate) type InitialData<M> =
+    Arc<dyn Fn(MessageSender<M>, usize) -> MessageSender<M> + Send + Sync>;
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Registers the callback fetching the initial data of the channel, replacing the previous one, `None` removes it.
+    /// Each time `subscribe` adds a subscriber to the channel, the callback is invoked and its result is delivered
+    /// to this subscriber only, before the live messages, for the snapshot and updates patterns.
+    /// The live messages published while the data is fetched wait in a buffer of the size of the subscriber,
+    /// and are then forwarded by a task, so the subscribers of such a channel need a tokio runtime.
+    /// The receivers of `subscribe_multiple` are not given the initial data.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// hub.set_initial_data(&"prices", Some(|| async { Some(100) }));
+    ///
+    /// let mut prices = hub.subscribe(&"prices", 10);
+    /// hub.clone_send(101, &"prices").unwrap();
+    /// assert_eq!(prices.recv().await, Some(100));
+    /// assert_eq!(prices.recv().await, Some(101));
+    /// # }
+    /// ```
+    pub fn set_initial_data<F, Fut>(&mut self, id: &ChannelId, fetch: Option<F>)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<M>> + Send + 'static,
+    {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        let Some(fetch) = fetch else {
+            self.initial_data.remove(&id);
+            return;
+        };
+        let initial_data: InitialData<M> = Arc::new(move |subscriber, channel_size| {
+            let (live, mut messages) = channel(channel_size, *subscriber.id());
+            let data = fetch();
+            tokio::spawn(async move {
+                let data = tokio::select! {
+                    _ = subscriber.closed() => return,
+                    data = data => data,
+                };
+                if let Some(data) = data {
+                    if subscriber.send(data).await.is_err() {
+                        return;
+                    }
+                }
+                loop {
+                    let msg = tokio::select! {
+                        biased;
+                        _ = subscriber.closed() => return,
+                        msg = messages.recv() => msg,
+                    };
+                    let Some(msg) = msg else { return };
+                    if subscriber.send(msg).await.is_err() {
+                        return;
+                    }
+                }
+            });
+            live
+        });
+        self.initial_data.insert(id, initial_data);
+    }
+
+    /// Returns true if the channel has a callback fetching its initial data.
+    pub fn has_initial_data(&self, id: &ChannelId) -> bool {
+        self.initial_data
+            .contains_key(self.aliases.get(id).unwrap_or(id))
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::set_initial_data`.
+    pub fn set_initial_data<F, Fut>(&self, id: &ChannelId, fetch: Option<F>)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<M>> + Send + 'static,
+    {
+        self.with(|hub| hub.set_initial_data(id, fetch))
+    }
+
+    /// See `NotifierHub::has_initial_data`.
+    pub fn has_initial_data(&self, id: &ChannelId) -> bool {
+        self.with(|hub| hub.has_initial_data(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tokio::time::{sleep, Duration};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_initial_data_before_live() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let fetches = Arc::new(AtomicU32::new(0));
+        let counter = fetches.clone();
+        hub.set_initial_data(
+            &"channel1",
+            Some(move || {
+                let fetch = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    sleep(Duration::from_millis(10)).await;
+                    Some(100 + fetch)
+                }
+            }),
+        );
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        hub.clone_send(1, &"channel1").unwrap();
+        let mut receiver2 = hub.subscribe(&"channel1", 10);
+        hub.clone_send(2, &"channel1").unwrap();
+
+        assert_eq!(receiver1.recv().await, Some(100));
+        assert_eq!(receiver1.recv().await, Some(1));
+        assert_eq!(receiver1.recv().await, Some(2));
+        assert_eq!(receiver2.recv().await, Some(101));
+        assert_eq!(receiver2.recv().await, Some(2));
+        assert!(hub.is_subscribed(&"channel1", &receiver2));
+
+        hub.unsubscribe(&"channel1", &receiver2).unwrap();
+        assert_eq!(receiver2.recv().await, None);
+        hub.set_initial_data(&"channel1", None::<fn() -> std::future::Ready<Option<u32>>>);
+        let mut receiver3 = hub.subscribe(&"channel1", 10);
+        hub.clone_send(3, &"channel1").unwrap();
+        assert_eq!(receiver3.recv().await, Some(3));
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,111 @@
+use std::{collections::HashSet, fmt::Debug, hash::Hash};
+
+use crate::{notifier::NotifierHub, sync::lock};
+
+impl<M, ChannelId: Eq + Hash + Debug> NotifierHub<M, ChannelId> {
+    /// Checks the internal consistency of the hub, so fuzz and property tests can validate it after
+    /// any sequence of operations. Returns a description of each broken invariant:
+    /// - a subscriber is bound at most once to each channel,
+    /// - the ids left by `rename_channel` have no subscriber nor waiter, and point to a channel that is not renamed,
+    /// - the control lanes are bound to their subscriber,
+    /// - the deduplication windows and the park buffers are consistent,
+    /// - the subscriber ids given by the hub are below its counter.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let receiver = hub.subscribe_multiple(&["a", "b"], 10);
+    /// hub.rename_channel(&"a", "c", true).unwrap();
+    /// hub.unsubscribe(&"b", &receiver).unwrap();
+    /// assert_eq!(hub.invariants(), Ok(()));
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// ```
+    pub fn invariants(&self) -> Result<(), Vec<String>> {
+        let mut violations = Vec::new();
+        let address = self as *const Self as usize;
+
+        for (channel, senders) in &self.senders {
+            let mut ids = HashSet::new();
+            for sender in senders {
+                let id = sender.id();
+                if !ids.insert(*id) {
+                    violations.push(format!("{id:?} is bound twice to the channel {channel:?}"));
+                }
+                if id.notifier_address == address && id.channel_counter >= self.connection_id {
+                    violations.push(format!(
+                        "{id:?} is above the counter {}",
+                        self.connection_id
+                    ));
+                }
+            }
+        }
+
+        for (old, new) in &self.aliases {
+            if self.aliases.contains_key(new) {
+                violations.push(format!(
+                    "{old:?} is renamed to {new:?}, which is renamed too"
+                ));
+            }
+            if self.senders.contains_key(old)
+                || self.creation_senders.contains_key(old)
+                || self.destruction_senders.contains_key(old)
+                || self.state_senders.contains_key(old)
+            {
+                violations.push(format!(
+                    "{old:?} has been renamed but still has subscribers or waiters"
+                ));
+            }
+        }
+
+        for (id, lane) in &self.control_lanes {
+            if lane.id() != id {
+                violations.push(format!(
+                    "The control lane of {id:?} is bound to {:?}",
+                    lane.id()
+                ));
+            }
+        }
+
+        for (channel, window) in &self.dedup_windows {
+            if !lock(window).is_consistent() {
+                violations.push(format!("The dedup window of {channel:?} is inconsistent"));
+            }
+        }
+        for (channel, buffer) in &self.parked {
+            if !lock(buffer).is_within_capacity() {
+                violations.push(format!(
+                    "The park buffer of {channel:?} exceeds its capacity"
+                ));
+            }
+        }
+
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(violations),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notifier::NotifierHub;
+
+    #[tokio::test]
+    async fn test_invariants() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let receiver = hub.subscribe_multiple(&["channel1", "channel2"], 10);
+        let _control = hub.subscribe_with_control(&"channel1", 10);
+        let _waiter = hub.get_state_waiter(&"channel2");
+        hub.set_park_buffer(&"channel3", Some(1));
+        hub.clone_send(1, &"channel3").unwrap();
+        hub.rename_channel(&"channel2", "channel4", true).unwrap();
+        hub.unsubscribe_all(&receiver);
+        assert_eq!(hub.invariants(), Ok(()));
+
+        let duplicate = hub.senders[&"channel1"][0].clone();
+        hub.senders.get_mut(&"channel1").unwrap().push(duplicate);
+        assert_eq!(hub.invariants().unwrap_err().len(), 1);
+    }
+}
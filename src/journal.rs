@@ -0,0 +1,403 @@
+use std::{collections::VecDeque, hash::Hash, sync::Mutex};
+use tokio::sync::mpsc::error::{SendError, TrySendError};
+
+use crate::{
+    error::NotifierError,
+    format::DynCodec,
+    handle::HubHandle,
+    notifier::{MessageReceiver, NotifierHub},
+    sync::{get_mut, lock},
+};
+
+/// A recorded message, encoded in the format of the channel if it has one.
+enum Entry<M> {
+    Message(M),
+    Frame(Vec<u8>),
+}
+
+/// The last messages published on a channel, with their sequence number.
+pub(crate) struct Journal<M> {
+    pub(crate) capacity: usize,
+    /// The sequence number of the next message.
+    next_seq: u64,
+    entries: VecDeque<(u64, Entry<M>)>,
+    /// The codec of the format of the channel, taken when the first message is recorded in a format.
+    codec: Option<DynCodec<M>>,
+}
+
+impl<M> Journal<M> {
+    /// Records the message, dropping the oldest one when the journal is full.
+    fn push(&mut self, entry: Entry<M>) {
+        if self.capacity > 0 {
+            if self.entries.len() == self.capacity {
+                self.entries.pop_front();
+            }
+            self.entries.push_back((self.next_seq, entry));
+        }
+        self.next_seq += 1;
+    }
+
+    /// Returns the sequence number of the oldest message still recorded.
+    fn oldest(&self) -> u64 {
+        self.entries.front().map_or(self.next_seq, |(seq, _)| *seq)
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Records the last `capacity` messages published on the channel with `clone_send`, `arc_send`, `send_with`,
+    /// `context_send` or a transaction, along with their sequence number, so a subscriber that missed some of them
+    /// can ask for them again with `request_replay`. The messages written to one subscriber only, the broadcasts
+    /// to all the channels and the publishes waiting for an answer, such as `barrier_send`, are not recorded,
+    /// as their answer could not be replayed. The sequence numbers start at 0 and are given to each publish
+    /// made while the channel is running, even if none of its subscribers accepted the message.
+    /// If the channel has a registered format, see `set_channel_format`, the messages are recorded encoded in it
+    /// and decoded for the replays. The journal keeps the format it first encoded a message in until it is disabled,
+    /// and the messages the format fails to encode are recorded as they are.
+    /// `None` disables the journaling and drops the recorded messages.
+    pub fn set_journal(&mut self, id: &ChannelId, capacity: Option<usize>) {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        match capacity {
+            Some(capacity) => {
+                let journal = self.journals.entry(id).or_insert_with(|| {
+                    Mutex::new(Journal {
+                        capacity,
+                        next_seq: 0,
+                        entries: VecDeque::new(),
+                        codec: None,
+                    })
+                });
+                let journal = get_mut(journal);
+                journal.capacity = capacity;
+                let excess = journal.entries.len().saturating_sub(capacity);
+                journal.entries.drain(..excess);
+            }
+            None => {
+                self.journals.remove(&id);
+            }
+        }
+    }
+
+    /// Returns the sequence number the next message published on the channel will get,
+    /// `None` if the journaling is disabled.
+    pub fn journal_seq(&self, id: &ChannelId) -> Option<u64> {
+        self.journals
+            .get(self.aliases.get(id).unwrap_or(id))
+            .map(|journal| lock(journal).next_seq)
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Records the message built by `msg` in the journal of the channel, if the journaling is enabled,
+    /// for the messages that can't be cloned. It is only built if the channel has a journal.
+    pub(crate) fn journal_with(&self, id: &ChannelId, msg: impl FnOnce() -> M) {
+        let Some(journal) = self.journals.get(id) else {
+            return;
+        };
+        let mut journal = lock(journal);
+        if journal.codec.is_none() {
+            journal.codec = self.channel_codec(id).ok();
+        }
+        let msg = msg();
+        let entry = match journal.codec.as_mut().map(|codec| codec.encode(&msg)) {
+            Some(Ok(frame)) => Entry::Frame(frame),
+            _ => Entry::Message(msg),
+        };
+        journal.push(entry);
+    }
+}
+
+impl<M: Clone, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Records the message in the journal of the channel, if the journaling is enabled.
+    pub(crate) fn journal(&self, id: &ChannelId, msg: &M) {
+        self.journal_with(id, || msg.clone())
+    }
+
+    /// Returns the recorded messages of the channel from the sequence number.
+    /// Fails if the journaling is disabled, if the journal no longer holds the first requested messages,
+    /// or if one of them can't be decoded from the format of the channel.
+    pub(crate) fn journal_from(
+        &self,
+        id: &ChannelId,
+        from_seq: u64,
+    ) -> Result<Vec<M>, NotifierError<M, ChannelId>> {
+        let Some(journal) = self.journals.get(id) else {
+            return Err(NotifierError::JournalDisabled(id.clone()));
+        };
+        let mut journal = lock(journal);
+        if from_seq < journal.oldest() {
+            return Err(NotifierError::ReplayUnavailable {
+                id: id.clone(),
+                oldest: journal.oldest(),
+            });
+        }
+        let Journal { entries, codec, .. } = &mut *journal;
+        entries
+            .iter()
+            .filter(|(seq, _)| *seq >= from_seq)
+            .map(|(seq, entry)| match entry {
+                Entry::Message(msg) => Ok(msg.clone()),
+                Entry::Frame(frame) => codec
+                    .as_mut()
+                    .and_then(|codec| codec.decode(frame).ok())
+                    .ok_or_else(|| NotifierError::JournalUnreadable {
+                        id: id.clone(),
+                        seq: *seq,
+                    }),
+            })
+            .collect()
+    }
+}
+
+impl<M: Clone, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::set_journal`.
+    pub fn set_journal(&self, id: &ChannelId, capacity: Option<usize>) {
+        self.with(|hub| hub.set_journal(id, capacity))
+    }
+
+    /// See `NotifierHub::journal_seq`.
+    pub fn journal_seq(&self, id: &ChannelId) -> Option<u64> {
+        self.with(|hub| hub.journal_seq(id))
+    }
+
+    /// See `NotifierHub::request_replay`.
+    pub fn request_replay(
+        &self,
+        id: &ChannelId,
+        receiver: &MessageReceiver<M>,
+        from_seq: u64,
+    ) -> Result<usize, NotifierError<M, ChannelId>>
+    where
+        M: Send + 'static,
+    {
+        self.with(|hub| hub.request_replay(id, receiver, from_seq))
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Clone + Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Writes again to the receiver only the recorded messages of the channel from the sequence number,
+    /// in order, without publishing them to the other subscribers. Returns the number of replayed messages.
+    /// Each of them spends a credit of the subscriber as a publish does, the replay stopping short when
+    /// the subscriber runs out of credits or when its circuit is open.
+    /// The messages that don't fit in the buffer of the receiver are written by a task as it is read,
+    /// so the receiver can be read right away. The live messages published meanwhile may be interleaved
+    /// with the replayed ones.
+    /// Fails with `NotifierError::ReplayUnavailable` if the journal no longer holds the first requested
+    /// messages, with the oldest sequence number it holds, so the subscriber can resynchronize otherwise.
+    ///
+    /// Must be called within a tokio runtime when the replay does not fit in the buffer of the receiver.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// hub.set_journal(&"orders", Some(100));
+    /// let mut receiver = hub.subscribe(&"orders", 1);
+    ///
+    /// let from = hub.journal_seq(&"orders").unwrap();
+    /// hub.clone_send("order 1", &"orders").unwrap().wait(None).await;
+    /// hub.clone_send("order 2", &"orders").unwrap().detach();
+    /// // The subscriber lost track of its messages, it asks for them again
+    /// while receiver.recv().await != Some("order 2") {}
+    ///
+    /// assert_eq!(hub.request_replay(&"orders", &receiver, from).unwrap(), 2);
+    /// assert_eq!(receiver.recv().await, Some("order 1"));
+    /// assert_eq!(receiver.recv().await, Some("order 2"));
+    /// # }
+    /// ```
+    pub fn request_replay(
+        &self,
+        id: &ChannelId,
+        receiver: &MessageReceiver<M>,
+        from_seq: u64,
+    ) -> Result<usize, NotifierError<M, ChannelId>> {
+        let id = self.aliases.get(id).unwrap_or(id);
+        let sender = self
+            .senders_of(id)
+            .iter()
+            .find(|sender| sender.is_bound_to(receiver))
+            .ok_or_else(|| NotifierError::NotSubscribed(id.clone()))?;
+        let messages = self.journal_from(id, from_seq)?;
+        // The replay stops at the first message the circuit or the credits of the subscriber refuse
+        let mut messages = messages
+            .into_iter()
+            .take_while(|_| self.admit_writing(sender.id()).is_ok());
+        let mut replayed = 0;
+        while let Some(msg) = messages.next() {
+            match sender.try_send(msg) {
+                Ok(()) => {
+                    self.breaker.record(*sender.id(), true);
+                    replayed += 1;
+                }
+                Err(TrySendError::Full(msg)) => {
+                    // The caller can't read the receiver while the replay is written, so the rest is left to a task
+                    let rest: Vec<_> = std::iter::once(msg).chain(messages).collect();
+                    replayed += rest.len();
+                    let sender = (**sender).clone();
+                    tokio::spawn(async move {
+                        for msg in rest {
+                            if sender.send(msg).await.is_err() {
+                                break;
+                            }
+                        }
+                    });
+                    break;
+                }
+                Err(TrySendError::Closed(msg)) => {
+                    self.credits.refund(sender.id());
+                    self.breaker.record(*sender.id(), false);
+                    return Err(NotifierError::SendingError(SendError(msg)));
+                }
+            }
+        }
+        Ok(replayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{barrier::Acked, codec::Codec};
+    use std::{io, sync::Arc};
+    use tokio::time::Duration;
+
+    #[tokio::test]
+    async fn test_request_replay() {
+        let handle: HubHandle<u32, &'static str> = NotifierHub::new().into_handle();
+        let mut receiver1 = handle.subscribe(&"channel1", 10);
+        let mut receiver2 = handle.subscribe(&"channel1", 10);
+        assert!(matches!(
+            handle.request_replay(&"channel1", &receiver1, 0),
+            Err(NotifierError::JournalDisabled("channel1"))
+        ));
+
+        handle.set_journal(&"channel1", Some(2));
+        for i in 0..3 {
+            handle.clone_send(i, &"channel1").unwrap().wait(None).await;
+        }
+        assert_eq!(handle.journal_seq(&"channel1"), Some(3));
+        assert!(matches!(
+            handle.request_replay(&"channel1", &receiver1, 0),
+            Err(NotifierError::ReplayUnavailable {
+                id: "channel1",
+                oldest: 1
+            })
+        ));
+        assert_eq!(
+            handle.request_replay(&"channel1", &receiver1, 2).unwrap(),
+            1
+        );
+        for i in [0, 1, 2, 2] {
+            assert_eq!(receiver1.recv().await, Some(i));
+        }
+        for i in 0..3 {
+            assert_eq!(receiver2.recv().await, Some(i));
+        }
+        assert!(receiver2.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_beyond_buffer() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_journal(&"channel1", Some(10));
+        let mut receiver = hub.subscribe(&"channel1", 2);
+        for i in 0..2 {
+            hub.clone_send(i, &"channel1").unwrap();
+        }
+        for i in 2..5 {
+            hub.journal(&"channel1", &i);
+        }
+
+        assert_eq!(hub.request_replay(&"channel1", &receiver, 0).unwrap(), 5);
+        for i in [0, 1, 0, 1, 2, 3, 4] {
+            assert_eq!(receiver.recv().await, Some(i));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_journal_of_each_publish() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_journal(&"channel1", Some(10));
+        let mut receiver = hub.subscribe(&"channel1", 10);
+        hub.clone_send(0, &"channel1").unwrap();
+        hub.send_with(&"channel1", || 1).unwrap();
+        let mut transaction = hub.transaction();
+        transaction.send(2, &"channel1");
+        transaction.commit().unwrap();
+        // Written to the subscriber only, so not recorded
+        hub.send_direct(3, &receiver.id()).unwrap();
+        assert_eq!(hub.journal_seq(&"channel1"), Some(3));
+        assert_eq!(hub.request_replay(&"channel1", &receiver, 0).unwrap(), 3);
+        for i in [0, 1, 2, 3, 0, 1, 2] {
+            assert_eq!(receiver.recv().await, Some(i));
+        }
+
+        let mut hub: NotifierHub<Arc<u32>, &'static str> = NotifierHub::new();
+        hub.set_journal(&"channel1", Some(10));
+        let mut receiver = hub.subscribe(&"channel1", 10);
+        hub.arc_send(4, &"channel1").unwrap();
+        assert_eq!(hub.request_replay(&"channel1", &receiver, 0).unwrap(), 1);
+        let sent = receiver.recv().await.unwrap();
+        let replayed = receiver.recv().await.unwrap();
+        assert!(Arc::ptr_eq(&sent, &replayed));
+    }
+
+    #[tokio::test]
+    async fn test_journal_skips_barriers() {
+        let mut hub = NotifierHub::<Acked<u32>, &'static str>::new();
+        hub.set_journal(&"channel1", Some(10));
+        let _receiver = hub.subscribe(&"channel1", 10);
+        hub.barrier_send(1, &"channel1", Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(hub.journal_seq(&"channel1"), Some(0));
+    }
+
+    /// Encodes the even numbers only, in decimal.
+    #[derive(Clone)]
+    struct Even;
+
+    impl Codec<u32> for Even {
+        fn encode(&mut self, msg: &u32) -> io::Result<Vec<u8>> {
+            match msg % 2 {
+                0 => Ok(msg.to_string().into_bytes()),
+                _ => Err(io::Error::from(io::ErrorKind::InvalidInput)),
+            }
+        }
+
+        fn decode(&mut self, frame: &[u8]) -> io::Result<u32> {
+            std::str::from_utf8(frame)
+                .ok()
+                .and_then(|frame| frame.parse().ok())
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_journal_in_format() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.register_format("even", Even);
+        hub.set_channel_format(&"channel1", Some("even"));
+        hub.set_journal(&"channel1", Some(10));
+        let mut receiver = hub.subscribe(&"channel1", 10);
+        for i in 0..3 {
+            hub.clone_send(i, &"channel1").unwrap();
+        }
+        let frames = lock(&hub.journals[&"channel1"])
+            .entries
+            .iter()
+            .map(|(_, entry)| matches!(entry, Entry::Frame(_)))
+            .collect::<Vec<_>>();
+        assert_eq!(frames, [true, false, true]);
+
+        assert_eq!(hub.request_replay(&"channel1", &receiver, 0).unwrap(), 3);
+        for i in [0, 1, 2, 0, 1, 2] {
+            assert_eq!(receiver.recv().await, Some(i));
+        }
+    }
+}
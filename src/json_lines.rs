@@ -0,0 +1,209 @@
+use futures::{future::poll_fn, SinkExt, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    hash::Hash,
+    io,
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    select,
+    sync::oneshot,
+};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::{
+    bridge::{IngestGuard, PipeGuard, PIPE_CHANNEL_SIZE},
+    codec::JsonCodec,
+    handle::HubHandle,
+    notifier::MessageReceiver,
+};
+
+/// A line of a JSON-lines stream: a message with the channel it is published on.
+///
+/// For instance `{"channel":"logs","msg":"Hello"}`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JsonRecord<M, ChannelId> {
+    /// The channel of the message.
+    pub channel: ChannelId,
+    /// The message itself.
+    pub msg: M,
+}
+
+/// Returns the next message received by one of the receivers, starting after the last one that received,
+/// or `None` once all of them are closed.
+fn poll_next_record<M, ChannelId: Clone>(
+    receivers: &mut [(ChannelId, MessageReceiver<M>)],
+    next: &mut usize,
+    cx: &mut Context<'_>,
+) -> Poll<Option<JsonRecord<M, ChannelId>>> {
+    let n = receivers.len();
+    let mut closed = 0;
+    for i in 0..n {
+        let k = (*next + i) % n;
+        let (channel, receiver) = &mut receivers[k];
+        match receiver.poll_recv(cx) {
+            Poll::Ready(Some(msg)) => {
+                *next = (k + 1) % n;
+                return Poll::Ready(Some(JsonRecord {
+                    channel: channel.clone(),
+                    msg,
+                }));
+            }
+            Poll::Ready(None) => closed += 1,
+            Poll::Pending => {}
+        }
+    }
+    if closed == n {
+        Poll::Ready(None)
+    } else {
+        Poll::Pending
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + Serialize + DeserializeOwned + 'static,
+    ChannelId: Eq + Hash + Clone + Send + Serialize + DeserializeOwned + 'static,
+{
+    /// Subscribes to the given channels and writes each of their messages to the writer as a `JsonRecord` line,
+    /// like `pipe_to_writer` does for a single channel.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let (writer, mut reader) = tokio::io::duplex(1024);
+    /// let mut guard = handle.pipe_json_lines(&["logs".to_string()], writer);
+    ///
+    /// handle.clone_send(42, &"logs".to_string()).unwrap().wait(None).await.into_result().unwrap();
+    /// guard.stop();
+    /// guard.join().await.unwrap();
+    ///
+    /// let mut output = String::new();
+    /// reader.read_to_string(&mut output).await.unwrap();
+    /// assert_eq!(output, "{\"channel\":\"logs\",\"msg\":42}\n");
+    /// # }
+    /// ```
+    pub fn pipe_json_lines<W>(&self, ids: &[ChannelId], writer: W) -> PipeGuard<io::Error>
+    where
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let handle = self.clone();
+        let mut receivers: Vec<_> = ids
+            .iter()
+            .map(|id| (id.clone(), self.subscribe(id, PIPE_CHANNEL_SIZE)))
+            .collect();
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut sink = FramedWrite::new(writer, JsonCodec::lines());
+            let mut written = 0;
+            let mut next = 0;
+            let result = loop {
+                let record = select! {
+                    record = poll_fn(|cx| poll_next_record(&mut receivers, &mut next, cx)) => record,
+                    _ = &mut stopped => None,
+                };
+                let Some(record) = record else {
+                    break Ok(());
+                };
+                if let Err(e) = sink.feed(record).await {
+                    break Err(e);
+                }
+                written += 1;
+                if receivers.iter().all(|(_, receiver)| receiver.is_empty()) {
+                    if let Err(e) = sink.flush().await {
+                        break Err(e);
+                    }
+                }
+            };
+            for (id, receiver) in &receivers {
+                let _ = handle.unsubscribe(id, receiver);
+            }
+            result?;
+            // Once unsubscribed, the messages left in the buffers are the last ones to write
+            for (channel, receiver) in &mut receivers {
+                while let Ok(msg) = receiver.try_recv() {
+                    let channel = channel.clone();
+                    sink.feed(JsonRecord { channel, msg }).await?;
+                    written += 1;
+                }
+            }
+            sink.close().await?;
+            Ok(written)
+        });
+        PipeGuard {
+            stop: Some(stop),
+            task,
+        }
+    }
+
+    /// Reads `JsonRecord` lines from the reader and publishes each message on its channel.
+    /// As `ingest_reader`, the ingestion stops at the end of the reader or at the first invalid line,
+    /// and the messages that can't be published are dropped.
+    pub fn ingest_json_lines<R>(&self, reader: R) -> IngestGuard
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        let handle = self.clone();
+        let task = tokio::spawn(async move {
+            let mut records =
+                FramedRead::new(reader, JsonCodec::<JsonRecord<M, ChannelId>>::lines());
+            let mut forwarded = 0;
+            while let Some(Ok(record)) = records.next().await {
+                if let Ok(handler) = handle.clone_send(record.msg, &record.channel) {
+                    let _ = handler.wait(None).await;
+                    forwarded += 1;
+                }
+            }
+            forwarded
+        });
+        IngestGuard { task }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notifier::NotifierHub;
+
+    #[tokio::test]
+    async fn test_ingest_json_lines() {
+        let handle = NotifierHub::<String, String>::new().into_handle();
+        let mut logs = handle.subscribe(&"logs".to_string(), 10);
+        let mut metrics = handle.subscribe(&"metrics".to_string(), 10);
+        let input: &[u8] = br#"{"channel":"logs","msg":"started"}
+{"channel":"metrics","msg":"cpu=3"}
+{"channel":"unknown","msg":"dropped"}
+"#;
+
+        let guard = handle.ingest_json_lines(input);
+        assert_eq!(guard.join().await.unwrap(), 2);
+        assert_eq!(logs.recv().await.unwrap(), "started");
+        assert_eq!(metrics.recv().await.unwrap(), "cpu=3");
+    }
+
+    #[tokio::test]
+    async fn test_json_lines_bridge() {
+        let source = NotifierHub::<u32, String>::new().into_handle();
+        let destination = NotifierHub::<u32, String>::new().into_handle();
+        let ids = ["channel1".to_string(), "channel2".to_string()];
+        let mut receiver = destination.subscribe_multiple(&ids, 10);
+        let (writer, reader) = tokio::io::duplex(1024);
+
+        let mut pipe = source.pipe_json_lines(&ids, writer);
+        let ingest = destination.ingest_json_lines(reader);
+        source.clone_send(1, &ids[0]).unwrap();
+        source.clone_send(2, &ids[1]).unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), 1);
+        assert_eq!(receiver.recv().await.unwrap(), 2);
+
+        pipe.stop();
+        assert_eq!(pipe.join().await.unwrap(), 2);
+        assert_eq!(ingest.join().await.unwrap(), 2);
+        assert_eq!(source.channel_number_subscriber(&ids[0]), 0);
+    }
+}
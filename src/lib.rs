@@ -29,7 +29,7 @@
 //!
 //!     // Wait for up to 100 milliseconds for senders to put the message in the channel buffer
 //!     // This ensures the message is sent successfully or times out.
-//!     handler.wait(Some(Duration::from_millis(100))).await.unwrap();
+//!     assert!(handler.wait(Some(Duration::from_millis(100))).await.all_ok());
 //!
 //!     assert_eq!(&receiver1.recv().await.unwrap(), &msg);
 //!     assert_eq!(&receiver2.recv().await.unwrap(), &msg);
@@ -191,4 +191,408 @@ pub mod error;
 
 pub mod closable_trait;
 
+/// Contains the `Notifier` trait, gathering the public operations of a hub.
+///
+/// Services taking an `impl Notifier` can be unit-tested without the concrete `NotifierHub`,
+/// with the `MockNotifier` of the `mock` module for instance.
+pub mod notifier_trait;
+
+/// Provides namespaced views of a hub, so a library can use the hub of an application without topic collisions.
+///
+/// ### Key Types:
+/// - `ScopedHub<M>`: A view prefixing the channel ids, obtained with `NotifierHub::scoped`.
+pub mod scope;
+
+/// Provides `HubHandle`, a cheap cloneable handle exposing the API of a hub with `&self`.
+///
+/// ### Key Types:
+/// - `HubHandle<M, ChannelId>`: Clone + Send + Sync handle, obtained with `NotifierHub::into_handle`.
+pub mod handle;
+
+/// Provides the bridges between the channels of a hub and the outside world.
+///
+/// ### Key Types:
+/// - `IngestGuard`: Controls a task forwarding a `Stream` into a channel, obtained with `HubHandle::ingest`.
+/// - `PipeGuard<E>`: Controls a task writing a channel into an `AsyncWrite`, obtained with `HubHandle::pipe_to_writer`.
+///
+/// Frames read from an `AsyncRead` are published with `HubHandle::ingest_reader`, and the values of tokio
+/// broadcast and watch channels with `HubHandle::mirror_broadcast` and `HubHandle::mirror_watch`.
+/// `HubHandle::to_std_receiver` feeds a `std::sync::mpsc::Receiver` for the synchronous consumers,
+/// and `HubHandle::bind_signals` publishes the OS signals on unix.
+pub mod bridge;
+
+/// Provides the federation of hubs: a child hub whose channels are mirrored to and from a parent hub.
+///
+/// ### Key Types:
+/// - `Propagation<ChannelId>`: Defines which channels go upward and downward.
+/// - `Federation`: The link between the hubs, obtained with `HubHandle::attach_child`.
+pub mod federation;
+
+/// Provides the codecs used by the bridges.
+///
+/// ### Key Types:
+/// - `Codec<M>`: Turns a message into a frame and back.
+/// - `FrameCodec<C, M>`: Delimits the frames of a `Codec` in a byte stream.
+/// - `JsonCodec<M>`: Line-delimited or length-delimited JSON frames, with the `json` feature.
+/// - `ProstCodec<M>`: Length-delimited protobuf frames, with the `prost` feature.
+pub mod codec;
+
+/// Provides the registry of the serialization formats, the bridges and the journal of each channel using
+/// the format selected for it. JSON is built in with the `json` feature, see `NotifierHub::register_json_format`.
+///
+/// ### Key Types:
+/// - `DynCodec<M>`: A codec picked from the registry, see `NotifierHub::register_format`.
+pub mod format;
+
+/// Provides the compression of the large frames of a codec, for the bridges carrying large payloads
+/// and, registered as the format of a channel, for its journal. No compression algorithm is built in.
+///
+/// ### Key Types:
+/// - `Compression`: Compresses a frame and back.
+/// - `Compressed<C, Z>`: A codec compressing the frames of another one above a threshold.
+pub mod compression;
+
+/// Provides the encryption of the frames of a codec, for the bridges crossing an untrusted network.
+///
+/// ### Key Types:
+/// - `FrameCipher`: Seals a frame and opens it back.
+/// - `Encrypted<C, K>`: A codec sealing the frames of another one.
+pub mod cipher;
+
+/// Provides the pipes writing a channel to a link they open again when it fails, for the bridges over flaky networks.
+///
+/// ### Key Types:
+/// - `ReconnectPolicy`: The backoff of the connection attempts and the buffering while the link is down.
+/// - `ReconnectGuard`: Controls a pipe, giving its `ConnectionEvent`s and its number of dropped messages.
+pub mod reconnect;
+
+/// Provides a bridge between the channels of a hub and newline-delimited JSON streams, in both directions.
+/// Available with the `json` feature.
+///
+/// ### Key Types:
+/// - `JsonRecord<M, ChannelId>`: A line of the stream, a message with its channel.
+#[cfg(feature = "json")]
+pub mod json_lines;
+
+/// Provides `ReadOnlyHub`, a facade of a hub for lower-privilege components, that can subscribe but not publish.
+pub mod read_only;
+
+/// Provides `MockNotifier`, a `Notifier` recording every call made on it. Available with the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
+
+/// Provides weak senders, that can publish in a channel without keeping it alive.
+///
+/// ### Key Types:
+/// - `WeakMessageSender<M>`: A sender that does not prevent the receiver from seeing the end of the channel.
+/// - `Downgrade`: Extension trait turning a `MessageSender` into a `WeakMessageSender`.
+pub mod weak_sender;
+
+/// Provides the `channels!` macro, generating a channel id enum checked at compile time.
+#[macro_use]
+pub mod channels;
+
+/// Provides the ephemeral subscriptions, whose channel is removed from the hub once its last subscriber is gone.
+///
+/// ### Key Types:
+/// - `EphemeralReceiver<M, ChannelId>`: A receiver signaling the hub when it is dropped.
+pub mod ephemeral;
+
+/// Provides the helpers for the channels named at runtime, sharing the names already known by the hub.
+///
+/// ### Key Types:
+/// - `StrHub<M>`: A hub whose channel ids are `Arc<str>`.
+pub mod str_hub;
+
+/// Provides the deduplication of the publishes, skipping the messages already published on a channel.
+///
+/// ### Key Types:
+/// - `MessageId`: Implemented by the messages carrying an id.
+/// - `Tagged`: A message with an id generated by the hub.
+pub mod dedup;
+
+/// Provides `NotifierHub::publish`, telling a delivered message from a channel without subscriber.
+///
+/// ### Key Types:
+/// - `PublishOutcome<M>`: Whether the message has been delivered, handing it back otherwise.
+pub mod publish;
+
+/// Provides the park buffers, keeping the messages published on a channel until its first subscriber.
+pub mod park;
+
+/// Provides the state waiters, receiving each transition of the state of a channel,
+/// and the state change waiters receiving the transitions of every channel.
+///
+/// ### Key Types:
+/// - `StateWaiter`: A receiver of the `ChannelState` transitions, obtained with `NotifierHub::get_state_waiter`.
+/// - `StateChanged<ChannelId>`: A transition of any channel, received by the waiters of `NotifierHub::get_state_change_waiter`.
+pub mod state_waiter;
+
+/// Provides the drain mode of a hub, refusing new subscriptions until the current subscribers are gone.
+pub mod drain;
+
+/// Provides `DynHub`, a hub whose channels carry different message types, checked at runtime.
+pub mod dyn_hub;
+
+/// Provides `BroadcastContext`, a channel prepared for many publishes with `NotifierHub::context_send`,
+/// keeping a snapshot of its subscribers.
+pub mod context;
+
+/// Provides the adapters of the receivers, keeping the `MessageReceiver` needed by the hub to unsubscribe.
+///
+/// ### Key Types:
+/// - `Subscription`: A receiver, possibly adapted.
+/// - `SubscriptionExt`: Extension trait providing `filter`, `map`, `take_until`, `timeout_per_message` and `chunked`.
+pub mod combinators;
+
+/// Provides the owned receiving of the messages published with `arc_send`.
+///
+/// ### Key Types:
+/// - `IntoOwned`: Extension trait unwrapping an `Arc<M>` when it is the last one, cloning it otherwise.
+/// - `OwnedReceiver`: Extension trait receiving owned messages from a `MessageReceiver<Arc<M>>`.
+pub mod owned;
+
+/// Provides transactions, allowing to publish on several channels at once with an all-or-nothing delivery.
+///
+/// ### Key Types:
+/// - `Transaction<M, ChannelId>`: Stages sends and commits them atomically, obtained with `NotifierHub::transaction`.
+pub mod transaction;
+
+/// Provides the channel groups: named sets of channels that can be subscribed at once,
+/// with `NotifierHub::define_group` and `NotifierHub::subscribe_group`.
+pub mod group;
+
+/// Provides the publish permissions, restricting who can publish on sensitive channels.
+///
+/// ### Key Types:
+/// - `PublishToken<ChannelId>`: A revocable capability, obtained with `NotifierHub::grant_publish`.
+pub mod permission;
+
+/// Provides the publisher identities, to trace which component published a message.
+///
+/// ### Key Types:
+/// - `Publisher<M, ChannelId>`: A handle publishing with an identity, obtained with `HubHandle::publisher`.
+/// - `Envelope<M>`: A message carrying the identity of its publisher.
+/// - `AuditRecord<ChannelId>`: A publish recorded in the audit log of the hub.
+pub mod publisher;
+
+/// Provides the rate limiting of the publishes, per channel and for the whole hub.
+///
+/// ### Key Types:
+/// - `RateLimitAction`: Defines whether an exceeding publish is rejected or delayed.
+pub mod rate_limit;
+
+/// Provides the circuit breaker, skipping the subscribers that keep failing for a cooldown.
+///
+/// ### Key Types:
+/// - `BreakerPolicy`: Defines after how many failures and for how long a subscriber is skipped.
+/// - `BreakerEvent`: Emitted to the breaker waiters when a circuit opens or closes.
+pub mod circuit_breaker;
+
+/// Provides the alerts raised by the hub on its own health, for the lagging subscribers and the slow writings.
+///
+/// ### Key Types:
+/// - `AlertPolicy`: Defines when an alert is raised.
+/// - `Alert<ChannelId>`: The event received by the alert waiters.
+pub mod alert;
+
+/// Provides the quarantine, where go the messages that repeatedly fail to be written.
+///
+/// ### Key Types:
+/// - `QuarantinePolicy`: Defines how many attempts are made before quarantining a message.
+/// - `QuarantinedMessage<M>`: A message in quarantine, with its subscriber.
+pub mod quarantine;
+
+/// Provides the sequencing of the publishes on one or several channels, so all their subscribers observe the same order.
+pub mod sequencer;
+
+/// Provides the control lane of the subscribers, delivering the control messages ahead of the queued data messages.
+///
+/// ### Key Types:
+/// - `ControlledReceiver<M>`: A receiver reading its control lane first.
+pub mod control;
+
+/// Provides the broadcasts resolving once every subscriber acknowledged the message.
+///
+/// ### Key Types:
+/// - `Acked<M>`: A message along with the `Ack` its subscriber uses to signal it has been processed.
+/// - `BarrierHandler<M, ChannelId>`: Waits for all the acknowledgements of a `barrier_send`.
+pub mod barrier;
+
+/// Provides the prepare/commit broadcasts, for the state changes that all the subscribers must accept.
+///
+/// ### Key Types:
+/// - `Phase<M>`: The messages received by the subscribers, a prepare with its `Vote`, then the decision.
+/// - `TwoPhaseHandler<M>`: Collects the votes and sends the decision.
+/// - `TwoPhaseOutcome`: Whether the change has been committed or rolled back.
+pub mod two_phase;
+
+/// Provides the requests broadcast to the subscribers of a channel, gathering their responses until a timeout,
+/// and the pings of the subscribers built on them.
+///
+/// ### Key Types:
+/// - `Request<M, R>`: A request along with the `Reply` its subscriber answers with.
+/// - `GatherHandler<W, R>`: Gathers the responses to a `scatter` or a `ping`.
+/// - `PingMessage`: The trait building the ping message of a channel.
+/// - `PingReport`: The subscribers that answered a `ping_channel`, and the ones that did not.
+pub mod scatter_gather;
+
+/// Provides the subscriptions processing their messages with a callback, one at a time or concurrently,
+/// see `HubHandle::subscribe_handler`, and their shutdown awaiting their `on_close` callback.
+///
+/// ### Key Types:
+/// - `HandlerSubscription<M, F>`: A callback subscription, configured then started with `spawn`.
+/// - `HandlerGuard`: Controls a started subscription, stopping it when dropped.
+/// - `HandlerShutdown`: The subscriptions that completed, or not, when their channel is shut down by `shutdown_handlers`.
+pub mod handler;
+
+/// Provides the routing of the publishes as pure functions the hub carries out: the state of a channel
+/// from its subscribers, and whether a publish is refused, written, parked or discarded.
+/// Only this routing is runtime-agnostic: the transitions of the channels and the writings to the subscribers,
+/// with their credits, circuits and full buffers, are still made by the hub over the tokio channels.
+///
+/// ### Key Types:
+/// - `Route`: What a publish does with its message, see `route`.
+/// - `Refusal`: Why a publish is refused, see `admit`.
+pub mod core;
+
+/// Provides the hand-off of the subscribers of a channel from a hub to another.
+pub mod handoff;
+
+/// Provides the callback invoked on each failed writing.
+///
+/// ### Key Types:
+/// - `ErrorEvent<ChannelId>`: The channel, the subscriber and the `FailureKind` of a failed writing.
+pub mod error_hook;
+
+/// Provides the callback invoked with each message discarded by the hub.
+///
+/// ### Key Types:
+/// - `DropReason`: The policy that discarded the message.
+pub mod drop_hook;
+
+/// Provides the pressure of the channels, the occupancy of the buffers of their subscribers,
+/// and its watch letting the producers adapt their rate, see `HubHandle::backpressure_watch`.
+pub mod backpressure;
+
+/// Provides the credit-based flow control, the subscribers being written to only up to the credits they grant.
+///
+/// ### Key Types:
+/// - `CreditReceiver<M>`: A receiver granting the credits of its subscriber, see `NotifierHub::subscribe_with_credits`.
+pub mod credits;
+
+/// Provides the hooks called before and after the fanout of each publish, with its channel and number of subscribers.
+pub mod broadcast_hook;
+
+/// Provides the clock of a hub, so the time based features can be tested with a `MockClock`.
+///
+/// ### Key Types:
+/// - `Clock`: The source of the current time.
+/// - `TokioClock`: The default clock, reading the tokio time.
+/// - `MockClock`: A clock moved forward by hand.
+pub mod clock;
+
+/// Provides the export and import of the configuration of a hub.
+///
+/// ### Key Types:
+/// - `HubConfig<ChannelId>`: The settings, policies, groups and aliases of a hub, serializable with the `serde` feature.
+pub mod config;
+
+/// Provides the buffer sizes and overflow policies of the creation and destruction waiters,
+/// the creation waiters coalescing their notifications, the waiters of several or all the channels,
+/// the departure waiters telling why each subscriber has been removed, and the removal of the waiters.
+///
+/// ### Key Types:
+/// - `WaiterConfig`: The buffer size and overflow policy of a waiter, see `NotifierHub::get_creation_waiter_with`.
+/// - `WaiterOverflow`: What happens to a notification finding the buffer of its waiter full.
+/// - `CoalescedWaiter`: A creation waiter receiving the number of new subscribers since its last receive.
+/// - `MultiCreationWaiter<ChannelId>`: A creation waiter of several channels, receiving the channel of each subscriber.
+/// - `MultiDestructionWaiter<M, ChannelId>`: Same as `MultiCreationWaiter` for the destructions.
+/// - `DepartureWaiter<M>`: A destruction waiter receiving each `Departure` with its `DestructionReason`.
+/// - `WaiterGuard<T, M, ChannelId>`: A waiter unregistered from its hub when dropped, see `HubHandle::guard_waiter`.
+pub mod waiter;
+
+/// Provides the garbage collection of the hub.
+///
+/// Channels that reached the Over state and waiters whose receiver has been dropped stay in the hub
+/// until they are collected, either manually, after each mutation, or periodically.
+///
+/// ### Key Types:
+/// - `GcPolicy`: Defines when the garbage is collected.
+/// - `GcReport<ChannelId>`: Describes what has been collected.
+pub mod gc;
+
+/// Provides the descriptions and tags of the channels, so large deployments can document their topics.
+///
+/// ### Key Types:
+/// - `ChannelMetadata`: The description and tags of a channel, set with `NotifierHub::describe_channel`.
+/// - `ChannelInfo<'a, ChannelId>`: A channel as seen by the predicate of `NotifierHub::channels_matching`.
+///
+/// The channels having a tag are published to at once with `NotifierHub::broadcast_to_tagged`.
+pub mod metadata;
+
+/// Provides the snapshots of the channels of a hub, and their comparison to detect leaks and churn.
+///
+/// ### Key Types:
+/// - `Topology<ChannelId>`: The channels of a hub at some point in time, obtained with `NotifierHub::topology`.
+/// - `TopologyDiff<ChannelId>`: The channels added and removed between two topologies, with their changes.
+pub mod topology;
+
+/// Provides the producers started and stopped with the subscribers of their channel,
+/// see `HubHandle::register_producer`.
+///
+/// ### Key Types:
+/// - `ProducerGuard`: Controls a registered producer, stopping it when dropped.
+pub mod producer;
+
+/// Provides the initial data of the channels, fetched for each new subscriber and delivered to it
+/// before the live messages, see `NotifierHub::set_initial_data`.
+pub mod initial_data;
+
+/// Provides the journals of the channels, recording their last messages with a sequence number so a subscriber
+/// can get the ones it missed written again to itself only, see `NotifierHub::request_replay`.
+pub mod journal;
+
+/// Provides the messages written to one subscriber only, without publishing them to the other subscribers
+/// of its channels, see `NotifierHub::send_to_subscriber` and `NotifierHub::send_direct`.
+///
+/// ### Key Types:
+/// - `Inbox<M>`: The private inbox of a subscriber, obtained with `NotifierHub::subscribe_with_inbox`.
+pub mod direct;
+
+/// Provides the detection of the dropped receivers, removing their subscriber without calling `clean_channel`,
+/// see `HubHandle::set_drop_detection`.
+pub mod drop_detection;
+
+/// Provides an estimation of the memory used by a hub, see `NotifierHub::memory_report`.
+pub mod memory;
+
+/// Provides utilities to test code built on the hub: a recording `TestSubscriber` with assertion helpers,
+/// and `flush` to let the hub activity settle deterministically under `tokio::time::pause`.
+/// Available with the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+/// Provides `NotifierHub::invariants`, checking the internal consistency of a hub for fuzz and property tests.
+/// Available with the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+pub mod invariants;
+
+/// Provides a load generator measuring the throughput, latency and drops of a hub under a configurable load,
+/// for capacity planning. Available with the `stress` feature.
+///
+/// ### Key Types:
+/// - `StressConfig`: The numbers of channels, publishers and subscribers, with their rates and capacities.
+/// - `StressReport`: The statistics of a run, obtained with `stress::run`.
+#[cfg(any(test, feature = "stress"))]
+pub mod stress;
+
+/// Gathers the state the hub keeps for a channel, to move or remove it at once.
+pub(crate) mod channel_entry;
+
+/// Locks the mutexes of the hub, whether they are poisoned or not.
+pub(crate) mod sync;
+
+// The messages of these tests predate the clippy gate and are kept as written
+#[allow(clippy::enum_variant_names)]
 mod test;
@@ -0,0 +1,130 @@
+use smallvec::{Array, SmallVec};
+use std::{collections::HashMap, hash::Hash, mem::size_of};
+
+use crate::notifier::NotifierHub;
+
+/// Estimation of the memory used by a single channel.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ChannelMemory {
+    /// Number of subscribers of the channel.
+    pub subscribers: usize,
+    /// Number of messages waiting in the buffers of the subscribers.
+    pub buffered_messages: usize,
+    /// Total capacity of the buffers of the subscribers.
+    pub buffer_capacity: usize,
+    /// Shallow size of the buffered messages, the data owned by the messages on the heap is not counted.
+    pub buffered_bytes: usize,
+    /// Number of creation waiters of the channel.
+    pub creation_waiters: usize,
+    /// Number of destruction waiters of the channel.
+    pub destruction_waiters: usize,
+}
+
+/// Estimation of the memory used by a hub, returned by `NotifierHub::memory_report`.
+/// Note that a subscriber of several channels is counted in each of them, as its buffer is shared.
+#[derive(Clone, Debug)]
+pub struct MemoryReport<ChannelId> {
+    /// Binding each known channel with its own estimation.
+    pub channels: HashMap<ChannelId, ChannelMemory>,
+    /// Bytes allocated by the internal maps and sender lists, whether they are used or not.
+    pub map_overhead_bytes: usize,
+}
+
+impl<ChannelId> MemoryReport<ChannelId> {
+    /// Returns the number of messages buffered across all the channels.
+    pub fn buffered_messages(&self) -> usize {
+        self.channels.values().map(|c| c.buffered_messages).sum()
+    }
+
+    /// Returns the shallow size of the messages buffered across all the channels.
+    pub fn buffered_bytes(&self) -> usize {
+        self.channels.values().map(|c| c.buffered_bytes).sum()
+    }
+}
+
+/// A list whose heap allocation can be estimated.
+trait HeapList {
+    /// Returns the bytes allocated on the heap by the list.
+    fn heap_bytes(&self) -> usize;
+}
+
+impl<T> HeapList for Vec<T> {
+    fn heap_bytes(&self) -> usize {
+        self.capacity() * size_of::<T>()
+    }
+}
+
+impl<A: Array> HeapList for SmallVec<A> {
+    fn heap_bytes(&self) -> usize {
+        match self.spilled() {
+            true => self.capacity() * size_of::<A::Item>(),
+            false => 0,
+        }
+    }
+}
+
+/// Estimates the bytes allocated by a map of lists.
+fn map_overhead<K, L: HeapList>(map: &HashMap<K, L>) -> usize {
+    map.capacity() * size_of::<(K, L)>() + map.values().map(L::heap_bytes).sum::<usize>()
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Estimates the memory used by the hub: buffered messages per channel and overhead of the internal maps.
+    /// This is meant for capacity planning and leak hunting, the values are approximations.
+    pub fn memory_report(&self) -> MemoryReport<ChannelId> {
+        let mut channels: HashMap<ChannelId, ChannelMemory> = HashMap::new();
+        for (id, senders) in &self.senders {
+            let memory = channels.entry(id.clone()).or_default();
+            memory.subscribers = senders.len();
+            for sender in senders {
+                let buffered = sender.max_capacity() - sender.capacity();
+                memory.buffered_messages += buffered;
+                memory.buffer_capacity += sender.max_capacity();
+            }
+            memory.buffered_bytes = memory.buffered_messages * size_of::<M>();
+        }
+        for (id, waiters) in &self.creation_senders {
+            channels.entry(id.clone()).or_default().creation_waiters = waiters.len();
+        }
+        for (id, waiters) in &self.destruction_senders {
+            channels.entry(id.clone()).or_default().destruction_waiters = waiters.len();
+        }
+        MemoryReport {
+            channels,
+            map_overhead_bytes: map_overhead(&self.senders)
+                + map_overhead(&self.creation_senders)
+                + map_overhead(&self.destruction_senders),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_report() {
+        let mut hub: NotifierHub<u64, &'static str> = NotifierHub::new();
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        let _receiver2 = hub.subscribe(&"channel1", 5);
+        let _waiter = hub.get_creation_waiter(&"channel2");
+
+        hub.clone_send(1, &"channel1").unwrap();
+        hub.clone_send(2, &"channel1").unwrap();
+        receiver1.recv().await.unwrap();
+
+        let report = hub.memory_report();
+        let channel1 = report.channels[&"channel1"];
+        assert_eq!(channel1.subscribers, 2);
+        assert_eq!(channel1.buffered_messages, 3);
+        assert_eq!(channel1.buffer_capacity, 15);
+        assert_eq!(channel1.buffered_bytes, 3 * size_of::<u64>());
+
+        let channel2 = report.channels[&"channel2"];
+        assert_eq!(channel2.subscribers, 0);
+        assert_eq!(channel2.creation_waiters, 1);
+
+        assert_eq!(report.buffered_messages(), 3);
+        assert!(report.map_overhead_bytes > 0);
+    }
+}
@@ -0,0 +1,235 @@
+use std::hash::Hash;
+
+use crate::{
+    handle::HubHandle,
+    notifier::{ChannelState, NotifierHub},
+    writing_handler::WritingHandler,
+};
+
+/// The description of a channel, set with `NotifierHub::describe_channel`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelMetadata {
+    /// What the channel carries.
+    pub description: String,
+    /// Free-form tags, in the order they were given, without duplicates.
+    pub tags: Vec<String>,
+}
+
+impl ChannelMetadata {
+    /// Returns true if the channel has the given tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+/// A channel as seen by the predicate of `NotifierHub::channels_matching`.
+#[derive(Clone, Copy, Debug)]
+pub struct ChannelInfo<'a, ChannelId> {
+    /// The id of the channel.
+    pub id: &'a ChannelId,
+    /// The state of the channel.
+    pub state: ChannelState,
+    /// Number of subscribers of the channel.
+    pub subscribers: usize,
+    /// The description and tags of the channel, if any.
+    pub metadata: Option<&'a ChannelMetadata>,
+}
+
+impl<ChannelId> ChannelInfo<'_, ChannelId> {
+    /// Returns true if the channel has the given tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.metadata.is_some_and(|metadata| metadata.has_tag(tag))
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Returns the channels accepted by the predicate, among the channels having subscribers or metadata.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// hub.describe_channel(&"logins", "user logins", ["audit"]);
+    /// let _receiver = hub.subscribe(&"logins", 10);
+    /// let _other = hub.subscribe(&"metrics", 10);
+    ///
+    /// let audited = hub.channels_matching(|info| info.has_tag("audit") && info.subscribers > 0);
+    /// assert_eq!(audited, vec!["logins"]);
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// ```
+    pub fn channels_matching(
+        &self,
+        predicate: impl Fn(&ChannelInfo<'_, ChannelId>) -> bool,
+    ) -> Vec<ChannelId> {
+        let described = self
+            .metadata
+            .keys()
+            .filter(|id| !self.senders.contains_key(id));
+        self.senders
+            .keys()
+            .chain(described)
+            .filter(|id| {
+                predicate(&ChannelInfo {
+                    id,
+                    state: self.channel_state(id),
+                    subscribers: self.channel_number_subscriber(id),
+                    metadata: self.metadata.get(id),
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Attaches a description and some tags to the channel, replacing the previous ones.
+    /// The channel doesn't have to exist yet, the metadata is kept until the channel is forgotten
+    /// or `remove_channel_metadata` is called, and follows the channel when it is renamed.
+    /// The metadata is part of the `topology` of the hub.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// hub.describe_channel(&"sessions", "user session events", ["auth", "audit"]);
+    ///
+    /// let metadata = hub.channel_metadata(&"sessions").unwrap();
+    /// assert_eq!(metadata.description, "user session events");
+    /// assert!(metadata.has_tag("audit"));
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// ```
+    pub fn describe_channel<T: Into<String>>(
+        &mut self,
+        id: &ChannelId,
+        description: &str,
+        tags: impl IntoIterator<Item = T>,
+    ) {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        let mut metadata = ChannelMetadata {
+            description: description.to_string(),
+            tags: Vec::new(),
+        };
+        for tag in tags.into_iter().map(Into::into) {
+            if !metadata.has_tag(&tag) {
+                metadata.tags.push(tag);
+            }
+        }
+        self.metadata.insert(id, metadata);
+    }
+
+    /// Returns the description and the tags of the channel, if any.
+    pub fn channel_metadata(&self, id: &ChannelId) -> Option<&ChannelMetadata> {
+        self.metadata.get(self.aliases.get(id).unwrap_or(id))
+    }
+
+    /// Removes the description and the tags of the channel, returning them.
+    pub fn remove_channel_metadata(&mut self, id: &ChannelId) -> Option<ChannelMetadata> {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        self.metadata.remove(&id)
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `broadcast_clone`, only on the channels having the tag.
+    pub fn broadcast_to_tagged(&self, tag: &str, msg: M) -> WritingHandler<M, ChannelId> {
+        let tagged = |id: &ChannelId| self.metadata.get(id).is_some_and(|m| m.has_tag(tag));
+        self.fanout_over(tagged, |len, senders| {
+            self.hooked(None, len, || {
+                self.publish_handler(None).cloning_broadcast(msg, senders)
+            })
+        })
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::channels_matching`.
+    pub fn channels_matching(
+        &self,
+        predicate: impl Fn(&ChannelInfo<'_, ChannelId>) -> bool,
+    ) -> Vec<ChannelId> {
+        self.with(|hub| hub.channels_matching(predicate))
+    }
+
+    /// See `NotifierHub::describe_channel`.
+    pub fn describe_channel<T: Into<String>>(
+        &self,
+        id: &ChannelId,
+        description: &str,
+        tags: impl IntoIterator<Item = T>,
+    ) {
+        self.with(|hub| hub.describe_channel(id, description, tags))
+    }
+
+    /// See `NotifierHub::channel_metadata`.
+    pub fn channel_metadata(&self, id: &ChannelId) -> Option<ChannelMetadata> {
+        self.with(|hub| hub.channel_metadata(id).cloned())
+    }
+
+    /// See `NotifierHub::remove_channel_metadata`.
+    pub fn remove_channel_metadata(&self, id: &ChannelId) -> Option<ChannelMetadata> {
+        self.with(|hub| hub.remove_channel_metadata(id))
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::broadcast_to_tagged`.
+    pub fn broadcast_to_tagged(&self, tag: &str, msg: M) -> WritingHandler<M, ChannelId> {
+        self.with(|hub| hub.broadcast_to_tagged(tag, msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_metadata_follows_the_channel() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let _receiver = hub.subscribe(&"channel1", 10);
+        hub.describe_channel(&"channel1", "first", ["a", "b", "a"]);
+        assert_eq!(
+            hub.channel_metadata(&"channel1").unwrap().tags,
+            vec!["a", "b"]
+        );
+
+        hub.rename_channel(&"channel1", "channel2", true).unwrap();
+        assert_eq!(hub.topology().metadata[&"channel2"].description, "first");
+        hub.describe_channel(&"channel1", "second", Vec::<String>::new());
+        assert_eq!(
+            hub.channel_metadata(&"channel2").unwrap().description,
+            "second"
+        );
+
+        assert!(hub.remove_channel_metadata(&"channel2").is_some());
+        assert!(hub.channel_metadata(&"channel2").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_to_tagged() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.describe_channel(&"channel1", "", ["audit"]);
+        hub.describe_channel(&"channel2", "", ["audit", "ops"]);
+        hub.describe_channel(&"channel4", "", ["audit"]);
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        let mut receiver2 = hub.subscribe(&"channel2", 10);
+        let mut receiver3 = hub.subscribe(&"channel3", 10);
+
+        let mut tagged = hub.channels_matching(|info| info.has_tag("audit"));
+        tagged.sort();
+        assert_eq!(tagged, vec!["channel1", "channel2", "channel4"]);
+
+        assert_eq!(hub.broadcast_to_tagged("audit", 1).len(), 2);
+        assert_eq!(receiver1.try_recv().unwrap(), 1);
+        assert_eq!(receiver2.try_recv().unwrap(), 1);
+        assert!(receiver3.try_recv().is_err());
+    }
+}
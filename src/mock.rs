@@ -0,0 +1,241 @@
+use std::{hash::Hash, sync::Mutex};
+
+use crate::{
+    closable_trait::ClosableMessage,
+    error::NotifierError,
+    notifier::{
+        ChannelState, CreationWaiter, DestructionWaiter, MessageReceiver, NotifierHub,
+        SmartChannelId,
+    },
+    notifier_trait::Notifier,
+    writing_handler::WritingHandler,
+};
+
+/// A call made on a `MockNotifier`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotifierCall<M, ChannelId> {
+    Subscribe {
+        id: ChannelId,
+        channel_size: usize,
+    },
+    SubscribeMultiple {
+        ids: Vec<ChannelId>,
+        channel_size: usize,
+    },
+    Unsubscribe {
+        id: ChannelId,
+        receiver: SmartChannelId,
+    },
+    Send {
+        id: ChannelId,
+        msg: M,
+    },
+    Broadcast {
+        msg: M,
+    },
+    Shutdown {
+        id: ChannelId,
+    },
+    CreationWaiter {
+        id: ChannelId,
+    },
+    DestructionWaiter {
+        id: ChannelId,
+    },
+}
+
+/// A `Notifier` recording every call made on it before forwarding it to an inner `NotifierHub`,
+/// so the receivers it returns behave as usual.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::{mock::{MockNotifier, NotifierCall}, notifier_trait::Notifier};
+///
+/// fn publish(notifier: &impl Notifier<String, &'static str>) {
+///     let _ = notifier.clone_send("Hello".to_string(), &"channel1");
+/// }
+///
+/// let mut mock = MockNotifier::new();
+/// let _receiver = mock.subscribe(&"channel1", 10);
+/// publish(&mock);
+/// assert_eq!(mock.sent_to(&"channel1"), vec!["Hello".to_string()]);
+/// ```
+pub struct MockNotifier<M, ChannelId: Eq + Hash> {
+    hub: NotifierHub<M, ChannelId>,
+    /// Behind a mutex as the sending methods only take `&self`
+    calls: Mutex<Vec<NotifierCall<M, ChannelId>>>,
+}
+
+impl<M, ChannelId: Eq + Hash> Default for MockNotifier<M, ChannelId> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> MockNotifier<M, ChannelId> {
+    /// Returns a mock with an empty inner hub.
+    pub fn new() -> Self {
+        Self {
+            hub: NotifierHub::new(),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the inner hub.
+    pub fn hub(&self) -> &NotifierHub<M, ChannelId> {
+        &self.hub
+    }
+
+    fn record(&self, call: NotifierCall<M, ChannelId>) {
+        self.calls.lock().unwrap().push(call);
+    }
+
+    /// Returns and clears the recorded calls.
+    pub fn take_calls(&self) -> Vec<NotifierCall<M, ChannelId>> {
+        std::mem::take(&mut self.calls.lock().unwrap())
+    }
+}
+
+impl<M: Clone, ChannelId: Eq + Hash + Clone> MockNotifier<M, ChannelId> {
+    /// Returns the recorded calls.
+    pub fn calls(&self) -> Vec<NotifierCall<M, ChannelId>> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Returns the messages sent to the given channel, broadcasts excluded.
+    pub fn sent_to(&self, channel: &ChannelId) -> Vec<M> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|call| match call {
+                NotifierCall::Send { id, msg } if id == channel => Some(msg.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl<M, ChannelId> Notifier<M, ChannelId> for MockNotifier<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    fn subscribe(&mut self, id: &ChannelId, channel_size: usize) -> MessageReceiver<M> {
+        self.record(NotifierCall::Subscribe {
+            id: id.clone(),
+            channel_size,
+        });
+        self.hub.subscribe(id, channel_size)
+    }
+
+    fn subscribe_multiple(&mut self, ids: &[ChannelId], channel_size: usize) -> MessageReceiver<M> {
+        self.record(NotifierCall::SubscribeMultiple {
+            ids: ids.to_vec(),
+            channel_size,
+        });
+        self.hub.subscribe_multiple(ids, channel_size)
+    }
+
+    fn unsubscribe(
+        &mut self,
+        id: &ChannelId,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<ChannelState, NotifierError<M, ChannelId>> {
+        self.record(NotifierCall::Unsubscribe {
+            id: id.clone(),
+            receiver: receiver.id(),
+        });
+        self.hub.unsubscribe(id, receiver)
+    }
+
+    fn clone_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.record(NotifierCall::Send {
+            id: id.clone(),
+            msg: msg.clone(),
+        });
+        self.hub.clone_send(msg, id)
+    }
+
+    fn broadcast_clone(&self, msg: M) -> WritingHandler<M, ChannelId> {
+        self.record(NotifierCall::Broadcast { msg: msg.clone() });
+        self.hub.broadcast_clone(msg)
+    }
+
+    fn shutdown_clone(
+        &mut self,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>>
+    where
+        M: ClosableMessage,
+    {
+        self.record(NotifierCall::Shutdown { id: id.clone() });
+        self.hub.shutdown_clone(id)
+    }
+
+    fn channel_state(&self, id: &ChannelId) -> ChannelState {
+        self.hub.channel_state(id)
+    }
+
+    fn get_creation_waiter(&mut self, id: &ChannelId) -> CreationWaiter {
+        self.record(NotifierCall::CreationWaiter { id: id.clone() });
+        self.hub.get_creation_waiter(id)
+    }
+
+    fn get_destruction_waiter(&mut self, id: &ChannelId) -> DestructionWaiter<M> {
+        self.record(NotifierCall::DestructionWaiter { id: id.clone() });
+        self.hub.get_destruction_waiter(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A service depending on the trait rather than the concrete hub
+    fn service(notifier: &mut impl Notifier<u32, &'static str>) -> MessageReceiver<u32> {
+        let receiver = notifier.subscribe(&"input", 10);
+        notifier.clone_send(1, &"input").unwrap();
+        notifier.broadcast_clone(2);
+        receiver
+    }
+
+    #[test]
+    fn test_mock_records_calls() {
+        let mut mock = MockNotifier::new();
+        let mut receiver = service(&mut mock);
+
+        assert_eq!(
+            mock.take_calls(),
+            vec![
+                NotifierCall::Subscribe {
+                    id: "input",
+                    channel_size: 10
+                },
+                NotifierCall::Send {
+                    id: "input",
+                    msg: 1
+                },
+                NotifierCall::Broadcast { msg: 2 },
+            ]
+        );
+        assert!(mock.calls().is_empty());
+        assert_eq!(receiver.try_recv().unwrap(), 1);
+        assert_eq!(receiver.try_recv().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_hub_implements_notifier() {
+        let mut hub = NotifierHub::new();
+        let mut receiver = service(&mut hub);
+        assert_eq!(
+            Notifier::channel_state(&hub, &"input"),
+            ChannelState::Running
+        );
+        assert_eq!(receiver.try_recv().unwrap(), 1);
+    }
+}
@@ -1,16 +1,60 @@
 use crate::{
+    alert::{AlertWaiters, Alerting},
+    broadcast_hook::BroadcastHooks,
+    circuit_breaker::CircuitBreaker,
+    clock::{SharedClock, TokioClock},
     closable_trait::ClosableMessage,
+    core::{self, Refusal, Route},
+    credits::Credits,
+    dedup::{first_message_id, DedupWindow},
+    drop_detection::DropDetection,
+    drop_hook::{DropHook, DropReason},
     error::{NotifierError, UnexpectedErrorKind},
+    error_hook::ErrorHook,
+    format::FormatRegistry,
+    gc::{GcPolicy, GcReport},
+    group::ChannelGroup,
+    handler::HandlerCompletion,
+    initial_data::InitialData,
+    journal::Journal,
+    metadata::ChannelMetadata,
+    park::ParkBuffer,
+    publisher::{AuditLog, PublisherId},
+    quarantine::{Quarantine, QuarantinePolicy},
+    rate_limit::{RateLimitAction, TokenBucket},
+    sequencer::Sequencer,
+    state_waiter::{StateChanged, StateSender},
     unexpected,
-    writing_handler::WritingHandler,
+    waiter::{
+        self, CoalescedSender, Departure, DestructionReason, EventSender, EventSenders,
+        WaiterStates,
+    },
+    weak_sender::{Downgrade, WeakMessageSender},
+    writing_handler::{DeliveryMode, Duration, WritingHandler},
 };
+use smallvec::{smallvec, SmallVec};
 use smart_channel::channel;
 pub use smart_channel::{Receiver, Sender};
-use std::{collections::HashMap, hash::Hash, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::{atomic::AtomicU64, Arc, Mutex},
+};
+use tokio::{sync::mpsc::UnboundedSender, time::Instant};
+
+/// The result of each channel of `shutdown_channels`, with its number of closed subscribers.
+pub type ShutdownResults<M, ChannelId> =
+    Vec<(ChannelId, Result<usize, NotifierError<M, ChannelId>>)>;
 
 /// The default size of a notification channel.
 pub(crate) const NOTIFIER_CHANNEL_SIZE: usize = 10;
 
+/// The number of subscribers of a channel stored without a heap allocation, most channels having a few subscribers.
+pub const INLINE_SUBSCRIBERS: usize = 4;
+
+/// The senders of the subscribers of a channel.
+pub(crate) type SenderList<M> = SmallVec<[MessageSender<M>; INLINE_SUBSCRIBERS]>;
+
 /// Represents the state of a channel. You can retrieve it by calling `channel_state` on the `NotifierHub`.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum ChannelState {
@@ -28,7 +72,7 @@ pub enum ChannelState {
 ///
 /// The address represents a specific field of a specific `NotifierHub`, ensuring its global uniqueness.
 /// We store the address as a `usize` instead of a raw pointer to simplify the type and to keep this type simple without involving generics.
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
 pub struct SmartChannelId {
     /// A counter that increments with each created channel to ensure uniqueness.
     pub(crate) channel_counter: usize,
@@ -49,29 +93,143 @@ pub type MessageReceiver<M> = Receiver<M, SmartChannelId>;
 
 /// Type alias for the receivers returned by the get_destruction_waiter method of the Hub
 pub type DestructionWaiter<M> = Receiver<DeadSender<M>, SmartChannelId>;
-type DestructionSender<M> = Sender<DeadSender<M>, SmartChannelId>;
+pub(crate) type DestructionSender<M> = Sender<DeadSender<M>, SmartChannelId>;
 
 /// Type alias for the receivers returned by the get_creation_waiter method of the Hub
 pub type CreationWaiter = Receiver<(), SmartChannelId>;
-type CreationSender = Sender<(), SmartChannelId>;
+pub(crate) type CreationSender = Sender<(), SmartChannelId>;
 
 /// The main data structure of the crate. It contains all the senders for subscribers and the waiters for channel creation notifications.
 /// The `ChannelId` is used to identify differents channels it can be any type as long as it implements Eq, Hash, et for the majority of the functions Clone
 pub struct NotifierHub<M, ChannelId: Eq + Hash> {
     /// Used to create new id for the smart_channels.
-    connection_id: usize,
+    pub(crate) connection_id: usize,
     /// Binding channel with message senders
-    senders: HashMap<ChannelId, Vec<MessageSender<M>>>,
+    pub(crate) senders: HashMap<ChannelId, SenderList<M>>,
     /// Binding channel with creation notifier
-    creation_senders: HashMap<ChannelId, Vec<CreationSender>>,
+    pub(crate) creation_senders: HashMap<ChannelId, Vec<CreationSender>>,
     /// Binding channel with destruction notifier
-    destruction_senders: HashMap<ChannelId, Vec<DestructionSender<M>>>,
+    pub(crate) destruction_senders: HashMap<ChannelId, Vec<DestructionSender<M>>>,
+    /// Binding channel with its maximum number of subscribers
+    pub(crate) subscriber_limits: HashMap<ChannelId, usize>,
+    /// Binding renamed channels with their new id
+    pub(crate) aliases: HashMap<ChannelId, ChannelId>,
+    /// Defines when the garbage is collected
+    pub(crate) gc_policy: GcPolicy,
+    /// What has been collected automatically and not reported yet
+    pub(crate) gc_report: GcReport<ChannelId>,
+    /// Defines how the messages are written when a buffer is full
+    pub(crate) delivery_mode: DeliveryMode,
+    /// The timeout of the handlers returned by the publishes
+    pub(crate) send_timeout: Option<Duration>,
+    /// Binding channel with the budget of its publishes
+    pub(crate) rate_limits: HashMap<ChannelId, Mutex<TokenBucket>>,
+    /// The budget shared by the publishes on all channels
+    pub(crate) hub_rate_limit: Option<Mutex<TokenBucket>>,
+    /// Defines what to do when a publish exceeds its budget
+    pub(crate) rate_limit_action: RateLimitAction,
+    /// Tracks the failing subscribers
+    pub(crate) breaker: Arc<CircuitBreaker>,
+    /// Defines when a message that can't be written is quarantined
+    pub(crate) quarantine_policy: Option<QuarantinePolicy>,
+    /// The messages that could not be written
+    pub(crate) quarantine: Arc<Quarantine<M>>,
+    /// Binding group names with their channels and subscribers
+    pub(crate) groups: HashMap<String, ChannelGroup<M, ChannelId>>,
+    /// Binding protected channels with the serials of their valid publish tokens
+    pub(crate) publish_grants: HashMap<ChannelId, HashSet<u64>>,
+    /// The last publishes, when auditing is enabled
+    pub(crate) audit: Mutex<AuditLog<ChannelId>>,
+    /// Called for each failed writing
+    pub(crate) error_hook: Option<ErrorHook<ChannelId>>,
+    /// Whether the new subscriptions are refused
+    pub(crate) draining: bool,
+    /// The ids recently published on each deduplicated channel
+    pub(crate) dedup_windows: HashMap<ChannelId, Mutex<DedupWindow>>,
+    /// The id given to the next tagged message
+    pub(crate) next_message_id: AtomicU64,
+    /// The ordering tasks of the sequenced channels
+    pub(crate) sequencers: HashMap<ChannelId, Sequencer>,
+    /// When to raise the alerts, if enabled
+    pub(crate) alerting: Option<Alerting<ChannelId>>,
+    /// The waiters of the alerts
+    pub(crate) alert_waiters: AlertWaiters<ChannelId>,
+    /// The control lanes of the subscribers having one
+    pub(crate) control_lanes: HashMap<SmartChannelId, MessageSender<M>>,
+    /// The inboxes of the subscribers made with `subscribe_with_inbox`
+    pub(crate) inboxes: HashMap<SmartChannelId, MessageSender<M>>,
+    /// Where the ephemeral receivers signal they are dropped, once the first one is created
+    pub(crate) ephemeral_leaves: Option<UnboundedSender<ChannelId>>,
+    /// The monitors of the subscribers, while the drop detection is enabled
+    pub(crate) drop_detection: Option<DropDetection<M>>,
+    /// Binding channel with the waiters of its state transitions
+    pub(crate) state_senders: HashMap<ChannelId, Vec<StateSender>>,
+    /// The state of each channel at its last state notification, `Uninitialised` ones excepted
+    pub(crate) observed_states: HashMap<ChannelId, ChannelState>,
+    /// The waiters of the state transitions of every channel
+    pub(crate) state_change_senders: Vec<Sender<StateChanged<ChannelId>, SmartChannelId>>,
+    /// Number of state transitions since the creation of the hub
+    pub(crate) state_changes: u64,
+    /// Binding channel with the messages published while it has no subscriber, when the parking is enabled
+    pub(crate) parked: HashMap<ChannelId, Mutex<ParkBuffer<M>>>,
+    /// The hooks called around the fanout of each publish
+    pub(crate) broadcast_hooks: BroadcastHooks<ChannelId>,
+    /// The source of the current time
+    pub(crate) clock: SharedClock,
+    /// Incremented each time the subscribers of a channel change, see `BroadcastContext`
+    pub(crate) generation: u64,
+    /// Binding channel with its description and tags
+    pub(crate) metadata: HashMap<ChannelId, ChannelMetadata>,
+    /// Binding channel with the callback fetching the initial data of its new subscribers
+    pub(crate) initial_data: HashMap<ChannelId, InitialData<M>>,
+    /// Binding channel with its last messages, when the journaling is enabled
+    pub(crate) journals: HashMap<ChannelId, Mutex<Journal<M>>>,
+    /// Binding the creation waiters having an overflow policy with their state
+    pub(crate) creation_states: WaiterStates<()>,
+    /// Binding the destruction waiters having an overflow policy with their state
+    pub(crate) destruction_states: WaiterStates<DeadSender<M>>,
+    /// Binding channel with its coalesced creation waiters
+    pub(crate) coalesced_waiters: HashMap<ChannelId, Vec<CoalescedSender>>,
+    /// Binding channel with the creation waiters of several channels watching it
+    pub(crate) creation_events: EventSenders<M, ChannelId, ()>,
+    /// Binding channel with the destruction waiters of several channels watching it
+    pub(crate) destruction_events: EventSenders<M, ChannelId, Departure<M>>,
+    /// The creation waiters of every channel
+    pub(crate) global_creation_events: Vec<Box<dyn EventSender<M, ChannelId, ()>>>,
+    /// The destruction waiters of every channel
+    pub(crate) global_destruction_events: Vec<Box<dyn EventSender<M, ChannelId, Departure<M>>>>,
+    /// Binding channel with the completion signals of its handler subscriptions
+    pub(crate) handler_completions: HashMap<ChannelId, Vec<HandlerCompletion>>,
+    /// The callback called with the messages discarded by the hub
+    pub(crate) drop_hook: Option<DropHook<M, ChannelId>>,
+    /// The credits of the subscribers made with `subscribe_with_credits`
+    pub(crate) credits: Arc<Credits>,
+    /// The serialization formats of the bridges, and the one of each channel
+    pub(crate) formats: FormatRegistry<M, ChannelId>,
+}
+
+/// What a publish accepted by `NotifierHub::admit` does with its message.
+pub(crate) enum Admission {
+    /// The message is written to the subscribers, not before the instant if the publish exceeded a rate limit.
+    /// The handler is only made by the fanout, see `NotifierHub::fanout_handler`.
+    Fanout(Option<Instant>),
+    /// The message is kept in the park buffer of the channel until its next subscriber.
+    Park,
+    /// The channel is over and does not park its messages, the message goes to the drop hook.
+    Discard,
 }
 
 /// Get the senders of a given channel and returns a pointer to an empty vec if uninitialised. First case returns immutable.
 macro_rules! get_senders {
     ($center:expr, $id:expr) => {
-        $center.senders.get(&$id).unwrap_or(&Vec::new())
+        $center.senders.get(&$id).unwrap_or(&SenderList::new())
+    };
+}
+
+/// Returns the channel an id refers to, following the alias left by `rename_channel` if any.
+macro_rules! resolve {
+    ($center:expr, $id:expr) => {
+        $center.aliases.get($id).unwrap_or($id)
     };
 }
 
@@ -89,11 +247,59 @@ impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
             senders: HashMap::new(),
             creation_senders: HashMap::new(),
             destruction_senders: HashMap::new(),
+            subscriber_limits: HashMap::new(),
+            aliases: HashMap::new(),
+            gc_policy: GcPolicy::default(),
+            gc_report: GcReport::default(),
+            delivery_mode: DeliveryMode::default(),
+            send_timeout: None,
+            rate_limits: HashMap::new(),
+            hub_rate_limit: None,
+            rate_limit_action: RateLimitAction::default(),
+            breaker: Arc::default(),
+            quarantine_policy: None,
+            quarantine: Arc::default(),
+            groups: HashMap::new(),
+            publish_grants: HashMap::new(),
+            audit: Mutex::default(),
+            error_hook: None,
+            draining: false,
+            dedup_windows: HashMap::new(),
+            next_message_id: first_message_id(),
+            sequencers: HashMap::new(),
+            alerting: None,
+            alert_waiters: Arc::default(),
+            control_lanes: HashMap::new(),
+            inboxes: HashMap::new(),
+            ephemeral_leaves: None,
+            drop_detection: None,
+            state_senders: HashMap::new(),
+            observed_states: HashMap::new(),
+            state_change_senders: Vec::new(),
+            state_changes: 0,
+            parked: HashMap::new(),
+            broadcast_hooks: BroadcastHooks::default(),
+            clock: Arc::new(TokioClock),
+            generation: 0,
+            metadata: HashMap::new(),
+            initial_data: HashMap::new(),
+            journals: HashMap::new(),
+            creation_states: HashMap::new(),
+            destruction_states: HashMap::new(),
+            coalesced_waiters: HashMap::new(),
+            creation_events: HashMap::new(),
+            destruction_events: HashMap::new(),
+            global_creation_events: Vec::new(),
+            global_destruction_events: Vec::new(),
+            handler_completions: HashMap::new(),
+            drop_hook: None,
+            credits: Arc::default(),
+            formats: FormatRegistry::default(),
         }
     }
 
     /// Generates a new unique `SmartChannelId` by incrementing the internal counter and associating it with the memory address of the `NotifierHub`.
-    fn get_new_id(&mut self) -> SmartChannelId {
+    pub(crate) fn get_new_id(&mut self) -> SmartChannelId {
         let channel_counter = self.connection_id;
         self.connection_id += 1;
         SmartChannelId {
@@ -102,27 +308,245 @@ impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
         }
     }
 
-    fn notify<T: Send + Clone>(
+    /// Sets the delivery mode of the hub, see `DeliveryMode`.
+    pub fn set_delivery_mode(&mut self, mode: DeliveryMode) {
+        self.delivery_mode = mode;
+    }
+
+    /// Returns the delivery mode of the hub.
+    pub fn delivery_mode(&self) -> DeliveryMode {
+        self.delivery_mode
+    }
+
+    /// Sets the default timeout of the writings, `None` removes it.
+    /// The handlers returned by the publishes are armed with a deadline, so `wait(None)` stops at it
+    /// instead of waiting indefinitely. An explicit duration given to `wait` still takes precedence.
+    pub fn set_send_timeout(&mut self, timeout: Option<Duration>) {
+        self.send_timeout = timeout;
+    }
+
+    /// Same as `set_send_timeout`, for building a hub.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    /// use tokio::time::Duration;
+    ///
+    /// let hub: NotifierHub<u32, &str> = NotifierHub::new().with_send_timeout(Duration::from_secs(1));
+    /// assert_eq!(hub.send_timeout(), Some(Duration::from_secs(1)));
+    /// ```
+    pub fn with_send_timeout(mut self, timeout: Duration) -> Self {
+        self.set_send_timeout(Some(timeout));
+        self
+    }
+
+    /// Returns the default timeout of the writings.
+    pub fn send_timeout(&self) -> Option<Duration> {
+        self.send_timeout
+    }
+
+    /// Returns an empty writing handler following the delivery mode and the send timeout of the hub.
+    pub(crate) fn writing_handler<T: Send + 'static, C>(&self) -> WritingHandler<T, C> {
+        let handler = WritingHandler::with_mode(self.delivery_mode);
+        match self.send_timeout {
+            Some(timeout) => handler.with_timeout(timeout),
+            None => handler,
+        }
+    }
+
+    /// Returns an empty writing handler for a publish, reporting to the circuit breaker if it is enabled,
+    /// and to the error hook if any. `channel` is `None` for the broadcasts over several channels.
+    pub(crate) fn publish_handler(
+        &self,
+        channel: Option<&ChannelId>,
+    ) -> WritingHandler<M, ChannelId>
+    where
+        M: Send + 'static,
+        ChannelId: Clone,
+    {
+        let mut handler = self
+            .writing_handler()
+            .on_channel(channel.cloned())
+            .with_reporter(self.failure_reporter(channel));
+        if let Some(breaker) = self.active_breaker() {
+            handler = handler.with_breaker(breaker);
+        }
+        if !self.credits.is_empty() {
+            handler = handler.with_credits(Arc::clone(&self.credits));
+        }
+        if let Some(policy) = self.quarantine_policy {
+            handler = handler.with_quarantine(Arc::clone(&self.quarantine), policy);
+        }
+        if let Some((budget, reporter)) = self.latency_reporter(channel) {
+            handler = handler.with_latency_budget(budget, reporter);
+        }
+        handler
+    }
+
+    /// Returns an empty writing handler for a publish on the given channel admitted by `admit`,
+    /// delayed until the instant the rate limits allow it, if any.
+    pub(crate) fn fanout_handler(
+        &self,
+        id: &ChannelId,
+        not_before: Option<Instant>,
+    ) -> WritingHandler<M, ChannelId>
+    where
+        M: Send + 'static,
+        ChannelId: Clone,
+    {
+        let handler = self.publish_handler(Some(id));
+        match not_before {
+            Some(instant) => handler.not_before(instant),
+            None => handler,
+        }
+    }
+
+    /// Decides what a publish on the channel does with `core::admit`, the publish being refused on a protected channel
+    /// unless `authorized` by a token. The handler of a publish written to the subscribers is throttled by the rate limits.
+    pub(crate) fn admit(&self, id: &ChannelId, authorized: bool) -> Result<Admission, Refusal> {
+        self.admission(id, authorized, true)
+    }
+
+    /// Same as `admit` without the rate limits, for the messages of the hub that skip them.
+    pub(crate) fn admit_unthrottled(
+        &self,
+        id: &ChannelId,
+        authorized: bool,
+    ) -> Result<Admission, Refusal> {
+        self.admission(id, authorized, false)
+    }
+
+    fn admission(
+        &self,
+        id: &ChannelId,
+        authorized: bool,
+        throttled: bool,
+    ) -> Result<Admission, Refusal> {
+        match self.route_of(id, authorized)? {
+            Route::Fanout if throttled => self
+                .throttle(id)
+                .map(Admission::Fanout)
+                .map_err(Refusal::RateLimited),
+            Route::Fanout => Ok(Admission::Fanout(None)),
+            Route::Park => Ok(Admission::Park),
+            Route::Discard | Route::Reject => Ok(Admission::Discard),
+        }
+    }
+
+    /// Same as `admit` without the rate limits, returning the route only.
+    pub(crate) fn route_of(&self, id: &ChannelId, authorized: bool) -> Result<Route, Refusal> {
+        let protected = !authorized && self.is_protected(id);
+        core::admit(
+            self.channel_state(id),
+            self.parked.contains_key(id),
+            protected,
+        )
+    }
+
+    /// Runs the fanout of a broadcast over the senders of all the channels, with their number.
+    /// The senders are borrowed from the hub, and only collected in deterministic mode, to be sorted
+    /// in subscription order.
+    pub(crate) fn fanout_all<T>(
+        &self,
+        fanout: impl FnOnce(usize, &mut dyn Iterator<Item = &MessageSender<M>>) -> T,
+    ) -> T {
+        self.fanout_over(|_| true, fanout)
+    }
+
+    /// Same as `fanout_all`, over the channels accepted by the filter.
+    pub(crate) fn fanout_over<T>(
+        &self,
+        filter: impl Fn(&ChannelId) -> bool,
+        fanout: impl FnOnce(usize, &mut dyn Iterator<Item = &MessageSender<M>>) -> T,
+    ) -> T {
+        let channels = || {
+            self.senders
+                .iter()
+                .filter(|(id, _)| !self.publish_grants.contains_key(id) && filter(id))
+                .map(|(_, senders)| senders)
+        };
+        let len = channels().map(|senders| senders.len()).sum();
+        let mut senders = channels().flatten();
+        if self.delivery_mode == DeliveryMode::Deterministic {
+            let mut sorted: Vec<_> = senders.collect();
+            sorted.sort_by_key(|s| s.id().channel_counter);
+            return fanout(len, &mut sorted.into_iter());
+        }
+        fanout(len, &mut senders)
+    }
+
+    fn notify<T: Send + Clone + 'static>(
+        &self,
         id: &ChannelId,
         m: T,
         map: &HashMap<ChannelId, Vec<NotificationSender<T>>>,
+        states: &WaiterStates<T>,
     ) -> WritingHandler<T> {
-        if let Some(waiters) = map.get(id) {
-            WritingHandler::new_cloning_broadcast(m, waiters)
-        } else {
-            WritingHandler::empty()
+        let Some(waiters) = map.get(id) else {
+            return WritingHandler::empty();
+        };
+        let handler = self
+            .writing_handler()
+            .with_reporter(self.failure_reporter(Some(id)));
+        match states.is_empty() {
+            true => handler.cloning_broadcast(m, waiters),
+            false => {
+                let waiters = waiter::offer_all(waiters, states, &m);
+                handler.cloning_broadcast(m, waiters)
+            }
         }
     }
 
     /// Sends a notification to all waiters subscribed to a channel after a sender is created.
     /// This function should only be called after a sender is added. Since notifications use the unit type `()`,
-    /// `new_cloning_broadcast` is used to broadcast to all waiters.
-    fn notify_creation(&mut self, id: &ChannelId) -> WritingHandler<()> {
-        Self::notify(id, (), &self.creation_senders)
+    /// `cloning_broadcast` is used to broadcast to all waiters.
+    pub(crate) fn notify_creation(&mut self, id: &ChannelId) -> WritingHandler<()>
+    where
+        ChannelId: Clone,
+    {
+        self.notify_state(id);
+        self.notify_coalesced(id);
+        self.notify_events(id, &(), &self.creation_events, &self.global_creation_events);
+        self.notify(id, (), &self.creation_senders, &self.creation_states)
+    }
+
+    /// Returns true if a channel of the hub holds the subscriber.
+    pub(crate) fn is_subscriber(&self, id: &SmartChannelId) -> bool {
+        self.senders
+            .values()
+            .flatten()
+            .any(|sender| sender.id() == id)
+    }
+
+    /// Stops the drop monitor, closes the inbox and the control lane, forgets the credits and leaves the groups
+    /// of a removed subscriber, once no channel holds it.
+    pub(crate) fn subscriber_removed(&mut self, id: &SmartChannelId) {
+        let tracked = self.drop_detection.is_some()
+            || !self.inboxes.is_empty()
+            || !self.control_lanes.is_empty()
+            || !self.credits.is_empty()
+            || !self.groups.is_empty();
+        if tracked && !self.is_subscriber(id) {
+            self.unwatch_drop(id);
+            self.inboxes.remove(id);
+            self.control_lanes.remove(id);
+            self.credits.remove(id);
+            self.leave_groups(id);
+        }
+    }
+
+    /// Returns the senders of the given channel, empty if the channel is uninitialised.
+    pub(crate) fn senders_of(&self, id: &ChannelId) -> &[MessageSender<M>] {
+        let id = resolve!(self, id);
+        match self.senders.get(id) {
+            Some(senders) => senders,
+            None => &[],
+        }
     }
 
     /// Returns `true` if the given receiver is subscribed to the specified channel.
     pub fn is_subscribed(&self, channel: &ChannelId, receiver: &MessageReceiver<M>) -> bool {
+        let channel = resolve!(self, channel);
         match self.channel_state(channel) {
             ChannelState::Running => get_senders!(self, channel)
                 .iter()
@@ -131,6 +555,21 @@ impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
         }
     }
 
+    /// Returns a weak sender bound to the given `receiver` for the specified `channel`, if it exists.
+    /// Unlike `get_sender`, the returned sender does not keep the channel alive, so it can be given
+    /// to auxiliary components without delaying the end of the channel for the receiver.
+    pub fn get_weak_sender(
+        &self,
+        channel: &ChannelId,
+        receiver: &MessageReceiver<M>,
+    ) -> Option<WeakMessageSender<M>> {
+        let channel = resolve!(self, channel);
+        get_senders!(self, channel)
+            .iter()
+            .find(|s| s.is_bound_to(receiver))
+            .map(|s| s.downgrade())
+    }
+
     pub fn number_of_waiter<T>(id: &ChannelId, map: &HashMap<ChannelId, Vec<T>>) -> usize {
         match map.get(id) {
             Some(w) => w.len(),
@@ -140,43 +579,59 @@ impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
 
     /// Returns the number of creation waiters for a given channel.
     pub fn number_of_creation_waiter(&self, id: &ChannelId) -> usize {
-        Self::number_of_waiter(id, &self.creation_senders)
+        Self::number_of_waiter(resolve!(self, id), &self.creation_senders)
     }
 
     /// Returns the number of destruction  waiters for a given channel.
     pub fn number_of_destruction_waiter(&self, id: &ChannelId) -> usize {
-        Self::number_of_waiter(id, &self.destruction_senders)
+        Self::number_of_waiter(resolve!(self, id), &self.destruction_senders)
     }
 
     /// Returns the current state of the specified channel.
     pub fn channel_state(&self, id: &ChannelId) -> ChannelState {
-        match self.senders.get(id) {
-            Some(s) if !s.is_empty() => ChannelState::Running,
-            Some(_) => ChannelState::Over,
-            None => ChannelState::Uninitialised,
-        }
+        core::channel_state(self.senders.get(resolve!(self, id)).map(|s| s.len()))
     }
 
     /// Returns the number of subscribers for a specific channel. Returns `0` if the channel is uninitialised or has ended.
     pub fn channel_number_subscriber(&self, id: &ChannelId) -> usize {
+        let id = resolve!(self, id);
         match self.channel_state(id) {
             ChannelState::Over | ChannelState::Uninitialised => 0,
             ChannelState::Running => get_senders!(self, id).len(),
         }
     }
 
-    /// Cleans up closed connections by removing senders that are closed. Returns the new state of the channel after cleaning.
-    pub fn clean_channel(&mut self, channel: &ChannelId) -> ChannelState {
+    /// Returns the maximum number of subscribers of the channel, if any.
+    pub fn subscriber_limit(&self, id: &ChannelId) -> Option<usize> {
+        self.subscriber_limits.get(resolve!(self, id)).copied()
+    }
+
+    /// Removes the closed senders of the channel, without notifying the destruction waiters.
+    /// Returns the new state of the channel and the removed senders.
+    pub(crate) fn remove_closed_senders(
+        &mut self,
+        channel: &ChannelId,
+    ) -> (ChannelState, Vec<MessageSender<M>>)
+    where
+        ChannelId: Clone,
+    {
+        let channel = &resolve!(self, channel).clone();
         let senders = match self.senders.get_mut(channel) {
             Some(s) => s,
-            None => return ChannelState::Uninitialised,
+            None => return (ChannelState::Uninitialised, Vec::new()),
         };
-        senders.retain(|s| !s.is_closed());
-        if senders.is_empty() {
-            ChannelState::Over
-        } else {
-            ChannelState::Running
+        let mut closed = Vec::new();
+        for sender in std::mem::take(senders) {
+            match sender.is_closed() {
+                true => closed.push(sender),
+                false => senders.push(sender),
+            }
         }
+        let state = core::channel_state(Some(senders.len()));
+        self.notify_state(channel);
+        self.clean_control_lanes();
+        self.subscribers_changed();
+        (state, closed)
     }
 }
 
@@ -187,13 +642,12 @@ where
 {
     /// Sends an `Arc`-wrapped message to all channels.
     /// Useful for broadcasting large messages without cloning the data.
-    pub fn broadcast_arc(&self, msg: M) -> WritingHandler<Arc<M>> {
-        let senders: Vec<_> = self
-            .senders
-            .values()
-            .flat_map(|s| s.iter().cloned())
-            .collect();
-        WritingHandler::new_arc_broadcast(msg, &senders)
+    pub fn broadcast_arc(&self, msg: M) -> WritingHandler<Arc<M>, ChannelId> {
+        self.fanout_all(|len, senders| {
+            self.hooked(None, len, || {
+                self.publish_handler(None).arc_broadcast(msg, senders)
+            })
+        })
     }
 
     /// Sends a reference-counted (`Arc`) message to the specified channel.
@@ -202,6 +656,8 @@ where
     /// Note:
     /// - `arc_send` should be used for large data structures or when you already have an `Arc<M>`.
     /// - Channels using `arc_send` are not compatible with channels using `clone_send` for the same `M`.
+    /// - The receivers needing an owned message can use `IntoOwned::into_owned`, which only clones it
+    ///   if another subscriber still holds it.
     ///
     /// Example:
     /// ```rust
@@ -215,14 +671,47 @@ where
         &self,
         msg: M,
         id: &ChannelId,
-    ) -> Result<WritingHandler<Arc<M>>, NotifierError<Arc<M>, ChannelId>> {
-        match self.channel_state(id) {
-            ChannelState::Running => Ok(WritingHandler::new_arc_broadcast(
-                msg,
-                get_senders!(self, id),
-            )),
-            ChannelState::Over => Ok(WritingHandler::empty()),
-            ChannelState::Uninitialised => Err(NotifierError::ChannelUninitialized(id.clone())),
+    ) -> Result<WritingHandler<Arc<M>, ChannelId>, NotifierError<Arc<M>, ChannelId>> {
+        let result = self.arc_send_on(msg, id, false);
+        self.audit(None, resolve!(self, id), &result);
+        result
+    }
+
+    /// Same as `arc_send`, even if the channel is protected.
+    pub(crate) fn unchecked_arc_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<Arc<M>, ChannelId>, NotifierError<Arc<M>, ChannelId>> {
+        self.arc_send_on(msg, id, true)
+    }
+
+    /// Same as `arc_send`, the protection of the channel being skipped if `authorized`.
+    fn arc_send_on(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        authorized: bool,
+    ) -> Result<WritingHandler<Arc<M>, ChannelId>, NotifierError<Arc<M>, ChannelId>> {
+        let id = resolve!(self, id);
+        match self.admit(id, authorized) {
+            Ok(admission) => {
+                let msg = Arc::new(msg);
+                if let Admission::Fanout(_) = admission {
+                    self.journal(id, &msg);
+                }
+                Ok(self.carry_out(
+                    id,
+                    admission,
+                    msg,
+                    |handler, senders, msg| match self.sequencers.get(id) {
+                        Some(sequencer) => sequencer.publish(handler, msg, senders),
+                        None => handler.cloning_broadcast(msg, senders),
+                    },
+                    |msg| msg,
+                ))
+            }
+            Err(refusal) => Err(refusal.into_error(id.clone(), Arc::new(msg))),
         }
     }
 }
@@ -234,13 +723,53 @@ where
 {
     /// Sends a notification to all waiters subscribed to a channel after someone unsubscribed.
     /// This function should only be called after a sender is added. Since notifications are simple senders,
-    /// `new_cloning_broadcast` is used to broadcast to all waiters.
-    fn notify_destruction(
+    /// `cloning_broadcast` is used to broadcast to all waiters.
+    pub(crate) fn notify_destruction(
         &mut self,
         id: &ChannelId,
         dead_sender: DeadSender<M>,
+        reason: DestructionReason,
     ) -> WritingHandler<DeadSender<M>> {
-        Self::notify(id, dead_sender, &self.destruction_senders)
+        self.notify_state(id);
+        self.subscriber_removed(dead_sender.id());
+        let departure = Departure {
+            sender: dead_sender.clone(),
+            reason,
+        };
+        self.notify_events(
+            id,
+            &departure,
+            &self.destruction_events,
+            &self.global_destruction_events,
+        );
+        self.notify(
+            id,
+            dead_sender,
+            &self.destruction_senders,
+            &self.destruction_states,
+        )
+    }
+
+    /// Cleans up closed connections by removing senders that are closed. Returns the new state of the channel after cleaning.
+    /// The destruction waiters of the channel are notified of each removed sender, with the
+    /// `DestructionReason::DetectedClosed` reason for the departure waiters.
+    pub fn clean_channel(&mut self, channel: &ChannelId) -> ChannelState {
+        let channel = &resolve!(self, channel).clone();
+        let (state, closed) = self.remove_closed_senders(channel);
+        for dead_sender in closed {
+            self.notify_destruction(channel, dead_sender, DestructionReason::DetectedClosed);
+        }
+        state
+    }
+
+    /// This function call the clean_channel method for all the initialized channels. Returns an hashmap binding each channel with its new state
+    pub fn clean_all(&mut self) -> HashMap<ChannelId, ChannelState> {
+        let mut map = HashMap::with_capacity(self.senders.len());
+        for id in self.senders.keys().cloned().collect::<Vec<_>>() {
+            map.insert(id.clone(), self.clean_channel(&id));
+        }
+        self.remove_closed_waiters();
+        map
     }
 
     /// Unsubscribes from all subscriptions for the given receiver across all channels.
@@ -261,6 +790,7 @@ where
         id: &ChannelId,
         receiver: &MessageReceiver<M>,
     ) -> Result<ChannelState, NotifierError<M, ChannelId>> {
+        let id = &resolve!(self, id).clone();
         match self.channel_state(id) {
             ChannelState::Running => {
                 if !self.is_subscribed(id, receiver) {
@@ -274,7 +804,10 @@ where
                             None => unexpected!(SenderIsMissing),
                         };
                         senders.retain(|sender| !sender.is_bound_to(receiver));
-                        self.notify_destruction(id, sender);
+                        self.breaker.forget(sender.id());
+                        self.notify_destruction(id, sender, DestructionReason::Unsubscribed);
+                        self.subscribers_changed();
+                        self.on_mutation();
                         Ok(self.channel_state(id))
                     }
                     None => unexpected!(InvalidChannelStateUnsubscribe), // Should never append as we already checked the state
@@ -308,13 +841,12 @@ where
     }
 
     /// Broadcasts the cloned message to all channels.
-    pub fn broadcast_clone(&self, msg: M) -> WritingHandler<M> {
-        let senders: Vec<_> = self
-            .senders
-            .values()
-            .flat_map(|s| s.iter().cloned())
-            .collect();
-        WritingHandler::new_cloning_broadcast(msg, &senders)
+    pub fn broadcast_clone(&self, msg: M) -> WritingHandler<M, ChannelId> {
+        self.fanout_all(|len, senders| {
+            self.hooked(None, len, || {
+                self.publish_handler(None).cloning_broadcast(msg, senders)
+            })
+        })
     }
 
     /// This is ideal for lightweight, clonable types (e.g., `String`, small structs).
@@ -335,53 +867,302 @@ where
         &self,
         msg: M,
         id: &ChannelId,
-    ) -> Result<WritingHandler<M>, NotifierError<M, ChannelId>> {
-        match self.channel_state(id) {
-            ChannelState::Running => Ok(WritingHandler::new_cloning_broadcast(
-                msg,
-                get_senders!(self, id),
-            )),
-            ChannelState::Over => Ok(WritingHandler::empty()),
-            ChannelState::Uninitialised => Err(NotifierError::ChannelUninitialized(id.clone())),
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.clone_send_as(None, msg, id)
+    }
+
+    /// Same as `clone_send`, the publisher being recorded in the audit log.
+    pub(crate) fn clone_send_as(
+        &self,
+        publisher: Option<&PublisherId>,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        let result = self.clone_send_on(msg, id, false, None);
+        self.audit(publisher, resolve!(self, id), &result);
+        result
+    }
+
+    /// Same as `clone_send`, even if the channel is protected. The `except` subscriber, if any, is skipped.
+    pub(crate) fn unchecked_clone_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        except: Option<SmartChannelId>,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.clone_send_on(msg, id, true, except)
+    }
+
+    /// Same as `clone_send`, the protection of the channel being skipped if `authorized`,
+    /// and the `except` subscriber if any.
+    fn clone_send_on(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        authorized: bool,
+        except: Option<SmartChannelId>,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        let id = resolve!(self, id);
+        match self.admit(id, authorized) {
+            Ok(admission) => {
+                if let Admission::Fanout(_) = admission {
+                    self.journal(id, &msg);
+                }
+                Ok(self.carry_out(
+                    id,
+                    admission,
+                    msg,
+                    |handler, senders, msg| {
+                        let senders = senders.iter().filter(|sender| Some(*sender.id()) != except);
+                        match self.sequencers.get(id) {
+                            Some(sequencer) => sequencer.publish(handler, msg, senders),
+                            None => handler.cloning_broadcast(msg, senders),
+                        }
+                    },
+                    |msg| msg,
+                ))
+            }
+            Err(refusal) => Err(refusal.into_error(id.clone(), msg)),
         }
     }
 }
 
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Sends a message built by the factory to each subscriber of the channel, the factory being called
+    /// once per subscriber. This allows broadcasting messages that can't be cloned but are cheap to build,
+    /// without the receivers having to deal with an `Arc` as with `arc_send`.
+    /// The factory is called once more for the message handed back by a refused publish,
+    /// for the one kept by the park buffer of a channel without subscriber, and for the one recorded by its journal.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    /// use tokio::sync::oneshot;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let mut receiver = hub.subscribe(&"replies", 10);
+    ///
+    /// // A oneshot sender is not `Clone`, each subscriber gets its own
+    /// hub.send_with(&"replies", || oneshot::channel::<u32>().0).unwrap();
+    /// assert!(receiver.try_recv().is_ok());
+    /// ```
+    pub fn send_with(
+        &self,
+        id: &ChannelId,
+        factory: impl Fn() -> M,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        let id = resolve!(self, id);
+        let result = match self.admit(id, false) {
+            Ok(admission) => {
+                if let Admission::Fanout(_) = admission {
+                    self.journal_with(id, &factory);
+                }
+                Ok(self.publish_each(id, admission, |_| factory(), &factory))
+            }
+            Err(refusal) => Err(refusal.into_error(id.clone(), factory())),
+        };
+        self.audit(None, id, &result);
+        result
+    }
+
+    /// Carries out a publish accepted by `admit`: the fanout writes the payload to the subscribers of the channel
+    /// within the broadcast hooks, while the message kept by the park buffer or given to the drop hook is built
+    /// from the payload by `lone`. The lag of the subscribers is checked afterwards.
+    pub(crate) fn carry_out<P>(
+        &self,
+        id: &ChannelId,
+        admission: Admission,
+        payload: P,
+        fanout: impl FnOnce(
+            WritingHandler<M, ChannelId>,
+            &[MessageSender<M>],
+            P,
+        ) -> WritingHandler<M, ChannelId>,
+        lone: impl FnOnce(P) -> M,
+    ) -> WritingHandler<M, ChannelId> {
+        let handler = match admission {
+            Admission::Fanout(not_before) => {
+                let senders = self.senders_of(id);
+                self.hooked(Some(id), senders.len(), || {
+                    fanout(self.fanout_handler(id, not_before), senders, payload)
+                })
+            }
+            Admission::Park => {
+                if let Err(msg) = self.park(id, lone(payload)) {
+                    self.dropped(id, DropReason::ParkOverflow, msg);
+                }
+                WritingHandler::empty()
+            }
+            Admission::Discard => {
+                self.dropped(id, DropReason::ChannelOver, lone(payload));
+                WritingHandler::empty()
+            }
+        };
+        self.check_lag(id);
+        handler
+    }
+
+    /// Same as `carry_out` with the message built for each subscriber, through the sequencer of the channel if any.
+    pub(crate) fn publish_each(
+        &self,
+        id: &ChannelId,
+        admission: Admission,
+        msg: impl FnMut(&MessageSender<M>) -> M,
+        lone: impl FnOnce() -> M,
+    ) -> WritingHandler<M, ChannelId> {
+        self.carry_out(
+            id,
+            admission,
+            msg,
+            |handler, senders, msg| match self.sequencers.get(id) {
+                Some(sequencer) => sequencer.publish_each(handler, senders, msg),
+                None => handler.writing_each(senders, msg),
+            },
+            |_| lone(),
+        )
+    }
+
+    /// Publishes on the channel a message each subscriber has to answer, for `barrier_send`, `scatter`
+    /// and `two_phase_send`: `pair` builds the message of a subscriber along with what the caller keeps
+    /// to read its answer, returned in the order of the subscribers.
+    /// The publish is admitted as by `clone_send`, so the protection and the rate limits of the channel apply,
+    /// and the messages are queued by its sequencer if any. A parked or dropped message would never be answered,
+    /// so the publish fails with `NotifierError::NoSubscriber`, handing the message back, if the channel has
+    /// no subscriber. The returned handler is split from the channel, the outcome of a writing being read
+    /// from its answer, a failed writing dropping the answering half with its message.
+    pub(crate) fn publish_with_reply<T, A>(
+        &self,
+        id: &ChannelId,
+        msg: T,
+        mut pair: impl FnMut(&MessageSender<M>, &T) -> (M, A),
+    ) -> Result<(WritingHandler<M>, Vec<A>), NotifierError<T, ChannelId>>
+    where
+        ChannelId: Clone,
+    {
+        let id = resolve!(self, id);
+        let admission = match self.admit(id, false) {
+            Ok(admission @ Admission::Fanout(_)) => admission,
+            Ok(Admission::Park | Admission::Discard) => {
+                return Err(NotifierError::NoSubscriber {
+                    id: id.clone(),
+                    msg,
+                })
+            }
+            Err(refusal) => return Err(refusal.into_error(id.clone(), msg)),
+        };
+        let mut answers = Vec::new();
+        let writings = self.publish_each(
+            id,
+            admission,
+            |sender| {
+                let (msg, answer) = pair(sender, &msg);
+                answers.push(answer);
+                msg
+            },
+            || unreachable!("Only the fanouts are published"),
+        );
+        Ok((writings.split_channel().0, answers))
+    }
+}
+
 impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
     /// This function returns a list containing all initialized channels
     pub fn get_channels(&self) -> Vec<ChannelId> {
         self.senders.keys().cloned().collect()
     }
 
-    /// This function call the clean_channel method for all the initialized channels. Returns an hashmap binding each channel with its new state
-    pub fn clean_all(&mut self) -> HashMap<ChannelId, ChannelState> {
-        let mut map = HashMap::with_capacity(self.senders.len());
-        for id in self.senders.keys().cloned().collect::<Vec<_>>() {
-            map.insert(id.clone(), self.clean_channel(&id));
-        }
-        map
-    }
-
     /// This function returns a receiver subscribed to the channels specified in the parameter. If the channel is uninitialised, it insert the sender with the insert sender function
     /// The third parameter represents the size for the tokio channels
+    /// While the hub is draining, no subscriber is added and the returned receiver is already over,
+    /// its `recv` returning `None` right away: `try_subscribe` fails with a `Draining` error instead.
+    /// The subscriber is first given the initial data of the channel, see `set_initial_data`.
     pub fn subscribe(&mut self, id: &ChannelId, channel_size: usize) -> MessageReceiver<M> {
-        let (sender, receiver) = channel(channel_size, self.get_new_id());
-        self.insert_sender(sender, id);
+        self.subscribe_prepared(id, channel_size, |_, _| {})
+    }
+
+    /// Same as `subscribe`, `prepare` being called with the new subscriber before the parked messages
+    /// are written to it. It is not called while draining.
+    pub(crate) fn subscribe_prepared(
+        &mut self,
+        id: &ChannelId,
+        channel_size: usize,
+        prepare: impl FnOnce(&mut Self, SmartChannelId),
+    ) -> MessageReceiver<M> {
+        let (mut sender, receiver) = channel(channel_size, self.get_new_id());
+        // While draining, the sender is dropped so the receiver is over right away
+        if !self.draining {
+            prepare(self, receiver.id());
+            if let Some(initial_data) = self.initial_data.get(resolve!(self, id)) {
+                sender = initial_data(sender, channel_size);
+            }
+            self.insert_sender(sender, id);
+        }
         receiver
     }
 
+    /// Same as `subscribe` but fails if the channel reached its subscriber limit, or with a `Draining` error
+    /// if the hub is draining, rather than returning a receiver that is already over.
+    pub fn try_subscribe(
+        &mut self,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        self.check_subscriptions(std::slice::from_ref(id))?;
+        Ok(self.subscribe(id, channel_size))
+    }
+
+    /// Sets the maximum number of subscribers of the channel, `None` removes the limit.
+    /// The limit is only enforced by the `try_subscribe` family, existing subscribers are kept.
+    pub fn set_subscriber_limit(&mut self, id: &ChannelId, limit: Option<usize>) {
+        let id = resolve!(self, id).clone();
+        match limit {
+            Some(limit) => self.subscriber_limits.insert(id, limit),
+            None => self.subscriber_limits.remove(&id),
+        };
+    }
+
+    /// Checks that a new subscriber could be added to each of the given channels.
+    /// A channel appearing several times counts for several subscriptions.
+    /// Returns the error of the first channel that would refuse the subscription.
+    fn check_subscriptions(&self, ids: &[ChannelId]) -> Result<(), NotifierError<M, ChannelId>> {
+        if self.draining {
+            return Err(NotifierError::Draining);
+        }
+        let mut added: HashMap<&ChannelId, usize> = HashMap::new();
+        for id in ids {
+            let id = resolve!(self, id);
+            let n = added.entry(id).or_default();
+            *n += 1;
+            if let Some(limit) = self.subscriber_limit(id) {
+                if get_senders!(self, id).len() + *n > limit {
+                    return Err(NotifierError::SubscriberLimitReached(id.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// This function insert the sender in the sender and call notify creation to notify the creation waiter of the channel creation
     /// It writing handler of the notify creation is ignored for now as i don't really now if it is a good idea to returns
     /// it as it would imply to returns a tupple instead of just the single receiver for the subscribe methods.
-    fn insert_sender(&mut self, sender: MessageSender<M>, id: &ChannelId) {
+    pub(crate) fn insert_sender(&mut self, sender: MessageSender<M>, id: &ChannelId) {
+        let id = &resolve!(self, id).clone();
+        self.flush_parked(id, &sender);
+        self.watch_drop(&sender);
         match self.senders.get_mut(id) {
             Some(senders) => senders.push(sender),
             None => {
-                self.senders.insert(id.clone(), vec![sender]);
+                self.senders.insert(id.clone(), smallvec![sender]);
             }
         }
         // Maybe we should wait it here ?
         let _ = self.notify_creation(id);
+        self.subscribers_changed();
+        self.on_mutation();
     }
 
     /// This functions takes in parameter a receiver and returns all the channels in which the receiver is subscribed.
@@ -399,7 +1180,17 @@ impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
         id: &ChannelId,
         map: &mut HashMap<ChannelId, Vec<NotificationSender<T>>>,
     ) -> Waiter<T> {
-        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, channel_id);
+        Self::waiter_with(channel_id, id, map, NOTIFIER_CHANNEL_SIZE)
+    }
+
+    /// Same as `get_waiter`, with the given buffer size.
+    pub(crate) fn waiter_with<T>(
+        channel_id: SmartChannelId,
+        id: &ChannelId,
+        map: &mut HashMap<ChannelId, Vec<NotificationSender<T>>>,
+        capacity: usize,
+    ) -> Waiter<T> {
+        let (sender, receiver) = channel(capacity, channel_id);
         match map.get_mut(id) {
             Some(s) => s.push(sender),
             None => {
@@ -411,42 +1202,161 @@ impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
 
     /// This function returns a creation waiter for the channel. The waiter is notified each time someone subscribe to the channel
     pub fn get_creation_waiter(&mut self, id: &ChannelId) -> CreationWaiter {
-        Self::get_waiter(self.get_new_id(), id, &mut self.creation_senders)
+        let id = &resolve!(self, id).clone();
+        let waiter = Self::get_waiter(self.get_new_id(), id, &mut self.creation_senders);
+        self.on_mutation();
+        waiter
     }
 
     /// This function returns a destruction waiter for the channel. The waiter is notified each time someone unsubscribe to the channel
     pub fn get_destruction_waiter(&mut self, id: &ChannelId) -> DestructionWaiter<M> {
-        Self::get_waiter(self.get_new_id(), id, &mut self.destruction_senders)
+        let id = &resolve!(self, id).clone();
+        let waiter = Self::get_waiter(self.get_new_id(), id, &mut self.destruction_senders);
+        self.on_mutation();
+        waiter
+    }
+
+    /// Renames the channel `old` into `new`, its subscribers, waiters and settings are moved at once
+    /// so publishers and subscribers don't have to coordinate.
+    /// If `keep_alias` is true, `old` is kept as an alias and any further use of it refers to `new`,
+    /// otherwise `old` becomes uninitialised.
+    /// Returns an error if `old` does not exist or if `new` is already in use.
+    pub fn rename_channel(
+        &mut self,
+        old: &ChannelId,
+        new: ChannelId,
+        keep_alias: bool,
+    ) -> Result<(), NotifierError<M, ChannelId>> {
+        let old = resolve!(self, old).clone();
+        if !self.is_known(&old) {
+            return Err(NotifierError::ChannelNotExist(old));
+        }
+        if old == new {
+            return Ok(());
+        }
+        if self.is_known(&new) {
+            return Err(NotifierError::ChannelAlreadyExist(new));
+        }
+        let entry = self.take_channel(&old);
+        self.insert_channel(&new, entry);
+        self.subscribers_changed();
+        for group in self.groups.values_mut() {
+            for id in group.channels.iter_mut().filter(|id| **id == old) {
+                *id = new.clone();
+            }
+        }
+
+        self.aliases.remove(&new);
+        for target in self.aliases.values_mut() {
+            if *target == old {
+                *target = new.clone();
+            }
+        }
+        if keep_alias {
+            self.aliases.insert(old, new);
+        }
+        Ok(())
     }
 }
 
 impl<M: Clone, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Replaces a receiver subscribed to the channel by a new one with the same `SmartChannelId` and buffer size,
+    /// so a consumer recovering from an error keeps its identity for the hub and everything keyed on it.
+    /// The new receiver takes the place of the old one in all its channels, gets the messages still buffered
+    /// in the old one, and starts with a closed circuit. The writings still in progress towards the old receiver
+    /// fail with `FailureKind::Closed`, their message being handed back by their handler, and can be written again
+    /// to the same subscriber. Returns a `NotSubscribed` error if the old receiver is not subscribed to the channel,
+    /// it is dropped in this case.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let receiver = hub.subscribe(&"jobs", 10);
+    /// let id = receiver.id();
+    ///
+    /// hub.clone_send("job 1", &"jobs").unwrap();
+    /// let mut receiver = hub.resubscribe(&"jobs", receiver).unwrap();
+    /// assert_eq!(receiver.id(), id);
+    /// assert_eq!(receiver.try_recv().unwrap(), "job 1");
+    /// ```
+    pub fn resubscribe(
+        &mut self,
+        id: &ChannelId,
+        mut old_receiver: MessageReceiver<M>,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        let id = resolve!(self, id);
+        let Some(old_sender) = self
+            .senders
+            .get(id)
+            .and_then(|senders| senders.iter().find(|s| s.is_bound_to(&old_receiver)))
+        else {
+            return Err(NotifierError::NotSubscribed(id.clone()));
+        };
+        let (sender, receiver) = channel(old_sender.max_capacity(), old_receiver.id());
+        // The writings still holding the old sender fail from now on, handing their message back
+        old_receiver.close();
+        while let Ok(msg) = old_receiver.try_recv() {
+            let _ = sender.try_send(msg); // Same capacity, it always fits
+        }
+        for senders in self.senders.values_mut() {
+            for s in senders.iter_mut().filter(|s| s.is_bound_to(&old_receiver)) {
+                *s = sender.clone();
+            }
+        }
+        self.regroup(&sender);
+        self.unwatch_drop(sender.id());
+        self.watch_drop(&sender);
+        self.breaker.forget(sender.id());
+        self.subscribers_changed();
+        self.on_mutation();
+        Ok(receiver)
+    }
+
     /// Subscribes to all the channels specified in the `ids` array by inserting the same sender into each channel.
     /// A single receiver is returned, bound to all channels.
     /// Since the sender is cloned for each channel, `M` must implement `Clone`.
     /// The third parameter represents the size for the tokio channels
+    /// As with `subscribe`, the returned receiver is already over while the hub is draining,
+    /// see `try_subscribe_multiple` to get a `Draining` error instead.
     pub fn subscribe_multiple(
         &mut self,
         ids: &[ChannelId],
         channel_size: usize,
     ) -> MessageReceiver<M> {
         let (sender, receiver) = channel(channel_size, self.get_new_id());
-        for id in ids {
-            self.insert_sender(sender.clone(), id);
+        if !self.draining {
+            for id in ids {
+                self.insert_sender(sender.clone(), id);
+            }
         }
         receiver
     }
 
+    /// All-or-nothing version of `subscribe_multiple`: every subscription is checked before any is made,
+    /// so if one of the channels refuses the subscriber, the hub is left untouched and the error
+    /// carries the channel that caused the failure.
+    pub fn try_subscribe_multiple(
+        &mut self,
+        ids: &[ChannelId],
+        channel_size: usize,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        self.check_subscriptions(ids)?;
+        Ok(self.subscribe_multiple(ids, channel_size))
+    }
+
     /// Returns the sender associated with a given `receiver` for the specified `channel`, if it exists.
     /// Returns `None` if no matching sender is found.
     /// Since the returned sender is cloned, `M` must implement `Clone`.
+    /// Note that the returned sender keeps the receiver open while held, see `get_weak_sender` otherwise.
     pub fn get_sender(
         &self,
         channel: &ChannelId,
         receiver: &MessageReceiver<M>,
     ) -> Option<MessageSender<M>> {
         self.senders
-            .get(channel)
+            .get(resolve!(self, channel))
             .and_then(|senders| senders.iter().find(|s| s.is_bound_to(receiver)).cloned())
     }
 
@@ -468,6 +1378,98 @@ impl<M: Clone, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
     }
 }
 
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + 'static + Clone,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `shutdown_clone`, the close message being built for the channel by the factory,
+    /// once per subscriber. This is useful for the message enums whose close message depends
+    /// on the channel being shut down, and `M` does not have to implement `ClosableMessage`.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let mut receiver = hub.subscribe(&"orders", 10);
+    ///
+    /// hub.shutdown_with_factory(&"orders", |channel| format!("{channel} is closed"))
+    ///     .unwrap();
+    /// assert_eq!(receiver.try_recv().unwrap(), "orders is closed");
+    /// ```
+    pub fn shutdown_with_factory(
+        &mut self,
+        channel: &ChannelId,
+        factory: impl Fn(&ChannelId) -> M,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        let mut handler = self
+            .writing_handler()
+            .with_reporter(self.failure_reporter(Some(channel)));
+        self.close_channel(channel, &mut handler, &factory)?;
+        Ok(handler)
+    }
+
+    /// Calls `shutdown_with_factory` for all the channels.
+    pub fn shutdown_all_with_factory(&mut self, factory: impl Fn(&ChannelId) -> M) {
+        for channel in self.get_channels() {
+            let _ = self.shutdown_with_factory(&channel, &factory); // We can ignore because get_channels returns valid data
+        }
+    }
+
+    /// Same as `shutdown_channels`, the close messages being built by the factory as in `shutdown_with_factory`.
+    pub fn shutdown_channels_with_factory(
+        &mut self,
+        channels: &[ChannelId],
+        factory: impl Fn(&ChannelId) -> M,
+    ) -> (ShutdownResults<M, ChannelId>, WritingHandler<M, ChannelId>) {
+        let mut handler = self
+            .writing_handler()
+            .with_reporter(self.failure_reporter(None));
+        let results = channels
+            .iter()
+            .map(|channel| {
+                let result = self.close_channel(channel, &mut handler, &factory);
+                (channel.clone(), result)
+            })
+            .collect();
+        (results, handler)
+    }
+
+    /// Removes the channel, notifies its destruction waiters and writes the close messages with the handler.
+    /// Returns the number of closed subscribers, or an error if the channel doesn't exist.
+    fn close_channel(
+        &mut self,
+        channel: &ChannelId,
+        handler: &mut WritingHandler<M, ChannelId>,
+        factory: &impl Fn(&ChannelId) -> M,
+    ) -> Result<usize, NotifierError<M, ChannelId>> {
+        let channel = &resolve!(self, channel).clone();
+        match self.senders.remove(channel) {
+            Some(dead_senders) => {
+                self.subscribers_changed();
+                // The close messages go through the control lanes, ahead of the queued messages
+                let lanes = self.control_lanes(&dead_senders);
+                for dead_sender in dead_senders.iter() {
+                    self.notify_destruction(
+                        channel,
+                        dead_sender.clone(),
+                        DestructionReason::ChannelClosed,
+                    );
+                }
+                self.notify_state(channel);
+                for dead_sender in dead_senders.iter() {
+                    self.control_lanes.remove(dead_sender.id());
+                }
+                *handler = std::mem::replace(handler, WritingHandler::empty())
+                    .writing_each(&lanes, |_| factory(channel));
+                Ok(dead_senders.len())
+            }
+            None => Err(NotifierError::ChannelNotExist(channel.clone())),
+        }
+    }
+}
+
 impl<M, ChannelId> NotifierHub<M, ChannelId>
 where
     M: Send + 'static + Clone + ClosableMessage,
@@ -476,24 +1478,45 @@ where
     /// This function takes as parameter a channel and shutdown it.
     /// Shutdown means send to all the subscriber a close message obtained via the ClosableTrait
     /// and remove the channel from the hub.
-    /// Here, the shutdown message will be broadcasted using clone.
     /// Destruction waiter will also be notified for all the dead senders.
-    /// Returns an error if the channel doesn't exist
+    /// Returns an error if the channel doesn't exist.
+    /// See `shutdown_with_factory` for a close message depending on the channel.
     pub fn shutdown_clone(
         &mut self,
         channel: &ChannelId,
-    ) -> Result<WritingHandler<M>, NotifierError<M, ChannelId>> {
-        match self.senders.remove(&channel) {
-            Some(dead_senders) => {
-                for dead_sender in dead_senders.iter() {
-                    self.notify_destruction(channel, dead_sender.clone());
-                }
-                let h =
-                    WritingHandler::new_cloning_broadcast(M::get_close_message(), &dead_senders);
-                Ok(h)
-            }
-            None => Err(NotifierError::ChannelNotExist(channel.clone())),
-        }
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.shutdown_with_factory(channel, |_| M::get_close_message())
+    }
+
+    /// Shuts down the given channels at once, as `shutdown_clone` does for each of them.
+    /// Returns the result of each channel, in the given order, with the number of closed subscribers
+    /// or an error if the channel doesn't exist, along with a single handler for all the close messages.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{closable_trait::ClosableMessage, notifier::NotifierHub};
+    ///
+    /// #[derive(Clone)]
+    /// struct Stop;
+    /// impl ClosableMessage for Stop {
+    ///     fn get_close_message() -> Self {
+    ///         Stop
+    ///     }
+    /// }
+    ///
+    /// let mut hub: NotifierHub<Stop, &str> = NotifierHub::new();
+    /// let _receiver = hub.subscribe(&"a", 10);
+    /// let _receiver = hub.subscribe(&"b", 10);
+    ///
+    /// let (results, handler) = hub.shutdown_channels(&["a", "b", "c"]);
+    /// assert!(matches!(results[..], [("a", Ok(1)), ("b", Ok(1)), ("c", Err(_))]));
+    /// assert_eq!(handler.len(), 2);
+    /// ```
+    pub fn shutdown_channels(
+        &mut self,
+        channels: &[ChannelId],
+    ) -> (ShutdownResults<M, ChannelId>, WritingHandler<M, ChannelId>) {
+        self.shutdown_channels_with_factory(channels, |_| M::get_close_message())
     }
 
     /// This method simply call shutdown_all for all the channels.
@@ -508,7 +1531,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::notifier::ChannelState;
+    use crate::{error_hook::FailureKind, notifier::ChannelState};
     use smart_channel::channel;
 
     #[tokio::test]
@@ -536,7 +1559,7 @@ mod tests {
         let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
         let (sender, receiver) = channel(10, hub.get_new_id());
 
-        hub.senders.insert("channel1", vec![sender.clone()]);
+        hub.senders.insert("channel1", smallvec![sender.clone()]);
         assert!(hub.is_subscribed(&"channel1", &receiver));
     }
 
@@ -546,7 +1569,7 @@ mod tests {
         let (sender1, _receiver1) = channel(10, hub.get_new_id());
         let (sender2, _receiver2) = channel(10, hub.get_new_id());
 
-        hub.senders.insert("channel1", vec![sender1, sender2]);
+        hub.senders.insert("channel1", smallvec![sender1, sender2]);
         assert_eq!(hub.channel_number_subscriber(&"channel1"), 2);
     }
 
@@ -557,7 +1580,7 @@ mod tests {
 
         hub.creation_senders.insert("channel1", vec![waiter_sender]);
         let handler = hub.notify_creation(&"channel1");
-        let result = handler.wait(None).await;
+        let result = handler.wait(None).await.into_result();
 
         assert!(result.is_ok());
         assert!(waiter_receiver.recv().await.is_some()); // Ensure notification was sent.
@@ -581,7 +1604,7 @@ mod tests {
         assert_eq!(hub.channel_state(&"channel1"), ChannelState::Uninitialised);
 
         let (sender, _receiver) = channel(10, hub.get_new_id());
-        hub.senders.insert("channel1", vec![sender]);
+        hub.senders.insert("channel1", smallvec![sender]);
         assert_eq!(hub.channel_state(&"channel1"), ChannelState::Running);
 
         hub.clean_channel(&"channel1"); // No receivers closed.
@@ -596,7 +1619,7 @@ mod tests {
         let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
         let (sender, _) = channel(10, hub.get_new_id());
 
-        hub.senders.insert("channel1", vec![sender]);
+        hub.senders.insert("channel1", smallvec![sender]);
         assert_eq!(hub.channel_state(&"channel1"), ChannelState::Running);
 
         hub.clean_channel(&"channel1"); // Clean closed connections.
@@ -609,8 +1632,8 @@ mod tests {
         let (sender1, _) = channel(10, hub.get_new_id());
         let (sender2, _receiver2) = channel(10, hub.get_new_id());
 
-        hub.senders.insert("channel1", vec![sender1.clone()]);
-        hub.senders.insert("channel2", vec![sender2.clone()]);
+        hub.senders.insert("channel1", smallvec![sender1.clone()]);
+        hub.senders.insert("channel2", smallvec![sender2.clone()]);
         assert_eq!(hub.channel_state(&"channel1"), ChannelState::Running);
         assert_eq!(hub.channel_state(&"channel2"), ChannelState::Running);
 
@@ -702,6 +1725,114 @@ mod tests {
         assert!(hub.is_subscribed(&"channel2", &receiver));
     }
 
+    #[tokio::test]
+    async fn test_try_subscribe() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        hub.set_subscriber_limit(&"channel1", Some(1));
+        assert_eq!(hub.subscriber_limit(&"channel1"), Some(1));
+
+        assert!(hub.try_subscribe(&"channel1", 100).is_ok());
+        assert!(matches!(
+            hub.try_subscribe(&"channel1", 100),
+            Err(NotifierError::SubscriberLimitReached("channel1"))
+        ));
+
+        hub.set_subscriber_limit(&"channel1", None);
+        assert!(hub.try_subscribe(&"channel1", 100).is_ok());
+        assert_eq!(hub.channel_number_subscriber(&"channel1"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_try_subscribe_multiple_rollback() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut waiter = hub.get_creation_waiter(&"channel1");
+        hub.set_subscriber_limit(&"channel2", Some(1));
+        let _receiver = hub.subscribe(&"channel2", 100);
+        waiter.try_recv().unwrap_err();
+
+        let result = hub.try_subscribe_multiple(&["channel1", "channel2"], 100);
+        assert!(matches!(
+            result,
+            Err(NotifierError::SubscriberLimitReached("channel2"))
+        ));
+        assert_eq!(hub.channel_state(&"channel1"), ChannelState::Uninitialised);
+        assert_eq!(hub.channel_number_subscriber(&"channel2"), 1);
+        assert!(waiter.try_recv().is_err()); // No creation has been notified
+
+        // The same channel twice counts for two subscriptions
+        hub.set_subscriber_limit(&"channel1", Some(1));
+        assert!(hub
+            .try_subscribe_multiple(&["channel1", "channel1"], 100)
+            .is_err());
+        let receiver = hub.try_subscribe_multiple(&["channel1"], 100).unwrap();
+        assert!(hub.is_subscribed(&"channel1", &receiver));
+    }
+
+    #[tokio::test]
+    async fn test_rename_channel() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver = hub.subscribe(&"channel1", 100);
+        let mut destruction_waiter = hub.get_destruction_waiter(&"channel1");
+        hub.set_subscriber_limit(&"channel1", Some(5));
+
+        hub.rename_channel(&"channel1", "renamed", false).unwrap();
+        assert_eq!(hub.channel_state(&"channel1"), ChannelState::Uninitialised);
+        assert_eq!(hub.channel_state(&"renamed"), ChannelState::Running);
+        assert_eq!(hub.number_of_destruction_waiter(&"renamed"), 1);
+        assert_eq!(hub.subscriber_limit(&"renamed"), Some(5));
+
+        hub.clone_send("Renamed".to_string(), &"renamed").unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), "Renamed");
+        hub.unsubscribe(&"renamed", &receiver).unwrap();
+        assert!(destruction_waiter.recv().await.is_some());
+
+        assert!(matches!(
+            hub.rename_channel(&"channel1", "other", false),
+            Err(NotifierError::ChannelNotExist("channel1"))
+        ));
+        hub.subscribe(&"other", 100);
+        assert!(matches!(
+            hub.rename_channel(&"renamed", "other", false),
+            Err(NotifierError::ChannelAlreadyExist("other"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rename_channel_with_settings_only() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        hub.set_dedup_window(&"channel1", Some(Duration::from_secs(1)));
+        hub.set_journal(&"channel1", Some(10));
+
+        hub.rename_channel(&"channel1", "renamed", false).unwrap();
+        assert_eq!(hub.dedup_window(&"channel1"), None);
+        assert_eq!(hub.dedup_window(&"renamed"), Some(Duration::from_secs(1)));
+        assert_eq!(hub.journal_seq(&"renamed"), Some(0));
+
+        hub.set_journal(&"other", Some(10));
+        assert!(matches!(
+            hub.rename_channel(&"other", "renamed", false),
+            Err(NotifierError::ChannelAlreadyExist("renamed"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rename_channel_with_alias() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver = hub.subscribe(&"channel1", 100);
+
+        hub.rename_channel(&"channel1", "channel2", true).unwrap();
+        hub.rename_channel(&"channel2", "channel3", true).unwrap();
+        assert_eq!(hub.get_channels(), vec!["channel3"]);
+
+        // Both aliases refer to the last name
+        let receiver2 = hub.subscribe(&"channel1", 100);
+        assert!(hub.is_subscribed(&"channel3", &receiver2));
+        hub.clone_send("Through alias".to_string(), &"channel2")
+            .unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), "Through alias");
+        assert_eq!(hub.channel_number_subscriber(&"channel1"), 2);
+    }
+
     #[tokio::test]
     async fn test_get_sender() {
         let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
@@ -747,7 +1878,7 @@ mod tests {
     #[tokio::test]
     async fn test_broadcast_arc() {
         let mut hub: NotifierHub<Arc<String>, &'static str> = NotifierHub::new();
-        let receiver1 = hub.subscribe_multiple(&["channel1", &"channel2"], 100);
+        let receiver1 = hub.subscribe_multiple(&["channel1", "channel2"], 100);
         let _receiver2 = hub.subscribe(&"channel3", 100);
 
         let msg = "Hello ARC broadcast!".to_string();
@@ -795,7 +1926,7 @@ mod tests {
         let receiver = hub.subscribe(&"channel1", 100);
         let msg = "Message !".to_string();
         let handler = hub.clone_send(msg.clone(), &"channel1").unwrap();
-        handler.wait(None).await.unwrap();
+        handler.wait(None).await.into_result().unwrap();
 
         // Test uninitialised channel
         let uninitialised_result = hub.clone_send("No such channel".to_string(), &"channel2");
@@ -861,6 +1992,29 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_deterministic_broadcast_order() {
+        let mut hub: NotifierHub<u32, u32> = NotifierHub::new();
+        hub.set_delivery_mode(DeliveryMode::Deterministic);
+        let (sender, mut receiver) = channel(100, hub.get_new_id());
+        for id in 0..20 {
+            hub.subscribe(&id, 10);
+            hub.senders.get_mut(&id).unwrap().push(sender.clone());
+        }
+        drop(sender);
+
+        // Each channel tags the message with its id, no runtime is needed as nothing is spawned
+        for id in 0..20 {
+            hub.clone_send(id, &id).unwrap();
+        }
+        let received: Vec<_> = (0..20).map(|_| receiver.try_recv().unwrap()).collect();
+        assert_eq!(received, (0..20).collect::<Vec<_>>());
+
+        let handler = hub.broadcast_clone(100);
+        assert_eq!(handler.len(), 40);
+        assert_eq!(handler.pending(), 0);
+    }
+
     #[tokio::test]
     async fn test_get_channels() {
         let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
@@ -874,6 +2028,112 @@ mod tests {
         assert!(channels.contains(&"channel2"));
         assert!(channels.contains(&"channel3"));
     }
+
+    #[tokio::test]
+    async fn test_resubscribe() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let old = hub.subscribe_multiple(&["channel1", "channel2"], 10);
+        let other = hub.subscribe(&"channel1", 10);
+        let id = old.id();
+        hub.clone_send(1, &"channel2").unwrap();
+
+        let mut receiver = hub.resubscribe(&"channel1", old).unwrap();
+        assert_eq!(receiver.id(), id);
+        assert_eq!(hub.channel_number_subscriber(&"channel1"), 2);
+        assert_eq!(receiver.try_recv().unwrap(), 1);
+        hub.clone_send(2, &"channel2").unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), 2);
+
+        assert!(matches!(
+            hub.resubscribe(&"channel2", other),
+            Err(NotifierError::NotSubscribed("channel2"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resubscribe_pending_writing() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let old = hub.subscribe(&"channel1", 1);
+        let id = old.id();
+        hub.clone_send(1, &"channel1").unwrap();
+        let pending = hub.clone_send(2, &"channel1").unwrap();
+        tokio::task::yield_now().await;
+
+        let mut receiver = hub.resubscribe(&"channel1", old).unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), 1);
+        let error = pending.wait(None).await.into_result().unwrap_err();
+        assert_eq!(error.into_undelivered_by_subscriber(), vec![(id, 2)]);
+
+        hub.define_group("jobs", &["channel2"]);
+        let old = hub.subscribe_group("jobs", 10).unwrap();
+        let mut receiver = hub.resubscribe(&"channel2", old).unwrap();
+        hub.define_group("jobs", &["channel2", "channel3"]);
+        hub.clone_send(3, &"channel3").unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_send_timeout() {
+        let mut hub: NotifierHub<String, &'static str> =
+            NotifierHub::new().with_send_timeout(Duration::from_secs(1));
+        let _receiver = hub.subscribe(&"channel1", 1);
+
+        hub.clone_send("Filling".to_string(), &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap();
+        let handler = hub.clone_send("Blocked".to_string(), &"channel1").unwrap();
+        assert!(handler.deadline().is_some());
+        match handler.wait(None).await.into_result() {
+            Err(NotifierError::WritingSendError(errors)) => {
+                assert_eq!(errors[0].kind, FailureKind::Timeout)
+            }
+            _ => panic!("Expected timeout error."),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_with() {
+        #[derive(Debug)]
+        struct Token(u32);
+        let mut hub: NotifierHub<Token, &'static str> = NotifierHub::new();
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        let mut receiver2 = hub.subscribe(&"channel1", 10);
+
+        let built = std::cell::Cell::new(0);
+        let handler = hub
+            .send_with(&"channel1", || {
+                built.set(built.get() + 1);
+                Token(built.get())
+            })
+            .unwrap();
+        assert_eq!(handler.wait(None).await.into_result().unwrap(), 2);
+        assert_eq!(built.get(), 2);
+        assert_eq!(receiver1.recv().await.unwrap().0, 1);
+        assert_eq!(receiver2.recv().await.unwrap().0, 2);
+        assert!(matches!(
+            hub.send_with(&"channel2", || Token(0)),
+            Err(NotifierError::ChannelUninitialized("channel2"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_inline_subscribers() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut receivers: Vec<_> = (0..INLINE_SUBSCRIBERS)
+            .map(|_| hub.subscribe(&"channel1", 10))
+            .collect();
+        assert!(!hub.senders[&"channel1"].spilled());
+        assert_eq!(hub.broadcast_clone(1).len(), INLINE_SUBSCRIBERS);
+
+        receivers.push(hub.subscribe(&"channel1", 10));
+        assert!(hub.senders[&"channel1"].spilled());
+        for receiver in &mut receivers[..INLINE_SUBSCRIBERS] {
+            assert_eq!(receiver.try_recv().ok(), Some(1));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -941,4 +2201,25 @@ mod shutdown_tests {
         assert_eq!(receiver2.recv().await.unwrap(), "CLOSE_MESSAGE");
         assert_eq!(receiver3.recv().await.unwrap(), "CLOSE_MESSAGE");
     }
+
+    #[tokio::test]
+    async fn test_shutdown_with_factory() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver1 = hub.subscribe(&"channel1", 100);
+        let mut receiver2 = hub.subscribe(&"channel2", 100);
+        let mut waiter = hub.get_destruction_waiter(&"channel1");
+
+        hub.shutdown_with_factory(&"channel1", |channel| format!("{channel} closed"))
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap();
+        assert_eq!(receiver1.recv().await.unwrap(), "channel1 closed");
+        assert!(waiter.recv().await.is_some());
+        assert_eq!(hub.channel_state(&"channel1"), ChannelState::Uninitialised);
+
+        hub.shutdown_all_with_factory(|channel| format!("{channel} stopped"));
+        assert_eq!(receiver2.recv().await.unwrap(), "channel2 stopped");
+    }
 }
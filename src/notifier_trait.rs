@@ -0,0 +1,108 @@
+use std::hash::Hash;
+
+use crate::{
+    closable_trait::ClosableMessage,
+    error::NotifierError,
+    notifier::{ChannelState, CreationWaiter, DestructionWaiter, MessageReceiver, NotifierHub},
+    writing_handler::WritingHandler,
+};
+
+/// The public operations of a hub. Services can take an `impl Notifier` instead of the concrete
+/// `NotifierHub`, so they can be tested with a `MockNotifier` (available with the `testing` feature).
+pub trait Notifier<M: Send + 'static, ChannelId> {
+    /// See `NotifierHub::subscribe`.
+    fn subscribe(&mut self, id: &ChannelId, channel_size: usize) -> MessageReceiver<M>;
+
+    /// See `NotifierHub::subscribe_multiple`.
+    fn subscribe_multiple(&mut self, ids: &[ChannelId], channel_size: usize) -> MessageReceiver<M>;
+
+    /// See `NotifierHub::unsubscribe`.
+    fn unsubscribe(
+        &mut self,
+        id: &ChannelId,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<ChannelState, NotifierError<M, ChannelId>>;
+
+    /// See `NotifierHub::clone_send`.
+    fn clone_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>>;
+
+    /// See `NotifierHub::broadcast_clone`.
+    fn broadcast_clone(&self, msg: M) -> WritingHandler<M, ChannelId>;
+
+    /// See `NotifierHub::shutdown_clone`.
+    fn shutdown_clone(
+        &mut self,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>>
+    where
+        M: ClosableMessage;
+
+    /// See `NotifierHub::channel_state`.
+    fn channel_state(&self, id: &ChannelId) -> ChannelState;
+
+    /// See `NotifierHub::get_creation_waiter`.
+    fn get_creation_waiter(&mut self, id: &ChannelId) -> CreationWaiter;
+
+    /// See `NotifierHub::get_destruction_waiter`.
+    fn get_destruction_waiter(&mut self, id: &ChannelId) -> DestructionWaiter<M>;
+}
+
+impl<M, ChannelId> Notifier<M, ChannelId> for NotifierHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    fn subscribe(&mut self, id: &ChannelId, channel_size: usize) -> MessageReceiver<M> {
+        NotifierHub::subscribe(self, id, channel_size)
+    }
+
+    fn subscribe_multiple(&mut self, ids: &[ChannelId], channel_size: usize) -> MessageReceiver<M> {
+        NotifierHub::subscribe_multiple(self, ids, channel_size)
+    }
+
+    fn unsubscribe(
+        &mut self,
+        id: &ChannelId,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<ChannelState, NotifierError<M, ChannelId>> {
+        NotifierHub::unsubscribe(self, id, receiver)
+    }
+
+    fn clone_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        NotifierHub::clone_send(self, msg, id)
+    }
+
+    fn broadcast_clone(&self, msg: M) -> WritingHandler<M, ChannelId> {
+        NotifierHub::broadcast_clone(self, msg)
+    }
+
+    fn shutdown_clone(
+        &mut self,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>>
+    where
+        M: ClosableMessage,
+    {
+        NotifierHub::shutdown_clone(self, id)
+    }
+
+    fn channel_state(&self, id: &ChannelId) -> ChannelState {
+        NotifierHub::channel_state(self, id)
+    }
+
+    fn get_creation_waiter(&mut self, id: &ChannelId) -> CreationWaiter {
+        NotifierHub::get_creation_waiter(self, id)
+    }
+
+    fn get_destruction_waiter(&mut self, id: &ChannelId) -> DestructionWaiter<M> {
+        NotifierHub::get_destruction_waiter(self, id)
+    }
+}
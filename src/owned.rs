@@ -0,0 +1,70 @@
+use std::{future::Future, sync::Arc};
+
+use crate::notifier::MessageReceiver;
+
+/// Allows the receivers of `arc_send` to get the message itself, without cloning it when they are the last
+/// one holding it. The last subscriber to handle a broadcast gets owned data for free.
+pub trait IntoOwned<M> {
+    /// Returns the message, unwrapped if no one else holds it, cloned otherwise.
+    fn into_owned(self) -> M
+    where
+        M: Clone;
+
+    /// Returns the message if no one else holds it, otherwise the shared message is given back.
+    fn try_into_owned(self) -> Result<M, Arc<M>>;
+}
+
+impl<M> IntoOwned<M> for Arc<M> {
+    fn into_owned(self) -> M
+    where
+        M: Clone,
+    {
+        Arc::unwrap_or_clone(self)
+    }
+
+    fn try_into_owned(self) -> Result<M, Arc<M>> {
+        Arc::try_unwrap(self)
+    }
+}
+
+/// Receives the messages of `arc_send` as owned values, see `IntoOwned`.
+pub trait OwnedReceiver<M> {
+    /// Same as `recv`, the message being unwrapped or cloned with `into_owned`.
+    fn recv_owned(&mut self) -> impl Future<Output = Option<M>> + Send;
+
+    /// Same as `try_recv`, the message being unwrapped or cloned with `into_owned`.
+    fn try_recv_owned(&mut self) -> Option<M>;
+}
+
+impl<M: Clone + Send + Sync> OwnedReceiver<M> for MessageReceiver<Arc<M>> {
+    async fn recv_owned(&mut self) -> Option<M> {
+        self.recv().await.map(IntoOwned::into_owned)
+    }
+
+    fn try_recv_owned(&mut self) -> Option<M> {
+        self.try_recv().ok().map(IntoOwned::into_owned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotifierHub;
+
+    #[tokio::test]
+    async fn test_into_owned() {
+        let mut hub: NotifierHub<Arc<Vec<u8>>, &'static str> = NotifierHub::new();
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        let mut receiver2 = hub.subscribe(&"channel1", 10);
+        hub.arc_send(vec![1, 2, 3], &"channel1").unwrap();
+
+        let first = receiver1.recv().await.unwrap();
+        let address = first.as_ptr();
+        let first = first.try_into_owned().unwrap_err();
+        assert_eq!(first.into_owned(), vec![1, 2, 3]);
+
+        // The last receiver gets the original allocation back
+        let last = receiver2.recv_owned().await.unwrap();
+        assert_eq!(last.as_ptr(), address);
+    }
+}
@@ -0,0 +1,263 @@
+use std::{collections::VecDeque, hash::Hash, sync::Mutex};
+use tokio::time::{timeout_at, Duration, Instant};
+
+use crate::{
+    drop_hook::DropReason,
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::{ChannelState, MessageSender, NotifierHub},
+    sync::{get_mut, into_inner, lock},
+    writing_handler::WritingHandler,
+};
+
+/// The messages published on a channel without subscriber, waiting for the first one.
+pub(crate) struct ParkBuffer<M> {
+    pub(crate) capacity: usize,
+    messages: VecDeque<M>,
+}
+
+impl<M> ParkBuffer<M> {
+    /// Returns true if the buffer holds at most its capacity.
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn is_within_capacity(&self) -> bool {
+        self.messages.len() <= self.capacity
+    }
+
+    /// Parks the message, dropping the oldest one when the buffer is full. Returns the dropped message, if any,
+    /// or hands the message back when the buffer has no room at all.
+    fn push(&mut self, msg: M) -> Result<Option<M>, M> {
+        if self.capacity == 0 {
+            return Err(msg);
+        }
+        let dropped = match self.messages.len() == self.capacity {
+            true => self.messages.pop_front(),
+            false => None,
+        };
+        self.messages.push_back(msg);
+        Ok(dropped)
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Parks the publishes made on the channel while it has no subscriber, instead of dropping them,
+    /// so the producers started before the consumers don't lose their first messages.
+    /// At most `capacity` messages are kept, the oldest ones being dropped first, and they are written in order
+    /// to the first subscriber. The messages not fitting in its buffer, or refused by its credits or its circuit,
+    /// are dropped too,
+    /// so its channel size should not be smaller than the capacity.
+    /// `None` disables the parking and drops the parked messages.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// hub.set_park_buffer(&"config", Some(10));
+    /// hub.clone_send("v1", &"config").unwrap();
+    /// hub.clone_send("v2", &"config").unwrap();
+    ///
+    /// let mut receiver = hub.subscribe(&"config", 10);
+    /// assert_eq!(receiver.try_recv().unwrap(), "v1");
+    /// assert_eq!(receiver.try_recv().unwrap(), "v2");
+    /// ```
+    pub fn set_park_buffer(&mut self, id: &ChannelId, capacity: Option<usize>)
+    where
+        ChannelId: Clone,
+    {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        let dropped: Vec<M> = match capacity {
+            Some(capacity) => {
+                let buffer = self.parked.entry(id.clone()).or_insert_with(|| {
+                    Mutex::new(ParkBuffer {
+                        capacity,
+                        messages: VecDeque::new(),
+                    })
+                });
+                let buffer = get_mut(buffer);
+                buffer.capacity = capacity;
+                let excess = buffer.messages.len().saturating_sub(capacity);
+                buffer.messages.drain(..excess).collect()
+            }
+            None => match self.parked.remove(&id) {
+                Some(buffer) => into_inner(buffer).messages.into(),
+                None => Vec::new(),
+            },
+        };
+        for msg in dropped {
+            self.dropped(&id, DropReason::ParkResized, msg);
+        }
+    }
+
+    /// Returns the capacity of the park buffer of the channel, if the parking is enabled.
+    pub fn park_buffer(&self, id: &ChannelId) -> Option<usize> {
+        self.parked
+            .get(self.aliases.get(id).unwrap_or(id))
+            .map(|buffer| lock(buffer).capacity)
+    }
+
+    /// Returns the number of messages parked on the channel.
+    pub fn parked_len(&self, id: &ChannelId) -> usize {
+        self.parked
+            .get(self.aliases.get(id).unwrap_or(id))
+            .map_or(0, |buffer| lock(buffer).messages.len())
+    }
+
+    /// Parks the message if the parking is enabled on the channel, hands it back otherwise
+    /// or when its buffer has no room at all. The oldest parked message is dropped when the buffer is full.
+    pub(crate) fn park(&self, id: &ChannelId, msg: M) -> Result<(), M> {
+        match self.parked.get(id) {
+            Some(buffer) => {
+                let dropped = lock(buffer).push(msg)?;
+                if let Some(dropped) = dropped {
+                    self.dropped(id, DropReason::ParkOverflow, dropped);
+                }
+                Ok(())
+            }
+            None => Err(msg),
+        }
+    }
+
+    /// Writes the messages parked on the channel to its first subscriber, within its credits and its circuit.
+    pub(crate) fn flush_parked(&mut self, id: &ChannelId, sender: &MessageSender<M>) {
+        let Some(buffer) = self.parked.get_mut(id) else {
+            return;
+        };
+        let messages = std::mem::take(&mut get_mut(buffer).messages);
+        let mut dropped = Vec::new();
+        for msg in messages {
+            match dropped.is_empty() {
+                true => {
+                    if let Err(msg) = self.try_write(sender, msg) {
+                        dropped.push(msg);
+                    }
+                }
+                false => dropped.push(msg),
+            }
+        }
+        for msg in dropped {
+            self.dropped(id, DropReason::FlushOverflow, msg);
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::set_park_buffer`.
+    pub fn set_park_buffer(&self, id: &ChannelId, capacity: Option<usize>) {
+        self.with(|hub| hub.set_park_buffer(id, capacity))
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Waits for the channel to have a subscriber, then publishes the message with `clone_send`.
+    /// Returns the writing handler along with how long the publish waited, or `NotifierError::NotReady`
+    /// handing back the message if nobody subscribed before the timeout.
+    /// Unlike a park buffer, the producer learns when the message is written and the timeout bounds its wait.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let consumer = handle.clone();
+    /// let task = tokio::spawn(async move {
+    ///     tokio::time::sleep(Duration::from_millis(10)).await;
+    ///     consumer.subscribe(&"jobs", 10).recv().await.unwrap()
+    /// });
+    ///
+    /// let (_, waited) = handle
+    ///     .publish_when_ready("start", &"jobs", Duration::from_secs(1))
+    ///     .await
+    ///     .unwrap();
+    /// assert!(waited >= Duration::from_millis(10));
+    /// assert_eq!(task.await.unwrap(), "start");
+    /// # }
+    /// ```
+    pub async fn publish_when_ready(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<(WritingHandler<M, ChannelId>, Duration), NotifierError<M, ChannelId>> {
+        let start = Instant::now();
+        let deadline = start + timeout;
+        let mut states = self.get_state_waiter(id);
+        loop {
+            match timeout_at(deadline, states.recv()).await {
+                Ok(Some(ChannelState::Running)) => {
+                    // The subscriber may have left since the notification
+                    if self.channel_state(id) == ChannelState::Running {
+                        break;
+                    }
+                }
+                Ok(Some(_)) => {}
+                Ok(None) | Err(_) => {
+                    return Err(NotifierError::NotReady {
+                        id: id.clone(),
+                        msg,
+                    })
+                }
+            }
+        }
+        let handler = self.clone_send(msg, id)?;
+        Ok((handler, start.elapsed()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::ChannelState;
+
+    #[tokio::test]
+    async fn test_park_buffer() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_park_buffer(&"channel1", Some(2));
+        for i in 0..3 {
+            hub.clone_send(i, &"channel1").unwrap();
+        }
+        assert_eq!(hub.parked_len(&"channel1"), 2);
+        assert_eq!(hub.channel_state(&"channel1"), ChannelState::Uninitialised);
+
+        let mut first = hub.subscribe(&"channel1", 10);
+        let mut second = hub.subscribe(&"channel1", 10);
+        assert_eq!(first.try_recv().unwrap(), 1);
+        assert_eq!(first.try_recv().unwrap(), 2);
+        assert!(second.try_recv().is_err());
+
+        hub.unsubscribe(&"channel1", &first).unwrap();
+        hub.unsubscribe(&"channel1", &second).unwrap();
+        hub.clone_send(3, &"channel1").unwrap();
+        assert_eq!(hub.parked_len(&"channel1"), 1);
+        hub.set_park_buffer(&"channel1", None);
+        assert_eq!(hub.park_buffer(&"channel1"), None);
+        let _ = hub.clone_send(4, &"channel1");
+        assert_eq!(hub.parked_len(&"channel1"), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_publish_when_ready_timeout() {
+        let handle = NotifierHub::<u32, &'static str>::new().into_handle();
+        let result = handle
+            .publish_when_ready(1, &"channel1", Duration::from_millis(10))
+            .await;
+        assert!(matches!(
+            result,
+            Err(NotifierError::NotReady { msg: 1, .. })
+        ));
+
+        let mut receiver = handle.subscribe(&"channel1", 10);
+        let (_, waited) = handle
+            .publish_when_ready(2, &"channel1", Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(waited, Duration::ZERO);
+        assert_eq!(receiver.recv().await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    hash::Hash,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::{error::NotifierError, notifier::NotifierHub, writing_handler::WritingHandler};
+
+/// Gives a unique serial to each token, whatever its hub.
+static TOKEN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A capability allowing to publish on a protected channel, obtained with `NotifierHub::grant_publish`.
+/// It can't be forged, and it stops working once revoked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PublishToken<ChannelId> {
+    channel: ChannelId,
+    serial: u64,
+}
+
+impl<ChannelId> PublishToken<ChannelId> {
+    /// Returns the channel the token allows to publish on.
+    pub fn channel(&self) -> &ChannelId {
+        &self.channel
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Returns true if publishing on the channel requires a `PublishToken`.
+    pub fn is_protected(&self, id: &ChannelId) -> bool {
+        self.publish_grants
+            .contains_key(self.aliases.get(id).unwrap_or(id))
+    }
+
+    /// Returns true if the token has been granted by this hub and is not revoked.
+    pub fn is_granted(&self, token: &PublishToken<ChannelId>) -> bool {
+        self.publish_grants
+            .get(self.aliases.get(&token.channel).unwrap_or(&token.channel))
+            .is_some_and(|serials| serials.contains(&token.serial))
+    }
+
+    /// Revokes the token. Returns false if it was not granted.
+    /// The channel stays protected even if no token is left.
+    pub fn revoke(&mut self, token: &PublishToken<ChannelId>) -> bool {
+        let id = self.aliases.get(&token.channel).unwrap_or(&token.channel);
+        self.publish_grants
+            .get_mut(id)
+            .is_some_and(|serials| serials.remove(&token.serial))
+    }
+
+    /// Revokes all the tokens of the channel and makes it public again.
+    pub fn unprotect(&mut self, id: &ChannelId) {
+        let id = self.aliases.get(id).unwrap_or(id);
+        self.publish_grants.remove(id);
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Returns a new token allowing to publish on the channel with `checked_send`.
+    /// Once a token has been granted for a channel, the channel is protected: `clone_send` and `arc_send`
+    /// fail with `PublishNotAllowed` and broadcasts skip it.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let mut receiver = hub.subscribe(&"admin", 10);
+    /// let token = hub.grant_publish(&"admin");
+    ///
+    /// assert!(hub.clone_send("Injected", &"admin").is_err());
+    /// hub.checked_send(&token, "Authorized").unwrap();
+    /// assert_eq!(receiver.try_recv().unwrap(), "Authorized");
+    /// ```
+    pub fn grant_publish(&mut self, id: &ChannelId) -> PublishToken<ChannelId> {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        let serial = TOKEN_COUNTER.fetch_add(1, Ordering::Relaxed);
+        self.publish_grants
+            .entry(id.clone())
+            .or_default()
+            .insert(serial);
+        PublishToken {
+            channel: id,
+            serial,
+        }
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `clone_send` on the channel of the token, that can be protected.
+    /// Fails with `PublishNotAllowed` if the token has been revoked.
+    pub fn checked_send(
+        &self,
+        token: &PublishToken<ChannelId>,
+        msg: M,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        if !self.is_granted(token) {
+            return Err(NotifierError::PublishNotAllowed {
+                id: token.channel.clone(),
+                msg,
+            });
+        }
+        self.unchecked_clone_send(msg, &token.channel, None)
+    }
+}
+
+impl<M, ChannelId> NotifierHub<Arc<M>, ChannelId>
+where
+    M: Send + Sync + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `arc_send` on the channel of the token, that can be protected.
+    /// Fails with `PublishNotAllowed` if the token has been revoked.
+    pub fn checked_arc_send(
+        &self,
+        token: &PublishToken<ChannelId>,
+        msg: M,
+    ) -> Result<WritingHandler<Arc<M>, ChannelId>, NotifierError<Arc<M>, ChannelId>> {
+        if !self.is_granted(token) {
+            return Err(NotifierError::PublishNotAllowed {
+                id: token.channel.clone(),
+                msg: Arc::new(msg),
+            });
+        }
+        self.unchecked_arc_send(msg, &token.channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::ChannelState;
+
+    #[tokio::test]
+    async fn test_revoke() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver = hub.subscribe(&"admin", 10);
+        let first = hub.grant_publish(&"admin");
+        let second = hub.grant_publish(&"admin");
+
+        assert!(hub.revoke(&first));
+        assert!(!hub.revoke(&first));
+        assert!(matches!(
+            hub.checked_send(&first, "Revoked".to_string()),
+            Err(NotifierError::PublishNotAllowed { msg, .. }) if msg == "Revoked"
+        ));
+        hub.checked_send(&second, "Granted".to_string()).unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), "Granted");
+
+        assert_eq!(hub.broadcast_clone("Broadcast".to_string()).len(), 0);
+        assert_eq!(hub.channel_state(&"admin"), ChannelState::Running);
+
+        hub.unprotect(&"admin");
+        assert!(!hub.is_granted(&second));
+        hub.clone_send("Public".to_string(), &"admin").unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), "Public");
+    }
+
+    #[tokio::test]
+    async fn test_token_of_another_hub() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut other: NotifierHub<String, &'static str> = NotifierHub::new();
+        let _receiver = hub.subscribe(&"admin", 10);
+        hub.grant_publish(&"admin");
+        let foreign = other.grant_publish(&"admin");
+        assert!(hub.checked_send(&foreign, "Forged".to_string()).is_err());
+    }
+}
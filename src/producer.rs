@@ -0,0 +1,144 @@
+use std::{future::Future, hash::Hash};
+use tokio::task::JoinHandle;
+
+use crate::{handle::HubHandle, notifier::ChannelState};
+
+/// Controls a producer registered with `HubHandle::register_producer`. Dropping the guard stops the producer.
+pub struct ProducerGuard {
+    task: JoinHandle<()>,
+}
+
+impl Drop for ProducerGuard {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl ProducerGuard {
+    /// Stops the producer, and no longer starts it when the channel gains a subscriber.
+    pub fn stop(&self) {
+        self.task.abort();
+    }
+
+    /// Returns true if the producer has been stopped, or the channel has been forgotten.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+/// Aborts the running producer when the supervisor stops.
+struct Running(JoinHandle<()>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    /// Runs a producer only while the channel has subscribers, so expensive data sources are idle when nobody listens.
+    /// The task returned by `producer` is spawned each time the channel becomes `Running`, and aborted when it
+    /// becomes `Over` or `Uninitialised`, following its state waiter. A subscriber dropped without being cleaned
+    /// keeps the channel running, see `NotifierHub::clean_channel`.
+    /// The producer gets a handle on the hub to publish with, and the supervision keeps the hub alive
+    /// until the guard is dropped or the channel is forgotten.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let _producer = handle.register_producer(&"prices", |handle| async move {
+    ///     for price in 100.. {
+    ///         if let Ok(handler) = handle.clone_send(price, &"prices") {
+    ///             handler.wait(None).await;
+    ///         }
+    ///         tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    ///     }
+    /// });
+    ///
+    /// // The producer only starts with the first subscriber
+    /// let mut prices = handle.subscribe(&"prices", 10);
+    /// assert!(prices.recv().await.unwrap() >= 100);
+    /// handle.unsubscribe(&"prices", &prices).unwrap();
+    /// # }
+    /// ```
+    pub fn register_producer<F, Fut>(&self, id: &ChannelId, producer: F) -> ProducerGuard
+    where
+        F: Fn(HubHandle<M, ChannelId>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut states = self.get_state_waiter(id);
+        let handle = self.clone();
+        let task = tokio::spawn(async move {
+            let mut running: Option<Running> = None;
+            while let Some(state) = states.recv().await {
+                match state {
+                    ChannelState::Running if running.is_none() => {
+                        running = Some(Running(tokio::spawn(producer(handle.clone()))));
+                    }
+                    ChannelState::Running => {}
+                    ChannelState::Over | ChannelState::Uninitialised => running = None,
+                }
+            }
+        });
+        ProducerGuard { task }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotifierHub;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use tokio::time::{sleep, Duration};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_producer_lifecycle() {
+        let handle: HubHandle<u32, &'static str> = NotifierHub::new().into_handle();
+        let (starts, iterations) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let (s, p) = (starts.clone(), iterations.clone());
+        let guard = handle.register_producer(&"channel1", move |handle| {
+            s.fetch_add(1, Ordering::SeqCst);
+            let p = p.clone();
+            async move {
+                loop {
+                    let _ = handle.clone_send(1, &"channel1");
+                    p.fetch_add(1, Ordering::SeqCst);
+                    sleep(Duration::from_millis(10)).await;
+                }
+            }
+        });
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 0);
+
+        let mut receiver = handle.subscribe(&"channel1", 100);
+        assert_eq!(receiver.recv().await, Some(1));
+        let other = handle.subscribe(&"channel1", 100);
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+
+        handle.unsubscribe_all(&receiver);
+        handle.unsubscribe_all(&other);
+        sleep(Duration::from_millis(10)).await;
+        let stopped = iterations.load(Ordering::SeqCst);
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(iterations.load(Ordering::SeqCst), stopped);
+
+        let _again = handle.subscribe(&"channel1", 100);
+        sleep(Duration::from_millis(1)).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+        guard.stop();
+        sleep(Duration::from_millis(1)).await;
+        assert!(guard.is_finished());
+    }
+}
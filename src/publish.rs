@@ -0,0 +1,169 @@
+use std::hash::Hash;
+
+use crate::{
+    core::{Refusal, Route},
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::NotifierHub,
+};
+
+/// The outcome of `NotifierHub::publish`. The message is handed back when nobody could receive it,
+/// so the caller can buffer it locally or drop it.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PublishOutcome<M> {
+    /// The message has been put in the buffer of this number of subscribers, or is still being written
+    /// to the ones whose buffer is full. The subscribers whose writing failed are not counted.
+    Delivered(usize),
+    /// The channel has no subscriber, and the message is parked until the first one, see `set_park_buffer`.
+    /// The oldest parked message is dropped if the buffer was full.
+    Parked,
+    /// The channel had subscribers, but they are all gone, even if they dropped their receiver
+    /// without unsubscribing, or its park buffer has no room at all.
+    NoSubscribers(M),
+    /// Nobody ever subscribed to the channel.
+    ChannelUnknown(M),
+}
+
+impl<M> PublishOutcome<M> {
+    /// Returns true if the message has been handed to at least one subscriber.
+    pub fn is_delivered(&self) -> bool {
+        matches!(self, PublishOutcome::Delivered(n) if *n > 0)
+    }
+
+    /// Returns the message if nobody could receive it.
+    pub fn into_undelivered(self) -> Option<M> {
+        match self {
+            PublishOutcome::Delivered(_) | PublishOutcome::Parked => None,
+            PublishOutcome::NoSubscribers(msg) | PublishOutcome::ChannelUnknown(msg) => Some(msg),
+        }
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `clone_send`, a publish on a channel that is over or uninitialised being an outcome rather than
+    /// an empty handler or an error. The writing handler is detached, so the failures of the writings are
+    /// reported to the error hook of the hub.
+    /// The protected channels and the rate limits still return an error.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{notifier::NotifierHub, publish::PublishOutcome};
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let mut backlog = Vec::new();
+    /// match hub.publish("first", &"events").unwrap() {
+    ///     PublishOutcome::Delivered(_) | PublishOutcome::Parked => {}
+    ///     PublishOutcome::NoSubscribers(msg) | PublishOutcome::ChannelUnknown(msg) => backlog.push(msg),
+    /// }
+    /// assert_eq!(backlog, vec!["first"]);
+    ///
+    /// let mut receiver = hub.subscribe(&"events", 10);
+    /// assert_eq!(hub.publish("second", &"events").unwrap(), PublishOutcome::Delivered(1));
+    /// assert_eq!(receiver.recv().await.unwrap(), "second");
+    /// # }
+    /// ```
+    pub fn publish(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<PublishOutcome<M>, NotifierError<M, ChannelId>> {
+        // The refused publishes are made by `clone_send`, for its error and its audit
+        let resolved = self.aliases.get(id).unwrap_or(id);
+        match self.route_of(resolved, false) {
+            Ok(Route::Park) => {
+                return Ok(match self.park(resolved, msg) {
+                    Ok(()) => PublishOutcome::Parked,
+                    Err(msg) => PublishOutcome::NoSubscribers(msg),
+                });
+            }
+            Ok(Route::Discard) => return Ok(PublishOutcome::NoSubscribers(msg)),
+            Err(Refusal::Uninitialised) => return Ok(PublishOutcome::ChannelUnknown(msg)),
+            _ => (),
+        }
+        let mut handler = self.clone_send(msg, id)?;
+        if let Some(msg) = handler.take_orphaned() {
+            return Ok(PublishOutcome::NoSubscribers(msg));
+        }
+        let n = handler.written();
+        handler.detach();
+        Ok(PublishOutcome::Delivered(n))
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::publish`.
+    pub fn publish(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<PublishOutcome<M>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.publish(msg, id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_outcomes() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        assert_eq!(
+            hub.publish(1, &"channel1").unwrap(),
+            PublishOutcome::ChannelUnknown(1)
+        );
+
+        let receiver = hub.subscribe(&"channel1", 10);
+        let outcome = hub.publish(2, &"channel1").unwrap();
+        assert!(outcome.is_delivered());
+        assert_eq!(outcome.into_undelivered(), None);
+
+        hub.unsubscribe(&"channel1", &receiver).unwrap();
+        assert_eq!(
+            hub.publish(3, &"channel1").unwrap(),
+            PublishOutcome::NoSubscribers(3)
+        );
+
+        hub.set_park_buffer(&"channel1", Some(0));
+        assert_eq!(
+            hub.publish(4, &"channel1").unwrap(),
+            PublishOutcome::NoSubscribers(4)
+        );
+        hub.set_park_buffer(&"channel1", Some(1));
+        assert_eq!(hub.publish(4, &"channel1").unwrap(), PublishOutcome::Parked);
+
+        hub.grant_publish(&"channel1");
+        assert!(matches!(
+            hub.publish(5, &"channel1"),
+            Err(NotifierError::PublishNotAllowed { msg: 5, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_dropped_receivers() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let receiver1 = hub.subscribe(&"channel1", 10);
+        let receiver2 = hub.subscribe(&"channel1", 10);
+        drop(receiver2);
+        assert_eq!(
+            hub.publish(1, &"channel1").unwrap(),
+            PublishOutcome::Delivered(1)
+        );
+
+        drop(receiver1);
+        assert_eq!(
+            hub.publish(2, &"channel1").unwrap(),
+            PublishOutcome::NoSubscribers(2)
+        );
+    }
+}
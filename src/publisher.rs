@@ -0,0 +1,256 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    hash::Hash,
+    sync::{Arc, MutexGuard},
+};
+use tokio::time::Instant;
+
+use crate::{
+    error::NotifierError, handle::HubHandle, notifier::NotifierHub, sync::lock,
+    writing_handler::WritingHandler,
+};
+
+/// The identity of a publishing component.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PublisherId(Arc<str>);
+
+impl PublisherId {
+    /// Returns a new identity with the given name.
+    pub fn new(name: &str) -> Self {
+        Self(Arc::from(name))
+    }
+
+    /// Returns the name of the publisher.
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PublisherId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A message with the identity of its publisher, sent by `Publisher::send_enveloped`.
+#[derive(Clone, Debug)]
+pub struct Envelope<M> {
+    /// Who published the message.
+    pub publisher: PublisherId,
+    /// The message itself.
+    pub msg: M,
+}
+
+/// A publish on a channel, recorded when the audit log of the hub is enabled.
+#[derive(Clone, Debug)]
+pub struct AuditRecord<ChannelId> {
+    /// Who published, `None` for a publish made directly on the hub.
+    pub publisher: Option<PublisherId>,
+    /// The channel of the publish.
+    pub channel: ChannelId,
+    /// The number of writings, or `None` if the publish has been rejected.
+    pub writings: Option<usize>,
+    /// When the publish has been made.
+    pub at: Instant,
+}
+
+/// The last publishes of a hub.
+pub(crate) struct AuditLog<ChannelId> {
+    capacity: usize,
+    records: VecDeque<AuditRecord<ChannelId>>,
+}
+
+impl<ChannelId> Default for AuditLog<ChannelId> {
+    fn default() -> Self {
+        Self {
+            capacity: 0,
+            records: VecDeque::new(),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    fn audit_log(&self) -> MutexGuard<'_, AuditLog<ChannelId>> {
+        lock(&self.audit)
+    }
+
+    /// Keeps the last `capacity` publishes on a channel in the audit log, `None` disables it.
+    pub fn set_audit_capacity(&mut self, capacity: Option<usize>) {
+        let mut log = self.audit_log();
+        log.capacity = capacity.unwrap_or(0);
+        while log.records.len() > log.capacity {
+            log.records.pop_front();
+        }
+    }
+
+    /// Returns the capacity of the audit log, `None` if it is disabled.
+    pub fn audit_capacity(&self) -> Option<usize> {
+        let capacity = self.audit_log().capacity;
+        (capacity > 0).then_some(capacity)
+    }
+
+    /// Removes the records of the audit log and returns them, the oldest first.
+    pub fn take_audit_log(&self) -> Vec<AuditRecord<ChannelId>> {
+        self.audit_log().records.drain(..).collect()
+    }
+
+    /// Records a publish in the audit log, if it is enabled.
+    pub(crate) fn audit<T: Send + 'static>(
+        &self,
+        publisher: Option<&PublisherId>,
+        channel: &ChannelId,
+        result: &Result<WritingHandler<T, ChannelId>, NotifierError<T, ChannelId>>,
+    ) where
+        ChannelId: Clone,
+    {
+        self.record_publish(
+            publisher,
+            channel,
+            result.as_ref().ok().map(WritingHandler::len),
+        );
+    }
+
+    /// Records a publish in the audit log with its number of writings, `None` if it failed.
+    pub(crate) fn record_publish(
+        &self,
+        publisher: Option<&PublisherId>,
+        channel: &ChannelId,
+        writings: Option<usize>,
+    ) where
+        ChannelId: Clone,
+    {
+        let mut log = self.audit_log();
+        if log.capacity == 0 {
+            return;
+        }
+        if log.records.len() == log.capacity {
+            log.records.pop_front();
+        }
+        log.records.push_back(AuditRecord {
+            publisher: publisher.cloned(),
+            channel: channel.clone(),
+            writings,
+            at: self.now(),
+        });
+    }
+}
+
+/// A handle publishing with an identity, obtained with `HubHandle::publisher`.
+/// Its publishes are recorded with its identity in the audit log, and its errors are wrapped
+/// in a `FromPublisher` error.
+pub struct Publisher<M, ChannelId: Eq + Hash> {
+    id: PublisherId,
+    handle: HubHandle<M, ChannelId>,
+}
+
+impl<M, ChannelId: Eq + Hash> Clone for Publisher<M, ChannelId> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            handle: self.handle.clone(),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> HubHandle<M, ChannelId> {
+    /// Returns a handle publishing with the given identity.
+    pub fn publisher(&self, name: &str) -> Publisher<M, ChannelId> {
+        Publisher {
+            id: PublisherId::new(name),
+            handle: self.clone(),
+        }
+    }
+}
+
+impl<M, ChannelId> Publisher<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Returns the identity of the publisher.
+    pub fn id(&self) -> &PublisherId {
+        &self.id
+    }
+
+    /// See `NotifierHub::clone_send`.
+    pub fn clone_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<M, ChannelId>, NotifierError<M, ChannelId>> {
+        self.handle
+            .with(|hub| hub.clone_send_as(Some(&self.id), msg, id))
+            .map_err(|error| NotifierError::FromPublisher {
+                publisher: self.id.clone(),
+                error: Box::new(error),
+            })
+    }
+}
+
+impl<M, ChannelId> Publisher<Envelope<M>, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Publishes the message in an `Envelope` carrying the identity of the publisher.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let handle = NotifierHub::new().into_handle();
+    /// let billing = handle.publisher("billing");
+    /// let mut receiver = handle.subscribe(&"invoices", 10);
+    ///
+    /// billing.send_enveloped(42, &"invoices").unwrap();
+    /// let envelope = receiver.try_recv().unwrap();
+    /// assert_eq!(envelope.publisher.name(), "billing");
+    /// assert_eq!(envelope.msg, 42);
+    /// ```
+    pub fn send_enveloped(
+        &self,
+        msg: M,
+        id: &ChannelId,
+    ) -> Result<WritingHandler<Envelope<M>, ChannelId>, NotifierError<Envelope<M>, ChannelId>> {
+        let envelope = Envelope {
+            publisher: self.id.clone(),
+            msg,
+        };
+        self.clone_send(envelope, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_audit_capacity(Some(2));
+        let _receiver = hub.subscribe(&"channel1", 10);
+        let handle = hub.into_handle();
+        let billing = handle.publisher("billing");
+
+        handle.clone_send(1, &"channel1").unwrap();
+        billing.clone_send(2, &"channel1").unwrap();
+        match billing.clone_send(3, &"channel2") {
+            Err(NotifierError::FromPublisher { publisher, error }) => {
+                assert_eq!(publisher.name(), "billing");
+                assert!(matches!(
+                    *error,
+                    NotifierError::ChannelUninitialized("channel2")
+                ));
+            }
+            _ => panic!("The channel should not exist"),
+        }
+
+        let log = handle.with(|hub| hub.take_audit_log());
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].publisher.as_ref().unwrap().name(), "billing");
+        assert_eq!(log[0].writings, Some(1));
+        assert_eq!(log[1].channel, "channel2");
+        assert_eq!(log[1].writings, None);
+    }
+}
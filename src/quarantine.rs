@@ -0,0 +1,196 @@
+use std::{
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+pub use tokio::time::Duration;
+use tokio::{sync::mpsc::Sender as TokioSender, time::timeout};
+
+use crate::{
+    error::SendFailure,
+    error_hook::FailureKind,
+    notifier::{NotifierHub, SmartChannelId},
+    sync::lock,
+    writing_handler::WritingHandler,
+};
+
+/// Defines when a message that can't be written to a subscriber is quarantined.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuarantinePolicy {
+    /// Number of attempts to write the message before quarantining it (at least 1).
+    pub max_attempts: usize,
+    /// How long each attempt waits for some room in the buffer of the subscriber.
+    pub attempt_timeout: Duration,
+}
+
+/// A message that could not be written to a subscriber.
+#[derive(Clone, Debug)]
+pub struct QuarantinedMessage<M> {
+    /// The subscriber the message was written to.
+    pub subscriber: SmartChannelId,
+    /// The message itself.
+    pub msg: M,
+    /// Number of failed attempts, including those before a requeue.
+    pub attempts: usize,
+}
+
+/// The messages of a hub that are in quarantine. It is shared with the writing tasks.
+pub(crate) struct Quarantine<M> {
+    messages: Mutex<Vec<QuarantinedMessage<M>>>,
+}
+
+impl<M> Default for Quarantine<M> {
+    fn default() -> Self {
+        Self {
+            messages: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+/// Writes the message to the subscriber, trying again each time an attempt times out.
+/// After the last attempt, the message is put in quarantine and reported as `FailureKind::Quarantined`.
+pub(crate) async fn deliver_or_quarantine<M>(
+    sender: TokioSender<M>,
+    msg: M,
+    subscriber: SmartChannelId,
+    quarantine: Arc<Quarantine<M>>,
+    policy: QuarantinePolicy,
+    previous_attempts: usize,
+) -> Result<(), SendFailure<M, ()>> {
+    for _ in 0..policy.max_attempts.max(1) {
+        match timeout(policy.attempt_timeout, sender.reserve()).await {
+            Ok(Ok(permit)) => {
+                permit.send(msg);
+                return Ok(());
+            }
+            Ok(Err(_)) => return Err(SendFailure::new(subscriber, FailureKind::Closed, Some(msg))),
+            Err(_) => continue,
+        }
+    }
+    lock(&quarantine.messages).push(QuarantinedMessage {
+        subscriber,
+        msg,
+        attempts: previous_attempts + policy.max_attempts.max(1),
+    });
+    Err(SendFailure::new(subscriber, FailureKind::Quarantined, None))
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Sets the quarantine policy of the hub, `None` disables it.
+    /// When enabled, a publish that can't be written to a subscriber because its buffer stays full
+    /// is retried, then moved to the quarantine instead of waiting forever.
+    /// The messages in quarantine can be inspected, requeued or taken.
+    /// Note that the writings aborted by a timeout given to `WritingHandler::wait` are not quarantined.
+    pub fn set_quarantine_policy(&mut self, policy: Option<QuarantinePolicy>) {
+        self.quarantine_policy = policy;
+    }
+
+    /// Returns the quarantine policy of the hub.
+    pub fn quarantine_policy(&self) -> Option<QuarantinePolicy> {
+        self.quarantine_policy
+    }
+
+    /// Returns the number of messages in quarantine.
+    pub fn quarantine_len(&self) -> usize {
+        lock(&self.quarantine.messages).len()
+    }
+
+    /// Returns a copy of the messages in quarantine.
+    pub fn quarantined(&self) -> Vec<QuarantinedMessage<M>>
+    where
+        M: Clone,
+    {
+        lock(&self.quarantine.messages).clone()
+    }
+
+    /// Removes all the messages from the quarantine and returns them.
+    pub fn take_quarantined(&self) -> Vec<QuarantinedMessage<M>> {
+        std::mem::take(&mut *lock(&self.quarantine.messages))
+    }
+
+    /// Writes again the messages in quarantine to their subscriber.
+    /// The messages whose subscriber is gone are reported as `SendingError`,
+    /// those failing again go back in quarantine with their attempts added up.
+    pub fn requeue_quarantined(&self) -> WritingHandler<M, ChannelId>
+    where
+        M: Send + 'static,
+        ChannelId: Clone,
+    {
+        let mut handler = self.publish_handler(None);
+        for quarantined in self.take_quarantined() {
+            let sender = self
+                .senders
+                .values()
+                .flatten()
+                .find(|s| *s.id() == quarantined.subscriber);
+            handler.requeue(sender, quarantined);
+        }
+        handler
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NotifierError;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_quarantine_and_requeue() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_quarantine_policy(Some(QuarantinePolicy {
+            max_attempts: 3,
+            attempt_timeout: Duration::from_millis(10),
+        }));
+        let mut receiver = hub.subscribe(&"channel1", 1);
+
+        hub.clone_send(1, &"channel1").unwrap();
+        let result = hub
+            .clone_send(2, &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result();
+        match result {
+            Err(NotifierError::WritingSendError(errors)) => assert!(matches!(
+                errors[..],
+                [SendFailure { subscriber: Some(id), kind: FailureKind::Quarantined, .. }] if id == receiver.id()
+            )),
+            _ => panic!("The message should have been quarantined"),
+        }
+
+        let quarantined = hub.quarantined();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].msg, 2);
+        assert_eq!(quarantined[0].attempts, 3);
+
+        assert_eq!(receiver.recv().await.unwrap(), 1);
+        hub.requeue_quarantined()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), 2);
+        assert_eq!(hub.quarantine_len(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requeue_without_subscriber() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.set_quarantine_policy(Some(QuarantinePolicy {
+            max_attempts: 1,
+            attempt_timeout: Duration::from_millis(10),
+        }));
+        let receiver = hub.subscribe(&"channel1", 1);
+        hub.clone_send(1, &"channel1").unwrap();
+        assert!(!hub
+            .clone_send(2, &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .all_ok());
+
+        hub.unsubscribe(&"channel1", &receiver).unwrap();
+        assert!(!hub.requeue_quarantined().wait(None).await.all_ok());
+        assert!(hub.take_quarantined().is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use std::{hash::Hash, sync::Mutex};
+use tokio::time::{Duration, Instant};
+
+use crate::{notifier::NotifierHub, sync::lock, writing_handler::DeliveryMode};
+
+/// Defines what happens when a publish exceeds the rate limit.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RateLimitAction {
+    /// The publish fails with a `RateLimited` error, handing the message back.
+    #[default]
+    Reject,
+    /// The message is accepted but its writing is delayed until the budget allows it,
+    /// so awaiting the returned `WritingHandler` is throttled.
+    /// In `DeliveryMode::Deterministic` nothing can be delayed, so the publish is rejected.
+    Wait,
+}
+
+/// A token bucket refilled at a constant rate, up to its burst size.
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    /// Number of tokens added per second.
+    rate: f64,
+    /// Maximum number of tokens.
+    burst: f64,
+    /// Available tokens, negative when some writings have been delayed.
+    tokens: f64,
+    /// The last time the bucket has been refilled.
+    last: Instant,
+}
+
+impl TokenBucket {
+    /// Returns a full bucket.
+    fn new(msgs_per_sec: u32, burst: u32, now: Instant) -> Self {
+        assert!(msgs_per_sec > 0, "a rate limit needs a non zero rate");
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate: f64::from(msgs_per_sec),
+            burst,
+            tokens: burst,
+            last: now,
+        }
+    }
+
+    /// Restarts the measure of the elapsed time from the given instant, when the clock of the hub changes.
+    pub(crate) fn rewind(&mut self, now: Instant) {
+        self.last = now;
+    }
+
+    /// Returns the rate and the burst size of the bucket.
+    pub(crate) fn limit(&self) -> (u32, u32) {
+        (self.rate as u32, self.burst as u32)
+    }
+
+    /// Adds the tokens earned since the last refill.
+    fn refill(&mut self, now: Instant) {
+        let earned = now.duration_since(self.last).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + earned).min(self.burst);
+        self.last = now;
+    }
+
+    /// Returns how long to wait before a token is available.
+    fn delay(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Limits the publishes on the given channel to `msgs_per_sec`, allowing bursts of `burst` messages.
+    /// Only `clone_send` and `arc_send` are limited, broadcasts are not.
+    /// Setting a new limit resets the budget of the channel.
+    ///
+    /// Panics if `msgs_per_sec` is 0.
+    pub fn set_rate_limit(&mut self, id: &ChannelId, msgs_per_sec: u32, burst: u32)
+    where
+        ChannelId: Clone,
+    {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        self.rate_limits.insert(
+            id,
+            Mutex::new(TokenBucket::new(msgs_per_sec, burst, self.now())),
+        );
+    }
+
+    /// Removes the rate limit of the given channel.
+    pub fn remove_rate_limit(&mut self, id: &ChannelId)
+    where
+        ChannelId: Clone,
+    {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        self.rate_limits.remove(&id);
+    }
+
+    /// Limits the publishes on all channels together, in addition to the limit of each channel.
+    /// `None` removes the limit.
+    ///
+    /// Panics if `msgs_per_sec` is 0.
+    pub fn set_hub_rate_limit(&mut self, limit: Option<(u32, u32)>) {
+        let now = self.now();
+        self.hub_rate_limit = limit
+            .map(|(msgs_per_sec, burst)| Mutex::new(TokenBucket::new(msgs_per_sec, burst, now)));
+    }
+
+    /// Sets what happens when a publish exceeds a rate limit, see `RateLimitAction`.
+    pub fn set_rate_limit_action(&mut self, action: RateLimitAction) {
+        self.rate_limit_action = action;
+    }
+
+    /// Returns what happens when a publish exceeds a rate limit.
+    pub fn rate_limit_action(&self) -> RateLimitAction {
+        self.rate_limit_action
+    }
+
+    /// Spends a token of the hub and of the channel for a publish.
+    /// Returns the instant the writing has to wait for, if any,
+    /// or the time to wait before retrying if the publish is rejected.
+    pub(crate) fn throttle(&self, id: &ChannelId) -> Result<Option<Instant>, Duration> {
+        let channel = self.rate_limits.get(id);
+        if channel.is_none() && self.hub_rate_limit.is_none() {
+            return Ok(None);
+        }
+        let now = self.now();
+        let mut buckets: Vec<_> = self
+            .hub_rate_limit
+            .iter()
+            .chain(channel)
+            .map(lock)
+            .collect();
+        let mut delay = Duration::ZERO;
+        for bucket in buckets.iter_mut() {
+            bucket.refill(now);
+            delay = delay.max(bucket.delay());
+        }
+        let wait = self.rate_limit_action == RateLimitAction::Wait
+            && self.delivery_mode == DeliveryMode::Concurrent;
+        if !delay.is_zero() && !wait {
+            return Err(delay);
+        }
+        for bucket in buckets.iter_mut() {
+            bucket.tokens -= 1.0;
+        }
+        Ok((!delay.is_zero()).then_some(now + delay))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NotifierError;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_reject() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut receiver = hub.subscribe(&"channel1", 100);
+        hub.set_rate_limit(&"channel1", 10, 2);
+
+        hub.clone_send(1, &"channel1").unwrap();
+        hub.clone_send(2, &"channel1").unwrap();
+        match hub.clone_send(3, &"channel1") {
+            Err(NotifierError::RateLimited {
+                id,
+                msg,
+                retry_after,
+            }) => {
+                assert_eq!(id, "channel1");
+                assert_eq!(msg, 3);
+                assert_eq!(retry_after, Duration::from_millis(100));
+            }
+            _ => panic!("The publish should have been rejected"),
+        }
+
+        tokio::time::advance(Duration::from_millis(100)).await;
+        hub.clone_send(3, &"channel1").unwrap();
+        for i in 1..=3 {
+            assert_eq!(receiver.try_recv().unwrap(), i);
+        }
+
+        hub.remove_rate_limit(&"channel1");
+        hub.clone_send(4, &"channel1").unwrap();
+        hub.clone_send(5, &"channel1").unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_wait() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut receiver = hub.subscribe(&"channel1", 100);
+        hub.set_rate_limit(&"channel1", 10, 1);
+        hub.set_rate_limit_action(RateLimitAction::Wait);
+
+        let start = Instant::now();
+        hub.clone_send(1, &"channel1").unwrap();
+        let handler = hub.clone_send(2, &"channel1").unwrap();
+        assert_eq!(handler.pending(), 1);
+        assert_eq!(receiver.try_recv().unwrap(), 1);
+        assert!(receiver.try_recv().is_err());
+
+        hub.clone_send(3, &"channel1")
+            .unwrap()
+            .wait(None)
+            .await
+            .into_result()
+            .unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+        assert_eq!(receiver.try_recv().unwrap(), 2);
+        assert_eq!(receiver.try_recv().unwrap(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hub_rate_limit() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let _receiver1 = hub.subscribe(&"channel1", 100);
+        let _receiver2 = hub.subscribe(&"channel2", 100);
+        hub.set_hub_rate_limit(Some((1, 2)));
+
+        hub.clone_send(1, &"channel1").unwrap();
+        hub.clone_send(2, &"channel2").unwrap();
+        assert!(matches!(
+            hub.clone_send(3, &"channel2"),
+            Err(NotifierError::RateLimited { .. })
+        ));
+        // Broadcasts are not limited
+        assert_eq!(hub.broadcast_clone(4).len(), 2);
+    }
+}
@@ -0,0 +1,166 @@
+use std::hash::Hash;
+
+use crate::{
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::{ChannelState, CreationWaiter, MessageReceiver},
+};
+
+/// A facade of a hub that can only subscribe and inspect, obtained with `HubHandle::read_only`.
+/// It can't publish, shut channels down, nor act on the subscriptions of others:
+/// only the holder of a receiver can unsubscribe it. Destruction waiters and senders are not exposed
+/// either, as they would allow to write to the receivers.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::notifier::NotifierHub;
+///
+/// let handle = NotifierHub::new().into_handle();
+/// let plugin = handle.read_only();
+///
+/// let mut receiver = plugin.subscribe(&"events", 10);
+/// handle.clone_send("Hello", &"events").unwrap();
+/// assert_eq!(receiver.try_recv().unwrap(), "Hello");
+/// ```
+///
+/// Publishing through the facade does not compile:
+/// ```compile_fail
+/// use notifier_hub::notifier::NotifierHub;
+///
+/// let plugin = NotifierHub::<&str, &str>::new().into_handle().read_only();
+/// plugin.clone_send("Injected", &"events");
+/// ```
+pub struct ReadOnlyHub<M, ChannelId: Eq + Hash> {
+    handle: HubHandle<M, ChannelId>,
+}
+
+impl<M, ChannelId: Eq + Hash> Clone for ReadOnlyHub<M, ChannelId> {
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle.clone(),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> HubHandle<M, ChannelId> {
+    /// Returns a facade of the hub that can only subscribe and inspect.
+    pub fn read_only(&self) -> ReadOnlyHub<M, ChannelId> {
+        ReadOnlyHub {
+            handle: self.clone(),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> ReadOnlyHub<M, ChannelId> {
+    /// See `NotifierHub::is_subscribed`.
+    pub fn is_subscribed(&self, channel: &ChannelId, receiver: &MessageReceiver<M>) -> bool {
+        self.handle.is_subscribed(channel, receiver)
+    }
+
+    /// See `NotifierHub::channel_state`.
+    pub fn channel_state(&self, id: &ChannelId) -> ChannelState {
+        self.handle.channel_state(id)
+    }
+
+    /// See `NotifierHub::channel_number_subscriber`.
+    pub fn channel_number_subscriber(&self, id: &ChannelId) -> usize {
+        self.handle.channel_number_subscriber(id)
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> ReadOnlyHub<M, ChannelId> {
+    /// See `NotifierHub::get_channels`.
+    pub fn get_channels(&self) -> Vec<ChannelId> {
+        self.handle.get_channels()
+    }
+
+    /// See `NotifierHub::subscribe`.
+    pub fn subscribe(&self, id: &ChannelId, channel_size: usize) -> MessageReceiver<M> {
+        self.handle.subscribe(id, channel_size)
+    }
+
+    /// See `NotifierHub::try_subscribe`.
+    pub fn try_subscribe(
+        &self,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        self.handle.try_subscribe(id, channel_size)
+    }
+
+    /// See `NotifierHub::subscribed_list`.
+    pub fn subscribed_list(&self, receiver: &MessageReceiver<M>) -> Vec<ChannelId> {
+        self.handle.subscribed_list(receiver)
+    }
+
+    /// See `NotifierHub::get_creation_waiter`.
+    pub fn get_creation_waiter(&self, id: &ChannelId) -> CreationWaiter {
+        self.handle.get_creation_waiter(id)
+    }
+}
+
+impl<M: Clone, ChannelId: Eq + Hash + Clone> ReadOnlyHub<M, ChannelId> {
+    /// See `NotifierHub::subscribe_multiple`.
+    pub fn subscribe_multiple(&self, ids: &[ChannelId], channel_size: usize) -> MessageReceiver<M> {
+        self.handle.subscribe_multiple(ids, channel_size)
+    }
+
+    /// See `NotifierHub::try_subscribe_multiple`.
+    pub fn try_subscribe_multiple(
+        &self,
+        ids: &[ChannelId],
+        channel_size: usize,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        self.handle.try_subscribe_multiple(ids, channel_size)
+    }
+}
+
+impl<M, ChannelId> ReadOnlyHub<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// See `NotifierHub::subscribe_group`.
+    pub fn subscribe_group(
+        &self,
+        name: &str,
+        channel_size: usize,
+    ) -> Result<MessageReceiver<M>, NotifierError<M, ChannelId>> {
+        self.handle
+            .with(|hub| hub.subscribe_group(name, channel_size))
+    }
+
+    /// Unsubscribes the given receiver from the channel, see `NotifierHub::unsubscribe`.
+    pub fn unsubscribe(
+        &self,
+        id: &ChannelId,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<ChannelState, NotifierError<M, ChannelId>> {
+        self.handle.unsubscribe(id, receiver)
+    }
+
+    /// Unsubscribes the given receiver from all its channels, see `NotifierHub::unsubscribe_all`.
+    pub fn unsubscribe_all(&self, receiver: &MessageReceiver<M>) -> Vec<ChannelId> {
+        self.handle.unsubscribe_all(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notifier::{ChannelState, NotifierHub};
+
+    #[tokio::test]
+    async fn test_read_only_subscription() {
+        let handle = NotifierHub::<String, &'static str>::new().into_handle();
+        let plugin = handle.read_only().clone();
+        let mut creation_waiter = plugin.get_creation_waiter(&"events");
+
+        let receiver = plugin.subscribe_multiple(&["events", "logs"], 10);
+        assert!(creation_waiter.try_recv().is_ok());
+        assert_eq!(plugin.channel_state(&"events"), ChannelState::Running);
+        assert_eq!(plugin.subscribed_list(&receiver).len(), 2);
+
+        assert_eq!(plugin.unsubscribe_all(&receiver).len(), 2);
+        assert_eq!(handle.channel_state(&"logs"), ChannelState::Over);
+    }
+}
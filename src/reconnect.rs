@@ -0,0 +1,588 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    hash::Hash,
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt, BufWriter},
+    select,
+    sync::{broadcast, oneshot},
+    task::JoinHandle,
+    time::{sleep_until, timeout, Duration, Instant},
+};
+use tokio_util::{bytes::BytesMut, codec::Encoder};
+
+use crate::{
+    bridge::PIPE_CHANNEL_SIZE,
+    handle::HubHandle,
+    notifier::{MessageReceiver, NOTIFIER_CHANNEL_SIZE},
+};
+
+/// What `pipe_with_reconnect` does with the messages buffered while the link was down.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OnReconnect {
+    /// The buffered messages are written first once the link is back, in order.
+    #[default]
+    Replay,
+    /// The buffered messages are dropped and counted, the link resuming with the new messages.
+    Drop,
+}
+
+/// Defines how `pipe_with_reconnect` gets its link back and what it keeps meanwhile.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReconnectPolicy {
+    /// The delay before the first new connection attempt, doubled after each failed one.
+    pub initial_backoff: Duration,
+    /// The longest delay between two connection attempts.
+    pub max_backoff: Duration,
+    /// The number of messages kept while the link is down, the oldest ones being dropped and counted first.
+    pub buffer: usize,
+    pub on_reconnect: OnReconnect,
+    /// The longest time a write or a flush of the link may take, after which the link is considered
+    /// down with `io::ErrorKind::TimedOut`. `None` waits for a stalled peer until the task is stopped.
+    pub write_timeout: Option<Duration>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            buffer: 1000,
+            on_reconnect: OnReconnect::default(),
+            write_timeout: None,
+        }
+    }
+}
+
+/// Emitted each time the link of a `pipe_with_reconnect` changes, see `ReconnectGuard::events`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConnectionEvent {
+    /// The link is up, the buffered messages being replayed if the policy says so.
+    Connected,
+    /// The link failed with the given error.
+    Disconnected(io::ErrorKind),
+    /// The connection attempt number `attempt` failed, the next one being made after `delay`.
+    Reconnecting { attempt: u32, delay: Duration },
+}
+
+/// Controls a task writing the messages of a channel to a link it reconnects, returned by `pipe_with_reconnect`.
+/// Dropping the guard stops the task as `stop` does, but without waiting for it.
+pub struct ReconnectGuard {
+    stop: Option<oneshot::Sender<()>>,
+    task: JoinHandle<usize>,
+    events: broadcast::Sender<ConnectionEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl ReconnectGuard {
+    /// Asks the task to stop. If the link is up, the messages already received are written, then it is shut down.
+    pub fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+
+    /// Returns true if the task is over.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Returns a receiver of the next changes of the link.
+    pub fn events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Returns the number of messages dropped because the buffer was full, because of `OnReconnect::Drop`
+    /// or because they could not be encoded.
+    /// The messages written to a link that fails before they are flushed are buffered again, so they may be
+    /// received twice by the peer.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Waits for the end of the task and returns the number of written messages.
+    /// Without a call to `stop`, the task ends when the channel is over.
+    pub async fn join(self) -> io::Result<usize> {
+        let ReconnectGuard { stop, task, .. } = self;
+        let result = task.await;
+        drop(stop);
+        result.map_err(io::Error::other)
+    }
+}
+
+/// The stop request of a `pipe_with_reconnect` task, which can be awaited by each of its steps.
+struct Stop {
+    receiver: oneshot::Receiver<()>,
+    requested: bool,
+}
+
+impl Stop {
+    /// Completes once the task is asked to stop, or never if it already has been.
+    async fn requested(&mut self) {
+        if self.requested {
+            std::future::pending::<()>().await;
+        }
+        let _ = (&mut self.receiver).await;
+        self.requested = true;
+    }
+}
+
+/// The state of a `pipe_with_reconnect` task, shared by its steps.
+struct Link<M> {
+    receiver: MessageReceiver<M>,
+    stopped: Stop,
+    policy: ReconnectPolicy,
+    /// The messages waiting for the link.
+    buffer: VecDeque<M>,
+    /// The messages written since the last flush, that may not have reached the peer yet.
+    unflushed: Vec<M>,
+    events: broadcast::Sender<ConnectionEvent>,
+    dropped: Arc<AtomicU64>,
+    written: usize,
+}
+
+/// Why a step of the task ended.
+enum Step<T> {
+    Done(T),
+    /// The channel is over or the task has been stopped.
+    Over,
+}
+
+impl<M: Clone> Link<M> {
+    fn emit(&self, event: ConnectionEvent) {
+        let _ = self.events.send(event);
+    }
+
+    fn drop_messages(&self, n: usize) {
+        self.dropped.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    /// Drops the oldest buffered messages beyond the size of the buffer.
+    fn trim(&mut self) {
+        let excess = self.buffer.len().saturating_sub(self.policy.buffer);
+        self.buffer.drain(..excess);
+        self.drop_messages(excess);
+    }
+
+    /// Buffers a message that can't be written now.
+    fn keep(&mut self, msg: M) {
+        self.buffer.push_back(msg);
+        self.trim();
+    }
+
+    /// Puts back the messages that may not have reached the peer ahead of the buffered ones, once the link failed.
+    fn requeue(&mut self) {
+        for msg in self.unflushed.drain(..).rev() {
+            self.buffer.push_front(msg);
+        }
+        self.trim();
+    }
+
+    /// Buffers the messages of the channel until the deadline.
+    async fn buffer_until(&mut self, deadline: Instant) -> Step<()> {
+        loop {
+            select! {
+                _ = sleep_until(deadline) => return Step::Done(()),
+                msg = self.receiver.recv() => match msg {
+                    Some(msg) => self.keep(msg),
+                    None => return Step::Over,
+                },
+                _ = self.stopped.requested() => return Step::Over,
+            }
+        }
+    }
+
+    /// Connects, waiting longer after each failed attempt.
+    async fn connect<W, F, Fut>(&mut self, connect: &mut F) -> Step<W>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = io::Result<W>>,
+    {
+        let mut delay = self.policy.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let result = select! {
+                result = connect() => result,
+                _ = self.stopped.requested() => return Step::Over,
+            };
+            match result {
+                Ok(writer) => {
+                    self.emit(ConnectionEvent::Connected);
+                    return Step::Done(writer);
+                }
+                Err(_) => {
+                    self.emit(ConnectionEvent::Reconnecting { attempt, delay });
+                    if let Step::Over = self.buffer_until(Instant::now() + delay).await {
+                        return Step::Over;
+                    }
+                    delay = (delay * 2).min(self.policy.max_backoff);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Runs an operation on the link until the write timeout of the policy, a stall failing with `TimedOut`.
+    /// Returns `Over` if the task is asked to stop meanwhile.
+    async fn guarded<T>(
+        &mut self,
+        operation: impl Future<Output = io::Result<T>>,
+    ) -> Step<io::Result<T>> {
+        let operation = async {
+            match self.policy.write_timeout {
+                Some(duration) => timeout(duration, operation)
+                    .await
+                    .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into())),
+                None => operation.await,
+            }
+        };
+        select! {
+            result = operation => Step::Done(result),
+            _ = self.stopped.requested() => Step::Over,
+        }
+    }
+
+    /// Writes a message to the link. The messages that can't be encoded are dropped.
+    async fn write<W, E>(
+        &mut self,
+        writer: &mut BufWriter<W>,
+        encoder: &mut E,
+        msg: M,
+    ) -> Step<io::Result<()>>
+    where
+        W: AsyncWrite + Unpin,
+        E: Encoder<M>,
+    {
+        let mut frame = BytesMut::new();
+        if encoder.encode(msg.clone(), &mut frame).is_err() {
+            self.drop_messages(1);
+            return Step::Done(Ok(()));
+        }
+        self.unflushed.push(msg);
+        self.guarded(writer.write_all(&frame)).await
+    }
+
+    /// Flushes the link, the messages written until then being delivered.
+    async fn flush<W: AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut BufWriter<W>,
+    ) -> Step<io::Result<()>> {
+        let step = self.guarded(writer.flush()).await;
+        if let Step::Done(Ok(())) = step {
+            self.written += self.unflushed.len();
+            self.unflushed.clear();
+        }
+        step
+    }
+
+    /// Returns how `forward` ends after a write or a flush, `None` if the link is still up.
+    /// The messages that may not have reached the peer are buffered again once the link failed.
+    fn ended(&mut self, step: Step<io::Result<()>>) -> Option<Step<io::Error>> {
+        match step {
+            Step::Done(Ok(())) => None,
+            Step::Done(Err(e)) => {
+                self.requeue();
+                Some(Step::Done(e))
+            }
+            Step::Over => Some(Step::Over),
+        }
+    }
+
+    /// Writes the buffered messages if the policy says so, then the messages of the channel while the link is up.
+    /// Returns the error of the link once it fails. A stop request ends a stalled write or flush right away,
+    /// the messages it holds being lost.
+    async fn forward<W, E>(&mut self, writer: W, encoder: &mut E) -> Step<io::Error>
+    where
+        W: AsyncWrite + Unpin,
+        E: Encoder<M>,
+    {
+        let mut writer = BufWriter::new(writer);
+        match self.policy.on_reconnect {
+            OnReconnect::Replay => {
+                while let Some(msg) = self.buffer.pop_front() {
+                    let step = self.write(&mut writer, encoder, msg).await;
+                    if let Some(end) = self.ended(step) {
+                        return end;
+                    }
+                }
+            }
+            OnReconnect::Drop => {
+                self.drop_messages(self.buffer.len());
+                self.buffer.clear();
+            }
+        }
+        loop {
+            if self.receiver.is_empty() {
+                let step = self.flush(&mut writer).await;
+                if let Some(end) = self.ended(step) {
+                    return end;
+                }
+            }
+            let msg = select! {
+                msg = self.receiver.recv() => msg,
+                _ = self.stopped.requested() => None,
+            };
+            let Some(msg) = msg else {
+                // Only bounded by the write timeout, as the stop request has already been received
+                if let Step::Done(_) = self.flush(&mut writer).await {
+                    let _ = self.guarded(writer.shutdown()).await;
+                }
+                return Step::Over;
+            };
+            let step = self.write(&mut writer, encoder, msg).await;
+            if let Some(end) = self.ended(step) {
+                return end;
+            }
+        }
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    /// Same as `pipe_to_writer`, the link being opened by `connect` and opened again each time it fails,
+    /// so the bridged hubs survive a flaky network. The connection attempts are spaced out following the policy,
+    /// and the messages published while the link is down are kept in a bounded buffer, to be replayed
+    /// or dropped once it is back. The changes of the link are given by `ReconnectGuard::events`.
+    /// Each link gets a new encoder from `encoder`. The subscription is removed when the task ends.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{bridge::LinesCodec, notifier::NotifierHub, reconnect::ReconnectPolicy};
+    /// use std::io;
+    /// use tokio::io::{duplex, AsyncBufReadExt, BufReader};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::<String, &str>::new().into_handle();
+    /// let (writer, reader) = duplex(1024);
+    /// let mut writer = Some(writer);
+    /// let mut guard = handle.pipe_with_reconnect(
+    ///     &"logs",
+    ///     // A socket would be connected here, this link can only be opened once
+    ///     move || std::future::ready(writer.take().ok_or(io::ErrorKind::ConnectionRefused.into())),
+    ///     LinesCodec::new,
+    ///     ReconnectPolicy::default(),
+    /// );
+    ///
+    /// handle.clone_send("Hello".to_string(), &"logs").unwrap();
+    /// let mut line = String::new();
+    /// BufReader::new(reader).read_line(&mut line).await.unwrap();
+    /// assert_eq!(line, "Hello\n");
+    /// guard.stop();
+    /// assert_eq!(guard.join().await.unwrap(), 1);
+    /// # }
+    /// ```
+    pub fn pipe_with_reconnect<W, F, Fut, E>(
+        &self,
+        id: &ChannelId,
+        mut connect: F,
+        encoder: impl Fn() -> E + Send + 'static,
+        policy: ReconnectPolicy,
+    ) -> ReconnectGuard
+    where
+        W: AsyncWrite + Send + Unpin + 'static,
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<W>> + Send,
+        E: Encoder<M> + Send + 'static,
+    {
+        let handle = self.clone();
+        let id = id.clone();
+        let receiver = self.subscribe(&id, PIPE_CHANNEL_SIZE);
+        let (stop, stopped) = oneshot::channel();
+        let (events, _) = broadcast::channel(NOTIFIER_CHANNEL_SIZE);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let mut link = Link {
+            receiver,
+            stopped: Stop {
+                receiver: stopped,
+                requested: false,
+            },
+            policy,
+            buffer: VecDeque::new(),
+            unflushed: Vec::new(),
+            events: events.clone(),
+            dropped: dropped.clone(),
+            written: 0,
+        };
+        let task = tokio::spawn(async move {
+            while let Step::Done(writer) = link.connect(&mut connect).await {
+                match link.forward(writer, &mut encoder()).await {
+                    Step::Done(e) => link.emit(ConnectionEvent::Disconnected(e.kind())),
+                    Step::Over => break,
+                }
+            }
+            let _ = handle.unsubscribe(&id, &link.receiver);
+            link.written
+        });
+        ReconnectGuard {
+            stop: Some(stop),
+            task,
+            events,
+            dropped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bridge::LinesCodec, notifier::NotifierHub};
+    use std::sync::Mutex;
+    use tokio::io::{duplex, AsyncBufReadExt, BufReader, DuplexStream};
+
+    type Links = Arc<Mutex<Vec<DuplexStream>>>;
+
+    /// Opens the links pushed by the test, or fails while there is none.
+    fn links() -> (
+        impl Fn() -> std::future::Ready<io::Result<DuplexStream>>,
+        Links,
+    ) {
+        let writers: Links = Arc::default();
+        let available = writers.clone();
+        let connect = move || {
+            let link = available.lock().unwrap().pop();
+            std::future::ready(link.ok_or_else(|| io::ErrorKind::ConnectionRefused.into()))
+        };
+        (connect, writers)
+    }
+
+    async fn next_line(reader: &mut BufReader<DuplexStream>) -> String {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        line.trim_end().to_string()
+    }
+
+    async fn test_reconnect(on_reconnect: OnReconnect) -> (Vec<String>, u64) {
+        let handle = NotifierHub::<String, &'static str>::new().into_handle();
+        let (connect, writers) = links();
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(40),
+            buffer: 2,
+            on_reconnect,
+            write_timeout: None,
+        };
+        let mut guard = handle.pipe_with_reconnect(&"channel1", connect, LinesCodec::new, policy);
+        let mut events = guard.events();
+        let mut attempts = Vec::new();
+        while let Ok(ConnectionEvent::Reconnecting { delay, .. }) = events.recv().await {
+            attempts.push(delay);
+            if attempts.len() == 4 {
+                break;
+            }
+        }
+        assert_eq!(
+            attempts,
+            [10, 20, 40, 40].map(Duration::from_millis).to_vec()
+        );
+
+        for msg in ["a", "b", "c"] {
+            handle
+                .clone_send(msg.to_string(), &"channel1")
+                .unwrap()
+                .wait(None)
+                .await;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let (writer, reader) = duplex(64);
+        writers.lock().unwrap().push(writer);
+        while events.recv().await.unwrap() != ConnectionEvent::Connected {}
+        handle
+            .clone_send("d".to_string(), &"channel1")
+            .unwrap()
+            .wait(None)
+            .await;
+
+        let mut reader = BufReader::new(reader);
+        let expected = match on_reconnect {
+            OnReconnect::Replay => 3,
+            OnReconnect::Drop => 1,
+        };
+        let mut lines = Vec::new();
+        for _ in 0..expected {
+            lines.push(next_line(&mut reader).await);
+        }
+        drop(reader);
+        handle
+            .clone_send("e".to_string(), &"channel1")
+            .unwrap()
+            .wait(None)
+            .await;
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            ConnectionEvent::Disconnected(_)
+        ));
+        guard.stop();
+        let dropped = guard.dropped();
+        assert_eq!(guard.join().await.unwrap(), expected);
+        (lines, dropped)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stalled_link() {
+        let handle = NotifierHub::<String, &'static str>::new().into_handle();
+        let (connect, writers) = links();
+        let (writer, _reader) = duplex(4);
+        writers.lock().unwrap().push(writer);
+        let policy = ReconnectPolicy {
+            write_timeout: Some(Duration::from_secs(1)),
+            ..ReconnectPolicy::default()
+        };
+        let mut guard = handle.pipe_with_reconnect(&"channel1", connect, LinesCodec::new, policy);
+        let mut events = guard.events();
+        handle
+            .clone_send("Too long for the link".to_string(), &"channel1")
+            .unwrap();
+        assert_eq!(events.recv().await.unwrap(), ConnectionEvent::Connected);
+        let start = Instant::now();
+        assert_eq!(
+            events.recv().await.unwrap(),
+            ConnectionEvent::Disconnected(io::ErrorKind::TimedOut)
+        );
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        guard.stop();
+        assert_eq!(guard.join().await.unwrap(), 0);
+
+        // Without a write timeout, only the stop request ends the stalled write
+        let (connect, writers) = links();
+        let (writer, _reader) = duplex(4);
+        writers.lock().unwrap().push(writer);
+        let mut guard = handle.pipe_with_reconnect(
+            &"channel1",
+            connect,
+            LinesCodec::new,
+            ReconnectPolicy::default(),
+        );
+        let mut events = guard.events();
+        handle
+            .clone_send("Too long for the link".to_string(), &"channel1")
+            .unwrap();
+        assert_eq!(events.recv().await.unwrap(), ConnectionEvent::Connected);
+        tokio::time::advance(Duration::from_secs(60)).await;
+        guard.stop();
+        assert_eq!(guard.join().await.unwrap(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reconnect_replay() {
+        let (lines, dropped) = test_reconnect(OnReconnect::Replay).await;
+        assert_eq!(lines, ["b", "c", "d"]);
+        assert_eq!(dropped, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reconnect_drop() {
+        let (lines, dropped) = test_reconnect(OnReconnect::Drop).await;
+        assert_eq!(lines, ["d"]);
+        assert_eq!(dropped, 3);
+    }
+}
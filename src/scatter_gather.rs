@@ -0,0 +1,372 @@
+use std::hash::Hash;
+use tokio::{
+    sync::oneshot,
+    time::{timeout_at, Duration, Instant},
+};
+
+use crate::{
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::{ChannelState, NotifierHub, SmartChannelId},
+    writing_handler::WritingHandler,
+};
+
+/// The reply channel of a subscriber to a request sent by `scatter`.
+/// Dropping it without calling `reply` means the subscriber does not answer.
+#[derive(Debug)]
+pub struct Reply<R> {
+    sender: oneshot::Sender<R>,
+}
+
+impl<R> Reply<R> {
+    /// Sends the response of the subscriber.
+    pub fn reply(self, response: R) {
+        let _ = self.sender.send(response);
+    }
+}
+
+/// A request delivered by `scatter`, along with the channel to reply to it.
+#[derive(Debug)]
+pub struct Request<M, R> {
+    pub msg: M,
+    pub reply: Reply<R>,
+}
+
+/// This trait should implement the messages of the channels pinged with `ping_channel`.
+pub trait PingMessage {
+    /// Returns the ping message, carrying the `Reply` the subscriber answers to show it is alive.
+    fn ping(pong: Reply<()>) -> Self;
+}
+
+/// The subscribers of a channel pinged by `ping_channel`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PingReport {
+    /// The subscribers that answered the ping in time.
+    pub alive: Vec<SmartChannelId>,
+    /// The subscribers whose writing failed, that dropped their `Reply` or that were too slow.
+    pub unresponsive: Vec<SmartChannelId>,
+}
+
+/// Gathers the responses of the subscribers reached by `scatter` or `ping`, `W` being the written messages.
+pub struct GatherHandler<W: Send + 'static, R> {
+    writings: WritingHandler<W>,
+    replies: Vec<(SmartChannelId, oneshot::Receiver<R>)>,
+    timeout: Duration,
+}
+
+impl<W: Send + 'static, R> GatherHandler<W, R> {
+    /// Returns the number of subscribers the request has been sent to.
+    pub fn len(&self) -> usize {
+        self.replies.len()
+    }
+
+    /// Returns the subscribers the request has been sent to.
+    pub fn subscribers(&self) -> Vec<SmartChannelId> {
+        self.replies
+            .iter()
+            .map(|(subscriber, _)| *subscriber)
+            .collect()
+    }
+
+    /// Returns true if the channel had no subscriber, so there is no response to wait for.
+    pub fn is_empty(&self) -> bool {
+        self.replies.is_empty()
+    }
+
+    /// Resolves once every subscriber responded, or at the timeout, and returns the responses along with
+    /// their subscriber, in the order of the subscribers of the channel. The subscribers whose writing failed,
+    /// that dropped their `Reply` or that were too slow are missing.
+    pub async fn wait(self) -> Vec<(SmartChannelId, R)> {
+        let deadline = Instant::now() + self.timeout;
+        // A failed writing drops its `Reply`, so the outcome of the writings is read from the replies
+        let _ = timeout_at(deadline, self.writings.wait(None)).await;
+        let mut responses = Vec::with_capacity(self.replies.len());
+        for (subscriber, reply) in self.replies {
+            if let Ok(Ok(response)) = timeout_at(deadline, reply).await {
+                responses.push((subscriber, response));
+            }
+        }
+        responses
+    }
+}
+
+impl<M, R, ChannelId> NotifierHub<Request<M, R>, ChannelId>
+where
+    M: Clone + Send + 'static,
+    R: Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Sends a clone of the request to each subscriber of the channel along with its own `Reply`,
+    /// so the returned handler gathers their responses until the timeout, for polling workers or health checks.
+    /// Fails with `NotifierError::NoSubscriber`, handing the request back, if the channel has no subscriber to respond.
+    /// A protected channel or an exceeded rate limit refuses the request, the error handing back its message
+    /// as no `Reply` was made for it.
+    pub fn scatter(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<GatherHandler<Request<M, R>, R>, NotifierError<M, ChannelId>> {
+        let (writings, replies) = self.publish_with_reply(id, msg, |sender, msg| {
+            let (reply, replied) = oneshot::channel();
+            let msg = Request {
+                msg: msg.clone(),
+                reply: Reply { sender: reply },
+            };
+            (msg, (*sender.id(), replied))
+        })?;
+        Ok(GatherHandler {
+            writings,
+            replies,
+            timeout,
+        })
+    }
+}
+
+impl<M, R, ChannelId> HubHandle<Request<M, R>, ChannelId>
+where
+    M: Clone + Send + 'static,
+    R: Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `NotifierHub::scatter`.
+    pub fn scatter(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<GatherHandler<Request<M, R>, R>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.scatter(msg, id, timeout))
+    }
+
+    /// Broadcasts the request on the channel and gathers the responses of its subscribers until the timeout,
+    /// see `NotifierHub::scatter`. The hub is not locked while the responses are awaited.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{notifier::NotifierHub, scatter_gather::Request};
+    /// use tokio::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::<Request<&str, usize>, _>::new().into_handle();
+    /// for load in [3, 7] {
+    ///     let mut worker = handle.subscribe(&"workers", 10);
+    ///     tokio::spawn(async move {
+    ///         while let Some(Request { msg, reply }) = worker.recv().await {
+    ///             assert_eq!(msg, "load?");
+    ///             reply.reply(load);
+    ///         }
+    ///     });
+    /// }
+    ///
+    /// let responses = handle
+    ///     .scatter_gather("load?", &"workers", Duration::from_secs(1))
+    ///     .await
+    ///     .unwrap();
+    /// let loads: Vec<_> = responses.into_iter().map(|(_, load)| load).collect();
+    /// assert_eq!(loads, vec![3, 7]);
+    /// # }
+    /// ```
+    pub async fn scatter_gather(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<Vec<(SmartChannelId, R)>, NotifierError<M, ChannelId>> {
+        Ok(self.scatter(msg, id, timeout)?.wait().await)
+    }
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: PingMessage + Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Sends the ping message of `PingMessage` to each subscriber of the channel, so the returned handler
+    /// gathers the subscribers answering it until the timeout. As a message of the hub itself,
+    /// the ping skips the protection and the rate limits of the channel.
+    pub fn ping(
+        &self,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<GatherHandler<M, ()>, NotifierError<M, ChannelId>> {
+        let id = self.aliases.get(id).unwrap_or(id);
+        if self.channel_state(id) == ChannelState::Uninitialised {
+            return Err(NotifierError::ChannelUninitialized(id.clone()));
+        }
+        let mut replies = Vec::new();
+        let writings = self
+            .publish_handler(Some(id))
+            .writing_each(self.senders_of(id), |sender| {
+                let (reply, replied) = oneshot::channel();
+                replies.push((*sender.id(), replied));
+                M::ping(Reply { sender: reply })
+            });
+        Ok(GatherHandler {
+            // The outcome of the writings is read from the replies, so their channel is not kept
+            writings: writings.split_channel().0,
+            replies,
+            timeout,
+        })
+    }
+}
+
+impl<M, ChannelId> HubHandle<M, ChannelId>
+where
+    M: PingMessage + Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Pings the subscribers of the channel and reports which ones answered before the timeout,
+    /// for the liveness dashboards, see `NotifierHub::ping`. The hub is not locked while the answers are awaited.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{
+    ///     notifier::NotifierHub,
+    ///     scatter_gather::{PingMessage, Reply},
+    /// };
+    /// use tokio::time::Duration;
+    ///
+    /// #[derive(Debug)]
+    /// enum Job {
+    ///     Run(u32),
+    ///     Ping(Reply<()>),
+    /// }
+    ///
+    /// impl PingMessage for Job {
+    ///     fn ping(pong: Reply<()>) -> Self {
+    ///         Job::Ping(pong)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// let mut worker = handle.subscribe(&"jobs", 10);
+    /// let _stuck = handle.subscribe(&"jobs", 10);
+    /// tokio::spawn(async move {
+    ///     while let Some(job) = worker.recv().await {
+    ///         match job {
+    ///             Job::Run(job) => println!("Running {job}"),
+    ///             Job::Ping(pong) => pong.reply(()),
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// let report = handle.ping_channel(&"jobs", Duration::from_millis(100)).await.unwrap();
+    /// assert_eq!((report.alive.len(), report.unresponsive.len()), (1, 1));
+    /// # }
+    /// ```
+    pub async fn ping_channel(
+        &self,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<PingReport, NotifierError<M, ChannelId>> {
+        let handler = self.with(|hub| hub.ping(id, timeout))?;
+        let mut unresponsive = handler.subscribers();
+        let alive: Vec<_> = handler
+            .wait()
+            .await
+            .into_iter()
+            .map(|(subscriber, ())| subscriber)
+            .collect();
+        unresponsive.retain(|subscriber| !alive.contains(subscriber));
+        Ok(PingReport {
+            alive,
+            unresponsive,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scatter_gather() {
+        let handle = NotifierHub::<Request<u32, u32>, &'static str>::new().into_handle();
+        let mut replying = handle.subscribe(&"channel1", 10);
+        let mut dropping = handle.subscribe(&"channel1", 10);
+        let mut slow = handle.subscribe(&"channel1", 10);
+        tokio::spawn(async move {
+            let Request { msg, reply } = replying.recv().await.unwrap();
+            reply.reply(msg * 2);
+        });
+        tokio::spawn(async move {
+            drop(dropping.recv().await);
+        });
+        let slow_id = slow.id();
+        tokio::spawn(async move {
+            let Request { reply, .. } = slow.recv().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            reply.reply(0);
+        });
+
+        let start = Instant::now();
+        let responses = handle
+            .scatter_gather(21, &"channel1", Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].1, 42);
+        assert_ne!(responses[0].0, slow_id);
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert!(matches!(
+            handle
+                .scatter_gather(1, &"channel2", Duration::from_secs(1))
+                .await,
+            Err(NotifierError::ChannelUninitialized("channel2"))
+        ));
+        handle.set_park_buffer(&"channel2", Some(1));
+        assert!(matches!(
+            handle
+                .scatter_gather(1, &"channel2", Duration::from_secs(1))
+                .await,
+            Err(NotifierError::NoSubscriber {
+                id: "channel2",
+                msg: 1
+            })
+        ));
+    }
+
+    #[derive(Debug)]
+    enum Probe {
+        Data,
+        Ping(Reply<()>),
+    }
+
+    impl PingMessage for Probe {
+        fn ping(pong: Reply<()>) -> Self {
+            Probe::Ping(pong)
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ping_channel() {
+        let handle = NotifierHub::<Probe, &'static str>::new().into_handle();
+        let mut alive = handle.subscribe(&"channel1", 10);
+        let stuck = handle.subscribe(&"channel1", 10);
+        let alive_id = alive.id();
+        tokio::spawn(async move {
+            while let Some(probe) = alive.recv().await {
+                if let Probe::Ping(pong) = probe {
+                    pong.reply(());
+                }
+            }
+        });
+        handle.with(|hub| {
+            hub.grant_publish(&"channel1");
+            hub.senders_of(&"channel1")[0]
+                .try_send(Probe::Data)
+                .unwrap();
+        });
+
+        let report = handle
+            .ping_channel(&"channel1", Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(report.alive, vec![alive_id]);
+        assert_eq!(report.unresponsive, vec![stuck.id()]);
+    }
+}
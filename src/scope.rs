@@ -0,0 +1,251 @@
+use crate::{
+    closable_trait::ClosableMessage,
+    error::NotifierError,
+    notifier::{ChannelState, CreationWaiter, DestructionWaiter, MessageReceiver, NotifierHub},
+    notifier_trait::Notifier,
+    writing_handler::WritingHandler,
+};
+
+/// The separator between the prefix of a scope and the id of a channel.
+pub const SCOPE_SEPARATOR: char = '/';
+
+/// A view of a hub whose channel ids are namespaced: the channel `id` of the view
+/// is the channel `prefix/id` of the hub. Obtained with `NotifierHub::scoped`.
+///
+/// The errors returned by the view contain the full ids of the hub.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::notifier::NotifierHub;
+///
+/// let mut hub: NotifierHub<&str, String> = NotifierHub::new();
+/// let mut plugin = hub.scoped("plugin");
+/// let mut receiver = plugin.subscribe("events", 10);
+/// drop(plugin);
+///
+/// hub.clone_send("Hello", &"plugin/events".to_string()).unwrap();
+/// assert_eq!(receiver.try_recv().unwrap(), "Hello");
+/// ```
+pub struct ScopedHub<'a, M> {
+    hub: &'a mut NotifierHub<M, String>,
+    prefix: String,
+}
+
+impl<M> NotifierHub<M, String> {
+    /// Returns a view of the hub whose channel ids are prefixed by `prefix/`.
+    pub fn scoped(&mut self, prefix: &str) -> ScopedHub<'_, M> {
+        ScopedHub {
+            hub: self,
+            prefix: prefix.to_string(),
+        }
+    }
+}
+
+impl<'a, M> ScopedHub<'a, M> {
+    /// Returns the id of the channel in the hub.
+    pub fn full_id(&self, id: &str) -> String {
+        format!("{}{SCOPE_SEPARATOR}{id}", self.prefix)
+    }
+
+    /// Returns the id of the channel in the view, if the channel is part of it.
+    pub fn local_id<'b>(&self, full_id: &'b str) -> Option<&'b str> {
+        full_id
+            .strip_prefix(self.prefix.as_str())?
+            .strip_prefix(SCOPE_SEPARATOR)
+    }
+
+    /// Returns the prefix of the view.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns a view nested in this one, whose ids are prefixed by `prefix/` in this view.
+    pub fn scoped(&mut self, prefix: &str) -> ScopedHub<'_, M> {
+        ScopedHub {
+            prefix: self.full_id(prefix),
+            hub: self.hub,
+        }
+    }
+
+    /// Returns the underlying hub.
+    pub fn hub(&mut self) -> &mut NotifierHub<M, String> {
+        self.hub
+    }
+
+    /// Returns the ids of the channels of the view.
+    pub fn get_channels(&self) -> Vec<String> {
+        self.hub
+            .senders
+            .keys()
+            .filter_map(|id| self.local_id(id))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// See `NotifierHub::channel_state`.
+    pub fn channel_state(&self, id: &str) -> ChannelState {
+        self.hub.channel_state(&self.full_id(id))
+    }
+
+    /// See `NotifierHub::channel_number_subscriber`.
+    pub fn channel_number_subscriber(&self, id: &str) -> usize {
+        self.hub.channel_number_subscriber(&self.full_id(id))
+    }
+
+    /// See `NotifierHub::subscribe`.
+    pub fn subscribe(&mut self, id: &str, channel_size: usize) -> MessageReceiver<M> {
+        let id = self.full_id(id);
+        self.hub.subscribe(&id, channel_size)
+    }
+
+    /// See `NotifierHub::get_creation_waiter`.
+    pub fn get_creation_waiter(&mut self, id: &str) -> CreationWaiter {
+        let id = self.full_id(id);
+        self.hub.get_creation_waiter(&id)
+    }
+
+    /// See `NotifierHub::get_destruction_waiter`.
+    pub fn get_destruction_waiter(&mut self, id: &str) -> DestructionWaiter<M> {
+        let id = self.full_id(id);
+        self.hub.get_destruction_waiter(&id)
+    }
+}
+
+impl<'a, M: Clone> ScopedHub<'a, M> {
+    /// See `NotifierHub::subscribe_multiple`.
+    pub fn subscribe_multiple(&mut self, ids: &[&str], channel_size: usize) -> MessageReceiver<M> {
+        let ids: Vec<_> = ids.iter().map(|id| self.full_id(id)).collect();
+        self.hub.subscribe_multiple(&ids, channel_size)
+    }
+}
+
+impl<'a, M: Send + Clone + 'static> ScopedHub<'a, M> {
+    /// See `NotifierHub::unsubscribe`.
+    pub fn unsubscribe(
+        &mut self,
+        id: &str,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<ChannelState, NotifierError<M, String>> {
+        let id = self.full_id(id);
+        self.hub.unsubscribe(&id, receiver)
+    }
+
+    /// See `NotifierHub::clone_send`.
+    pub fn clone_send(
+        &self,
+        msg: M,
+        id: &str,
+    ) -> Result<WritingHandler<M, String>, NotifierError<M, String>> {
+        self.hub.clone_send(msg, &self.full_id(id))
+    }
+
+    /// Broadcasts the cloned message to all the channels of the view, the other channels of the hub are not affected.
+    pub fn broadcast_clone(&self, msg: M) -> WritingHandler<M, String> {
+        let senders = self
+            .hub
+            .senders
+            .iter()
+            .filter(|(id, _)| self.local_id(id).is_some())
+            .flat_map(|(_, senders)| senders);
+        self.hub
+            .publish_handler(None)
+            .cloning_broadcast(msg, senders)
+    }
+}
+
+impl<'a, M> Notifier<M, String> for ScopedHub<'a, M>
+where
+    M: Send + Clone + 'static,
+{
+    fn subscribe(&mut self, id: &String, channel_size: usize) -> MessageReceiver<M> {
+        ScopedHub::subscribe(self, id, channel_size)
+    }
+
+    fn subscribe_multiple(&mut self, ids: &[String], channel_size: usize) -> MessageReceiver<M> {
+        let ids: Vec<_> = ids.iter().map(String::as_str).collect();
+        ScopedHub::subscribe_multiple(self, &ids, channel_size)
+    }
+
+    fn unsubscribe(
+        &mut self,
+        id: &String,
+        receiver: &MessageReceiver<M>,
+    ) -> Result<ChannelState, NotifierError<M, String>> {
+        ScopedHub::unsubscribe(self, id, receiver)
+    }
+
+    fn clone_send(
+        &self,
+        msg: M,
+        id: &String,
+    ) -> Result<WritingHandler<M, String>, NotifierError<M, String>> {
+        ScopedHub::clone_send(self, msg, id)
+    }
+
+    fn broadcast_clone(&self, msg: M) -> WritingHandler<M, String> {
+        ScopedHub::broadcast_clone(self, msg)
+    }
+
+    fn shutdown_clone(
+        &mut self,
+        id: &String,
+    ) -> Result<WritingHandler<M, String>, NotifierError<M, String>>
+    where
+        M: ClosableMessage,
+    {
+        let id = self.full_id(id);
+        self.hub.shutdown_clone(&id)
+    }
+
+    fn channel_state(&self, id: &String) -> ChannelState {
+        ScopedHub::channel_state(self, id)
+    }
+
+    fn get_creation_waiter(&mut self, id: &String) -> CreationWaiter {
+        ScopedHub::get_creation_waiter(self, id)
+    }
+
+    fn get_destruction_waiter(&mut self, id: &String) -> DestructionWaiter<M> {
+        ScopedHub::get_destruction_waiter(self, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scopes_do_not_collide() {
+        let mut hub: NotifierHub<u32, String> = NotifierHub::new();
+        let mut first = hub.scoped("first").subscribe("events", 10);
+        let mut second = hub.scoped("second").subscribe("events", 10);
+        let mut global = hub.subscribe(&"events".to_string(), 10);
+
+        hub.scoped("first").clone_send(1, "events").unwrap();
+        assert_eq!(first.try_recv().unwrap(), 1);
+        assert!(second.try_recv().is_err());
+
+        hub.scoped("second").broadcast_clone(2);
+        assert_eq!(second.try_recv().unwrap(), 2);
+        assert!(first.try_recv().is_err());
+        assert!(global.try_recv().is_err());
+
+        assert_eq!(hub.scoped("first").get_channels(), vec!["events"]);
+        assert_eq!(hub.scoped("fir").get_channels(), Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn test_nested_scope() {
+        let mut hub: NotifierHub<u32, String> = NotifierHub::new();
+        let mut app = hub.scoped("app");
+        let mut receiver = app.scoped("db").subscribe("queries", 10);
+        assert_eq!(app.scoped("db").prefix(), "app/db");
+
+        app.clone_send(1, "db/queries").unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), 1);
+        assert_eq!(
+            hub.channel_state(&"app/db/queries".to_string()),
+            ChannelState::Running
+        );
+    }
+}
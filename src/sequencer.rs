@@ -0,0 +1,323 @@
+use std::{future::Future, hash::Hash, pin::Pin};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    handle::HubHandle,
+    notifier::{MessageSender, NotifierHub},
+    writing_handler::WritingHandler,
+};
+
+type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The ordering task of one or several channels, performing their publishes one after the other.
+#[derive(Clone)]
+pub(crate) struct Sequencer {
+    queue: mpsc::UnboundedSender<Job>,
+}
+
+impl Sequencer {
+    /// Spawns the ordering task, it stops once the sequencer is dropped and the queued publishes are done.
+    fn spawn() -> Self {
+        let (queue, mut jobs) = mpsc::unbounded_channel::<Job>();
+        tokio::spawn(async move {
+            while let Some(job) = jobs.recv().await {
+                job.await;
+            }
+        });
+        Self { queue }
+    }
+
+    /// Returns true if both sequencers are the same ordering task.
+    pub(crate) fn same_task(&self, other: &Sequencer) -> bool {
+        self.queue.same_channel(&other.queue)
+    }
+
+    /// Queues the publish, the returned handler following its writings.
+    /// The writings of a publish are all over before the next publish starts.
+    /// The job outlives the borrow of the hub, so it is the only publish holding its own copy of the senders.
+    pub(crate) fn publish<'a, M: Clone + Send + 'static, ChannelId>(
+        &self,
+        handler: WritingHandler<M, ChannelId>,
+        msg: M,
+        senders: impl IntoIterator<Item = &'a MessageSender<M>>,
+    ) -> WritingHandler<M, ChannelId> {
+        // The job only holds the handler, the failures are bound to the channel by the returned one
+        let (handler, channel) = handler.split_channel();
+        let senders: Vec<_> = senders.into_iter().cloned().collect();
+        let subscribers = senders.iter().map(|s| *s.id()).collect();
+        let deadline = handler.deadline();
+        let (done, outcome) = oneshot::channel();
+        let job = Box::pin(async move {
+            let _ = done.send(handler.cloning_broadcast(msg, &senders).wait(None).await);
+        });
+        // If the task is gone the job is dropped, and the writings are reported as aborted
+        let _ = self.queue.send(job);
+        WritingHandler::sequenced(outcome, subscribers, deadline).on_channel(channel)
+    }
+
+    /// Same as `publish` with the message built for each subscriber, the messages being built right away.
+    pub(crate) fn publish_each<M: Send + 'static, ChannelId>(
+        &self,
+        handler: WritingHandler<M, ChannelId>,
+        senders: &[MessageSender<M>],
+        mut msg: impl FnMut(&MessageSender<M>) -> M,
+    ) -> WritingHandler<M, ChannelId> {
+        let (handler, channel) = handler.split_channel();
+        let (senders, messages): (Vec<_>, Vec<_>) = senders
+            .iter()
+            .map(|sender| (share(sender), msg(sender)))
+            .unzip();
+        let subscribers = senders.iter().map(|s| *s.id()).collect();
+        let deadline = handler.deadline();
+        let (done, outcome) = oneshot::channel();
+        let job = Box::pin(async move {
+            let writings = handler.writing_to(senders.iter().zip(messages));
+            let _ = done.send(writings.wait(None).await);
+        });
+        let _ = self.queue.send(job);
+        WritingHandler::sequenced(outcome, subscribers, deadline).on_channel(channel)
+    }
+}
+
+/// Returns a sender to the same subscriber, which unlike `Clone` does not require the messages to be `Clone`.
+fn share<M>(sender: &MessageSender<M>) -> MessageSender<M> {
+    let (_, unused) = mpsc::channel(1);
+    smart_channel::bind((**sender).clone(), unused, *sender.id()).0
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Funnels all the publishes on the channel through an ordering task owned by the hub, so every subscriber
+    /// observes the same sequence even when several tasks publish concurrently. A publish is only written
+    /// once the writings of the previous one are over, so a subscriber that does not read its messages holds
+    /// up the whole channel until the send timeout of the hub, if any.
+    /// The broadcasts over several channels and `send_to_subscriber` are not sequenced.
+    ///
+    /// Must be called within a tokio runtime, as the ordering task is spawned right away.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = NotifierHub::new().into_handle();
+    /// handle.enable_sequencer(&"ledger");
+    /// let mut receiver = handle.subscribe(&"ledger", 1);
+    ///
+    /// let publishers: Vec<_> = (0..4)
+    ///     .map(|i| {
+    ///         let handle = handle.clone();
+    ///         tokio::spawn(async move { handle.clone_send(i, &"ledger").unwrap().wait(None).await })
+    ///     })
+    ///     .collect();
+    /// for _ in 0..4 {
+    ///     receiver.recv().await.unwrap();
+    /// }
+    /// for publisher in publishers {
+    ///     assert!(publisher.await.unwrap().all_ok());
+    /// }
+    /// # }
+    /// ```
+    pub fn enable_sequencer(&mut self, id: &ChannelId)
+    where
+        ChannelId: Clone,
+    {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        self.sequencers.entry(id).or_insert_with(Sequencer::spawn);
+    }
+
+    /// Funnels the publishes on all the given channels through a single ordering task, so the publishes of a task
+    /// on several of these channels are received in the same relative order by any subscriber following more
+    /// than one of them. Each publish waits for the writings of the previous one on any of the channels.
+    /// The channels that were already sequenced get the new ordering task, their queued publishes are still performed.
+    ///
+    /// Must be called within a tokio runtime, as the ordering task is spawned right away.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// hub.enable_shared_sequencer(&["orders", "payments"]);
+    /// let mut receiver = hub.subscribe_multiple(&["orders", "payments"], 1);
+    ///
+    /// let order = hub.clone_send("order 1", &"orders").unwrap();
+    /// let payment = hub.clone_send("payment 1", &"payments").unwrap(); // Waits for the order to be read
+    /// assert_eq!(receiver.recv().await.unwrap(), "order 1");
+    /// assert_eq!(receiver.recv().await.unwrap(), "payment 1");
+    /// order.wait(None).await.into_result().unwrap();
+    /// payment.wait(None).await.into_result().unwrap();
+    /// # }
+    /// ```
+    pub fn enable_shared_sequencer(&mut self, ids: &[ChannelId])
+    where
+        ChannelId: Clone,
+    {
+        let sequencer = Sequencer::spawn();
+        for id in ids {
+            let id = self.aliases.get(id).unwrap_or(id).clone();
+            self.sequencers.insert(id, sequencer.clone());
+        }
+    }
+
+    /// Stops sequencing the publishes on the channel. The publishes already queued are still performed in order.
+    /// Returns false if the channel was not sequenced.
+    pub fn disable_sequencer(&mut self, id: &ChannelId) -> bool {
+        let id = self.aliases.get(id).unwrap_or(id);
+        self.sequencers.remove(id).is_some()
+    }
+
+    /// Returns true if the publishes on the channel go through a sequencer.
+    pub fn is_sequenced(&self, id: &ChannelId) -> bool {
+        self.sequencers
+            .contains_key(self.aliases.get(id).unwrap_or(id))
+    }
+}
+
+impl<M, ChannelId: Eq + Hash> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::enable_sequencer`.
+    pub fn enable_sequencer(&self, id: &ChannelId)
+    where
+        ChannelId: Clone,
+    {
+        self.with(|hub| hub.enable_sequencer(id))
+    }
+
+    /// See `NotifierHub::enable_shared_sequencer`.
+    pub fn enable_shared_sequencer(&self, ids: &[ChannelId])
+    where
+        ChannelId: Clone,
+    {
+        self.with(|hub| hub.enable_shared_sequencer(ids))
+    }
+
+    /// See `NotifierHub::disable_sequencer`.
+    pub fn disable_sequencer(&self, id: &ChannelId) -> bool {
+        self.with(|hub| hub.disable_sequencer(id))
+    }
+
+    /// See `NotifierHub::is_sequenced`.
+    pub fn is_sequenced(&self, id: &ChannelId) -> bool {
+        self.with(|hub| hub.is_sequenced(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::{NotifierError, SendFailure},
+        error_hook::FailureKind,
+        notifier::MessageReceiver,
+    };
+    use tokio::time::Duration;
+
+    async fn read(mut receiver: MessageReceiver<u32>, n: usize) -> Vec<u32> {
+        let mut received = Vec::with_capacity(n);
+        for _ in 0..n {
+            received.push(receiver.recv().await.unwrap());
+            tokio::task::yield_now().await;
+        }
+        received
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_same_order_for_all_subscribers() {
+        let handle = NotifierHub::new().into_handle();
+        handle.enable_sequencer(&"channel1");
+        assert!(handle.is_sequenced(&"channel1"));
+        let slow = tokio::spawn(read(handle.subscribe(&"channel1", 1), 200));
+        let fast = tokio::spawn(read(handle.subscribe(&"channel1", 200), 200));
+
+        let publishers: Vec<_> = (0..4)
+            .map(|p| {
+                let handle = handle.clone();
+                tokio::spawn(async move {
+                    for i in 0..50 {
+                        let handler = handle.clone_send(p * 100 + i, &"channel1").unwrap();
+                        assert_eq!(handler.wait(None).await.into_result().unwrap(), 2);
+                    }
+                })
+            })
+            .collect();
+        for publisher in publishers {
+            publisher.await.unwrap();
+        }
+        assert_eq!(slow.await.unwrap(), fast.await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_cross_channel_order() {
+        let handle = NotifierHub::new().into_handle();
+        handle.enable_shared_sequencer(&["a", "b"]);
+        let both = tokio::spawn(read(handle.subscribe_multiple(&["a", "b"], 1), 100));
+
+        let publisher = handle.clone();
+        let published = tokio::spawn(async move {
+            let mut handlers = Vec::new();
+            for i in 0..100 {
+                let channel = if i % 3 == 0 { "a" } else { "b" };
+                handlers.push(publisher.clone_send(i, &channel).unwrap());
+            }
+            for handler in handlers {
+                handler.wait(None).await.into_result().unwrap();
+            }
+        });
+        assert_eq!(both.await.unwrap(), (0..100).collect::<Vec<_>>());
+        published.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sequenced_send_paths() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.enable_sequencer(&"channel1");
+        let mut receiver = hub.subscribe(&"channel1", 1);
+
+        let handlers = [
+            hub.clone_send(1, &"channel1").unwrap(),
+            hub.send_with(&"channel1", || 2).unwrap(),
+            hub.send_to_subscriber_on(3, &receiver, &"channel1")
+                .unwrap(),
+            hub.send_direct(4, &receiver.id()).unwrap(),
+        ];
+        for handler in &handlers {
+            assert_eq!(handler.pending(), 1);
+        }
+        for i in 1..=4 {
+            assert_eq!(receiver.recv().await, Some(i));
+        }
+        for handler in handlers {
+            assert!(handler.wait(None).await.all_ok());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_sequenced_timeout() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        hub.enable_sequencer(&"channel1");
+        let mut wedged = hub.subscribe(&"channel1", 1);
+
+        hub.clone_send(1, &"channel1").unwrap();
+        let handler = hub.clone_send(2, &"channel1").unwrap();
+        assert_eq!(handler.pending(), 1);
+        match handler
+            .wait(Some(Duration::from_millis(10)))
+            .await
+            .into_result()
+        {
+            Err(NotifierError::WritingSendError(errors)) => assert!(matches!(
+                errors[..],
+                [SendFailure { subscriber: Some(id), kind: FailureKind::Timeout, .. }] if id == wedged.id()
+            )),
+            _ => panic!("The writing should have timed out"),
+        }
+
+        // The sequencer still delivers the message
+        assert_eq!(wedged.recv().await.unwrap(), 1);
+        assert_eq!(wedged.recv().await.unwrap(), 2);
+        assert!(hub.disable_sequencer(&"channel1"));
+        assert!(!hub.is_sequenced(&"channel1"));
+    }
+}
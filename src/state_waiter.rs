@@ -0,0 +1,231 @@
+use smart_channel::channel;
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    handle::HubHandle,
+    notifier::{
+        ChannelState, NotifierHub, Receiver, Sender, SmartChannelId, NOTIFIER_CHANNEL_SIZE,
+    },
+};
+
+/// Type alias for the receivers returned by the get_state_waiter method of the Hub
+pub type StateWaiter = Receiver<ChannelState, SmartChannelId>;
+
+/// A transition of the state of a channel, received by the state change waiters.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StateChanged<ChannelId> {
+    /// The channel whose state changed.
+    pub id: ChannelId,
+    /// The state of the channel before the transition.
+    pub from: ChannelState,
+    /// The state of the channel after the transition.
+    pub to: ChannelState,
+}
+
+/// Type alias for the receivers returned by the get_state_change_waiter method of the Hub
+pub type StateChangeWaiter<ChannelId> = Receiver<StateChanged<ChannelId>, SmartChannelId>;
+
+/// A state waiter, with the last state it has been sent.
+pub(crate) struct StateSender {
+    pub(crate) sender: Sender<ChannelState, SmartChannelId>,
+    last: ChannelState,
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Returns a waiter receiving the current state of the channel, then each of its transitions,
+    /// so a task can await the channel becoming `Over` without polling `channel_state`.
+    /// A waiter that does not read its states skips the transitions made while its buffer is full,
+    /// it is then sent the state of the channel at the next transition.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::{ChannelState, NotifierHub};
+    ///
+    /// let mut hub = NotifierHub::<u32, &str>::new();
+    /// let mut states = hub.get_state_waiter(&"jobs");
+    /// let receiver = hub.subscribe(&"jobs", 10);
+    /// hub.unsubscribe(&"jobs", &receiver).unwrap();
+    ///
+    /// assert_eq!(states.try_recv().unwrap(), ChannelState::Uninitialised);
+    /// assert_eq!(states.try_recv().unwrap(), ChannelState::Running);
+    /// assert_eq!(states.try_recv().unwrap(), ChannelState::Over);
+    /// ```
+    pub fn get_state_waiter(&mut self, id: &ChannelId) -> StateWaiter
+    where
+        ChannelId: Clone,
+    {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, self.get_new_id());
+        let last = self.channel_state(&id);
+        let _ = sender.try_send(last);
+        self.state_senders
+            .entry(id)
+            .or_default()
+            .push(StateSender { sender, last });
+        receiver
+    }
+
+    /// Returns a waiter receiving each transition of the state of every channel of the hub, including the
+    /// ones created later: the first subscriber, the last one removed, the shutdowns and the garbage collections.
+    /// As the state waiters, a waiter that does not read its transitions skips the ones made while its buffer is full.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{
+    ///     notifier::{ChannelState, NotifierHub},
+    ///     state_waiter::StateChanged,
+    /// };
+    ///
+    /// let mut hub = NotifierHub::<u32, &str>::new();
+    /// let mut changes = hub.get_state_change_waiter();
+    /// let receiver = hub.subscribe(&"jobs", 10);
+    /// hub.unsubscribe(&"jobs", &receiver).unwrap();
+    ///
+    /// assert_eq!(
+    ///     changes.try_recv().unwrap(),
+    ///     StateChanged { id: "jobs", from: ChannelState::Uninitialised, to: ChannelState::Running }
+    /// );
+    /// assert_eq!(changes.try_recv().unwrap().to, ChannelState::Over);
+    /// assert_eq!(hub.state_change_count(), 2);
+    /// ```
+    pub fn get_state_change_waiter(&mut self) -> StateChangeWaiter<ChannelId> {
+        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, self.get_new_id());
+        self.state_change_senders.push(sender);
+        receiver
+    }
+
+    /// Returns the number of transitions of the state of the channels since the creation of the hub.
+    pub fn state_change_count(&self) -> u64 {
+        self.state_changes
+    }
+
+    /// Sends the state of the channel to its state waiters, if it changed since their last state,
+    /// and to the state change waiters if it changed since the last notification.
+    /// The waiters whose receiver has been dropped are removed.
+    pub(crate) fn notify_state(&mut self, id: &ChannelId)
+    where
+        ChannelId: Clone,
+    {
+        let state = self.channel_state(id);
+        let from = match state {
+            ChannelState::Uninitialised => self.observed_states.remove(id),
+            _ => self.observed_states.insert(id.clone(), state),
+        };
+        let from = from.unwrap_or(ChannelState::Uninitialised);
+        if from != state {
+            self.state_changes += 1;
+            self.state_change_senders
+                .retain(|sender| !sender.is_closed());
+            for sender in &self.state_change_senders {
+                let _ = sender.try_send(StateChanged {
+                    id: id.clone(),
+                    from,
+                    to: state,
+                });
+            }
+        }
+        send_state(&mut self.state_senders, id, state);
+    }
+}
+
+/// Sends the state to the waiters of the channel whose last state differs, and removes the closed ones.
+pub(crate) fn send_state<ChannelId: Eq + Hash>(
+    state_senders: &mut HashMap<ChannelId, Vec<StateSender>>,
+    id: &ChannelId,
+    state: ChannelState,
+) {
+    let Some(waiters) = state_senders.get_mut(id) else {
+        return;
+    };
+    waiters.retain(|w| !w.sender.is_closed());
+    for waiter in waiters.iter_mut().filter(|w| w.last != state) {
+        if waiter.sender.try_send(state).is_ok() {
+            waiter.last = state;
+        }
+    }
+    if waiters.is_empty() {
+        state_senders.remove(id);
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::get_state_waiter`.
+    pub fn get_state_waiter(&self, id: &ChannelId) -> StateWaiter {
+        self.with(|hub| hub.get_state_waiter(id))
+    }
+
+    /// See `NotifierHub::get_state_change_waiter`.
+    pub fn get_state_change_waiter(&self) -> StateChangeWaiter<ChannelId> {
+        self.with(|hub| hub.get_state_change_waiter())
+    }
+
+    /// See `NotifierHub::state_change_count`.
+    pub fn state_change_count(&self) -> u64 {
+        self.with(|hub| hub.state_change_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_state_waiter() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let receiver = hub.subscribe(&"channel1", 10);
+        let mut states = hub.get_state_waiter(&"channel1");
+        assert_eq!(states.try_recv().unwrap(), ChannelState::Running);
+
+        let _other = hub.subscribe(&"channel1", 10);
+        assert!(states.try_recv().is_err());
+
+        drop(receiver);
+        hub.clean_channel(&"channel1");
+        assert!(states.try_recv().is_err());
+        hub.shutdown_with_factory(&"channel1", |_| 0).unwrap();
+        assert_eq!(states.recv().await.unwrap(), ChannelState::Uninitialised);
+
+        let _receiver = hub.subscribe(&"channel1", 10);
+        assert_eq!(states.recv().await.unwrap(), ChannelState::Running);
+    }
+
+    #[tokio::test]
+    async fn test_state_change_waiter() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut changes = hub.get_state_change_waiter();
+        let receiver1 = hub.subscribe(&"channel1", 10);
+        let _receiver2 = hub.subscribe(&"channel2", 10);
+        let other = hub.subscribe(&"channel1", 10);
+        drop(receiver1);
+        hub.clean_channel(&"channel1");
+        hub.shutdown_with_factory(&"channel2", |_| 0).unwrap();
+        hub.unsubscribe(&"channel1", &other).unwrap();
+        hub.collect_garbage();
+
+        let expected = [
+            (
+                "channel1",
+                ChannelState::Uninitialised,
+                ChannelState::Running,
+            ),
+            (
+                "channel2",
+                ChannelState::Uninitialised,
+                ChannelState::Running,
+            ),
+            (
+                "channel2",
+                ChannelState::Running,
+                ChannelState::Uninitialised,
+            ),
+            ("channel1", ChannelState::Running, ChannelState::Over),
+            ("channel1", ChannelState::Over, ChannelState::Uninitialised),
+        ];
+        for (id, from, to) in expected {
+            assert_eq!(changes.try_recv().unwrap(), StateChanged { id, from, to });
+        }
+        assert!(changes.try_recv().is_err());
+        assert_eq!(hub.state_change_count(), 5);
+        assert!(hub.observed_states.is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use crate::{
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::{ChannelState, MessageReceiver, NotifierHub},
+    writing_handler::WritingHandler,
+};
+
+/// A hub whose channels are named at runtime, such as the per-entity channels `"jobs/42"`.
+/// The names are kept as `Arc<str>`, so a name already known by the hub is shared instead of allocated again
+/// by the `_str` methods.
+pub type StrHub<M> = NotifierHub<M, Arc<str>>;
+
+impl<M> NotifierHub<M, Arc<str>> {
+    /// Returns the id of the channel named `name`. If the hub already knows the channel, through its subscribers
+    /// or its waiters, its id is shared without allocating. The names the hub does not know are not kept,
+    /// so the per-entity channels don't pile up once they are over.
+    pub fn intern(&self, name: &str) -> Arc<str> {
+        let name = self.aliases.get(name).map_or(name, |target| &**target);
+        self.senders
+            .get_key_value(name)
+            .map(|(id, _)| id)
+            .or_else(|| self.creation_senders.get_key_value(name).map(|(id, _)| id))
+            .or_else(|| {
+                self.destruction_senders
+                    .get_key_value(name)
+                    .map(|(id, _)| id)
+            })
+            .map_or_else(|| Arc::from(name), Arc::clone)
+    }
+
+    /// Same as `subscribe`, the channel being given by its name.
+    pub fn subscribe_str(&mut self, name: &str, channel_size: usize) -> MessageReceiver<M> {
+        let id = self.intern(name);
+        self.subscribe(&id, channel_size)
+    }
+
+    /// Same as `channel_state`, the channel being given by its name.
+    pub fn channel_state_str(&self, name: &str) -> ChannelState {
+        self.channel_state(&self.intern(name))
+    }
+}
+
+impl<M: Send + Clone + 'static> NotifierHub<M, Arc<str>> {
+    /// Same as `clone_send`, the channel being given by its name.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::str_hub::StrHub;
+    ///
+    /// let mut hub = StrHub::new();
+    /// let job = format!("jobs/{}", 42);
+    /// let mut receiver = hub.subscribe_str(&job, 10);
+    ///
+    /// hub.clone_send_str("done", "jobs/42").unwrap();
+    /// assert_eq!(receiver.try_recv().unwrap(), "done");
+    /// ```
+    pub fn clone_send_str(
+        &self,
+        msg: M,
+        name: &str,
+    ) -> Result<WritingHandler<M, Arc<str>>, NotifierError<M, Arc<str>>> {
+        self.clone_send(msg, &self.intern(name))
+    }
+}
+
+impl<M> HubHandle<M, Arc<str>> {
+    /// See `NotifierHub::subscribe_str`.
+    pub fn subscribe_str(&self, name: &str, channel_size: usize) -> MessageReceiver<M> {
+        self.with(|hub| hub.subscribe_str(name, channel_size))
+    }
+}
+
+impl<M: Send + Clone + 'static> HubHandle<M, Arc<str>> {
+    /// See `NotifierHub::clone_send_str`.
+    pub fn clone_send_str(
+        &self,
+        msg: M,
+        name: &str,
+    ) -> Result<WritingHandler<M, Arc<str>>, NotifierError<M, Arc<str>>> {
+        self.with(|hub| hub.clone_send_str(msg, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_interned_names() {
+        let mut hub: StrHub<u32> = StrHub::new();
+        let mut receiver = hub.subscribe_str("jobs/1", 10);
+        let _waiter = hub.get_creation_waiter(&Arc::from("jobs/2"));
+
+        let id = hub.intern("jobs/1");
+        assert!(Arc::ptr_eq(&id, &hub.intern("jobs/1")));
+        assert!(Arc::ptr_eq(&hub.intern("jobs/2"), &hub.intern("jobs/2")));
+        assert!(!Arc::ptr_eq(&hub.intern("jobs/3"), &hub.intern("jobs/3")));
+
+        hub.clone_send_str(1, "jobs/1").unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), 1);
+        assert_eq!(hub.channel_state_str("jobs/1"), ChannelState::Running);
+        assert!(matches!(
+            hub.clone_send_str(2, "jobs/3"),
+            Err(NotifierError::ChannelUninitialized(id)) if &*id == "jobs/3"
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+use tokio::{
+    task::JoinSet,
+    time::{interval, Duration, Instant, MissedTickBehavior},
+};
+
+use crate::{notifier::NotifierHub, writing_handler::DeliveryMode};
+
+/// The load generated by `run`. Each channel gets its own publishers and subscribers.
+#[derive(Clone, Debug)]
+pub struct StressConfig {
+    /// Number of channels.
+    pub channels: usize,
+    /// Number of publishing tasks per channel.
+    pub publishers: usize,
+    /// Number of subscribers per channel.
+    pub subscribers: usize,
+    /// Size of the buffer of each subscriber.
+    pub channel_size: usize,
+    /// Number of messages sent by each publisher.
+    pub messages: usize,
+    /// Messages per second of each publisher, `None` to publish as fast as possible.
+    pub rate: Option<f64>,
+    /// The delivery mode of the hub. In deterministic mode, a message finding a full buffer is dropped.
+    pub delivery_mode: DeliveryMode,
+    /// The send timeout of the hub, the writings still pending after it are dropped.
+    pub send_timeout: Option<Duration>,
+}
+
+impl Default for StressConfig {
+    fn default() -> Self {
+        Self {
+            channels: 1,
+            publishers: 1,
+            subscribers: 1,
+            channel_size: 100,
+            messages: 1000,
+            rate: None,
+            delivery_mode: DeliveryMode::default(),
+            send_timeout: None,
+        }
+    }
+}
+
+/// The statistics of a run.
+#[derive(Clone, Debug, Default)]
+pub struct StressReport {
+    /// Number of publishes.
+    pub published: usize,
+    /// Number of messages handed to a subscriber.
+    pub delivered: usize,
+    /// Number of writings that failed or timed out.
+    pub dropped: usize,
+    /// Number of messages read by the subscribers.
+    pub received: usize,
+    /// Time between the first publish and the last message read.
+    pub elapsed: Duration,
+    /// Time between the publish and the read of each received message, sorted.
+    latencies: Vec<Duration>,
+}
+
+impl StressReport {
+    /// Returns the number of messages read per second.
+    pub fn throughput(&self) -> f64 {
+        match self.elapsed.is_zero() {
+            true => 0.0,
+            false => self.received as f64 / self.elapsed.as_secs_f64(),
+        }
+    }
+
+    /// Returns the latency below which the given fraction of the messages have been read,
+    /// for instance `0.99` for the 99th percentile. `None` if no message has been read.
+    pub fn latency_percentile(&self, fraction: f64) -> Option<Duration> {
+        let last = self.latencies.len().checked_sub(1)?;
+        let rank = (fraction.clamp(0.0, 1.0) * last as f64).round() as usize;
+        Some(self.latencies[rank])
+    }
+
+    /// Returns the mean latency, `None` if no message has been read.
+    pub fn mean_latency(&self) -> Option<Duration> {
+        let n = u32::try_from(self.latencies.len())
+            .ok()
+            .filter(|n| *n > 0)?;
+        Some(self.latencies.iter().sum::<Duration>() / n)
+    }
+
+    /// Returns the highest latency, `None` if no message has been read.
+    pub fn max_latency(&self) -> Option<Duration> {
+        self.latencies.last().copied()
+    }
+}
+
+/// Publishes the configured load on a new hub and waits for the subscribers to read everything.
+/// Each message carries the instant of its publish, so the latency is measured by the subscribers.
+/// Available with the `stress` feature.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::stress::{run, StressConfig};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let report = run(&StressConfig {
+///     channels: 2,
+///     subscribers: 3,
+///     messages: 100,
+///     ..Default::default()
+/// })
+/// .await;
+/// assert_eq!(report.published, 200);
+/// assert_eq!(report.received, 600);
+/// println!("{:.0} msg/s, p99 {:?}", report.throughput(), report.latency_percentile(0.99));
+/// # }
+/// ```
+pub async fn run(config: &StressConfig) -> StressReport {
+    let mut hub: NotifierHub<Instant, usize> = NotifierHub::new();
+    hub.set_delivery_mode(config.delivery_mode);
+    hub.set_send_timeout(config.send_timeout);
+
+    let mut subscribers = JoinSet::new();
+    for channel in 0..config.channels {
+        for _ in 0..config.subscribers {
+            let mut receiver = hub.subscribe(&channel, config.channel_size);
+            subscribers.spawn(async move {
+                let mut latencies = Vec::new();
+                while let Some(sent) = receiver.recv().await {
+                    latencies.push(sent.elapsed());
+                }
+                latencies
+            });
+        }
+    }
+
+    let handle = hub.into_handle();
+    let start = Instant::now();
+    let mut publishers = JoinSet::new();
+    for channel in 0..config.channels {
+        for _ in 0..config.publishers {
+            let handle = handle.clone();
+            let (messages, rate) = (config.messages, config.rate);
+            publishers.spawn(async move {
+                let mut ticks = rate.map(|rate| {
+                    let mut ticks = interval(Duration::from_secs_f64(1.0 / rate));
+                    ticks.set_missed_tick_behavior(MissedTickBehavior::Burst);
+                    ticks
+                });
+                let (mut delivered, mut dropped) = (0, 0);
+                for _ in 0..messages {
+                    if let Some(ticks) = &mut ticks {
+                        ticks.tick().await;
+                    }
+                    if let Ok(handler) = handle.clone_send(Instant::now(), &channel) {
+                        let report = handler.wait(None).await;
+                        delivered += report.delivered();
+                        dropped += report.len() - report.delivered();
+                    }
+                }
+                (delivered, dropped)
+            });
+        }
+    }
+
+    let mut report = StressReport {
+        published: config.channels * config.publishers * config.messages,
+        ..Default::default()
+    };
+    while let Some(Ok((delivered, dropped))) = publishers.join_next().await {
+        report.delivered += delivered;
+        report.dropped += dropped;
+    }
+    // Dropping the hub closes the channels, the subscribers stop once they have read everything
+    drop(handle);
+    while let Some(Ok(latencies)) = subscribers.join_next().await {
+        report.latencies.extend(latencies);
+    }
+    report.elapsed = start.elapsed();
+    report.received = report.latencies.len();
+    report.latencies.sort_unstable();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stress_drops() {
+        let report = run(&StressConfig {
+            publishers: 2,
+            subscribers: 2,
+            channel_size: 1,
+            messages: 10,
+            rate: Some(1000.0),
+            delivery_mode: DeliveryMode::Deterministic,
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(report.published, 20);
+        assert_eq!(report.delivered + report.dropped, 40);
+        assert_eq!(report.received, report.delivered);
+        assert!(report.elapsed >= Duration::from_millis(9));
+        assert!(report.latency_percentile(0.5) <= report.max_latency());
+    }
+}
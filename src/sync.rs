@@ -0,0 +1,17 @@
+use std::sync::{Mutex, MutexGuard};
+
+/// Locks the mutex, even if a thread panicked while holding it. The states guarded by the mutexes of the hub
+/// are updated in short sections that leave them consistent, so a poisoned one is still usable.
+pub(crate) fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Same as `lock`, for a mutex held exclusively.
+pub(crate) fn get_mut<T: ?Sized>(mutex: &mut Mutex<T>) -> &mut T {
+    mutex.get_mut().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Same as `lock`, consuming the mutex.
+pub(crate) fn into_inner<T>(mutex: Mutex<T>) -> T {
+    mutex.into_inner().unwrap_or_else(|e| e.into_inner())
+}
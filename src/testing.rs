@@ -0,0 +1,187 @@
+use std::{fmt::Debug, hash::Hash};
+use tokio::time::{sleep, timeout_at, Duration, Instant};
+
+use crate::notifier::{MessageReceiver, NotifierHub};
+
+/// How long the assertions of `TestSubscriber` wait for an expected message.
+/// Under `tokio::time::pause` the clock advances instantly once every task is idle, so this costs nothing.
+pub const DEFAULT_TEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Lets every task spawned by the hub run until it is blocked.
+/// Under `tokio::time::pause` the clock only advances once all the tasks are idle, which makes
+/// this deterministic. Without a paused clock, it just leaves a short time for the tasks to run.
+pub async fn flush() {
+    sleep(Duration::from_millis(1)).await;
+}
+
+/// A subscriber recording everything it receives, with some assertion helpers.
+/// All the assertions panic with a message describing what has been received so far.
+///
+/// Example:
+/// ```rust
+/// use notifier_hub::{notifier::NotifierHub, testing::TestSubscriber};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut hub = NotifierHub::new();
+///     let mut subscriber = TestSubscriber::subscribe(&mut hub, &"channel1", 10);
+///
+///     hub.clone_send("First", &"channel1").unwrap();
+///     hub.clone_send("Second", &"channel1").unwrap();
+///
+///     subscriber.assert_received_in_order(&["First", "Second"]).await;
+/// }
+/// ```
+pub struct TestSubscriber<M> {
+    receiver: MessageReceiver<M>,
+    received: Vec<M>,
+}
+
+impl<M: PartialEq + Debug> TestSubscriber<M> {
+    /// Wraps an existing receiver.
+    pub fn new(receiver: MessageReceiver<M>) -> Self {
+        Self {
+            receiver,
+            received: Vec::new(),
+        }
+    }
+
+    /// Subscribes to the given channel of the hub.
+    pub fn subscribe<ChannelId: Eq + Hash + Clone>(
+        hub: &mut NotifierHub<M, ChannelId>,
+        id: &ChannelId,
+        channel_size: usize,
+    ) -> Self {
+        Self::new(hub.subscribe(id, channel_size))
+    }
+
+    /// Returns the wrapped receiver, to unsubscribe it for instance.
+    pub fn receiver(&self) -> &MessageReceiver<M> {
+        &self.receiver
+    }
+
+    /// Records the messages already in the buffer and returns everything received so far.
+    pub fn collect(&mut self) -> &[M] {
+        while let Ok(msg) = self.receiver.try_recv() {
+            self.received.push(msg);
+        }
+        &self.received
+    }
+
+    /// Returns everything recorded so far, without reading the buffer.
+    pub fn received(&self) -> &[M] {
+        &self.received
+    }
+
+    /// Records incoming messages until the predicate holds on the recorded ones, or the deadline is reached.
+    async fn record_until(&mut self, deadline: Instant, predicate: impl Fn(&[M]) -> bool) -> bool {
+        self.collect();
+        loop {
+            if predicate(&self.received) {
+                return true;
+            }
+            match timeout_at(deadline, self.receiver.recv()).await {
+                Ok(Some(msg)) => self.received.push(msg),
+                Ok(None) | Err(_) => return predicate(&self.received),
+            }
+        }
+    }
+
+    /// Asserts that the message has been received, waiting for it up to `DEFAULT_TEST_TIMEOUT`.
+    pub async fn assert_received(&mut self, expected: &M) {
+        let deadline = Instant::now() + DEFAULT_TEST_TIMEOUT;
+        if !self
+            .record_until(deadline, |received| received.contains(expected))
+            .await
+        {
+            panic!(
+                "Expected to receive {expected:?}, received {:?}",
+                self.received
+            );
+        }
+    }
+
+    /// Asserts that the messages have been received in this order, other messages may be interleaved.
+    /// Waits for them up to `DEFAULT_TEST_TIMEOUT`.
+    pub async fn assert_received_in_order(&mut self, expected: &[M]) {
+        let deadline = Instant::now() + DEFAULT_TEST_TIMEOUT;
+        let in_order = |received: &[M]| {
+            let mut received = received.iter();
+            expected.iter().all(|e| received.any(|r| r == e))
+        };
+        if !self.record_until(deadline, in_order).await {
+            panic!(
+                "Expected to receive {expected:?} in order, received {:?}",
+                self.received
+            );
+        }
+    }
+
+    /// Asserts that nothing is received during the given duration.
+    /// The messages already buffered but not recorded yet count as received.
+    pub async fn assert_silent_for(&mut self, duration: Duration) {
+        let n = self.received.len();
+        if let Ok(Some(msg)) = timeout_at(Instant::now() + duration, self.receiver.recv()).await {
+            self.received.push(msg);
+        }
+        if self.collect().len() != n {
+            panic!(
+                "Expected no message for {duration:?}, received {:?}",
+                &self.received[n..]
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_assert_received() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut subscriber = TestSubscriber::subscribe(&mut hub, &"channel1", 10);
+
+        hub.clone_send("First".to_string(), &"channel1").unwrap();
+        hub.clone_send("Second".to_string(), &"channel1").unwrap();
+        subscriber.assert_received(&"Second".to_string()).await;
+        subscriber
+            .assert_received_in_order(&["First".to_string(), "Second".to_string()])
+            .await;
+        subscriber.assert_silent_for(Duration::from_secs(10)).await;
+        assert_eq!(subscriber.received().len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_flush_pending_writings() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut subscriber = TestSubscriber::subscribe(&mut hub, &"channel1", 1);
+
+        hub.clone_send(1, &"channel1").unwrap();
+        let handler = hub.clone_send(2, &"channel1").unwrap();
+        assert_eq!(handler.pending(), 1);
+        drop(handler);
+
+        assert_eq!(subscriber.collect(), &[1]);
+        flush().await;
+        assert_eq!(subscriber.collect(), &[1, 2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[should_panic(expected = "Expected to receive")]
+    async fn test_assert_received_fails() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut subscriber = TestSubscriber::subscribe(&mut hub, &"channel1", 10);
+        hub.clone_send(1, &"channel1").unwrap();
+        subscriber.assert_received_in_order(&[2, 1]).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[should_panic(expected = "Expected no message")]
+    async fn test_assert_silent_fails() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut subscriber = TestSubscriber::subscribe(&mut hub, &"channel1", 10);
+        hub.clone_send(1, &"channel1").unwrap();
+        subscriber.assert_silent_for(Duration::from_secs(1)).await;
+    }
+}
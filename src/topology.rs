@@ -0,0 +1,174 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    handle::HubHandle,
+    metadata::ChannelMetadata,
+    notifier::{ChannelState, NotifierHub},
+};
+
+/// A channel in a `Topology`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChannelTopology {
+    /// The state of the channel.
+    pub state: ChannelState,
+    /// Number of subscribers of the channel, including the closed ones not cleaned yet.
+    pub subscribers: usize,
+    /// Number of creation waiters of the channel.
+    pub creation_waiters: usize,
+    /// Number of destruction waiters of the channel.
+    pub destruction_waiters: usize,
+}
+
+/// The channels of a hub at some point in time, returned by `NotifierHub::topology`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Topology<ChannelId: Eq + Hash> {
+    /// Binding each known channel with its description.
+    pub channels: HashMap<ChannelId, ChannelTopology>,
+    /// Binding the ids left by `rename_channel` with the channel they point to.
+    pub aliases: HashMap<ChannelId, ChannelId>,
+    /// Binding the described channels with their description and tags, see `NotifierHub::describe_channel`.
+    pub metadata: HashMap<ChannelId, ChannelMetadata>,
+}
+
+/// What changed between two topologies, returned by `Topology::diff`. The channels are in no particular order.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TopologyDiff<ChannelId> {
+    /// The channels only known by the later topology.
+    pub added: Vec<ChannelId>,
+    /// The channels only known by the earlier topology.
+    pub removed: Vec<ChannelId>,
+    /// The channels of both topologies whose number of subscribers changed, with the difference.
+    pub subscriber_deltas: Vec<(ChannelId, isize)>,
+    /// The channels of both topologies whose state changed, with the earlier and the later state.
+    pub state_changes: Vec<(ChannelId, ChannelState, ChannelState)>,
+}
+
+impl<ChannelId> TopologyDiff<ChannelId> {
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.subscriber_deltas.is_empty()
+            && self.state_changes.is_empty()
+    }
+
+    /// Returns the total difference of subscribers over the channels of both topologies.
+    pub fn subscriber_delta(&self) -> isize {
+        self.subscriber_deltas.iter().map(|(_, delta)| delta).sum()
+    }
+}
+
+impl<ChannelId: Eq + Hash + Clone> Topology<ChannelId> {
+    /// Compares this topology with a later one.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let _orders = hub.subscribe(&"orders", 10);
+    /// let before = hub.topology();
+    ///
+    /// let _also_orders = hub.subscribe(&"orders", 10);
+    /// let _payments = hub.subscribe(&"payments", 10);
+    /// let diff = before.diff(&hub.topology());
+    /// assert_eq!(diff.added, vec!["payments"]);
+    /// assert_eq!(diff.subscriber_deltas, vec![("orders", 1)]);
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// ```
+    pub fn diff(&self, later: &Topology<ChannelId>) -> TopologyDiff<ChannelId> {
+        let mut diff = TopologyDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            subscriber_deltas: Vec::new(),
+            state_changes: Vec::new(),
+        };
+        for (id, before) in &self.channels {
+            let Some(after) = later.channels.get(id) else {
+                diff.removed.push(id.clone());
+                continue;
+            };
+            let delta = after.subscribers as isize - before.subscribers as isize;
+            if delta != 0 {
+                diff.subscriber_deltas.push((id.clone(), delta));
+            }
+            if before.state != after.state {
+                diff.state_changes
+                    .push((id.clone(), before.state, after.state));
+            }
+        }
+        diff.added = later
+            .channels
+            .keys()
+            .filter(|id| !self.channels.contains_key(id))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Returns a snapshot of the channels of the hub, to compare with a later one with `Topology::diff`.
+    /// The channels known only by their waiters are `Uninitialised`.
+    pub fn topology(&self) -> Topology<ChannelId> {
+        let known = self
+            .senders
+            .keys()
+            .chain(self.creation_senders.keys())
+            .chain(self.destruction_senders.keys());
+        let channels = known
+            .map(|id| {
+                let channel = ChannelTopology {
+                    state: self.channel_state(id),
+                    subscribers: self.senders.get(id).map_or(0, |s| s.len()),
+                    creation_waiters: self.creation_senders.get(id).map_or(0, Vec::len),
+                    destruction_waiters: self.destruction_senders.get(id).map_or(0, Vec::len),
+                };
+                (id.clone(), channel)
+            })
+            .collect();
+        Topology {
+            channels,
+            aliases: self.aliases.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::topology`.
+    pub fn topology(&self) -> Topology<ChannelId> {
+        self.with(|hub| hub.topology())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_topology_diff() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let receiver1 = hub.subscribe(&"channel1", 10);
+        let _receiver2 = hub.subscribe(&"channel2", 10);
+        let before = hub.topology();
+        assert!(before.diff(&before).is_empty());
+
+        drop(receiver1);
+        hub.clean_channel(&"channel1");
+        let _waiter = hub.get_creation_waiter(&"channel3");
+        hub.forget_channel(&"channel2");
+        let after = hub.topology();
+        assert_eq!(after.channels[&"channel3"].creation_waiters, 1);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, vec!["channel3"]);
+        assert_eq!(diff.removed, vec!["channel2"]);
+        assert_eq!(diff.subscriber_deltas, vec![("channel1", -1)]);
+        assert_eq!(
+            diff.state_changes,
+            vec![("channel1", ChannelState::Running, ChannelState::Over)]
+        );
+        assert_eq!(diff.subscriber_delta(), -1);
+    }
+}
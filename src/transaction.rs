@@ -0,0 +1,248 @@
+use std::hash::Hash;
+
+use crate::{core::Route, drop_hook::DropReason, error::NotifierError, notifier::NotifierHub};
+
+/// A set of sends staged on several channels, committed all at once.
+/// On commit, a slot is reserved in the buffer of every targeted subscriber before anything is sent.
+/// If one of them can't accept the message (full or closed), all the reservations are released
+/// and nothing is delivered.
+pub struct Transaction<'a, M, ChannelId: Eq + Hash> {
+    hub: &'a NotifierHub<M, ChannelId>,
+    staged: Vec<(ChannelId, M)>,
+}
+
+impl<M, ChannelId> NotifierHub<M, ChannelId>
+where
+    M: Clone,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Starts a new transaction on the hub.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let mut receiver1 = hub.subscribe(&"channel1", 10);
+    /// let mut receiver2 = hub.subscribe(&"channel2", 10);
+    ///
+    /// let mut transaction = hub.transaction();
+    /// transaction.send("Update 1", &"channel1");
+    /// transaction.send("Update 2", &"channel2");
+    /// assert_eq!(transaction.commit().unwrap(), 2);
+    ///
+    /// assert_eq!(receiver1.try_recv().unwrap(), "Update 1");
+    /// assert_eq!(receiver2.try_recv().unwrap(), "Update 2");
+    /// ```
+    pub fn transaction(&self) -> Transaction<'_, M, ChannelId> {
+        Transaction {
+            hub: self,
+            staged: Vec::new(),
+        }
+    }
+}
+
+impl<M, ChannelId> Transaction<'_, M, ChannelId>
+where
+    M: Clone,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Stages the message to be sent on the given channel. Nothing is sent before `commit`.
+    pub fn send(&mut self, msg: M, id: &ChannelId) -> &mut Self {
+        self.staged.push((id.clone(), msg));
+        self
+    }
+
+    /// Returns the number of staged sends.
+    pub fn len(&self) -> usize {
+        self.staged.len()
+    }
+
+    /// Returns true if nothing has been staged.
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Delivers all the staged messages, or none of them.
+    /// Returns the number of delivered messages, or an error pointing the channel and the subscriber
+    /// that could not accept its message: its buffer is full or closed, its circuit is open or it is out of credits.
+    /// The credits spent before the rollback are given back. Sending to an uninitialised or a protected channel
+    /// also rolls back the transaction, the error handing back the message staged for it.
+    /// The messages staged for a channel without subscriber are parked or given to the drop hook once the others
+    /// are delivered, as with `clone_send`, and each channel is journaled, audited and run between the broadcast
+    /// hooks as for a publish. As they are all written at once, the messages skip the rate limits
+    /// and the sequencers of the channels, and may overtake the publishes queued by a sequencer.
+    /// Nothing is quarantined as a full buffer rolls back the transaction, and the staged messages are not
+    /// deduplicated, `dedup_send` being the way to do so.
+    /// Note that closed subscribers that have not been cleaned make the transaction fail.
+    pub fn commit(mut self) -> Result<usize, NotifierError<M, ChannelId>> {
+        let hub = self.hub;
+        let mut routes = Vec::with_capacity(self.staged.len());
+        for (index, (id, _)) in self.staged.iter().enumerate() {
+            match hub.route_of(hub.aliases.get(id).unwrap_or(id), false) {
+                Ok(route) => routes.push(route),
+                Err(refusal) => {
+                    let (id, msg) = self.staged.swap_remove(index);
+                    return Err(refusal.into_error(id, msg));
+                }
+            }
+        }
+
+        let mut permits = Vec::with_capacity(self.staged.len());
+        let mut spent = Vec::new();
+        for ((id, _), route) in self.staged.iter().zip(&routes) {
+            let mut reserved = Vec::new();
+            if *route == Route::Fanout {
+                for sender in hub.senders_of(id) {
+                    let permit = match hub.admit_writing(sender.id()) {
+                        Ok(()) => {
+                            spent.push(*sender.id());
+                            sender.try_reserve().ok()
+                        }
+                        Err(_) => None,
+                    };
+                    match permit {
+                        Some(permit) => reserved.push((permit, *sender.id())),
+                        // Dropping the permits releases the reserved slots
+                        None => {
+                            for sender in &spent {
+                                hub.credits.refund(sender);
+                            }
+                            return Err(NotifierError::TransactionRolledBack(
+                                id.clone(),
+                                *sender.id(),
+                            ));
+                        }
+                    }
+                }
+            }
+            permits.push(reserved);
+        }
+
+        let mut n = 0;
+        for (((id, msg), route), reserved) in self.staged.into_iter().zip(routes).zip(permits) {
+            let id = hub.aliases.get(&id).unwrap_or(&id);
+            let written = reserved.len();
+            match route {
+                Route::Fanout => {
+                    hub.journal(id, &msg);
+                    hub.hooked(Some(id), written, || {
+                        for (permit, sender) in reserved {
+                            permit.send(msg.clone());
+                            hub.breaker.record(sender, true);
+                        }
+                    });
+                }
+                Route::Park => {
+                    if let Err(msg) = hub.park(id, msg) {
+                        hub.dropped(id, DropReason::ParkOverflow, msg);
+                    }
+                }
+                Route::Discard => hub.dropped(id, DropReason::ChannelOver, msg),
+                Route::Reject => {}
+            }
+            hub.check_lag(id);
+            hub.record_publish(None, id, Some(written));
+            n += written;
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_commit() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        let mut receiver2 = hub.subscribe_multiple(&["channel1", "channel2"], 10);
+
+        let mut transaction = hub.transaction();
+        transaction
+            .send("First".to_string(), &"channel1")
+            .send("Second".to_string(), &"channel2");
+        assert_eq!(transaction.len(), 2);
+        assert_eq!(transaction.commit().unwrap(), 3);
+
+        assert_eq!(receiver1.recv().await.unwrap(), "First");
+        assert_eq!(receiver2.recv().await.unwrap(), "First");
+        assert_eq!(receiver2.recv().await.unwrap(), "Second");
+    }
+
+    #[tokio::test]
+    async fn test_rollback_on_full_buffer() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        let mut receiver2 = hub.subscribe(&"channel2", 1);
+
+        let mut transaction = hub.transaction();
+        transaction
+            .send("First".to_string(), &"channel1")
+            .send("Second".to_string(), &"channel2")
+            .send("Third".to_string(), &"channel2");
+        assert!(matches!(
+            transaction.commit(),
+            Err(NotifierError::TransactionRolledBack("channel2", id)) if id == receiver2.id()
+        ));
+
+        assert!(receiver1.try_recv().is_err());
+        assert!(receiver2.try_recv().is_err());
+
+        // The reservations have been released
+        hub.clone_send("After".to_string(), &"channel2").unwrap();
+        assert_eq!(receiver2.recv().await.unwrap(), "After");
+    }
+
+    #[tokio::test]
+    async fn test_rollback_on_uninitialised_channel() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver = hub.subscribe(&"channel1", 10);
+
+        let mut transaction = hub.transaction();
+        transaction
+            .send("First".to_string(), &"channel1")
+            .send("Second".to_string(), &"channel2");
+        assert!(matches!(
+            transaction.commit(),
+            Err(NotifierError::ChannelUninitialized("channel2"))
+        ));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_on_protected_channel() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        let _receiver2 = hub.subscribe(&"channel2", 10);
+        hub.grant_publish(&"channel2");
+
+        let mut transaction = hub.transaction();
+        transaction
+            .send("First".to_string(), &"channel1")
+            .send("Second".to_string(), &"channel2");
+        assert!(matches!(
+            transaction.commit(),
+            Err(NotifierError::PublishNotAllowed { id: "channel2", msg }) if msg == "Second"
+        ));
+        assert!(receiver1.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_park_on_commit() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        hub.set_park_buffer(&"channel2", Some(10));
+
+        let mut transaction = hub.transaction();
+        transaction
+            .send("First".to_string(), &"channel1")
+            .send("Parked".to_string(), &"channel2");
+        assert_eq!(transaction.commit().unwrap(), 1);
+        assert_eq!(receiver1.recv().await.unwrap(), "First");
+
+        let mut receiver2 = hub.subscribe(&"channel2", 10);
+        assert_eq!(receiver2.recv().await.unwrap(), "Parked");
+    }
+}
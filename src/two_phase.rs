@@ -0,0 +1,324 @@
+use std::hash::Hash;
+use tokio::{
+    sync::{mpsc::Sender as TokioSender, oneshot},
+    time::{timeout_at, Duration, Instant},
+};
+
+use crate::{
+    error::NotifierError,
+    handle::HubHandle,
+    notifier::{NotifierHub, SmartChannelId},
+    writing_handler::WritingHandler,
+};
+
+/// The token a subscriber uses to answer a `Phase::Prepare`.
+/// Dropping it without answering counts as an abort.
+#[derive(Debug)]
+pub struct Vote {
+    sender: oneshot::Sender<bool>,
+}
+
+impl Vote {
+    /// Signals the subscriber is ready to apply the change.
+    pub fn ready(self) {
+        let _ = self.sender.send(true);
+    }
+
+    /// Signals the subscriber can't apply the change, so it is rolled back for everyone.
+    pub fn abort(self) {
+        let _ = self.sender.send(false);
+    }
+}
+
+/// The messages of a two-phase broadcast, see `NotifierHub::two_phase_send`.
+#[derive(Debug)]
+pub enum Phase<M> {
+    /// The change to prepare, to be answered with the vote.
+    Prepare { msg: M, vote: Vote },
+    /// All the subscribers are ready, the prepared change can be applied.
+    Commit,
+    /// At least one subscriber aborted, the prepared change must be dropped.
+    Rollback,
+}
+
+/// The outcome of a two-phase broadcast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TwoPhaseOutcome {
+    /// Every subscriber was ready, `delivered` of them received `Phase::Commit`.
+    /// The `undelivered` ones could not receive it before the deadline, or were gone.
+    Committed {
+        delivered: usize,
+        undelivered: Vec<SmartChannelId>,
+    },
+    /// These subscribers aborted, did not receive the prepare, or did not vote in time,
+    /// so every prepared subscriber received `Phase::Rollback`, except the `undelivered` ones.
+    RolledBack {
+        aborted: Vec<SmartChannelId>,
+        undelivered: Vec<SmartChannelId>,
+    },
+}
+
+/// Drives a two-phase broadcast returned by `two_phase_send`.
+pub struct TwoPhaseHandler<M: Send + 'static> {
+    writings: WritingHandler<Phase<M>>,
+    votes: Vec<(
+        TokioSender<Phase<M>>,
+        SmartChannelId,
+        oneshot::Receiver<bool>,
+    )>,
+    timeout: Duration,
+}
+
+impl<M: Send + 'static> TwoPhaseHandler<M> {
+    /// Returns the number of subscribers that have to vote.
+    pub fn len(&self) -> usize {
+        self.votes.len()
+    }
+
+    /// Returns true if the channel had no subscriber.
+    pub fn is_empty(&self) -> bool {
+        self.votes.is_empty()
+    }
+
+    /// Collects the votes until the timeout, then sends `Phase::Commit` if they are all ready,
+    /// `Phase::Rollback` otherwise, to the subscribers that received the prepare.
+    /// The decision is written within the same timeout, once it is over it only reaches the subscribers
+    /// with room in their buffer, the other ones being reported as undelivered.
+    pub async fn wait(self) -> TwoPhaseOutcome {
+        let deadline = Instant::now() + self.timeout;
+        // A failed writing drops its `Vote`, which counts as an abort
+        let _ = timeout_at(deadline, self.writings.wait(None)).await;
+        let mut prepared = Vec::with_capacity(self.votes.len());
+        let mut aborted = Vec::new();
+        for (sender, id, vote) in self.votes {
+            match timeout_at(deadline, vote).await {
+                Ok(Ok(true)) => prepared.push((sender, id)),
+                Ok(Ok(false)) => {
+                    prepared.push((sender, id));
+                    aborted.push(id)
+                }
+                // The vote has been dropped without answering, or the subscriber is too slow
+                _ => {
+                    aborted.push(id);
+                    if !sender.is_closed() {
+                        prepared.push((sender, id))
+                    }
+                }
+            }
+        }
+
+        let committed = aborted.is_empty();
+        let mut delivered = 0;
+        let mut undelivered = Vec::new();
+        for (sender, id) in prepared {
+            let decision = if committed {
+                Phase::Commit
+            } else {
+                Phase::Rollback
+            };
+            // The inner send is polled first, so a subscriber with room still gets it past the deadline
+            match timeout_at(deadline, sender.send(decision)).await {
+                Ok(Ok(())) => delivered += 1,
+                _ => undelivered.push(id),
+            }
+        }
+        if committed {
+            TwoPhaseOutcome::Committed {
+                delivered,
+                undelivered,
+            }
+        } else {
+            TwoPhaseOutcome::RolledBack {
+                aborted,
+                undelivered,
+            }
+        }
+    }
+}
+
+impl<M, ChannelId> NotifierHub<Phase<M>, ChannelId>
+where
+    M: Clone + Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Starts a two-phase broadcast for a coordinated state change: each subscriber of the channel receives
+    /// a `Phase::Prepare` with the message and votes, then the returned handler sends the decision,
+    /// either `Phase::Commit` or `Phase::Rollback`, and returns the outcome.
+    /// Fails with `NotifierError::NoSubscriber`, handing the message back, if the channel has no subscriber to vote.
+    /// Only the prepare is a publish on the channel, refused if it is protected or exceeds its rate limit:
+    /// the decision is written by the handler to the subscribers that received the prepare, bypassing the
+    /// sequencer, and the ones joining meanwhile receive nothing.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{
+    ///     notifier::NotifierHub,
+    ///     two_phase::{Phase, TwoPhaseOutcome},
+    /// };
+    /// use tokio::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::<Phase<u32>, _>::new();
+    /// let mut receiver = hub.subscribe(&"schema", 10);
+    /// tokio::spawn(async move {
+    ///     let mut pending = None;
+    ///     while let Some(phase) = receiver.recv().await {
+    ///         match phase {
+    ///             Phase::Prepare { msg, vote } => {
+    ///                 pending = Some(msg);
+    ///                 vote.ready();
+    ///             }
+    ///             Phase::Commit => println!("Now using version {:?}", pending.take()),
+    ///             Phase::Rollback => pending = None,
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// let handler = hub.two_phase_send(2, &"schema", Duration::from_secs(1)).unwrap();
+    /// assert_eq!(
+    ///     handler.wait().await,
+    ///     TwoPhaseOutcome::Committed {
+    ///         delivered: 1,
+    ///         undelivered: Vec::new()
+    ///     }
+    /// );
+    /// # }
+    /// ```
+    pub fn two_phase_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<TwoPhaseHandler<M>, NotifierError<M, ChannelId>> {
+        let (writings, votes) = self.publish_with_reply(id, msg, |sender, msg| {
+            let (vote, voted) = oneshot::channel();
+            let msg = Phase::Prepare {
+                msg: msg.clone(),
+                vote: Vote { sender: vote },
+            };
+            (msg, ((**sender).clone(), *sender.id(), voted))
+        })?;
+        Ok(TwoPhaseHandler {
+            writings,
+            votes,
+            timeout,
+        })
+    }
+}
+
+impl<M, ChannelId> HubHandle<Phase<M>, ChannelId>
+where
+    M: Clone + Send + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    /// Same as `NotifierHub::two_phase_send`.
+    pub fn two_phase_send(
+        &self,
+        msg: M,
+        id: &ChannelId,
+        timeout: Duration,
+    ) -> Result<TwoPhaseHandler<M>, NotifierError<M, ChannelId>> {
+        self.with(|hub| hub.two_phase_send(msg, id, timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_two_phase_commit() {
+        let mut hub = NotifierHub::<Phase<u32>, &'static str>::new();
+        let mut receiver1 = hub.subscribe(&"channel1", 10);
+        let mut receiver2 = hub.subscribe(&"channel1", 10);
+
+        let handler = hub
+            .two_phase_send(1, &"channel1", Duration::from_secs(1))
+            .unwrap();
+        for receiver in [&mut receiver1, &mut receiver2] {
+            match receiver.try_recv().unwrap() {
+                Phase::Prepare { msg: 1, vote } => vote.ready(),
+                _ => panic!("Expected a prepare"),
+            }
+        }
+        assert_eq!(
+            handler.wait().await,
+            TwoPhaseOutcome::Committed {
+                delivered: 2,
+                undelivered: Vec::new()
+            }
+        );
+        assert!(matches!(receiver1.try_recv().unwrap(), Phase::Commit));
+        assert!(matches!(receiver2.try_recv().unwrap(), Phase::Commit));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_two_phase_rollback() {
+        let mut hub = NotifierHub::<Phase<u32>, &'static str>::new();
+        let mut ready = hub.subscribe(&"channel1", 10);
+        let mut silent = hub.subscribe(&"channel1", 10);
+
+        let handler = hub
+            .two_phase_send(1, &"channel1", Duration::from_secs(1))
+            .unwrap();
+        let Phase::Prepare { vote, .. } = ready.try_recv().unwrap() else {
+            panic!("Expected a prepare")
+        };
+        vote.ready();
+        let _vote = silent.try_recv().unwrap();
+
+        assert_eq!(
+            handler.wait().await,
+            TwoPhaseOutcome::RolledBack {
+                aborted: vec![silent.id()],
+                undelivered: Vec::new()
+            }
+        );
+        assert!(matches!(ready.try_recv().unwrap(), Phase::Rollback));
+        assert!(matches!(silent.try_recv().unwrap(), Phase::Rollback));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_two_phase_full_subscriber() {
+        let mut hub = NotifierHub::<Phase<u32>, &'static str>::new();
+        let mut ready = hub.subscribe(&"channel1", 10);
+        let mut full = hub.subscribe(&"channel1", 1);
+
+        let handler = hub
+            .two_phase_send(1, &"channel1", Duration::from_secs(1))
+            .unwrap();
+        for receiver in [&mut ready, &mut full] {
+            match receiver.try_recv().unwrap() {
+                Phase::Prepare { vote, .. } => vote.ready(),
+                _ => panic!("Expected a prepare"),
+            }
+        }
+        hub.send_with(&"channel1", || Phase::Commit).unwrap();
+
+        let start = Instant::now();
+        assert_eq!(
+            handler.wait().await,
+            TwoPhaseOutcome::Committed {
+                delivered: 1,
+                undelivered: vec![full.id()]
+            }
+        );
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_two_phase_without_subscriber() {
+        let mut hub = NotifierHub::<Phase<u32>, &'static str>::new();
+        hub.set_park_buffer(&"channel1", Some(1));
+        assert!(matches!(
+            hub.two_phase_send(1, &"channel1", Duration::from_secs(1)),
+            Err(NotifierError::NoSubscriber {
+                id: "channel1",
+                msg: 1
+            })
+        ));
+        let mut receiver = hub.subscribe(&"channel1", 10);
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,947 @@
+use smart_channel::channel;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    watch,
+};
+
+use crate::{
+    gc::remove_closed,
+    handle::HubHandle,
+    notifier::{
+        CreationWaiter, DeadSender, DestructionWaiter, NotifierHub, Receiver, Sender,
+        SmartChannelId, NOTIFIER_CHANNEL_SIZE,
+    },
+    sync::lock,
+};
+
+/// What happens to a notification finding the buffer of its waiter full.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum WaiterOverflow {
+    /// The notification is written as the publishes are, following the delivery mode and the send timeout of the hub:
+    /// the writing waits for some room in a task, or fails in deterministic mode.
+    #[default]
+    Publish,
+    /// The notification is dropped, the waiter having notifications to read anyway.
+    Coalesce,
+    /// The notification is dropped and counted, see `NotifierHub::dropped_notifications`.
+    Drop,
+    /// The notification is queued without limit, the queue being written in order as soon as the waiter has some room.
+    Grow,
+}
+
+/// The buffer size and overflow policy of a creation or destruction waiter,
+/// see `NotifierHub::get_creation_waiter_with`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WaiterConfig {
+    /// Size of the buffer of the waiter.
+    pub capacity: usize,
+    /// What happens to the notifications once the buffer is full.
+    pub overflow: WaiterOverflow,
+}
+
+impl Default for WaiterConfig {
+    /// The configuration of `get_creation_waiter` and `get_destruction_waiter`.
+    fn default() -> Self {
+        Self {
+            capacity: NOTIFIER_CHANNEL_SIZE,
+            overflow: WaiterOverflow::default(),
+        }
+    }
+}
+
+/// The overflow state of a waiter whose policy is not `WaiterOverflow::Publish`.
+pub(crate) struct WaiterState<T> {
+    overflow: WaiterOverflow,
+    dropped: u64,
+    /// The notifications waiting for some room, once the buffer overflowed with `WaiterOverflow::Grow`.
+    queue: Option<mpsc::UnboundedSender<T>>,
+}
+
+/// Binding each waiter having an overflow policy with its state.
+pub(crate) type WaiterStates<T> = HashMap<SmartChannelId, Mutex<WaiterState<T>>>;
+
+impl<T: Send + 'static> WaiterState<T> {
+    /// Writes the notification following the overflow policy.
+    fn offer(&mut self, waiter: &Sender<T, SmartChannelId>, notification: T) {
+        if let Some(queue) = &self.queue {
+            // Once queued, the notifications go through the queue to keep their order
+            let _ = queue.send(notification);
+            return;
+        }
+        let Err(TrySendError::Full(notification)) = waiter.try_send(notification) else {
+            return;
+        };
+        match self.overflow {
+            WaiterOverflow::Publish | WaiterOverflow::Coalesce => {}
+            WaiterOverflow::Drop => self.dropped += 1,
+            WaiterOverflow::Grow => {
+                let (queue, mut queued) = mpsc::unbounded_channel();
+                let _ = queue.send(notification);
+                let waiter = (**waiter).clone();
+                tokio::spawn(async move {
+                    while let Some(notification) = queued.recv().await {
+                        if waiter.send(notification).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+                self.queue = Some(queue);
+            }
+        }
+    }
+}
+
+/// Writes the notification to the waiters having an overflow policy, and returns the other ones.
+pub(crate) fn offer_all<'a, T: Clone + Send + 'static>(
+    waiters: &'a [Sender<T, SmartChannelId>],
+    states: &WaiterStates<T>,
+    notification: &T,
+) -> Vec<&'a Sender<T, SmartChannelId>> {
+    waiters
+        .iter()
+        .filter(|waiter| match states.get(waiter.id()) {
+            Some(state) => {
+                lock(state).offer(waiter, notification.clone());
+                false
+            }
+            None => true,
+        })
+        .collect()
+}
+
+/// A creation waiter receiving the number of new subscribers since its last receive, instead of one notification
+/// per subscriber, obtained with `NotifierHub::get_coalesced_creation_waiter`.
+pub struct CoalescedWaiter {
+    count: Arc<AtomicUsize>,
+    changed: watch::Receiver<()>,
+}
+
+impl CoalescedWaiter {
+    /// Waits for some new subscribers and returns their number, `None` once the waiter has been removed
+    /// from the hub and every subscriber has been received.
+    pub async fn recv(&mut self) -> Option<usize> {
+        loop {
+            let count = self.count.swap(0, Ordering::AcqRel);
+            if count > 0 {
+                return Some(count);
+            }
+            if self.changed.changed().await.is_err() {
+                let count = self.count.swap(0, Ordering::AcqRel);
+                return (count > 0).then_some(count);
+            }
+        }
+    }
+
+    /// Returns the number of new subscribers since the last receive, without waiting.
+    pub fn try_recv(&mut self) -> usize {
+        self.changed.mark_unchanged();
+        self.count.swap(0, Ordering::AcqRel)
+    }
+}
+
+/// The side of a `CoalescedWaiter` kept by the hub.
+pub(crate) struct CoalescedSender {
+    count: Arc<AtomicUsize>,
+    changed: watch::Sender<()>,
+}
+
+impl CoalescedSender {
+    /// Returns true if the waiter has been dropped.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.changed.is_closed()
+    }
+
+    /// Counts a new subscriber and wakes the waiter up.
+    fn notify(&self) {
+        self.count.fetch_add(1, Ordering::AcqRel);
+        self.changed.send_replace(());
+    }
+}
+
+/// Why a subscriber has been removed from a channel.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DestructionReason {
+    /// The subscriber has been unsubscribed, or has left a group.
+    Unsubscribed,
+    /// The channel has been closed.
+    ChannelClosed,
+    /// The receiver of the subscriber has been dropped, and its sender removed by `clean_channel`.
+    DetectedClosed,
+}
+
+/// A subscriber removed from a channel, received by the departure waiters.
+#[derive(Clone, Debug)]
+pub struct Departure<M> {
+    /// The sender of the removed subscriber.
+    pub sender: DeadSender<M>,
+    /// Why the subscriber has been removed.
+    pub reason: DestructionReason,
+}
+
+/// A destruction waiter receiving each removed subscriber with the reason of its removal.
+pub type DepartureWaiter<M> = Receiver<Departure<M>, SmartChannelId>;
+
+/// A creation waiter of several channels, receiving the channel of each new subscriber.
+pub type MultiCreationWaiter<ChannelId> = Receiver<ChannelId, SmartChannelId>;
+
+/// A destruction waiter of several channels, receiving the channel of each removed subscriber with its sender.
+pub type MultiDestructionWaiter<M, ChannelId> =
+    Receiver<(ChannelId, DeadSender<M>), SmartChannelId>;
+
+/// A waiter whose notifications carry their channel. `E` is what the hub notifies: `()` for the creations,
+/// the departure for the destructions.
+pub(crate) trait EventSender<M, ChannelId: Eq + Hash, E>: Send + Sync {
+    /// Returns the id of the waiter.
+    fn id(&self) -> &SmartChannelId;
+
+    /// Returns true if the waiter has been dropped.
+    fn is_closed(&self) -> bool;
+
+    /// Writes the notification of the channel, as the other waiters of the hub are.
+    fn send(&self, hub: &NotifierHub<M, ChannelId>, id: &ChannelId, event: &E);
+}
+
+/// Binding channel with the waiters whose notifications carry their channel.
+pub(crate) type EventSenders<M, ChannelId, E> =
+    HashMap<ChannelId, Vec<Box<dyn EventSender<M, ChannelId, E>>>>;
+
+struct CreationEvents<ChannelId>(Sender<ChannelId, SmartChannelId>);
+
+impl<M, ChannelId> EventSender<M, ChannelId, ()> for CreationEvents<ChannelId>
+where
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    fn id(&self) -> &SmartChannelId {
+        self.0.id()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
+    fn send(&self, hub: &NotifierHub<M, ChannelId>, id: &ChannelId, _: &()) {
+        let _ = hub
+            .writing_handler::<_, ()>()
+            .with_reporter(hub.failure_reporter(Some(id)))
+            .cloning_broadcast(id.clone(), [&self.0]);
+    }
+}
+
+struct DestructionEvents<M, ChannelId>(Sender<(ChannelId, DeadSender<M>), SmartChannelId>);
+
+impl<M, ChannelId> EventSender<M, ChannelId, Departure<M>> for DestructionEvents<M, ChannelId>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone + Send + 'static,
+{
+    fn id(&self) -> &SmartChannelId {
+        self.0.id()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
+    fn send(&self, hub: &NotifierHub<M, ChannelId>, id: &ChannelId, departure: &Departure<M>) {
+        let _ = hub
+            .writing_handler::<_, ()>()
+            .with_reporter(hub.failure_reporter(Some(id)))
+            .cloning_broadcast((id.clone(), departure.sender.clone()), [&self.0]);
+    }
+}
+
+struct DepartureEvents<M>(Sender<Departure<M>, SmartChannelId>);
+
+impl<M, ChannelId> EventSender<M, ChannelId, Departure<M>> for DepartureEvents<M>
+where
+    M: Send + Clone + 'static,
+    ChannelId: Eq + Hash + Clone,
+{
+    fn id(&self) -> &SmartChannelId {
+        self.0.id()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
+    fn send(&self, hub: &NotifierHub<M, ChannelId>, id: &ChannelId, departure: &Departure<M>) {
+        let _ = hub
+            .writing_handler::<_, ()>()
+            .with_reporter(hub.failure_reporter(Some(id)))
+            .cloning_broadcast(departure.clone(), [&self.0]);
+    }
+}
+
+/// Removes the waiters of the map matching the predicate, and the entries left empty.
+/// Returns the number of removed waiters.
+fn remove_events<M, ChannelId: Eq + Hash, E>(
+    map: &mut EventSenders<M, ChannelId, E>,
+    removed: impl Fn(&dyn EventSender<M, ChannelId, E>) -> bool,
+) -> usize {
+    let mut count = 0;
+    map.retain(|_, waiters| {
+        let n = waiters.len();
+        waiters.retain(|waiter| !removed(waiter.as_ref()));
+        count += n - waiters.len();
+        !waiters.is_empty()
+    });
+    count
+}
+
+/// Removes the waiters of the list matching the predicate, and returns their number.
+fn remove_global<M, ChannelId: Eq + Hash, E>(
+    waiters: &mut Vec<Box<dyn EventSender<M, ChannelId, E>>>,
+    removed: impl Fn(&dyn EventSender<M, ChannelId, E>) -> bool,
+) -> usize {
+    let n = waiters.len();
+    waiters.retain(|waiter| !removed(waiter.as_ref()));
+    n - waiters.len()
+}
+
+/// Removes the waiter from the entries of the map, and the entries left empty. Returns true if it was there.
+fn remove_sender<T, ChannelId: Eq + Hash>(
+    map: &mut HashMap<ChannelId, Vec<Sender<T, SmartChannelId>>>,
+    id: &SmartChannelId,
+) -> bool {
+    let mut found = false;
+    map.retain(|_, waiters| {
+        let n = waiters.len();
+        waiters.retain(|waiter| waiter.id() != id);
+        found |= waiters.len() < n;
+        !waiters.is_empty()
+    });
+    found
+}
+
+/// A waiter unregistered from its hub when dropped, obtained with `HubHandle::guard_waiter`.
+/// It dereferences to the waiter.
+pub struct WaiterGuard<T, M, ChannelId: Eq + Hash> {
+    waiter: Receiver<T, SmartChannelId>,
+    hub: Weak<Mutex<NotifierHub<M, ChannelId>>>,
+}
+
+impl<T, M, ChannelId: Eq + Hash> Deref for WaiterGuard<T, M, ChannelId> {
+    type Target = Receiver<T, SmartChannelId>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.waiter
+    }
+}
+
+impl<T, M, ChannelId: Eq + Hash> DerefMut for WaiterGuard<T, M, ChannelId> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.waiter
+    }
+}
+
+impl<T, M, ChannelId: Eq + Hash> Drop for WaiterGuard<T, M, ChannelId> {
+    fn drop(&mut self) {
+        if let Some(hub) = self.hub.upgrade() {
+            lock(&hub).remove_waiter(&self.waiter);
+        }
+    }
+}
+
+/// Returns the number of dropped notifications of the waiter.
+fn dropped<T>(states: &WaiterStates<T>, id: &SmartChannelId) -> Option<u64> {
+    states.get(id).map(|state| lock(state).dropped)
+}
+
+impl<M, ChannelId: Eq + Hash> NotifierHub<M, ChannelId> {
+    /// Unregisters the waiter from the hub, whatever the channels it waits for, so it receives nothing more
+    /// and its sender is freed right away. Works for the creation, destruction, state, state change
+    /// and multi-channel waiters.
+    /// Returns false if the waiter was not registered.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let mut waiter = hub.get_creation_waiter(&"jobs");
+    /// assert!(hub.remove_waiter(&waiter));
+    /// let _receiver = hub.subscribe(&"jobs", 10);
+    /// assert!(waiter.try_recv().is_err());
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// ```
+    pub fn remove_waiter<T>(&mut self, waiter: &Receiver<T, SmartChannelId>) -> bool {
+        let id = &waiter.id();
+        let mut found = remove_sender(&mut self.creation_senders, id)
+            | remove_sender(&mut self.destruction_senders, id);
+        let events = remove_events(&mut self.creation_events, |w| w.id() == id)
+            + remove_events(&mut self.destruction_events, |w| w.id() == id)
+            + remove_global(&mut self.global_creation_events, |w| w.id() == id)
+            + remove_global(&mut self.global_destruction_events, |w| w.id() == id);
+        found |= events > 0;
+        self.state_senders.retain(|_, waiters| {
+            let n = waiters.len();
+            waiters.retain(|waiter| waiter.sender.id() != id);
+            found |= waiters.len() < n;
+            !waiters.is_empty()
+        });
+        let n = self.state_change_senders.len();
+        self.state_change_senders.retain(|waiter| waiter.id() != id);
+        found |= self.state_change_senders.len() < n;
+        self.creation_states.remove(id);
+        self.destruction_states.remove(id);
+        found
+    }
+
+    /// Notifies the waiters of the map bound to the channel, and the waiters of all the channels.
+    pub(crate) fn notify_events<E>(
+        &self,
+        id: &ChannelId,
+        event: &E,
+        map: &EventSenders<M, ChannelId, E>,
+        global: &[Box<dyn EventSender<M, ChannelId, E>>],
+    ) {
+        for waiter in map.get(id).into_iter().flatten().chain(global) {
+            waiter.send(self, id, event);
+        }
+    }
+
+    /// Counts a new subscriber for the coalesced waiters of the channel.
+    pub(crate) fn notify_coalesced(&self, id: &ChannelId) {
+        for waiter in self.coalesced_waiters.get(id).into_iter().flatten() {
+            waiter.notify();
+        }
+    }
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> NotifierHub<M, ChannelId> {
+    /// Registers the overflow state of a new waiter.
+    fn configure_waiter<T>(states: &mut WaiterStates<T>, id: SmartChannelId, config: WaiterConfig) {
+        if config.overflow != WaiterOverflow::Publish {
+            states.insert(
+                id,
+                Mutex::new(WaiterState {
+                    overflow: config.overflow,
+                    dropped: 0,
+                    queue: None,
+                }),
+            );
+        }
+    }
+
+    /// Same as `get_creation_waiter`, with the given buffer size and overflow policy, so a slow waiter
+    /// neither holds writing tasks nor loses its notifications silently.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{notifier::NotifierHub, waiter::{WaiterConfig, WaiterOverflow}};
+    ///
+    /// let mut hub = NotifierHub::new();
+    /// let config = WaiterConfig { capacity: 1, overflow: WaiterOverflow::Drop };
+    /// let waiter = hub.get_creation_waiter_with(&"jobs", config);
+    /// let _receivers = [hub.subscribe(&"jobs", 10), hub.subscribe(&"jobs", 10)];
+    /// assert_eq!(hub.dropped_notifications(&waiter), 1);
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// ```
+    pub fn get_creation_waiter_with(
+        &mut self,
+        id: &ChannelId,
+        config: WaiterConfig,
+    ) -> CreationWaiter {
+        let id = &self.aliases.get(id).unwrap_or(id).clone();
+        let waiter = Self::waiter_with(
+            self.get_new_id(),
+            id,
+            &mut self.creation_senders,
+            config.capacity,
+        );
+        Self::configure_waiter(&mut self.creation_states, waiter.id(), config);
+        self.on_mutation();
+        waiter
+    }
+
+    /// Same as `get_destruction_waiter`, with the given buffer size and overflow policy.
+    pub fn get_destruction_waiter_with(
+        &mut self,
+        id: &ChannelId,
+        config: WaiterConfig,
+    ) -> DestructionWaiter<M> {
+        let id = &self.aliases.get(id).unwrap_or(id).clone();
+        let waiter = Self::waiter_with(
+            self.get_new_id(),
+            id,
+            &mut self.destruction_senders,
+            config.capacity,
+        );
+        Self::configure_waiter(&mut self.destruction_states, waiter.id(), config);
+        self.on_mutation();
+        waiter
+    }
+
+    /// Returns the number of notifications dropped because the buffer of the waiter was full,
+    /// always 0 if its policy is not `WaiterOverflow::Drop`.
+    pub fn dropped_notifications<T>(&self, waiter: &Receiver<T, SmartChannelId>) -> u64 {
+        let id = waiter.id();
+        dropped(&self.creation_states, &id)
+            .or_else(|| dropped(&self.destruction_states, &id))
+            .unwrap_or(0)
+    }
+
+    /// Returns a creation waiter coalescing the notifications, so a burst of subscriptions wakes it up
+    /// only once with their number.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let mut waiter = hub.get_coalesced_creation_waiter(&"jobs");
+    /// let _receivers: Vec<_> = (0..100).map(|_| hub.subscribe(&"jobs", 10)).collect();
+    /// assert_eq!(waiter.recv().await, Some(100));
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// # }
+    /// ```
+    pub fn get_coalesced_creation_waiter(&mut self, id: &ChannelId) -> CoalescedWaiter {
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        let count = Arc::new(AtomicUsize::new(0));
+        let (changed, mut receiver) = watch::channel(());
+        receiver.mark_unchanged();
+        self.coalesced_waiters
+            .entry(id)
+            .or_default()
+            .push(CoalescedSender {
+                count: count.clone(),
+                changed,
+            });
+        self.on_mutation();
+        CoalescedWaiter {
+            count,
+            changed: receiver,
+        }
+    }
+
+    /// Returns one creation waiter for all the given channels, each notification carrying the channel
+    /// of the new subscriber, so a single receiver watches many channels.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let mut waiter = hub.get_creation_waiter_multi(&["orders", "payments"]);
+    /// let _payments = hub.subscribe(&"payments", 10);
+    /// let _orders = hub.subscribe(&"orders", 10);
+    /// assert_eq!(waiter.recv().await, Some("payments"));
+    /// assert_eq!(waiter.recv().await, Some("orders"));
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// # }
+    /// ```
+    pub fn get_creation_waiter_multi(&mut self, ids: &[ChannelId]) -> MultiCreationWaiter<ChannelId>
+    where
+        ChannelId: Send + 'static,
+    {
+        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, self.get_new_id());
+        for id in ids {
+            let id = self.aliases.get(id).unwrap_or(id).clone();
+            self.creation_events
+                .entry(id)
+                .or_default()
+                .push(Box::new(CreationEvents(sender.clone())));
+        }
+        self.on_mutation();
+        receiver
+    }
+
+    /// Returns one destruction waiter for all the given channels, each notification carrying the channel
+    /// of the removed subscriber along with its sender.
+    pub fn get_destruction_waiter_multi(
+        &mut self,
+        ids: &[ChannelId],
+    ) -> MultiDestructionWaiter<M, ChannelId>
+    where
+        M: Send + Clone + 'static,
+        ChannelId: Send + 'static,
+    {
+        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, self.get_new_id());
+        for id in ids {
+            let id = self.aliases.get(id).unwrap_or(id).clone();
+            self.destruction_events
+                .entry(id)
+                .or_default()
+                .push(Box::new(DestructionEvents(sender.clone())));
+        }
+        self.on_mutation();
+        receiver
+    }
+
+    /// Returns a destruction waiter of the channel receiving each removed subscriber along with the reason
+    /// of its removal, telling the explicit unsubscribes from the receivers found dropped by `clean_channel`.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::{notifier::NotifierHub, waiter::DestructionReason};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let mut departures = hub.get_departure_waiter(&"jobs");
+    /// let worker1 = hub.subscribe(&"jobs", 10);
+    /// let worker2 = hub.subscribe(&"jobs", 10);
+    ///
+    /// hub.unsubscribe(&"jobs", &worker1).unwrap();
+    /// drop(worker2);
+    /// hub.clean_channel(&"jobs");
+    /// assert_eq!(departures.recv().await.unwrap().reason, DestructionReason::Unsubscribed);
+    /// assert_eq!(departures.recv().await.unwrap().reason, DestructionReason::DetectedClosed);
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// # }
+    /// ```
+    pub fn get_departure_waiter(&mut self, id: &ChannelId) -> DepartureWaiter<M>
+    where
+        M: Send + Clone + 'static,
+    {
+        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, self.get_new_id());
+        let id = self.aliases.get(id).unwrap_or(id).clone();
+        self.destruction_events
+            .entry(id)
+            .or_default()
+            .push(Box::new(DepartureEvents(sender)));
+        self.on_mutation();
+        receiver
+    }
+
+    /// Returns a creation waiter of every channel of the hub, including the ones created later,
+    /// each notification carrying the channel of the new subscriber.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut hub = NotifierHub::new();
+    /// let mut waiter = hub.get_creation_waiter_all();
+    /// let _receiver = hub.subscribe(&"created later", 10);
+    /// assert_eq!(waiter.recv().await, Some("created later"));
+    /// # let _: NotifierHub<u32, &str> = hub;
+    /// # }
+    /// ```
+    pub fn get_creation_waiter_all(&mut self) -> MultiCreationWaiter<ChannelId>
+    where
+        ChannelId: Send + 'static,
+    {
+        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, self.get_new_id());
+        self.global_creation_events
+            .push(Box::new(CreationEvents(sender)));
+        self.on_mutation();
+        receiver
+    }
+
+    /// Returns a destruction waiter of every channel of the hub, including the ones created later,
+    /// each notification carrying the channel of the removed subscriber along with its sender.
+    pub fn get_destruction_waiter_all(&mut self) -> MultiDestructionWaiter<M, ChannelId>
+    where
+        M: Send + Clone + 'static,
+        ChannelId: Send + 'static,
+    {
+        let (sender, receiver) = channel(NOTIFIER_CHANNEL_SIZE, self.get_new_id());
+        self.global_destruction_events
+            .push(Box::new(DestructionEvents(sender)));
+        self.on_mutation();
+        receiver
+    }
+
+    /// Removes the waiters whose receiver has been dropped, returning the number of creation
+    /// and destruction waiters removed.
+    pub(crate) fn remove_closed_waiters(&mut self) -> (usize, usize) {
+        let mut coalesced = 0;
+        self.coalesced_waiters.retain(|_, waiters| {
+            let n = waiters.len();
+            waiters.retain(|waiter| !waiter.is_closed());
+            coalesced += n - waiters.len();
+            !waiters.is_empty()
+        });
+        let creation_waiters = remove_closed(&mut self.creation_senders)
+            + remove_events(&mut self.creation_events, |w| w.is_closed())
+            + remove_global(&mut self.global_creation_events, |w| w.is_closed())
+            + coalesced;
+        let destruction_waiters = remove_closed(&mut self.destruction_senders)
+            + remove_events(&mut self.destruction_events, |w| w.is_closed())
+            + remove_global(&mut self.global_destruction_events, |w| w.is_closed());
+        self.collect_waiter_states();
+        (creation_waiters, destruction_waiters)
+    }
+
+    /// Forgets the overflow states of the waiters that have been removed.
+    fn collect_waiter_states(&mut self) {
+        let creation_senders = &self.creation_senders;
+        self.creation_states
+            .retain(|id, _| is_registered(creation_senders, id));
+        let destruction_senders = &self.destruction_senders;
+        self.destruction_states
+            .retain(|id, _| is_registered(destruction_senders, id));
+    }
+}
+
+/// Returns true if the waiter is still in the map.
+fn is_registered<T, ChannelId>(
+    waiters: &HashMap<ChannelId, Vec<Sender<T, SmartChannelId>>>,
+    id: &SmartChannelId,
+) -> bool {
+    waiters.values().flatten().any(|waiter| waiter.id() == id)
+}
+
+impl<M, ChannelId: Eq + Hash + Clone> HubHandle<M, ChannelId> {
+    /// See `NotifierHub::get_creation_waiter_with`.
+    pub fn get_creation_waiter_with(&self, id: &ChannelId, config: WaiterConfig) -> CreationWaiter {
+        self.with(|hub| hub.get_creation_waiter_with(id, config))
+    }
+
+    /// See `NotifierHub::get_destruction_waiter_with`.
+    pub fn get_destruction_waiter_with(
+        &self,
+        id: &ChannelId,
+        config: WaiterConfig,
+    ) -> DestructionWaiter<M> {
+        self.with(|hub| hub.get_destruction_waiter_with(id, config))
+    }
+
+    /// See `NotifierHub::dropped_notifications`.
+    pub fn dropped_notifications<T>(&self, waiter: &Receiver<T, SmartChannelId>) -> u64 {
+        self.with(|hub| hub.dropped_notifications(waiter))
+    }
+
+    /// See `NotifierHub::get_departure_waiter`.
+    pub fn get_departure_waiter(&self, id: &ChannelId) -> DepartureWaiter<M>
+    where
+        M: Send + Clone + 'static,
+    {
+        self.with(|hub| hub.get_departure_waiter(id))
+    }
+
+    /// See `NotifierHub::get_creation_waiter_multi`.
+    pub fn get_creation_waiter_multi(&self, ids: &[ChannelId]) -> MultiCreationWaiter<ChannelId>
+    where
+        ChannelId: Send + 'static,
+    {
+        self.with(|hub| hub.get_creation_waiter_multi(ids))
+    }
+
+    /// See `NotifierHub::get_destruction_waiter_multi`.
+    pub fn get_destruction_waiter_multi(
+        &self,
+        ids: &[ChannelId],
+    ) -> MultiDestructionWaiter<M, ChannelId>
+    where
+        M: Send + Clone + 'static,
+        ChannelId: Send + 'static,
+    {
+        self.with(|hub| hub.get_destruction_waiter_multi(ids))
+    }
+
+    /// See `NotifierHub::get_creation_waiter_all`.
+    pub fn get_creation_waiter_all(&self) -> MultiCreationWaiter<ChannelId>
+    where
+        ChannelId: Send + 'static,
+    {
+        self.with(|hub| hub.get_creation_waiter_all())
+    }
+
+    /// See `NotifierHub::get_destruction_waiter_all`.
+    pub fn get_destruction_waiter_all(&self) -> MultiDestructionWaiter<M, ChannelId>
+    where
+        M: Send + Clone + 'static,
+        ChannelId: Send + 'static,
+    {
+        self.with(|hub| hub.get_destruction_waiter_all())
+    }
+
+    /// See `NotifierHub::remove_waiter`.
+    pub fn remove_waiter<T>(&self, waiter: &Receiver<T, SmartChannelId>) -> bool {
+        self.with(|hub| hub.remove_waiter(waiter))
+    }
+
+    /// Wraps the waiter of this hub in a guard unregistering it when dropped, see `NotifierHub::remove_waiter`.
+    ///
+    /// Example:
+    /// ```rust
+    /// use notifier_hub::notifier::NotifierHub;
+    ///
+    /// let handle = NotifierHub::new().into_handle();
+    /// let waiter = handle.guard_waiter(handle.get_destruction_waiter(&"jobs"));
+    /// assert_eq!(handle.topology().channels[&"jobs"].destruction_waiters, 1);
+    ///
+    /// drop(waiter);
+    /// assert!(handle.topology().channels.is_empty());
+    /// # let _: notifier_hub::handle::HubHandle<u32, &str> = handle;
+    /// ```
+    pub fn guard_waiter<T>(
+        &self,
+        waiter: Receiver<T, SmartChannelId>,
+    ) -> WaiterGuard<T, M, ChannelId> {
+        WaiterGuard {
+            waiter,
+            hub: Arc::downgrade(&self.hub),
+        }
+    }
+
+    /// See `NotifierHub::get_coalesced_creation_waiter`.
+    pub fn get_coalesced_creation_waiter(&self, id: &ChannelId) -> CoalescedWaiter {
+        self.with(|hub| hub.get_coalesced_creation_waiter(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::ChannelState;
+
+    #[tokio::test]
+    async fn test_waiter_overflow() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let config = |overflow| WaiterConfig {
+            capacity: 2,
+            overflow,
+        };
+        let mut coalesced =
+            hub.get_creation_waiter_with(&"channel1", config(WaiterOverflow::Coalesce));
+        let dropping = hub.get_creation_waiter_with(&"channel1", config(WaiterOverflow::Drop));
+        let mut growing = hub.get_creation_waiter_with(&"channel1", config(WaiterOverflow::Grow));
+        let _receivers: Vec<_> = (0..5).map(|_| hub.subscribe(&"channel1", 10)).collect();
+
+        assert_eq!(hub.dropped_notifications(&coalesced), 0);
+        assert_eq!(hub.dropped_notifications(&dropping), 3);
+        for _ in 0..2 {
+            coalesced.recv().await.unwrap();
+        }
+        assert!(coalesced.try_recv().is_err());
+        for _ in 0..5 {
+            growing.recv().await.unwrap();
+        }
+
+        drop(dropping);
+        hub.collect_garbage();
+        assert_eq!(hub.creation_states.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_multi_waiters() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut creations = hub.get_creation_waiter_multi(&["channel1", "channel2"]);
+        let mut destructions = hub.get_destruction_waiter_multi(&["channel1", "channel2"]);
+        let receiver1 = hub.subscribe(&"channel1", 10);
+        let receiver2 = hub.subscribe(&"channel2", 10);
+        let _receiver3 = hub.subscribe(&"channel3", 10);
+        assert_eq!(creations.recv().await, Some("channel1"));
+        assert_eq!(creations.recv().await, Some("channel2"));
+        assert!(creations.try_recv().is_err());
+
+        hub.unsubscribe(&"channel2", &receiver2).unwrap();
+        let (channel, dead) = destructions.recv().await.unwrap();
+        assert_eq!(channel, "channel2");
+        assert!(dead.is_bound_to(&receiver2));
+
+        drop(creations);
+        drop(destructions);
+        hub.unsubscribe(&"channel1", &receiver1).unwrap();
+        assert_eq!(hub.collect_garbage().creation_waiters, 2);
+        assert!(hub.creation_events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_global_waiters() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut creations = hub.get_creation_waiter_all();
+        let mut destructions = hub.get_destruction_waiter_all();
+        let receiver = hub.subscribe_multiple(&["channel1", "channel2"], 10);
+        let mut created = vec![
+            creations.recv().await.unwrap(),
+            creations.recv().await.unwrap(),
+        ];
+        created.sort();
+        assert_eq!(created, ["channel1", "channel2"]);
+
+        hub.unsubscribe(&"channel2", &receiver).unwrap();
+        assert_eq!(destructions.recv().await.unwrap().0, "channel2");
+        drop(destructions);
+        assert_eq!(hub.collect_garbage().destruction_waiters, 1);
+        assert_eq!(hub.global_creation_events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_clean_channel_departures() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut destructions = hub.get_destruction_waiter(&"channel1");
+        let mut multi = hub.get_destruction_waiter_multi(&["channel1"]);
+        let mut departures = hub.get_departure_waiter(&"channel1");
+        let receiver1 = hub.subscribe(&"channel1", 10);
+        let receiver2 = hub.subscribe(&"channel1", 10);
+        let _receiver3 = hub.subscribe(&"channel1", 10);
+
+        hub.unsubscribe(&"channel1", &receiver1).unwrap();
+        drop(receiver2);
+        assert_eq!(hub.clean_channel(&"channel1"), ChannelState::Running);
+        assert_eq!(hub.clean_channel(&"channel1"), ChannelState::Running);
+
+        let departure = departures.recv().await.unwrap();
+        assert!(departure.sender.is_bound_to(&receiver1));
+        assert_eq!(departure.reason, DestructionReason::Unsubscribed);
+        let departure = departures.recv().await.unwrap();
+        assert_eq!(departure.reason, DestructionReason::DetectedClosed);
+        assert!(departures.try_recv().is_err());
+
+        destructions.recv().await.unwrap();
+        assert_eq!(destructions.recv().await.unwrap(), departure.sender);
+        multi.recv().await.unwrap();
+        assert_eq!(multi.recv().await, Some(("channel1", departure.sender)));
+        assert!(destructions.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remove_waiter() {
+        let handle: HubHandle<u32, &'static str> = NotifierHub::new().into_handle();
+        let creation = handle.get_creation_waiter(&"channel1");
+        let multi = handle.get_destruction_waiter_multi(&["channel1", "channel2"]);
+        let state = handle.guard_waiter(handle.get_state_waiter(&"channel1"));
+        let mut guarded = handle.guard_waiter(handle.get_creation_waiter_all());
+
+        assert!(handle.remove_waiter(&creation));
+        assert!(!handle.remove_waiter(&creation));
+        assert!(handle.remove_waiter(&multi));
+        drop(state);
+        handle.with(|hub| {
+            assert!(hub.creation_senders.is_empty());
+            assert!(hub.destruction_events.is_empty());
+            assert!(hub.state_senders.is_empty());
+        });
+
+        let _receiver = handle.subscribe(&"channel1", 10);
+        assert_eq!(guarded.recv().await, Some("channel1"));
+        drop(guarded);
+        handle.with(|hub| assert!(hub.global_creation_events.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_waiter() {
+        let mut hub: NotifierHub<u32, &'static str> = NotifierHub::new();
+        let mut waiter = hub.get_coalesced_creation_waiter(&"channel1");
+        assert_eq!(waiter.try_recv(), 0);
+        let _receivers: Vec<_> = (0..3).map(|_| hub.subscribe(&"channel1", 10)).collect();
+        assert_eq!(waiter.recv().await, Some(3));
+        let _other = hub.subscribe(&"channel1", 10);
+        assert_eq!(waiter.try_recv(), 1);
+
+        let _another = hub.subscribe(&"channel1", 10);
+        drop(hub);
+        assert_eq!(waiter.recv().await, Some(1));
+        assert_eq!(waiter.recv().await, None);
+    }
+}
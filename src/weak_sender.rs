@@ -0,0 +1,116 @@
+use tokio::sync::mpsc::{
+    self,
+    error::{SendError, TrySendError},
+    WeakSender,
+};
+
+use crate::notifier::{MessageReceiver, MessageSender, SmartChannelId};
+
+/// A sender that does not keep its channel alive.
+/// Once the hub and all the strong senders are gone, the receiver sees the end of the channel
+/// even if some weak senders are still held somewhere. Sending through a dead weak sender fails
+/// and hands the message back.
+#[derive(Debug)]
+pub struct WeakMessageSender<M> {
+    id: SmartChannelId,
+    sender: WeakSender<M>,
+}
+
+impl<M> Clone for WeakMessageSender<M> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+/// Allows to get a weak handle from a `MessageSender`.
+pub trait Downgrade<M> {
+    /// Returns a weak sender bound to the same receiver.
+    fn downgrade(&self) -> WeakMessageSender<M>;
+}
+
+impl<M> Downgrade<M> for MessageSender<M> {
+    fn downgrade(&self) -> WeakMessageSender<M> {
+        WeakMessageSender {
+            id: *self.id(),
+            sender: (**self).downgrade(),
+        }
+    }
+}
+
+impl<M> WeakMessageSender<M> {
+    /// Returns the id of the bound receiver.
+    pub fn id(&self) -> &SmartChannelId {
+        &self.id
+    }
+
+    /// Returns `true` if `self` is associated with the given `Receiver`.
+    pub fn is_bound_to(&self, receiver: &MessageReceiver<M>) -> bool {
+        self.id == receiver.id()
+    }
+
+    /// Returns `true` if a strong sender still exists and the receiver is still open.
+    pub fn is_alive(&self) -> bool {
+        self.sender.upgrade().is_some_and(|s| !s.is_closed())
+    }
+
+    /// Returns a strong sender bound to the same receiver, if a strong sender still exists.
+    pub(crate) fn upgrade(&self) -> Option<MessageSender<M>> {
+        let (_, unused) = mpsc::channel(1);
+        Some(smart_channel::bind(self.sender.upgrade()?, unused, self.id).0)
+    }
+
+    /// Sends the message if the channel is still alive, otherwise the message is returned in the error.
+    pub async fn send(&self, msg: M) -> Result<(), SendError<M>> {
+        match self.sender.upgrade() {
+            Some(sender) => sender.send(msg).await,
+            None => Err(SendError(msg)),
+        }
+    }
+
+    /// Same as `send`, but fails instead of waiting if the buffer is full.
+    pub fn try_send(&self, msg: M) -> Result<(), TrySendError<M>> {
+        match self.sender.upgrade() {
+            Some(sender) => sender.try_send(msg),
+            None => Err(TrySendError::Closed(msg)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::NotifierHub;
+
+    #[tokio::test]
+    async fn test_weak_sender_send() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver = hub.subscribe(&"channel1", 100);
+
+        let weak = hub.get_weak_sender(&"channel1", &receiver).unwrap();
+        assert!(weak.is_bound_to(&receiver));
+        assert!(weak.is_alive());
+
+        weak.send("Weak message".to_string()).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), "Weak message");
+        assert!(hub.get_weak_sender(&"channel2", &receiver).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_weak_sender_does_not_keep_channel_alive() {
+        let mut hub: NotifierHub<String, &'static str> = NotifierHub::new();
+        let mut receiver = hub.subscribe(&"channel1", 100);
+
+        let weak = hub.get_sender(&"channel1", &receiver).unwrap().downgrade();
+        hub.unsubscribe(&"channel1", &receiver).unwrap();
+
+        assert!(!weak.is_alive());
+        assert!(receiver.recv().await.is_none());
+        assert!(matches!(
+            weak.try_send("Too late".to_string()),
+            Err(TrySendError::Closed(_))
+        ));
+    }
+}
@@ -1,78 +1,311 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+};
 pub use tokio::time::Duration;
-use tokio::{sync::mpsc::error::SendError, task::JoinHandle, time::timeout};
+use tokio::{
+    sync::mpsc::{
+        error::{SendError, TrySendError},
+        Sender as TokioSender,
+    },
+    sync::oneshot,
+    task::{Id, JoinError, JoinSet},
+    time::{sleep_until, timeout_at, Instant},
+};
 
 use crate::{
-    error::{NotifierError, UnexpectedErrorKind},
+    alert::LatencyReporter,
+    circuit_breaker::CircuitBreaker,
+    credits::Credits,
+    error::{NotifierError, SendFailure},
+    error_hook::{FailureKind, FailureReporter},
     notifier::{Sender, SmartChannelId},
+    quarantine::{deliver_or_quarantine, Quarantine, QuarantinePolicy, QuarantinedMessage},
 };
 
-type Handler<M> = JoinHandle<Result<(), SendError<M>>>;
+type WritingResult<M> = Result<(), SendFailure<M, ()>>;
+type Sequenced<M> = oneshot::Receiver<BroadcastReport<M>>;
+
+/// Defines how the writings are performed when a buffer is full.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeliveryMode {
+    /// A task is spawned for each subscriber whose buffer is full, waiting for some room.
+    #[default]
+    Concurrent,
+    /// Everything is delivered inline in subscription order and nothing is ever spawned,
+    /// a full buffer is reported as a `FailureKind::Full` failure. This gives reproducible interleavings in tests.
+    Deterministic,
+}
+
+/// The outcome of a broadcast, returned by `WritingHandler::wait`.
+/// A broadcast often reaches some subscribers and fails for others, the report tells both apart
+/// without going through an error.
+#[derive(Debug)]
+pub struct BroadcastReport<M, ChannelId = ()> {
+    /// Number of messages put in the buffer of their subscriber.
+    delivered: usize,
+    /// The failed writings, including the timeouts.
+    failures: Vec<SendFailure<M, ChannelId>>,
+}
+
+impl<M, ChannelId> BroadcastReport<M, ChannelId> {
+    /// Returns the number of messages put in the buffer of their subscriber.
+    pub fn delivered(&self) -> usize {
+        self.delivered
+    }
+
+    /// Returns the number of failed writings, the timeouts excepted.
+    pub fn failed(&self) -> usize {
+        self.failures.len() - self.timed_out()
+    }
+
+    /// Returns the number of writings that did not finish before the deadline of `wait`.
+    pub fn timed_out(&self) -> usize {
+        self.failures
+            .iter()
+            .filter(|failure| failure.kind == FailureKind::Timeout)
+            .count()
+    }
+
+    /// Returns the number of writings of the broadcast.
+    pub fn len(&self) -> usize {
+        self.delivered + self.failures.len()
+    }
+
+    /// Returns true if the broadcast had no subscriber.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if every writing succeeded, which is the case of a broadcast without subscriber.
+    pub fn all_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Returns true if some writings succeeded and others failed.
+    pub fn partial(&self) -> bool {
+        self.delivered > 0 && !self.failures.is_empty()
+    }
+
+    /// Returns the failed writings, including the timeouts.
+    pub fn failures(&self) -> &[SendFailure<M, ChannelId>] {
+        &self.failures
+    }
+
+    /// Returns the failed writings, with the messages they hand back.
+    pub fn into_failures(self) -> Vec<SendFailure<M, ChannelId>> {
+        self.failures
+    }
+
+    /// Returns the number of delivered messages if every writing succeeded,
+    /// the failures as a `NotifierError::WritingSendError` otherwise.
+    pub fn into_result(self) -> Result<usize, NotifierError<M, ChannelId>> {
+        if self.failures.is_empty() {
+            Ok(self.delivered)
+        } else {
+            Err(NotifierError::WritingSendError(self.failures))
+        }
+    }
+}
 
 /// `WritingHandler` is responsible for managing and awaiting multiple asynchronous
 /// tasks that send messages via Tokio channels.
 /// It allows for broadcasting messages to multiple senders and waiting for all tasks to complete.
 /// You can either broadcast a message behind an `Arc` (for efficiency) or clone the message.
-#[derive(Default)]
-pub struct WritingHandler<M: Send + 'static> {
-    handlers: Vec<Handler<M>>,
+///
+/// Each message is first put in the buffer of its sender with `try_send`, without spawning anything.
+/// A task is only spawned for the senders whose buffer is full, so in the common case
+/// the writing is already over when the handler is returned.
+///
+/// The spawned tasks live in a single `JoinSet`, only made for the first full buffer. Dropping the handler
+/// detaches them, so the messages are still sent in the background.
+///
+/// There is no lighter handler for a single subscriber, but its publish takes the same fast path: the senders
+/// are borrowed from the hub, the last one is given the message itself rather than a clone, and nothing
+/// is spawned nor allocated while its buffer has room, besides the clone of the channel id bound to the handler
+/// and the reporters of the error hook and the alerts when the hub has them.
+///
+/// A panic while writing to a subscriber (for instance in a custom `Clone` implementation) is caught
+/// and reported as `FailureKind::Panicked` for this subscriber only, the other writings are not affected.
+///
+/// In `DeliveryMode::Deterministic`, nothing is spawned and full buffers are reported as errors.
+pub struct WritingHandler<M: Send + 'static, ChannelId = ()> {
+    /// Number of messages directly put in their channel buffer.
+    delivered: usize,
+    /// Failures caught while writing.
+    errors: Vec<SendFailure<M, ChannelId>>,
+    /// Tasks spawned for the senders that would have blocked, created with the first one
+    /// so the publishes whose messages all fit in the buffers don't allocate it.
+    handlers: Option<JoinSet<WritingResult<M>>>,
+    /// Binding each spawned task with the subscriber it is writing to.
+    tasks: HashMap<Id, SmartChannelId>,
+    /// Defines what to do when a buffer is full.
+    mode: DeliveryMode,
+    /// The writings are delayed until this instant, when the publish exceeded a rate limit.
+    not_before: Option<Instant>,
+    /// The deadline used by `wait` when no duration is given.
+    deadline: Option<Instant>,
+    /// Receives the outcome of each writing, when the circuit breaker of the hub is enabled.
+    breaker: Option<Arc<CircuitBreaker>>,
+    /// Spent by each writing to the subscribers limited by their credits, if some of them are.
+    credits: Option<Arc<Credits>>,
+    /// Where the messages go when their writing keeps failing, when the quarantine of the hub is enabled.
+    quarantine: Option<(Arc<Quarantine<M>>, QuarantinePolicy)>,
+    /// Receives each failure, when the hub has an error hook.
+    reporter: Option<FailureReporter>,
+    /// Receives the writings exceeding the latency budget, when the alerts of the hub are enabled.
+    latency: Option<(Duration, LatencyReporter)>,
+    /// The outcome of a publish queued in the sequencer of its channel, and its subscribers.
+    sequenced: Option<(Sequenced<M>, Vec<SmartChannelId>)>,
+    /// The channel of the publish, given to each failure.
+    channel: Option<ChannelId>,
 }
 
-fn get_handler<M: Send + 'static>(sender: Sender<M, SmartChannelId>, msg: M) -> Handler<M> {
-    tokio::spawn(async move { sender.send(msg).await })
+impl<M: Send + 'static, ChannelId> Drop for WritingHandler<M, ChannelId> {
+    fn drop(&mut self) {
+        if let Some(handlers) = &mut self.handlers {
+            handlers.detach_all();
+        }
+    }
 }
 
-impl<M: Send + 'static + Sync> WritingHandler<Arc<M>> {
-    /// Creates a `WritingHandler` for broadcasting messages across multiple senders using `Arc<M>`.
+impl<M: Send + 'static + Sync, ChannelId: Clone> WritingHandler<Arc<M>, ChannelId> {
+    /// Broadcasts the message across multiple senders using `Arc<M>`.
     /// This avoids cloning the message for each sender but requires `M` to implement `Sync`.
     /// This approach is efficient for large messages.
-    pub(crate) fn new_arc_broadcast(msg: M, senders: &[Sender<Arc<M>, SmartChannelId>]) -> Self {
+    pub(crate) fn arc_broadcast<'a>(
+        mut self,
+        msg: M,
+        senders: impl IntoIterator<Item = &'a Sender<Arc<M>, SmartChannelId>>,
+    ) -> Self {
         let msg = Arc::new(msg);
-        WritingHandler {
-            handlers: senders
-                .iter()
-                .map(|sender| {
-                    let msg = Arc::clone(&msg);
-                    let sender = sender.clone();
-                    get_handler(sender, msg)
-                })
-                .collect(),
+        let mut senders = senders.into_iter().peekable();
+        while let Some(sender) = senders.next() {
+            if senders.peek().is_none() {
+                self.write(sender, msg); // Avoiding one reference count
+                break;
+            }
+            self.write(sender, Arc::clone(&msg));
         }
+        self
     }
 }
-impl<M: Send + 'static + Clone> WritingHandler<M> {
-    /// Creates a `WritingHandler` by cloning the message for each sender.
+impl<M: Send + 'static + Clone, ChannelId: Clone> WritingHandler<M, ChannelId> {
+    /// Broadcasts the message by cloning it for each sender.
     /// This is useful when sending simple notification messages.
-    pub(crate) fn new_cloning_broadcast(msg: M, senders: &[Sender<M, SmartChannelId>]) -> Self {
-        if senders.is_empty() {
-            return Self::empty();
+    /// The senders are borrowed, so the caller doesn't have to collect them.
+    pub(crate) fn cloning_broadcast<'a>(
+        mut self,
+        msg: M,
+        senders: impl IntoIterator<Item = &'a Sender<M, SmartChannelId>>,
+    ) -> Self {
+        let mut senders = senders.into_iter().peekable();
+        while let Some(sender) = senders.next() {
+            if senders.peek().is_none() {
+                self.write(sender, msg); // Avoiding one clone
+                break;
+            }
+            match catch_unwind(AssertUnwindSafe(|| msg.clone())) {
+                Ok(msg) => self.write(sender, msg),
+                Err(_) => {
+                    self.report(sender.id(), false);
+                    self.fail(SendFailure::new(*sender.id(), FailureKind::Panicked, None))
+                }
+            }
         }
-        let mut handlers = senders
-            .iter()
-            .skip(1)
-            .map(|sender| {
-                let msg = msg.clone();
-                let sender = sender.clone();
-                get_handler(sender, msg)
-            })
-            .collect::<Vec<_>>();
-        handlers.push(get_handler(senders[0].clone(), msg)); // Avoiding one clone
-        WritingHandler { handlers }
+        self
     }
 }
 
-impl<M: Send + 'static> WritingHandler<M> {
+impl<M: Send + 'static, ChannelId> WritingHandler<M, ChannelId> {
     /// Returns an empty handler with no tasks.
     /// Calling `wait` on this handler returns immediately with success.
     pub fn empty() -> Self {
         Self {
-            handlers: Vec::new(),
+            delivered: 0,
+            errors: Vec::new(),
+            handlers: None,
+            tasks: HashMap::new(),
+            mode: DeliveryMode::default(),
+            not_before: None,
+            deadline: None,
+            breaker: None,
+            credits: None,
+            quarantine: None,
+            reporter: None,
+            latency: None,
+            sequenced: None,
+            channel: None,
+        }
+    }
+
+    /// Binds the handler to the channel of the publish, given to each of its failures.
+    pub(crate) fn on_channel(mut self, channel: Option<ChannelId>) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// Splits the handler from its channel, for a handler moved to a task that must not hold the channel.
+    pub(crate) fn split_channel(mut self) -> (WritingHandler<M>, Option<ChannelId>) {
+        let handler = WritingHandler {
+            delivered: self.delivered,
+            errors: self.errors.drain(..).map(SendFailure::unbound).collect(),
+            handlers: self.handlers.take(),
+            tasks: std::mem::take(&mut self.tasks),
+            mode: self.mode,
+            not_before: self.not_before,
+            deadline: self.deadline,
+            breaker: self.breaker.take(),
+            credits: self.credits.take(),
+            quarantine: self.quarantine.take(),
+            reporter: self.reporter.take(),
+            latency: self.latency.take(),
+            sequenced: self.sequenced.take(),
+            channel: None,
+        };
+        (handler, self.channel.take())
+    }
+
+    /// Returns the deadline the handler has been armed with by the send timeout of the hub, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns the number of spawned writings that have not been collected yet.
+    fn spawned(&self) -> usize {
+        self.handlers.as_ref().map_or(0, JoinSet::len)
+    }
+
+    /// Returns the number of subscribers of the publish queued in a sequencer, if any.
+    fn sequenced_len(&self) -> usize {
+        self.sequenced
+            .as_ref()
+            .map_or(0, |(_, subscribers)| subscribers.len())
+    }
+
+    /// Returns the number of messages put in a buffer, or still being written to a full one.
+    pub(crate) fn written(&self) -> usize {
+        self.delivered + self.pending()
+    }
+
+    /// Takes the message back if every subscriber of the publish had dropped its receiver.
+    pub(crate) fn take_orphaned(&mut self) -> Option<M> {
+        let orphaned = self.written() == 0
+            && self
+                .errors
+                .iter()
+                .all(|failure| failure.kind == FailureKind::Closed && failure.msg.is_some());
+        match orphaned {
+            true => self.errors.pop().and_then(|failure| failure.msg),
+            false => None,
         }
     }
 
     /// Returns the number of writing.
     pub fn len(&self) -> usize {
-        self.handlers.len()
+        self.delivered + self.errors.len() + self.spawned() + self.sequenced_len()
     }
 
     /// Returns true if the handler is empty
@@ -80,40 +313,357 @@ impl<M: Send + 'static> WritingHandler<M> {
         self.len() == 0
     }
 
+    /// Returns the number of writings that had to be delegated to a task because the buffer was full,
+    /// or that are queued in the sequencer of the channel.
+    pub fn pending(&self) -> usize {
+        self.spawned() + self.sequenced_len()
+    }
+
+    /// Aborts all the pending writings at once. Messages already put in a buffer are not affected.
+    /// The writings queued in a sequencer are not aborted, as it would break the order of the channel.
+    pub fn abort(&mut self) {
+        if let Some(handlers) = &mut self.handlers {
+            handlers.abort_all();
+        }
+    }
+
+    /// Returns a handler following a publish queued in the sequencer of its channel,
+    /// `done` receiving the outcome of its writings once the sequencer performed them.
+    pub(crate) fn sequenced(
+        done: Sequenced<M>,
+        subscribers: Vec<SmartChannelId>,
+        deadline: Option<Instant>,
+    ) -> Self {
+        let mut handler = Self::empty();
+        handler.sequenced = Some((done, subscribers));
+        handler.deadline = deadline;
+        handler
+    }
+
+    /// Returns an empty handler writing with the given mode.
+    pub(crate) fn with_mode(mode: DeliveryMode) -> Self {
+        let mut handler = Self::empty();
+        handler.mode = mode;
+        handler
+    }
+
+    /// Delays all the writings of the handler until the given instant.
+    pub(crate) fn not_before(mut self, instant: Instant) -> Self {
+        self.not_before = Some(instant);
+        self
+    }
+
+    /// Arms the handler with a deadline, `timeout` from now, used by `wait` when no duration is given.
+    pub(crate) fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    /// Keeps the failures in the given buffer, reused from a previous report.
+    pub(crate) fn with_errors(mut self, errors: Vec<SendFailure<M, ChannelId>>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Reports each failure to the error hook of the hub, if any.
+    pub(crate) fn with_reporter(mut self, reporter: Option<FailureReporter>) -> Self {
+        self.reporter = reporter;
+        self
+    }
+
+    /// Reports the writings taking longer than the budget.
+    pub(crate) fn with_latency_budget(
+        mut self,
+        budget: Duration,
+        reporter: LatencyReporter,
+    ) -> Self {
+        self.latency = Some((budget, reporter));
+        self
+    }
+
+    /// Reports the outcome of each writing to the given circuit breaker, and skips the open circuits.
+    pub(crate) fn with_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Skips the writings to the subscribers that have no credit left.
+    pub(crate) fn with_credits(mut self, credits: Arc<Credits>) -> Self {
+        self.credits = Some(credits);
+        self
+    }
+
+    /// Quarantines the messages whose writing keeps failing, instead of waiting for some room forever.
+    pub(crate) fn with_quarantine(
+        mut self,
+        quarantine: Arc<Quarantine<M>>,
+        policy: QuarantinePolicy,
+    ) -> Self {
+        self.quarantine = Some((quarantine, policy));
+        self
+    }
+}
+
+impl<M: Send + 'static, ChannelId: Clone> WritingHandler<M, ChannelId> {
+    /// Writes to each sender the message built for it, when the subscribers don't get the same message.
+    pub(crate) fn writing_each(
+        self,
+        senders: &[Sender<M, SmartChannelId>],
+        mut msg: impl FnMut(&Sender<M, SmartChannelId>) -> M,
+    ) -> Self {
+        self.writing_to(senders.iter().map(|sender| (sender, msg(sender))))
+    }
+
+    /// Writes each message to the sender it comes with.
+    pub(crate) fn writing_to<'a>(
+        mut self,
+        messages: impl IntoIterator<Item = (&'a Sender<M, SmartChannelId>, M)>,
+    ) -> Self {
+        for (sender, msg) in messages {
+            self.write(sender, msg);
+        }
+        self
+    }
+
+    /// Keeps the failure for `wait`, and reports it to the error hook if any.
+    fn fail(&mut self, failure: SendFailure<M, ()>) {
+        if let Some(reporter) = &self.reporter {
+            reporter(failure.subscriber, failure.kind);
+        }
+        self.errors.push(failure.bound(self.channel.clone()));
+    }
+
+    /// Reports the outcome of a writing to the circuit breaker, if any.
+    fn report(&self, id: &SmartChannelId, success: bool) {
+        if let Some(breaker) = &self.breaker {
+            breaker.record(*id, success);
+        }
+    }
+
+    /// Spawns a task writing the message once there is some room in the buffer.
+    /// `attempts` is the number of attempts already made, for a message coming from the quarantine.
+    fn spawn(&mut self, sender: &Sender<M, SmartChannelId>, msg: M, attempts: usize) {
+        self.reap();
+        // The tokio sender is cloned as the smart one requires `M: Clone`
+        let tokio_sender: TokioSender<M> = (**sender).clone();
+        let id = *sender.id();
+        let not_before = self.not_before;
+        let quarantine = self.quarantine.clone();
+        let latency = self.latency.clone();
+        let task = self
+            .handlers
+            .get_or_insert_with(JoinSet::new)
+            .spawn(async move {
+                if let Some(instant) = not_before {
+                    sleep_until(instant).await;
+                }
+                let start = Instant::now();
+                let result = match quarantine {
+                    Some((quarantine, policy)) => {
+                        deliver_or_quarantine(tokio_sender, msg, id, quarantine, policy, attempts)
+                            .await
+                    }
+                    None => tokio_sender.send(msg).await.map_err(|SendError(msg)| {
+                        SendFailure::new(id, FailureKind::Closed, Some(msg))
+                    }),
+                };
+                if let Some((budget, reporter)) = latency {
+                    let elapsed = start.elapsed();
+                    if result.is_ok() && elapsed > budget {
+                        reporter(id, elapsed);
+                    }
+                }
+                result
+            });
+        self.tasks.insert(task.id(), id);
+    }
+
+    /// Writes again a message coming from the quarantine, if its subscriber is still there.
+    pub(crate) fn requeue(
+        &mut self,
+        sender: Option<&Sender<M, SmartChannelId>>,
+        quarantined: QuarantinedMessage<M>,
+    ) {
+        match sender {
+            Some(sender) => self.spawn(sender, quarantined.msg, quarantined.attempts),
+            None => self.fail(SendFailure::new(
+                quarantined.subscriber,
+                FailureKind::Closed,
+                Some(quarantined.msg),
+            )),
+        }
+    }
+
+    /// Records the outcome of a finished writing task.
+    fn record(&mut self, result: Result<(Id, WritingResult<M>), JoinError>) {
+        let task = match &result {
+            Ok((task, _)) => *task,
+            Err(e) => e.id(),
+        };
+        let id = self.tasks.remove(&task);
+        if let Some(id) = &id {
+            self.report(id, matches!(result, Ok((_, Ok(())))));
+        }
+        match result {
+            Ok((_, Ok(()))) => self.delivered += 1,
+            Ok((_, Err(failure))) => self.fail(failure),
+            Err(e) => {
+                let kind = match e.is_panic() {
+                    true => FailureKind::Panicked,
+                    false => FailureKind::Aborted,
+                };
+                self.fail(SendFailure {
+                    channel: None,
+                    subscriber: id,
+                    kind,
+                    msg: None,
+                })
+            }
+        }
+    }
+
+    /// Collects the tasks that are already over, so their resources are freed without waiting for `wait`.
+    fn reap(&mut self) {
+        while let Some(result) = self
+            .handlers
+            .as_mut()
+            .and_then(JoinSet::try_join_next_with_id)
+        {
+            self.record(result);
+        }
+    }
+
+    /// Tries to put the message in the buffer of the sender without blocking.
+    /// If the buffer is full, a task is spawned to wait for some room.
+    fn write(&mut self, sender: &Sender<M, SmartChannelId>, msg: M) {
+        if let Some(breaker) = &self.breaker {
+            if !breaker.allows(sender.id()) {
+                return self.fail(SendFailure::new(
+                    *sender.id(),
+                    FailureKind::CircuitOpen,
+                    None,
+                ));
+            }
+        }
+        if let Some(credits) = &self.credits {
+            if !credits.spend(sender.id()) {
+                return self.fail(SendFailure::new(
+                    *sender.id(),
+                    FailureKind::NoCredit,
+                    Some(msg),
+                ));
+            }
+        }
+        if self.not_before.is_some() {
+            return self.spawn(sender, msg, 0);
+        }
+        match sender.try_send(msg) {
+            Ok(()) => {
+                self.report(sender.id(), true);
+                self.delivered += 1
+            }
+            Err(TrySendError::Full(msg)) if self.mode == DeliveryMode::Deterministic => {
+                self.report(sender.id(), false);
+                self.fail(SendFailure::new(*sender.id(), FailureKind::Full, Some(msg)))
+            }
+            Err(TrySendError::Full(msg)) => self.spawn(sender, msg, 0),
+            Err(TrySendError::Closed(msg)) => {
+                self.report(sender.id(), false);
+                self.fail(SendFailure::new(
+                    *sender.id(),
+                    FailureKind::Closed,
+                    Some(msg),
+                ))
+            }
+        }
+    }
+
+    /// Lets the pending writings finish in the background, for the publishes whose outcome is not awaited.
+    /// Unlike dropping the handler, the failures of the pending writings are still reported to the error hook
+    /// and the circuit breaker of the hub, the handler being waited by a task until its deadline, if any.
+    /// The failures of the writings already over have been reported when they happened.
+    pub fn detach(self) {
+        if self.pending() > 0 {
+            let (handler, _) = self.split_channel();
+            tokio::spawn(async move {
+                let _ = handler.wait(None).await;
+            });
+        }
+    }
+
     /// Waits for all tasks in the handler to finish.
-    /// If `duration` is `None`, this method waits indefinitely.
-    /// If `duration` is `Some`, it waits only for the given time.
-    /// Returns the number of completed tasks on success or a vector of caught errors.
-    /// Note that here the second generic type is unit as we are not using it anyway in the returned errors.
-    pub async fn wait(self, duration: Option<Duration>) -> Result<usize, NotifierError<M, ()>> {
-        let n = self.handlers.len();
-        let mut errors = Vec::new();
-
-        for handler in self.handlers {
-            let result = match duration {
-                Some(duration) => timeout(duration, handler).await,
-                None => Ok(handler.await),
+    /// If `duration` is `None`, this method waits until the deadline of the handler if the hub has a send timeout,
+    /// indefinitely otherwise.
+    /// If `duration` is `Some`, it waits only for the given time, the writings still pending at the deadline
+    /// are aborted and reported as timeouts.
+    /// A publish queued in a sequencer that is not over at the deadline is reported as timeouts,
+    /// but the sequencer still performs it.
+    /// Returns the report of the broadcast, telling the delivered messages from the failed writings.
+    /// The failures carry the channel of the publish, none for a broadcast over several channels.
+    pub async fn wait(mut self, duration: Option<Duration>) -> BroadcastReport<M, ChannelId> {
+        let deadline = duration
+            .map(|duration| Instant::now() + duration)
+            .or(self.deadline);
+
+        let mut handlers = self.handlers.take().unwrap_or_default();
+        loop {
+            let result = match deadline {
+                Some(deadline) => match timeout_at(deadline, handlers.join_next_with_id()).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        let pending: Vec<_> = self.tasks.values().copied().collect();
+                        for id in pending {
+                            self.report(&id, false);
+                            self.fail(SendFailure::new(id, FailureKind::Timeout, None));
+                        }
+                        handlers.abort_all();
+                        break;
+                    }
+                },
+                None => handlers.join_next_with_id().await,
             };
-
             match result {
-                Ok(Ok(Ok(()))) => (),
-                Ok(Ok(Err(e))) => errors.push(NotifierError::SendingError(e)),
-                Ok(Err(e)) => errors.push(NotifierError::JoiningError(e)),
-                Err(_) => errors.push(NotifierError::WritingTimeout(match duration {
-                    Some(d) => d,
-                    None => {
-                        return Err(NotifierError::UnexpectedError(
-                            UnexpectedErrorKind::DurationIsMissing, // Should never append as if duration is None we put the result in Ok
-                        ));
-                    }
-                })),
+                Some(result) => self.record(result),
+                None => break,
             }
         }
 
-        if errors.is_empty() {
-            Ok(n)
-        } else {
-            Err(NotifierError::WritingSendError(errors))
+        if let Some((done, subscribers)) = self.sequenced.take() {
+            let outcome = match deadline {
+                Some(deadline) => timeout_at(deadline, done)
+                    .await
+                    .map_err(|_| FailureKind::Timeout),
+                None => Ok(done.await),
+            };
+            // The failures have already been reported by the handler of the sequencer
+            let kind = match outcome {
+                Ok(Ok(report)) => {
+                    self.delivered += report.delivered;
+                    let channel = &self.channel;
+                    self.errors.extend(
+                        report
+                            .failures
+                            .into_iter()
+                            .map(|failure| failure.bound(channel.clone())),
+                    );
+                    None
+                }
+                Ok(Err(_)) => Some(FailureKind::Aborted),
+                Err(kind) => Some(kind),
+            };
+            if let Some(kind) = kind {
+                let channel = &self.channel;
+                self.errors.extend(
+                    subscribers
+                        .into_iter()
+                        .map(|id| SendFailure::new(id, kind, None).bound(channel.clone())),
+                );
+            }
+        }
+
+        BroadcastReport {
+            delivered: self.delivered,
+            failures: std::mem::take(&mut self.errors),
         }
     }
 }
@@ -130,32 +680,82 @@ mod tests {
 
     #[tokio::test]
     async fn test_empty_handler_wait() {
-        let handler: WritingHandler<String> = WritingHandler::empty();
-        let result = handler.wait(None).await;
+        let handler: WritingHandler<String> = WritingHandler::<_>::empty();
+        let result = handler.wait(None).await.into_result();
         assert_eq!(result.unwrap(), 0);
     }
 
     #[tokio::test]
     async fn test_handler_len() {
-        let handler: WritingHandler<String> = WritingHandler::empty();
+        let handler: WritingHandler<String> = WritingHandler::<_>::empty();
         assert_eq!(handler.len(), 0);
         assert!(handler.is_empty());
         let (tx1, _) = channel(10, TEST_ID);
         let (tx2, _) = channel(10, TEST_ID);
 
         let message = "Hello from Arc!";
-        let handler = WritingHandler::new_arc_broadcast(message, &[tx1, tx2]);
+        let handler = WritingHandler::<_>::empty().arc_broadcast(message, &[tx1, tx2]);
         assert!(handler.len() == 2)
     }
 
+    #[test]
+    fn test_inline_write_without_runtime() {
+        // No runtime here, so this would panic if a task was spawned
+        let (tx1, mut rx1) = channel(10, TEST_ID);
+        let (tx2, mut rx2) = channel(10, TEST_ID);
+
+        let handler =
+            WritingHandler::<_>::empty().cloning_broadcast("Inline".to_string(), &[tx1, tx2]);
+        assert_eq!(handler.len(), 2);
+        assert_eq!(handler.pending(), 0);
+
+        assert_eq!(rx1.try_recv().unwrap(), "Inline");
+        assert_eq!(rx2.try_recv().unwrap(), "Inline");
+    }
+
+    #[test]
+    fn test_single_subscriber_fast_path() {
+        #[derive(Debug, PartialEq)]
+        struct NoClone;
+
+        impl Clone for NoClone {
+            fn clone(&self) -> Self {
+                panic!("The message of a single subscriber must not be cloned")
+            }
+        }
+
+        let (tx, mut rx) = channel(10, TEST_ID);
+        let handler = WritingHandler::<_>::empty().cloning_broadcast(NoClone, &[tx]);
+        assert!(handler.handlers.is_none());
+        assert!(handler.errors.is_empty());
+        assert_eq!(rx.try_recv().unwrap(), NoClone);
+    }
+
+    #[tokio::test]
+    async fn test_only_full_senders_are_pending() {
+        let (tx1, mut rx1) = channel(1, TEST_ID);
+        let (tx2, mut rx2) = channel(10, TEST_ID);
+        tx1.try_send("Filling".to_string()).unwrap();
+
+        let handler =
+            WritingHandler::<_>::empty().cloning_broadcast("Message".to_string(), &[tx1, tx2]);
+        assert_eq!(handler.len(), 2);
+        assert_eq!(handler.pending(), 1);
+        assert_eq!(rx2.recv().await.unwrap(), "Message");
+
+        assert_eq!(rx1.recv().await.unwrap(), "Filling");
+        assert_eq!(handler.wait(None).await.into_result().unwrap(), 2);
+        assert_eq!(rx1.recv().await.unwrap(), "Message");
+    }
+
     #[tokio::test]
     async fn test_arc_broadcast_success() {
         let (tx1, mut rx1) = channel(10, TEST_ID);
         let (tx2, mut rx2) = channel(10, TEST_ID);
 
         let message = "Hello from Arc!";
-        let handler = WritingHandler::new_arc_broadcast(message, &[tx1, tx2]);
-        handler.wait(None).await.unwrap();
+        let handler = WritingHandler::<_>::empty().arc_broadcast(message, &[tx1, tx2]);
+        handler.wait(None).await.into_result().unwrap();
 
         assert_eq!(rx1.recv().await.unwrap(), Arc::new("Hello from Arc!"));
         assert_eq!(rx2.recv().await.unwrap(), Arc::new("Hello from Arc!"));
@@ -167,8 +767,8 @@ mod tests {
         let (tx2, mut rx2) = channel(10, TEST_ID);
 
         let message = "Hello from Arc!".to_string();
-        let handler = WritingHandler::new_cloning_broadcast(message, &[tx1, tx2]);
-        handler.wait(None).await.unwrap();
+        let handler = WritingHandler::<_>::empty().cloning_broadcast(message, &[tx1, tx2]);
+        handler.wait(None).await.into_result().unwrap();
 
         assert_eq!(*rx1.recv().await.unwrap(), String::from("Hello from Arc!"));
         assert_eq!(*rx2.recv().await.unwrap(), String::from("Hello from Arc!"));
@@ -178,52 +778,181 @@ mod tests {
     async fn test_timeout_error() {
         let (tx1, _rx1) = channel(1, TEST_ID);
 
-        let valid_handler = WritingHandler::new_cloning_broadcast(
+        let valid_handler = WritingHandler::<_>::empty().cloning_broadcast(
             "Message should pass".to_string(),
-            &[tx1.clone()],
+            std::slice::from_ref(&tx1),
         );
-        valid_handler.wait(None).await.unwrap();
+        valid_handler.wait(None).await.into_result().unwrap();
 
-        let err_handler = WritingHandler::new_cloning_broadcast(
+        let err_handler = WritingHandler::<_>::empty().cloning_broadcast(
             "Message should not pass".to_string(),
-            &[tx1.clone()],
+            std::slice::from_ref(&tx1),
         ); // The channel is full because of the previous messages, but the receiver never read so the sending is infinite
 
-        let result = err_handler.wait(Some(Duration::from_millis(500))).await;
+        let result = err_handler
+            .wait(Some(Duration::from_millis(500)))
+            .await
+            .into_result();
         assert!(result.is_err());
 
         if let Err(NotifierError::WritingSendError(errors)) = result {
             assert!(errors.len() == 1);
-            assert!(matches!(errors[0], NotifierError::WritingTimeout(_)));
+            assert_eq!(errors[0].kind, FailureKind::Timeout);
         } else {
             panic!("Expected timeout error.");
         }
     }
 
+    #[tokio::test]
+    async fn test_dropped_handler_still_writes() {
+        let (tx, mut rx) = channel(1, TEST_ID);
+        tx.try_send("Filling".to_string()).unwrap();
+
+        let handler = WritingHandler::<_>::empty().cloning_broadcast("Detached".to_string(), &[tx]);
+        assert_eq!(handler.pending(), 1);
+        drop(handler);
+
+        assert_eq!(rx.recv().await.unwrap(), "Filling");
+        assert_eq!(rx.recv().await.unwrap(), "Detached");
+    }
+
+    #[tokio::test]
+    async fn test_abort_pending_writings() {
+        let (tx1, mut rx1) = channel(1, TEST_ID);
+        let (tx2, mut rx2) = channel(1, TEST_ID);
+        tx1.try_send("Filling".to_string()).unwrap();
+        tx2.try_send("Filling".to_string()).unwrap();
+
+        let mut handler =
+            WritingHandler::<_>::empty().cloning_broadcast("Aborted".to_string(), &[tx1, tx2]);
+        assert_eq!(handler.pending(), 2);
+        handler.abort();
+        let result = handler.wait(None).await.into_result();
+        if let Err(NotifierError::WritingSendError(errors)) = result {
+            assert_eq!(errors.len(), 2);
+            assert!(errors.iter().all(|e| e.kind == FailureKind::Aborted));
+        } else {
+            panic!("Expected cancelled writings.");
+        }
+
+        assert_eq!(rx1.recv().await.unwrap(), "Filling");
+        assert_eq!(rx2.recv().await.unwrap(), "Filling");
+        assert!(rx1.try_recv().is_err());
+        assert!(rx2.try_recv().is_err());
+    }
+
+    /// Panics on its first clone
+    struct Poisoned(Arc<std::sync::atomic::AtomicBool>);
+
+    impl Clone for Poisoned {
+        fn clone(&self) -> Self {
+            if !self.0.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                panic!("Poisoned clone");
+            }
+            Poisoned(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicking_clone_is_isolated() {
+        let poisoned_id = SmartChannelId {
+            channel_counter: 2,
+            notifier_address: 1,
+        };
+        let (tx1, _rx1) = channel(10, poisoned_id);
+        let (tx2, mut rx2) = channel(10, TEST_ID);
+        let (tx3, mut rx3) = channel(10, TEST_ID);
+
+        let msg = Poisoned(Arc::new(std::sync::atomic::AtomicBool::new(false)));
+        let handler = WritingHandler::<_>::empty().cloning_broadcast(msg, &[tx1, tx2, tx3]);
+        assert_eq!(handler.len(), 3);
+
+        assert!(rx2.recv().await.is_some());
+        assert!(rx3.recv().await.is_some());
+        match handler.wait(None).await.into_result() {
+            Err(NotifierError::WritingSendError(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].kind, FailureKind::Panicked);
+                assert_eq!(errors[0].subscriber, Some(poisoned_id));
+            }
+            _ => panic!("Expected a panicked sender."),
+        }
+    }
+
+    #[test]
+    fn test_deterministic_full_buffer() {
+        let (tx1, mut rx1) = channel(1, TEST_ID);
+        let (tx2, mut rx2) = channel(2, TEST_ID);
+        tx1.try_send("Filling".to_string()).unwrap();
+
+        // Would panic here without runtime if something was spawned
+        let handler = WritingHandler::<_>::with_mode(DeliveryMode::Deterministic)
+            .cloning_broadcast("Message".to_string(), &[tx1, tx2]);
+        assert_eq!(handler.pending(), 0);
+        assert_eq!(rx1.try_recv().unwrap(), "Filling");
+        assert!(rx1.try_recv().is_err());
+        assert_eq!(rx2.try_recv().unwrap(), "Message");
+
+        let result = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(handler.wait(None))
+            .into_result();
+        match result {
+            Err(NotifierError::WritingSendError(errors)) => assert!(matches!(
+                &errors[..],
+                [SendFailure { subscriber: Some(id), kind: FailureKind::Full, msg: Some(msg), .. }]
+                    if *id == TEST_ID && msg == "Message"
+            )),
+            _ => panic!("Expected a full buffer."),
+        }
+    }
+
     #[tokio::test]
     async fn test_send_error() {
         let (tx, _) = channel(10, TEST_ID); // Receiver dropped intentionally.
 
-        let handler = WritingHandler::new_cloning_broadcast("Join test".to_string(), &[tx]);
+        let handler =
+            WritingHandler::<_>::empty().cloning_broadcast("Join test".to_string(), &[tx]);
 
-        let result = handler.wait(None).await;
+        let result = handler.wait(None).await.into_result();
         assert!(result.is_err());
         if let Err(NotifierError::WritingSendError(errors)) = result {
-            assert!(matches!(errors[0], NotifierError::SendingError(_)));
+            assert_eq!(errors[0].kind, FailureKind::Closed);
+            assert_eq!(errors[0].msg.as_deref(), Some("Join test"));
         } else {
             panic!("Expected join error.");
         }
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_partial_report() {
+        let (tx1, _rx1) = channel(1, TEST_ID);
+        let (tx2, _) = channel(10, TEST_ID); // Dropped receiver.
+        let (tx3, _rx3) = channel(10, TEST_ID);
+        tx1.try_send(0).unwrap();
+
+        let handler = WritingHandler::<_>::empty().cloning_broadcast(1, &[tx1, tx2, tx3]);
+        let report = handler.wait(Some(Duration::from_millis(10))).await;
+        assert_eq!(report.delivered(), 1);
+        assert_eq!(report.failed(), 1);
+        assert_eq!(report.timed_out(), 1);
+        assert_eq!(report.len(), 3);
+        assert!(report.partial());
+        assert!(!report.all_ok());
+
+        let report = WritingHandler::<u32>::empty().wait(None).await;
+        assert!(report.all_ok() && !report.partial() && report.is_empty());
+    }
+
     #[tokio::test]
     async fn test_multiple_errors() {
         let (tx1, _) = channel(10, TEST_ID); // Dropped receiver.
         let (tx2, _) = channel(10, TEST_ID); // Dropped receiver.
 
-        let handler =
-            WritingHandler::new_cloning_broadcast("Multi-error test".to_string(), &[tx1, tx2]);
+        let handler = WritingHandler::<_>::empty()
+            .cloning_broadcast("Multi-error test".to_string(), &[tx1, tx2]);
 
-        let result = handler.wait(None).await;
+        let result = handler.wait(None).await.into_result();
         assert!(result.is_err());
         if let Err(NotifierError::WritingSendError(errors)) = result {
             assert_eq!(errors.len(), 2); // Two sends should fail
@@ -236,12 +965,13 @@ mod tests {
     async fn test_no_error_with_successful_senders() {
         let (tx, mut rx) = channel(10, TEST_ID);
 
-        let handler = WritingHandler::new_cloning_broadcast("Success message".to_string(), &[tx]);
+        let handler =
+            WritingHandler::<_>::empty().cloning_broadcast("Success message".to_string(), &[tx]);
         tokio::spawn(async move {
             let _ = rx.recv().await;
         });
 
-        let result = handler.wait(None).await;
+        let result = handler.wait(None).await.into_result();
         assert!(result.is_ok());
     }
 }